@@ -3,8 +3,13 @@ use fluffl::{
     extras::{
         audio::{music_player::*, ogg::*, *},
         shapes::ShapePainter2D,
+        sprites::{Sprite, SpriteBatch, UvRect},
     },
     io::*,
+    ogl::{
+        texture::{HasTextureBuilder, HasTextureObj, TextureObj},
+        BlendState, GlStateCache,
+    },
     prelude::*,
     text_writer::*,
     window::{event_util::*, *},
@@ -18,6 +23,9 @@ type ShortDeviceContext = FlufflAudioDeviceContext<ShortCallback, ShortState>;
 
 static mut RAND_STATE: u64 = 0;
 
+const BRICK_WIDTH: f32 = 55.;
+const BRICK_HEIGHT: f32 = 16.;
+
 static FLUFFL_CONFIG: &str = r#"
     {
         "width":630,
@@ -102,7 +110,91 @@ impl Brick {
             0.,
         );
     }
+
+    /// bricks go through the sprite batch so the whole wall takes a single draw call
+    pub fn render_sprite(&mut self, sprites: &mut SpriteBatch, texture: &BrickTexture) {
+        self.glow_weight *= self.glow_decay;
+        let center = [
+            self.pos[0] + self.dims[0] * 0.5,
+            self.pos[1] + self.dims[1] * 0.5,
+        ];
+        let [r, g, b, a] = self.color;
+        let glow = self.glow_weight;
+        let sprite = Sprite::new(center, texture.size);
+        if glow > 0.01 {
+            let tint = [r * glow, g * glow, b * glow, a * glow];
+            sprites.draw(
+                texture.id(),
+                &sprite.with_uv(texture.glow_uv).with_tint(tint),
+            );
+        }
+        let body = sprite
+            .with_uv(texture.body_uv)
+            .with_tint(self.color)
+            .with_z(1.);
+        sprites.draw(texture.id(), &body);
+    }
+}
+
+/// what `ShapePainter2D::draw_rectangle(..)` draws for a brick, baked into a texture with the
+/// rounded body on the left and the glow around it on the right
+pub struct BrickTexture {
+    texture: TextureObj<u8>,
+    /// the size of a sprite showing one half, the brick plus the room its glow takes up
+    size: [f32; 2],
+    body_uv: UvRect,
+    glow_uv: UvRect,
+}
+
+impl BrickTexture {
+    const ROUNDNESS: f32 = 5.;
+    const MARGIN: f32 = 24.;
+
+    pub fn new(gl: &GlowGL, dims: [f32; 2]) -> Self {
+        let width = (dims[0] + 2. * Self::MARGIN).ceil() as usize;
+        let height = (dims[1] + 2. * Self::MARGIN).ceil() as usize;
+        let mut pixels = vec![0u8; 2 * width * height * 4];
+        for y in 0..height {
+            for x in 0..width {
+                // signed distance to the rounded box, like the shape painter's shader
+                let px = (x as f32 + 0.5 - width as f32 * 0.5).abs() - dims[0] * 0.5;
+                let py = (y as f32 + 0.5 - height as f32 * 0.5).abs() - dims[1] * 0.5;
+                let outside = (px.max(0.).powi(2) + py.max(0.).powi(2)).sqrt();
+                let d = outside + px.max(py).min(0.) - Self::ROUNDNESS;
+
+                let body = ((1. - d) * 0.5).clamp(0., 1.);
+                // the painter's falloff, faded out before the edge so the halves don't bleed
+                let fade = (1. - d.max(0.) / (Self::MARGIN - Self::ROUNDNESS)).clamp(0., 1.);
+                let glow = (2. / (d.abs().sqrt() + 0.5) * fade * fade).min(1.);
+
+                // drawn additively, so color and alpha both carry the coverage
+                for (column, value) in [(x, body), (x + width, glow)] {
+                    let texel = (y * 2 * width + column) * 4;
+                    pixels[texel..texel + 4].fill((value * 255.) as u8);
+                }
+            }
+        }
+
+        let texture = TextureObj::<u8>::builder(gl)
+            .with_width(2 * width as u32)
+            .with_height(height as u32)
+            .with_format(glow::RGBA)
+            .with_pixels(pixels)
+            .build();
+        let atlas = (2 * width as u32, height as u32);
+        Self {
+            texture,
+            size: [width as f32, height as f32],
+            body_uv: UvRect::from_pixels(0, 0, width as u32, height as u32, atlas),
+            glow_uv: UvRect::from_pixels(width as u32, 0, width as u32, height as u32, atlas),
+        }
+    }
+
+    fn id(&self) -> Option<glow::Texture> {
+        self.texture.texture_id()
+    }
 }
+
 #[derive(Copy, Clone, PartialEq, Eq)]
 enum GuiState {
     Menu,
@@ -118,6 +210,8 @@ pub struct BrickAppState {
     ball_fired: bool,
     //graphics stuff
     painter: ShapePainter2D,
+    sprites: SpriteBatch,
+    brick_texture: BrickTexture,
     writer: Option<TextWriter>,
     //audio stuff
     boss_intro_track: Option<ShortDeviceContext>,
@@ -138,6 +232,8 @@ impl BrickAppState {
             brick_list: vec![],
             mouse_pos: [0.; 2],
             painter: ShapePainter2D::new(gl),
+            sprites: SpriteBatch::new(gl),
+            brick_texture: BrickTexture::new(gl, [BRICK_WIDTH, BRICK_HEIGHT]),
             player_paddle,
             time: 0.0,
             writer: None,
@@ -148,9 +244,6 @@ impl BrickAppState {
         }
     }
     pub fn init_bricks(&mut self) {
-        const BRICK_WIDTH: f32 = 55.;
-        const BRICK_HEIGHT: f32 = 16.;
-
         self.brick_list.clear();
 
         let color_palette = [
@@ -323,10 +416,7 @@ pub fn draw_game_stage(
         gl.viewport(0, 0, win_dims.0 as i32, win_dims.1 as i32);
     }
 
-    unsafe {
-        gl.enable(glow::BLEND);
-        gl.blend_func(glow::ONE, glow::ONE);
-    }
+    GlStateCache::with(gl, |state| state.set_blend(BlendState::ADDITIVE));
 
     let ball_rad = brick_state.ball_list[0].rad;
     let fired_status = brick_state.ball_fired;
@@ -451,9 +541,11 @@ pub fn draw_game_stage(
         .render(&mut brick_state.painter, t);
 
     //draw bricks
+    brick_state.sprites.begin(window_ptr.window().get_bounds());
     for brick in brick_state.brick_list.iter_mut() {
-        brick.render(&mut brick_state.painter, 0.0);
+        brick.render_sprite(&mut brick_state.sprites, &brick_state.brick_texture);
     }
+    brick_state.sprites.end();
 
     //draw ball
     for ball in brick_state.ball_list.iter_mut() {
@@ -461,9 +553,7 @@ pub fn draw_game_stage(
         ball.step(0.1);
     }
 
-    unsafe {
-        gl.disable(glow::BLEND);
-    }
+    GlStateCache::with(gl, |state| state.set_blend(BlendState::DISABLED));
 }
 
 pub fn handle_events(
@@ -16,6 +16,7 @@ use fluffl::{
         },
         Interval,
     },
+    codecs::ico::Ico,
     collections::fixed_stack::FixedStack,
     console::*,
     //playing music files requires more than what the base library provides
@@ -23,6 +24,7 @@ use fluffl::{
     gui::*,
     io::*,
     math::{Vec2, Vec4, WaveKind, FP32, FP64},
+    ogl::{DepthState, GlStateCache},
     prelude::*,
     text_writer::{self, HasTextWriterBuilder, TextWriter, UROOB},
     window::{event_util::*, *},
@@ -106,8 +108,12 @@ pub async fn main() {
     let mixer_device = MixerAudioDeviceContext::new(ctx);
     mixer_device.resume();
 
+    let icon = load_file!("./resources/pokeball.ico")
+        .ok()
+        .and_then(|bytes| Ico::load(std::io::Cursor::new(bytes)).ok());
+
     let app_state = MainState {
-        gui_manager: setup_test_gui(GuiManager::new(gl.clone())),
+        gui_manager: setup_test_gui(GuiManager::new(gl.clone()), icon.as_ref()),
         key_frequency_table: vec![
             (KeyCode::KEY_A, 262.0),
             (KeyCode::KEY_S, 294.0),
@@ -195,6 +201,17 @@ async fn process_events(
                     *wave_type = new_type;
                 }
 
+                // cycles the gui through: no theme -> light -> dark -> no theme
+                if let KeyCode::F1 = code {
+                    match gui_manager.theme().map(|theme| theme.name()) {
+                        None => gui_manager.set_theme(Theme::light()),
+                        Some("light") => gui_manager.set_theme(Theme::dark()),
+                        Some(_) => {
+                            gui_manager.clear_theme();
+                        }
+                    }
+                }
+
                 // if let KeyCode::KEY_E = code {
                 //     let file_pointer_to_music =
                 //         std::fs::File::open("./wasm_bins/resources/taunt.adhoc")
@@ -367,9 +384,8 @@ async fn draw_scene(
     // }
     unsafe {
         gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT | glow::STENCIL_BUFFER_BIT);
-        gl.enable(glow::DEPTH_TEST);
-        gl.depth_func(glow::LEQUAL);
     }
+    GlStateCache::with(&gl, |state| state.set_depth(DepthState::LESS_EQUAL));
 
     let (win_width, win_height) = win_ptr.window().get_bounds_f32();
     let speed_t = ((FP64::from(x) - 0) / 200).clamp(FP64::from(0), FP64::from(1));
@@ -522,6 +538,7 @@ fn time_to_string(elapsed_time_ms: i64) -> String {
 
 fn setup_test_gui(
     mut manager: GuiManager<FlufflState<MainState>>,
+    icon: Option<&Ico>,
 ) -> GuiManager<FlufflState<MainState>> {
     let origin = manager.add_component(GuiComponentKey::default(), Box::new(OriginState::new()));
 
@@ -567,6 +584,18 @@ fn setup_test_gui(
         .with_drag(true)
         .build();
 
+    if let Some(entry) = icon.and_then(|icon| icon.entries.first()) {
+        manager
+            .builder_image()
+            .with_parent(red_frame)
+            .with_bounds([32., 32.])
+            .with_position([40.0, 6.0])
+            .with_ico_entry(entry)
+            .with_scale_mode(ImageScaleMode::Fit)
+            .with_roundness([8.0; 4])
+            .build();
+    }
+
     let orange_frame = manager
         .builder_frame()
         .with_parent(pink_frame)
@@ -1,2 +1,8 @@
 pub mod base64;
 pub mod ico;
+pub mod image;
+pub mod inflate;
+#[cfg(feature = "jpeg")]
+pub mod jpeg;
+pub mod png;
+pub mod qoi;
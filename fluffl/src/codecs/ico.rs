@@ -1,3 +1,4 @@
+use super::image::{DecodedImage, ImageError};
 #[allow(unused_imports)]
 use std::{
     fmt::Debug,
@@ -70,6 +71,55 @@ impl IcoEntry {
             bitmap: vec![],
         })
     }
+
+    /// the width in pixels, the directory stores 256 as 0
+    pub fn pixel_width(&self) -> u32 {
+        resolve_dimension(self.width)
+    }
+
+    /// the height in pixels, the directory stores 256 as 0
+    pub fn pixel_height(&self) -> u32 {
+        resolve_dimension(self.height)
+    }
+
+    /// ## Description
+    /// converts the bitmap to tightly packed RGBA
+    /// ## Comments
+    /// - ico bitmaps are stored as BGR(A), so channels get swizzled here
+    /// - fails for palette entries (below 24bpp) and bitmaps that don't cover the whole entry
+    pub fn decode(&self) -> Result<DecodedImage, ImageError> {
+        let width = self.pixel_width() as usize;
+        let height = self.pixel_height() as usize;
+        let bytes_per_pixel = match self.bits_per_pixel {
+            24 => 3,
+            32 => 4,
+            _ => return Err(ImageError::Unsupported("ico entries below 24bpp")),
+        };
+        if self.bitmap.len() != width * height * bytes_per_pixel {
+            return Err(ImageError::Corrupt(
+                "ico bitmap doesn't match the entry size",
+            ));
+        }
+
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for pixel in self.bitmap.chunks_exact(bytes_per_pixel) {
+            let alpha = if bytes_per_pixel == 4 { pixel[3] } else { 255 };
+            pixels.extend_from_slice(&[pixel[2], pixel[1], pixel[0], alpha]);
+        }
+        Ok(DecodedImage {
+            width: width as u32,
+            height: height as u32,
+            pixels,
+        })
+    }
+}
+
+fn resolve_dimension(dimension: u8) -> u32 {
+    if dimension == 0 {
+        256
+    } else {
+        dimension as u32
+    }
 }
 
 impl Debug for IcoEntry {
@@ -130,8 +180,8 @@ impl Ico {
         for entry in entries.iter_mut() {
             let bitmap_filesize = entry.bitmap_filesize as usize;
             let offset = entry.offset as u64;
-            let width = entry.width as usize;
-            let height = entry.height as usize;
+            let width = entry.pixel_width() as usize;
+            let height = entry.pixel_height() as usize;
 
             // make sure vector is same size as bitmap
             bmp_bytes.resize(bitmap_filesize, 0);
@@ -142,16 +192,41 @@ impl Ico {
             // read the bitmap data into the vector
             input.read_exact(&mut bmp_bytes)?;
 
+            // newer icons (usually the 256px ones) embed a whole PNG instead of a bitmap
+            if bmp_bytes.starts_with(&[0x89, b'P', b'N', b'G']) {
+                let image = super::png::decode(&bmp_bytes)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                for rgba in image.pixels.chunks_exact(4) {
+                    entry
+                        .bitmap
+                        .extend_from_slice(&[rgba[2], rgba[1], rgba[0], rgba[3]]);
+                }
+                entry.bits_per_pixel = 32;
+                continue;
+            }
+
             // according to the spec, the BMP file will have NO file-header (DIB header is still included)
             // Update: turns out this was all pointless, but im keeping here as reference
             // let mut bmp_file = Cursor::new(&bmp_bytes[..]);
             // let bmp_header = DIBHeader::load(&mut bmp_file)?;
             // println!("bmp header = {:?}", bmp_header);
 
-            let padding = 0;
+            // palette based entries (below 24bpp) aren't supported, their bitmap stays empty
             let bytes_per_pixel = entry.bits_per_pixel as usize / 8;
-            let image_row_bytes = (width * bytes_per_pixel) + padding;
-            let pixel_info = &bmp_bytes[40..];
+            if bytes_per_pixel < 3 {
+                continue;
+            }
+            // rows are padded to a multiple of 4 bytes
+            let image_row_bytes = (width * bytes_per_pixel + 3) & !3;
+            let pixel_info = match bmp_bytes.get(40..) {
+                Some(pixel_info) if pixel_info.len() >= image_row_bytes * height => pixel_info,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "ico bitmap is truncated",
+                    ))
+                }
+            };
 
             // bitmaps are stored flipped for some dumb reason
             // so I have to unflip them
@@ -171,7 +246,8 @@ impl Ico {
     pub fn dump_ppm<P: AsRef<std::path::Path>>(&self, path: P) -> io::Result<()> {
         let mut ppm = std::fs::File::create(path)?;
         writeln!(ppm, "P3")?;
-        writeln!(ppm, "{} {}", self.entries[0].width, self.entries[0].height)?;
+        let entry = &self.entries[0];
+        writeln!(ppm, "{} {}", entry.pixel_width(), entry.pixel_height())?;
         writeln!(ppm, "{max_color_val}", max_color_val = 255)?;
         for pixel in self.entries[0].bitmap.chunks_exact(4) {
             writeln!(ppm, " {} {} {} ", pixel[2], pixel[1], pixel[0]).unwrap();
@@ -206,3 +282,37 @@ pub fn parse_ico() {
     let ico = Ico::load(ico_file).unwrap();
     ico.dump_ppm("../dump/complex.ppm").unwrap();
 }
+
+#[test]
+fn ico_entries_decode_to_rgba() {
+    let entry = |width: u8, bits_per_pixel: u16, bitmap: Vec<u8>| IcoEntry {
+        width,
+        height: width,
+        colors: 0,
+        reserved: 0,
+        planes: 1,
+        bits_per_pixel,
+        bitmap_filesize: 0,
+        offset: 0,
+        bitmap,
+    };
+
+    let image = entry(1, 24, vec![1, 2, 3]).decode().unwrap();
+    assert_eq!(image.pixels, vec![3, 2, 1, 255]);
+
+    // 0 means 256, so a 1 pixel bitmap is far too short
+    let large = entry(0, 32, vec![0; 4]);
+    assert_eq!((large.pixel_width(), large.pixel_height()), (256, 256));
+    assert!(large.decode().is_err());
+    let image = entry(0, 32, [1, 2, 3, 4].repeat(256 * 256))
+        .decode()
+        .unwrap();
+    assert_eq!((image.width, image.height), (256, 256));
+    assert_eq!(image.pixels[..4], [3, 2, 1, 4]);
+
+    assert!(entry(2, 32, vec![0; 4]).decode().is_err());
+    assert_eq!(
+        entry(1, 4, vec![0]).decode(),
+        Err(ImageError::Unsupported("ico entries below 24bpp"))
+    );
+}
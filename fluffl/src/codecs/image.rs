@@ -0,0 +1,106 @@
+//! The common output of the image decoders, and `decode(..)` which picks the decoder by looking at the
+//! first few bytes.
+use std::fmt;
+
+/// # Description
+/// An image decoded to tightly packed RGBA8, rows going top to bottom
+/// # Comments
+/// - that's the layout `HasTextureBuilder::with_image(..)` uploads
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl DecodedImage {
+    /// `(0,0)` is the top-left pixel
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let offset = (y as usize * self.width as usize + x as usize) * 4;
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&self.pixels[offset..offset + 4]);
+        pixel
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ImageFormat {
+    Png,
+    Qoi,
+    /// only decodable with the `jpeg` feature
+    Jpeg,
+}
+
+impl ImageFormat {
+    /// guesses the format from the magic bytes at the start of the file
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&[0x89, b'P', b'N', b'G']) {
+            Some(Self::Png)
+        } else if bytes.starts_with(b"qoif") {
+            Some(Self::Qoi)
+        } else if bytes.starts_with(&[0xff, 0xd8]) {
+            Some(Self::Jpeg)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ImageError {
+    /// the data ended before the image did
+    Truncated,
+    Corrupt(&'static str),
+    /// valid, but uses something these decoders don't implement
+    Unsupported(&'static str),
+    UnknownFormat,
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "image data is truncated"),
+            Self::Corrupt(reason) => write!(f, "image data is corrupt: {}", reason),
+            Self::Unsupported(what) => write!(f, "unsupported image feature: {}", what),
+            Self::UnknownFormat => write!(f, "unknown image format"),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+impl From<ImageError> for crate::Error {
+    fn from(err: ImageError) -> Self {
+        Self::GenericError(err.to_string())
+    }
+}
+
+/// ## Description
+/// decodes a PNG, QOI or (with the `jpeg` feature) JPEG file
+pub fn decode(bytes: &[u8]) -> Result<DecodedImage, ImageError> {
+    match ImageFormat::sniff(bytes) {
+        Some(ImageFormat::Png) => super::png::decode(bytes),
+        Some(ImageFormat::Qoi) => super::qoi::decode(bytes),
+        #[cfg(feature = "jpeg")]
+        Some(ImageFormat::Jpeg) => super::jpeg::decode(bytes),
+        #[cfg(not(feature = "jpeg"))]
+        Some(ImageFormat::Jpeg) => Err(ImageError::Unsupported(
+            "JPEG decoding needs the `jpeg` feature",
+        )),
+        None => Err(ImageError::UnknownFormat),
+    }
+}
+
+#[test]
+fn image_formats_get_sniffed() {
+    assert_eq!(
+        ImageFormat::sniff(&super::png::encode_rgba(1, 1, &[0; 4])),
+        Some(ImageFormat::Png)
+    );
+    assert_eq!(ImageFormat::sniff(b"qoif...."), Some(ImageFormat::Qoi));
+    assert_eq!(
+        ImageFormat::sniff(&[0xff, 0xd8, 0xff]),
+        Some(ImageFormat::Jpeg)
+    );
+    assert_eq!(decode(b"GIF89a"), Err(ImageError::UnknownFormat));
+}
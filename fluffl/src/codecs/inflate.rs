@@ -0,0 +1,293 @@
+//! A deflate/zlib decompressor (RFC 1950/1951), handles stored, fixed and dynamic huffman blocks.
+//! Huffman codes are decoded one bit at a time from the canonical code lengths, which is slow-ish but
+//! short and plenty for loading textures.
+use super::image::ImageError;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// the order code length code lengths are stored in
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+const MAX_BITS: usize = 15;
+
+/// ## Description
+/// decompresses a zlib stream, checking its header and adler32 checksum
+pub fn zlib_decompress(bytes: &[u8]) -> Result<Vec<u8>, ImageError> {
+    if bytes.len() < 6 {
+        return Err(ImageError::Truncated);
+    }
+    let (cmf, flg) = (bytes[0], bytes[1]);
+    if cmf & 0x0f != 8 || !(((cmf as u16) << 8) | flg as u16).is_multiple_of(31) {
+        return Err(ImageError::Corrupt("bad zlib header"));
+    }
+    if flg & 0x20 != 0 {
+        return Err(ImageError::Unsupported("zlib preset dictionaries"));
+    }
+
+    let mut reader = BitReader::new(&bytes[2..]);
+    let out = inflate_stream(&mut reader)?;
+    let checksum_at = 2 + reader.byte_aligned_position();
+    let checksum = bytes
+        .get(checksum_at..checksum_at + 4)
+        .ok_or(ImageError::Truncated)?;
+    if u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]])
+        != super::png::adler32(&out)
+    {
+        return Err(ImageError::Corrupt("zlib checksum mismatch"));
+    }
+    Ok(out)
+}
+
+/// decompresses raw deflate data
+pub fn inflate(bytes: &[u8]) -> Result<Vec<u8>, ImageError> {
+    inflate_stream(&mut BitReader::new(bytes))
+}
+
+fn inflate_stream(reader: &mut BitReader) -> Result<Vec<u8>, ImageError> {
+    let mut out = Vec::new();
+    loop {
+        let is_final = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align_to_byte();
+                let len = reader.bits(16)? as u16;
+                let nlen = reader.bits(16)? as u16;
+                if len != !nlen {
+                    return Err(ImageError::Corrupt("stored block length mismatch"));
+                }
+                for _ in 0..len {
+                    out.push(reader.bits(8)? as u8);
+                }
+            }
+            1 => {
+                let (literals, distances) = fixed_tables();
+                inflate_block(reader, &mut out, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_tables(reader)?;
+                inflate_block(reader, &mut out, &literals, &distances)?;
+            }
+            _ => return Err(ImageError::Corrupt("invalid deflate block type")),
+        }
+        if is_final {
+            return Ok(out);
+        }
+    }
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<(), ImageError> {
+    loop {
+        let symbol = literals.decode(reader)?;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let index = symbol as usize - 257;
+                let len =
+                    LENGTH_BASE[index] as usize + reader.bits(LENGTH_EXTRA[index] as u32)? as usize;
+                let dist_symbol = distances.decode(reader)? as usize;
+                if dist_symbol >= DIST_BASE.len() {
+                    return Err(ImageError::Corrupt("invalid deflate distance"));
+                }
+                let dist = DIST_BASE[dist_symbol] as usize
+                    + reader.bits(DIST_EXTRA[dist_symbol] as u32)? as usize;
+                if dist > out.len() {
+                    return Err(ImageError::Corrupt("deflate distance too far back"));
+                }
+                // copies byte by byte on purpose, matches may overlap what they produce
+                let start = out.len() - dist;
+                for i in 0..len {
+                    out.push(out[start + i]);
+                }
+            }
+            _ => return Err(ImageError::Corrupt("invalid deflate symbol")),
+        }
+    }
+}
+
+fn fixed_tables() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_tables(reader: &mut BitReader) -> Result<(Huffman, Huffman), ImageError> {
+    let num_literals = reader.bits(5)? as usize + 257;
+    let num_distances = reader.bits(5)? as usize + 1;
+    let num_code_lengths = reader.bits(4)? as usize + 4;
+
+    let mut code_length_lengths = [0u8; 19];
+    for &index in CODE_LENGTH_ORDER.iter().take(num_code_lengths) {
+        code_length_lengths[index] = reader.bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_length_lengths);
+
+    let mut lengths = Vec::with_capacity(num_literals + num_distances);
+    while lengths.len() < num_literals + num_distances {
+        let (value, repeat) = match code_lengths.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths.last().ok_or(ImageError::Corrupt(
+                    "deflate repeat without a previous length",
+                ))?;
+                (previous, 3 + reader.bits(2)?)
+            }
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() != num_literals + num_distances {
+        return Err(ImageError::Corrupt("deflate code lengths overflow"));
+    }
+    Ok((
+        Huffman::new(&lengths[..num_literals]),
+        Huffman::new(&lengths[num_literals..]),
+    ))
+}
+
+/// A canonical huffman code, stored as the number of codes per length and the symbols sorted by code
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; offsets[MAX_BITS + 1] as usize];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, ImageError> {
+        // `first` is the first code of the current length, `index` where its symbols start
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..=MAX_BITS {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(ImageError::Corrupt("invalid huffman code"))
+    }
+}
+
+/// reads bits starting at the least significant bit of each byte
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    acc: u32,
+    num_bits: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            position: 0,
+            acc: 0,
+            num_bits: 0,
+        }
+    }
+
+    fn bits(&mut self, count: u32) -> Result<u32, ImageError> {
+        while self.num_bits < count {
+            let byte = *self.bytes.get(self.position).ok_or(ImageError::Truncated)?;
+            self.acc |= (byte as u32) << self.num_bits;
+            self.position += 1;
+            self.num_bits += 8;
+        }
+        let value = self.acc & ((1u64 << count) - 1) as u32;
+        self.acc >>= count;
+        self.num_bits -= count;
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        self.acc = 0;
+        self.num_bits = 0;
+    }
+
+    /// where the next whole byte starts, partially read bytes count as consumed
+    fn byte_aligned_position(&self) -> usize {
+        self.position - (self.num_bits / 8) as usize
+    }
+}
+
+#[cfg(test)]
+fn from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn inflate_handles_every_block_type() {
+    // produced by zlib, the first one uses a dynamic huffman block
+    let dynamic = from_hex(
+        "78da258a810900300cc26e4df4ff1bd6762028314a9189648b3f0aa9ddc7e355c74c4f299107bba516f4",
+    );
+    assert_eq!(
+        zlib_decompress(&dynamic).unwrap(),
+        b"bbadabaababacaabaaabacaadaacdbdbaabbcaabadbbbdabcdbaaabdacba"
+    );
+    let stored = from_hex("7801010700f8ff73746f726564210bef02b3");
+    assert_eq!(zlib_decompress(&stored).unwrap(), b"stored!");
+
+    // the encoder in `png` writes fixed huffman blocks with overlapping matches
+    let text = b"fluffl fluffl fluffl flufffffffffffffffffl";
+    let fixed = super::png::zlib_compress(text);
+    assert_eq!(zlib_decompress(&fixed).unwrap(), text);
+    assert_eq!(inflate(&fixed[2..fixed.len() - 4]).unwrap(), text);
+
+    let mut corrupt = dynamic.clone();
+    let last = corrupt.len() - 1;
+    corrupt[last] ^= 0xff;
+    assert_eq!(
+        zlib_decompress(&corrupt),
+        Err(ImageError::Corrupt("zlib checksum mismatch"))
+    );
+    assert_eq!(zlib_decompress(&dynamic[..20]), Err(ImageError::Truncated));
+}
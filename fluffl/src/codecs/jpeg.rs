@@ -0,0 +1,651 @@
+//! A baseline JPEG decoder (sequential, huffman coded, 8-bit samples), enabled by the `jpeg` feature.
+//! Progressive and arithmetic coded files aren't supported, and subsampled chroma gets upsampled by
+//! repeating samples, which is cheaper than filtering and fine for textures.
+use super::image::{DecodedImage, ImageError};
+
+/// maps the position of a coefficient in the zig-zag sequence to its index in the 8x8 block
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/// ## Description
+/// decodes a baseline JPEG to RGBA8
+/// ## Comments
+/// - 3-component images are assumed to be YCbCr (JFIF), CMYK isn't supported
+/// - entropy coded data that ends early decodes as if it was padded with zeros, like most decoders do
+pub fn decode(bytes: &[u8]) -> Result<DecodedImage, ImageError> {
+    if !bytes.starts_with(&[0xff, 0xd8]) {
+        return Err(ImageError::Corrupt("missing JPEG start of image"));
+    }
+
+    let mut decoder = Decoder::default();
+    let mut pos = 2;
+    loop {
+        let marker = match bytes.get(pos..pos + 2).ok_or(ImageError::Truncated)? {
+            [0xff, marker] => *marker,
+            _ => return Err(ImageError::Corrupt("expected a JPEG marker")),
+        };
+        pos += 2;
+        match marker {
+            // fill bytes before a marker
+            0xff => {
+                pos -= 1;
+                continue;
+            }
+            0x01 | 0xd0..=0xd8 => continue,
+            0xd9 => break,
+            _ => (),
+        }
+
+        let len = bytes.get(pos..pos + 2).ok_or(ImageError::Truncated)?;
+        let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+        let segment = bytes
+            .get(pos + 2..pos + len.max(2))
+            .ok_or(ImageError::Truncated)?;
+        pos += len;
+        match marker {
+            0xdb => decoder.read_quant_tables(segment)?,
+            0xc4 => decoder.read_huffman_tables(segment)?,
+            0xdd => decoder.restart_interval = read_u16(segment, 0)? as usize,
+            0xc0 | 0xc1 => decoder.read_frame(segment)?,
+            0xc2..=0xcf => {
+                return Err(ImageError::Unsupported(
+                    "progressive, lossless or arithmetic coded JPEGs",
+                ))
+            }
+            0xda => pos = decoder.decode_scan(segment, bytes, pos)?,
+            // APPn, comments and the like
+            _ => (),
+        }
+    }
+    decoder.into_image()
+}
+
+fn read_u16(bytes: &[u8], pos: usize) -> Result<u16, ImageError> {
+    let word = bytes.get(pos..pos + 2).ok_or(ImageError::Truncated)?;
+    Ok(u16::from_be_bytes([word[0], word[1]]))
+}
+
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    quant_table: usize,
+    dc_table: usize,
+    ac_table: usize,
+    dc_prediction: i32,
+    blocks_w: usize,
+    blocks_h: usize,
+    /// decoded samples, `blocks_w*8` wide
+    plane: Vec<u8>,
+}
+
+struct Frame {
+    width: usize,
+    height: usize,
+    h_max: usize,
+    v_max: usize,
+    mcus_x: usize,
+    mcus_y: usize,
+    components: Vec<Component>,
+}
+
+struct Decoder {
+    /// in zig-zag order, like they are stored
+    quant_tables: [[u16; 64]; 4],
+    dc_tables: [Option<HuffmanTable>; 4],
+    ac_tables: [Option<HuffmanTable>; 4],
+    restart_interval: usize,
+    frame: Option<Frame>,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self {
+            quant_tables: [[1; 64]; 4],
+            dc_tables: Default::default(),
+            ac_tables: Default::default(),
+            restart_interval: 0,
+            frame: None,
+        }
+    }
+}
+
+impl Decoder {
+    fn read_quant_tables(&mut self, mut segment: &[u8]) -> Result<(), ImageError> {
+        while let Some(&info) = segment.first() {
+            let (is_16_bit, id) = (info >> 4 == 1, (info & 3) as usize);
+            let size = if is_16_bit { 128 } else { 64 };
+            let values = segment.get(1..1 + size).ok_or(ImageError::Truncated)?;
+            for (k, entry) in self.quant_tables[id].iter_mut().enumerate() {
+                *entry = if is_16_bit {
+                    u16::from_be_bytes([values[k * 2], values[k * 2 + 1]])
+                } else {
+                    values[k] as u16
+                };
+            }
+            segment = &segment[1 + size..];
+        }
+        Ok(())
+    }
+
+    fn read_huffman_tables(&mut self, mut segment: &[u8]) -> Result<(), ImageError> {
+        while let Some(&info) = segment.first() {
+            let counts = segment.get(1..17).ok_or(ImageError::Truncated)?;
+            let total: usize = counts.iter().map(|&count| count as usize).sum();
+            let symbols = segment.get(17..17 + total).ok_or(ImageError::Truncated)?;
+            let table = HuffmanTable::new(counts, symbols);
+            let id = (info & 3) as usize;
+            if info >> 4 == 0 {
+                self.dc_tables[id] = Some(table);
+            } else {
+                self.ac_tables[id] = Some(table);
+            }
+            segment = &segment[17 + total..];
+        }
+        Ok(())
+    }
+
+    fn read_frame(&mut self, segment: &[u8]) -> Result<(), ImageError> {
+        if segment.first() != Some(&8) {
+            return Err(ImageError::Unsupported(
+                "JPEG sample precision other than 8",
+            ));
+        }
+        let height = read_u16(segment, 1)? as usize;
+        let width = read_u16(segment, 3)? as usize;
+        let count = *segment.get(5).ok_or(ImageError::Truncated)? as usize;
+        if !matches!(count, 1 | 3) {
+            return Err(ImageError::Unsupported(
+                "JPEGs that aren't grayscale or YCbCr",
+            ));
+        }
+        if width == 0 || height == 0 {
+            return Err(ImageError::Unsupported(
+                "JPEGs with the height set by a DNL marker",
+            ));
+        }
+
+        let mut components = Vec::with_capacity(count);
+        for params in segment
+            .get(6..6 + count * 3)
+            .ok_or(ImageError::Truncated)?
+            .chunks_exact(3)
+        {
+            let (h, v) = ((params[1] >> 4) as usize, (params[1] & 15) as usize);
+            if !(1..=4).contains(&h) || !(1..=4).contains(&v) {
+                return Err(ImageError::Corrupt("invalid JPEG sampling factors"));
+            }
+            components.push(Component {
+                id: params[0],
+                h,
+                v,
+                quant_table: (params[2] & 3) as usize,
+                dc_table: 0,
+                ac_table: 0,
+                dc_prediction: 0,
+                blocks_w: 0,
+                blocks_h: 0,
+                plane: Vec::new(),
+            });
+        }
+
+        let h_max = components.iter().map(|c| c.h).max().unwrap_or(1);
+        let v_max = components.iter().map(|c| c.v).max().unwrap_or(1);
+        let mcus_x = width.div_ceil(8 * h_max);
+        let mcus_y = height.div_ceil(8 * v_max);
+        for component in &mut components {
+            component.blocks_w = mcus_x * component.h;
+            component.blocks_h = mcus_y * component.v;
+            component.plane = vec![0; component.blocks_w * component.blocks_h * 64];
+        }
+        self.frame = Some(Frame {
+            width,
+            height,
+            h_max,
+            v_max,
+            mcus_x,
+            mcus_y,
+            components,
+        });
+        Ok(())
+    }
+
+    /// ## Returns
+    /// the position of the marker following the entropy coded data
+    fn decode_scan(
+        &mut self,
+        segment: &[u8],
+        bytes: &[u8],
+        data_start: usize,
+    ) -> Result<usize, ImageError> {
+        let frame = self
+            .frame
+            .as_mut()
+            .ok_or(ImageError::Corrupt("JPEG scan before the frame header"))?;
+        let count = *segment.first().ok_or(ImageError::Truncated)? as usize;
+        let mut scan = Vec::with_capacity(count);
+        for params in segment
+            .get(1..1 + count * 2)
+            .ok_or(ImageError::Truncated)?
+            .chunks_exact(2)
+        {
+            let index = frame
+                .components
+                .iter()
+                .position(|c| c.id == params[0])
+                .ok_or(ImageError::Corrupt(
+                    "JPEG scan references an unknown component",
+                ))?;
+            let component = &mut frame.components[index];
+            component.dc_table = (params[1] >> 4 & 3) as usize;
+            component.ac_table = (params[1] & 3) as usize;
+            component.dc_prediction = 0;
+            scan.push(index);
+        }
+
+        let idct = idct_table();
+        let mut reader = EntropyReader::new(bytes, data_start);
+        // a single component scan isn't interleaved, its blocks only cover the component itself
+        let (units_x, units) = if let [index] = scan[..] {
+            let component = &frame.components[index];
+            let blocks_x = (frame.width * component.h)
+                .div_ceil(frame.h_max)
+                .div_ceil(8);
+            let blocks_y = (frame.height * component.v)
+                .div_ceil(frame.v_max)
+                .div_ceil(8);
+            (blocks_x, blocks_x * blocks_y)
+        } else {
+            (frame.mcus_x, frame.mcus_x * frame.mcus_y)
+        };
+
+        for unit in 0..units {
+            if self.restart_interval > 0 && unit > 0 && unit % self.restart_interval == 0 {
+                reader.restart();
+                for &index in &scan {
+                    frame.components[index].dc_prediction = 0;
+                }
+            }
+            let (unit_x, unit_y) = (unit % units_x, unit / units_x);
+            for &index in &scan {
+                let component = &mut frame.components[index];
+                let (h, v) = if scan.len() == 1 {
+                    (1, 1)
+                } else {
+                    (component.h, component.v)
+                };
+                for block in 0..h * v {
+                    let block_x = unit_x * h + block % h;
+                    let block_y = unit_y * v + block / h;
+                    let dc_table = self.dc_tables[component.dc_table]
+                        .as_ref()
+                        .ok_or(ImageError::Corrupt("missing JPEG huffman table"))?;
+                    let ac_table = self.ac_tables[component.ac_table]
+                        .as_ref()
+                        .ok_or(ImageError::Corrupt("missing JPEG huffman table"))?;
+                    let coefficients = decode_block(
+                        &mut reader,
+                        dc_table,
+                        ac_table,
+                        &self.quant_tables[component.quant_table],
+                        &mut component.dc_prediction,
+                    )?;
+                    let stride = component.blocks_w * 8;
+                    let origin = block_y * 8 * stride + block_x * 8;
+                    inverse_dct(&coefficients, &idct, &mut component.plane[origin..], stride);
+                }
+            }
+        }
+        Ok(reader.next_marker())
+    }
+
+    fn into_image(self) -> Result<DecodedImage, ImageError> {
+        let frame = self
+            .frame
+            .ok_or(ImageError::Corrupt("JPEG without a frame header"))?;
+        let (width, height) = (frame.width, frame.height);
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        let sample = |component: &Component, x: usize, y: usize| {
+            let (x, y) = (x * component.h / frame.h_max, y * component.v / frame.v_max);
+            component.plane[y * component.blocks_w * 8 + x] as f32
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                match &frame.components[..] {
+                    [luma, cb, cr] => {
+                        let luma = sample(luma, x, y);
+                        let cb = sample(cb, x, y) - 128.0;
+                        let cr = sample(cr, x, y) - 128.0;
+                        let to_u8 = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+                        pixels.extend_from_slice(&[
+                            to_u8(luma + 1.402 * cr),
+                            to_u8(luma - 0.344136 * cb - 0.714136 * cr),
+                            to_u8(luma + 1.772 * cb),
+                            255,
+                        ]);
+                    }
+                    [gray, ..] => {
+                        let gray = sample(gray, x, y) as u8;
+                        pixels.extend_from_slice(&[gray, gray, gray, 255]);
+                    }
+                    [] => unreachable!("frames always have components"),
+                }
+            }
+        }
+        Ok(DecodedImage {
+            width: width as u32,
+            height: height as u32,
+            pixels,
+        })
+    }
+}
+
+/// ## Returns
+/// the dequantized coefficients of one 8x8 block in natural (row major) order
+fn decode_block(
+    reader: &mut EntropyReader,
+    dc_table: &HuffmanTable,
+    ac_table: &HuffmanTable,
+    quant_table: &[u16; 64],
+    dc_prediction: &mut i32,
+) -> Result<[f32; 64], ImageError> {
+    let mut coefficients = [0.0; 64];
+    let size = dc_table.decode(reader)?;
+    if size > 11 {
+        return Err(ImageError::Corrupt("JPEG DC difference wider than 11 bits"));
+    }
+    // corrupt data can keep adding up differences, that's garbage but mustn't overflow
+    *dc_prediction = dc_prediction.wrapping_add(reader.receive_extend(size));
+    coefficients[0] = dc_prediction.wrapping_mul(quant_table[0] as i32) as f32;
+
+    let mut k = 1;
+    while k < 64 {
+        let run_size = ac_table.decode(reader)?;
+        let (run, size) = ((run_size >> 4) as usize, run_size & 15);
+        if size == 0 {
+            if run != 15 {
+                // end of block
+                break;
+            }
+            k += 16;
+            continue;
+        }
+        if size > 10 {
+            return Err(ImageError::Corrupt(
+                "JPEG AC coefficient wider than 10 bits",
+            ));
+        }
+        k += run;
+        if k >= 64 {
+            return Err(ImageError::Corrupt(
+                "JPEG coefficient run past the end of the block",
+            ));
+        }
+        coefficients[ZIGZAG[k]] = (reader.receive_extend(size) * quant_table[k] as i32) as f32;
+        k += 1;
+    }
+    Ok(coefficients)
+}
+
+/// `table[x][u]` is `C(u)/2 * cos((2x+1)*u*PI/16)`
+fn idct_table() -> [[f32; 8]; 8] {
+    let mut table = [[0.0; 8]; 8];
+    for (x, row) in table.iter_mut().enumerate() {
+        for (u, entry) in row.iter_mut().enumerate() {
+            let scale = if u == 0 {
+                std::f32::consts::FRAC_1_SQRT_2
+            } else {
+                1.0
+            };
+            let angle = (2 * x + 1) as f32 * u as f32 * std::f32::consts::PI / 16.0;
+            *entry = scale * 0.5 * angle.cos();
+        }
+    }
+    table
+}
+
+/// a separable float IDCT, writes level shifted samples into `out`
+fn inverse_dct(coefficients: &[f32; 64], table: &[[f32; 8]; 8], out: &mut [u8], stride: usize) {
+    // transform the rows first, then the columns
+    let mut rows = [0.0f32; 64];
+    for v in 0..8 {
+        for x in 0..8 {
+            rows[v * 8 + x] = (0..8).map(|u| table[x][u] * coefficients[v * 8 + u]).sum();
+        }
+    }
+    for y in 0..8 {
+        for x in 0..8 {
+            let value: f32 = (0..8).map(|v| table[y][v] * rows[v * 8 + x]).sum();
+            out[y * stride + x] = (value + 128.0).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+/// A canonical huffman table, as stored in DHT segments
+struct HuffmanTable {
+    counts: [u8; 16],
+    symbols: Vec<u8>,
+}
+
+impl HuffmanTable {
+    fn new(counts: &[u8], symbols: &[u8]) -> Self {
+        let mut table = Self {
+            counts: [0; 16],
+            symbols: symbols.to_vec(),
+        };
+        table.counts.copy_from_slice(counts);
+        table
+    }
+
+    fn decode(&self, reader: &mut EntropyReader) -> Result<u8, ImageError> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts {
+            code |= reader.bit() as i32;
+            let count = count as i32;
+            if code - first < count {
+                return self
+                    .symbols
+                    .get((index + code - first) as usize)
+                    .copied()
+                    .ok_or(ImageError::Corrupt("invalid JPEG huffman table"));
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(ImageError::Corrupt("invalid JPEG huffman code"))
+    }
+}
+
+/// reads entropy coded data most significant bit first, undoing the `0xff 0x00` byte stuffing
+struct EntropyReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    byte: u8,
+    bits_left: u32,
+}
+
+impl<'a> EntropyReader<'a> {
+    fn new(bytes: &'a [u8], pos: usize) -> Self {
+        Self {
+            bytes,
+            pos,
+            byte: 0,
+            bits_left: 0,
+        }
+    }
+
+    fn bit(&mut self) -> u32 {
+        if self.bits_left == 0 {
+            self.byte = match self.bytes.get(self.pos..) {
+                Some([0xff, 0x00, ..]) => {
+                    self.pos += 2;
+                    0xff
+                }
+                // a marker (or the end of the data), stay put and feed zeros
+                Some([0xff, ..]) | None | Some([]) => 0,
+                Some([byte, ..]) => {
+                    self.pos += 1;
+                    *byte
+                }
+            };
+            self.bits_left = 8;
+        }
+        self.bits_left -= 1;
+        ((self.byte >> self.bits_left) & 1) as u32
+    }
+
+    /// reads a `size` bit value and sign-extends it the way JPEG stores coefficients
+    fn receive_extend(&mut self, size: u8) -> i32 {
+        let value = (0..size).fold(0i32, |value, _| value << 1 | self.bit() as i32);
+        if size > 0 && value < 1 << (size - 1) {
+            value - (1 << size) + 1
+        } else {
+            value
+        }
+    }
+
+    /// drops the partial byte and skips the `RSTn` marker
+    fn restart(&mut self) {
+        self.bits_left = 0;
+        if let Some([0xff, 0xd0..=0xd7, ..]) = self.bytes.get(self.pos..) {
+            self.pos += 2;
+        }
+    }
+
+    /// where the marker ending the scan starts
+    fn next_marker(&self) -> usize {
+        let mut pos = self.pos;
+        while let Some(&[first, second]) = self.bytes.get(pos..pos + 2) {
+            if first == 0xff && second != 0 && !(0xd0..=0xd7).contains(&second) {
+                return pos;
+            }
+            pos += 1;
+        }
+        self.bytes.len()
+    }
+}
+
+/// writes a 16x16 4:2:0 JPEG, with made up huffman tables that are easy to emit by hand
+#[cfg(test)]
+fn build_test_jpeg() -> Vec<u8> {
+    let segment = |jpeg: &mut Vec<u8>, marker: u8, data: &[u8]| {
+        jpeg.extend_from_slice(&[0xff, marker]);
+        jpeg.extend_from_slice(&(data.len() as u16 + 2).to_be_bytes());
+        jpeg.extend_from_slice(data);
+    };
+    let mut jpeg = vec![0xff, 0xd8];
+
+    // every step is 8, except the first horizontal frequency
+    let mut quant = vec![0];
+    quant.extend_from_slice(&[8; 64]);
+    quant[2] = 24;
+    segment(&mut jpeg, 0xdb, &quant);
+
+    segment(
+        &mut jpeg,
+        0xc0,
+        &[8, 0, 16, 0, 16, 3, 1, 0x22, 0, 2, 0x11, 0, 3, 0x11, 0],
+    );
+
+    // DC: the 4-bit code of a category is the category itself
+    let mut dc = vec![0x00];
+    let mut counts = [0u8; 16];
+    counts[3] = 12;
+    dc.extend_from_slice(&counts);
+    dc.extend(0..12u8);
+    segment(&mut jpeg, 0xc4, &dc);
+    // AC: '0' ends the block, '10' is a size 1 coefficient right after the previous one
+    let mut ac = vec![0x10, 1, 1];
+    ac.extend_from_slice(&[0; 14]);
+    ac.extend_from_slice(&[0x00, 0x01]);
+    segment(&mut jpeg, 0xc4, &ac);
+
+    segment(&mut jpeg, 0xda, &[3, 1, 0x00, 2, 0x00, 3, 0x00, 0, 63, 0]);
+
+    // (category, value bits) of the DC differences of the four luma blocks, then Cb and Cr
+    let blocks = [(6, 40), (7, 47), (6, 40), (0, 0), (5, 20), (5, 11)];
+    let (mut acc, mut num_bits) = (0u64, 0);
+    let mut data = Vec::new();
+    let mut write = |value: u32, len: u32| {
+        acc = acc << len | value as u64;
+        num_bits += len;
+        while num_bits >= 8 {
+            num_bits -= 8;
+            let byte = (acc >> num_bits) as u8;
+            data.push(byte);
+            if byte == 0xff {
+                data.push(0);
+            }
+        }
+    };
+    for (index, (category, bits)) in blocks.into_iter().enumerate() {
+        write(category, 4);
+        write(bits, category);
+        if index == 3 {
+            // the last luma block gets a +1 at the first horizontal frequency
+            write(0b10, 2);
+            write(1, 1);
+        }
+        write(0, 1);
+    }
+    // pad with ones
+    write(0x7f, 7);
+    jpeg.extend_from_slice(&data);
+    jpeg.extend_from_slice(&[0xff, 0xd9]);
+    jpeg
+}
+
+#[test]
+fn jpeg_decodes_subsampled_color() {
+    let image = decode(&build_test_jpeg()).unwrap();
+    assert_eq!((image.width, image.height), (16, 16));
+
+    let close = |pixel: [u8; 4], expected: [u8; 3]| {
+        pixel[..3]
+            .iter()
+            .zip(expected)
+            .all(|(&a, b)| a.abs_diff(b) <= 1)
+    };
+    // Y=168, Cb=148, Cr=108 then Y=88 and Y=128 with the same chroma
+    assert!(close(image.pixel(0, 0), [140, 175, 203]));
+    assert!(close(image.pixel(15 - 8, 7), [140, 175, 203]));
+    assert!(close(image.pixel(8, 0), [60, 95, 123]));
+    assert!(close(image.pixel(3, 12), [100, 135, 163]));
+    // a single horizontal cosine on top of Y=128: 24/(4*sqrt(2))*cos((2x+1)*PI/16)
+    assert!(close(image.pixel(8, 8), [104, 139, 167]));
+    assert!(close(image.pixel(15, 15), [96, 131, 159]));
+    assert_eq!(image.pixel(11, 9)[..3], image.pixel(11, 14)[..3]);
+
+    assert!(matches!(
+        decode(&[0xff, 0xd8, 0xff, 0xc2, 0, 2]),
+        Err(ImageError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn jpeg_rejects_oversized_coefficients() {
+    let jpeg = build_test_jpeg();
+    let find = |segment: &[u8]| {
+        let start = jpeg
+            .windows(segment.len())
+            .position(|w| w == segment)
+            .unwrap();
+        // the symbols follow the 16 counts
+        start + segment.len() + 16
+    };
+
+    // category 6, the first DC difference, turned into a 255 bit one
+    let mut bad_dc = jpeg.clone();
+    bad_dc[find(&[0xff, 0xc4, 0, 31, 0x00]) + 6] = 0xff;
+    assert!(matches!(decode(&bad_dc), Err(ImageError::Corrupt(_))));
+
+    // the size 1 AC coefficient turned into a 15 bit one
+    let mut bad_ac = jpeg.clone();
+    bad_ac[find(&[0xff, 0xc4, 0, 21, 0x10]) + 1] = 0x0f;
+    assert!(matches!(decode(&bad_ac), Err(ImageError::Corrupt(_))));
+}
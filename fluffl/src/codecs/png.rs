@@ -0,0 +1,684 @@
+//! A small PNG encoder and decoder.
+//! Only 8-bit RGBA images are written. Pixel data gets compressed with fixed-huffman deflate and a greedy
+//! LZ77 matcher, which is nowhere near as good as zlib but does well on screenshots (big flat areas,
+//! repeated rows).
+//! Every color type, bit depth and interlacing get read, always ending up as RGBA8.
+use super::{
+    image::{DecodedImage, ImageError},
+    inflate::zlib_decompress,
+};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+/// ## Description
+/// encodes tightly packed RGBA8 pixels, rows going top to bottom
+/// ## Comments
+/// - panics if `rgba` doesn't hold exactly `width*height*4` bytes
+pub fn encode_rgba(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let row_len = width as usize * 4;
+    assert_eq!(
+        rgba.len(),
+        row_len * height as usize,
+        "pixel data doesn't match the dimensions"
+    );
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    // bit depth 8, color type 6 (RGBA), default compression/filtering, no interlacing
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    // every scanline starts with its filter type, which is always 'none' here
+    let mut scanlines = Vec::with_capacity(rgba.len() + height as usize);
+    for row in rgba.chunks_exact(row_len.max(1)).take(height as usize) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+
+    let mut png = Vec::new();
+    png.extend_from_slice(&SIGNATURE);
+    write_chunk(&mut png, b"IHDR", &ihdr);
+    write_chunk(&mut png, b"IDAT", &zlib_compress(&scanlines));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let crc_start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[crc_start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+pub fn adler32(bytes: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the most bytes that can be summed before `b` could overflow
+    for chunk in bytes.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+/// wraps a single fixed-huffman deflate block in a zlib stream
+pub(crate) fn zlib_compress(bytes: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+    // CMF/FLG: deflate with a 32K window, no dictionary, fastest compression
+    writer.bytes.extend_from_slice(&[0x78, 0x01]);
+    deflate_fixed(&mut writer, bytes);
+    let mut out = writer.finish();
+    out.extend_from_slice(&adler32(bytes).to_be_bytes());
+    out
+}
+
+const WINDOW_SIZE: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: u32 = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+fn deflate_fixed(writer: &mut BitWriter, bytes: &[u8]) {
+    // BFINAL = 1, BTYPE = 01 (fixed huffman)
+    writer.write_bits(1, 1);
+    writer.write_bits(1, 2);
+
+    // the most recent position every 3-byte prefix was seen at
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let hash = |pos: usize| {
+        let key = (bytes[pos] as u32) << 16 | (bytes[pos + 1] as u32) << 8 | bytes[pos + 2] as u32;
+        (key.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+    };
+
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let mut match_len = 0;
+        let mut match_dist = 0;
+        if pos + MIN_MATCH <= bytes.len() {
+            let slot = hash(pos);
+            let candidate = head[slot];
+            head[slot] = pos;
+            if candidate != usize::MAX && pos - candidate <= WINDOW_SIZE {
+                let max_len = (bytes.len() - pos).min(MAX_MATCH);
+                match_len = (0..max_len)
+                    .take_while(|&i| bytes[candidate + i] == bytes[pos + i])
+                    .count();
+                match_dist = pos - candidate;
+            }
+        }
+
+        if match_len >= MIN_MATCH {
+            write_length(writer, match_len);
+            write_distance(writer, match_dist);
+            // keep the hash table warm inside the match, otherwise long runs never get found again
+            for inner in pos + 1..(pos + match_len).min(bytes.len().saturating_sub(MIN_MATCH - 1)) {
+                head[hash(inner)] = inner;
+            }
+            pos += match_len;
+        } else {
+            write_literal(writer, bytes[pos] as u16);
+            pos += 1;
+        }
+    }
+    write_literal(writer, 256);
+}
+
+/// writes a symbol of the fixed literal/length alphabet
+fn write_literal(writer: &mut BitWriter, symbol: u16) {
+    let (code, len) = match symbol {
+        0..=143 => (0x30 + symbol, 8),
+        144..=255 => (0x190 + symbol - 144, 9),
+        256..=279 => (symbol - 256, 7),
+        _ => (0xc0 + symbol - 280, 8),
+    };
+    writer.write_huffman(code as u32, len);
+}
+
+fn write_length(writer: &mut BitWriter, len: usize) {
+    let index = LENGTH_BASE
+        .iter()
+        .rposition(|&base| base as usize <= len)
+        .unwrap_or(0);
+    write_literal(writer, 257 + index as u16);
+    writer.write_bits(
+        (len - LENGTH_BASE[index] as usize) as u32,
+        LENGTH_EXTRA[index] as u32,
+    );
+}
+
+fn write_distance(writer: &mut BitWriter, dist: usize) {
+    let index = DIST_BASE
+        .iter()
+        .rposition(|&base| base as usize <= dist)
+        .unwrap_or(0);
+    writer.write_huffman(index as u32, 5);
+    writer.write_bits(
+        (dist - DIST_BASE[index] as usize) as u32,
+        DIST_EXTRA[index] as u32,
+    );
+}
+
+/// deflate packs bits starting at the least significant bit of each byte
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    num_bits: u32,
+}
+
+impl BitWriter {
+    fn write_bits(&mut self, value: u32, len: u32) {
+        self.acc |= (value as u64) << self.num_bits;
+        self.num_bits += len;
+        while self.num_bits >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.num_bits -= 8;
+        }
+    }
+
+    /// huffman codes are stored most significant bit first, unlike everything else
+    fn write_huffman(&mut self, code: u32, len: u32) {
+        let reversed = code.reverse_bits() >> (32 - len);
+        self.write_bits(reversed, len);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.num_bits > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+/// `(x, y, dx, dy)` of the seven Adam7 passes
+const ADAM7_PASSES: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+struct Header {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> Result<Self, ImageError> {
+        if data.len() != 13 {
+            return Err(ImageError::Corrupt("bad IHDR length"));
+        }
+        let header = Self {
+            width: read_u32(data, 0)?,
+            height: read_u32(data, 4)?,
+            bit_depth: data[8],
+            color_type: data[9],
+            interlaced: data[12] == 1,
+        };
+        let valid_depth = match header.color_type {
+            0 => matches!(header.bit_depth, 1 | 2 | 4 | 8 | 16),
+            3 => matches!(header.bit_depth, 1 | 2 | 4 | 8),
+            2 | 4 | 6 => matches!(header.bit_depth, 8 | 16),
+            _ => return Err(ImageError::Corrupt("invalid color type")),
+        };
+        if !valid_depth {
+            return Err(ImageError::Corrupt("invalid bit depth for the color type"));
+        }
+        if data[10] != 0 || data[11] != 0 || data[12] > 1 {
+            return Err(ImageError::Unsupported(
+                "non-standard compression or filtering",
+            ));
+        }
+        Ok(header)
+    }
+
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * self.bit_depth as usize
+    }
+
+    fn row_len(&self, width: usize) -> usize {
+        (width * self.bits_per_pixel()).div_ceil(8)
+    }
+}
+
+/// ## Description
+/// decodes a PNG file to RGBA8
+/// ## Comments
+/// - 16-bit channels get truncated to 8 bits, gamma and color profile chunks are ignored
+pub fn decode(bytes: &[u8]) -> Result<DecodedImage, ImageError> {
+    if !bytes.starts_with(&SIGNATURE) {
+        return Err(ImageError::Corrupt("missing PNG signature"));
+    }
+
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut compressed = Vec::new();
+    let mut pos = SIGNATURE.len();
+    loop {
+        let len = read_u32(bytes, pos)? as usize;
+        let chunk = bytes
+            .get(pos + 4..pos + 8 + len)
+            .ok_or(ImageError::Truncated)?;
+        if read_u32(bytes, pos + 8 + len)? != crc32(chunk) {
+            return Err(ImageError::Corrupt("chunk checksum mismatch"));
+        }
+        let (kind, data) = chunk.split_at(4);
+        match kind {
+            b"IHDR" => header = Some(Header::parse(data)?),
+            b"PLTE" => palette = data,
+            b"tRNS" => transparency = data,
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            // lowercase first letter means the chunk is safe to skip
+            _ if kind[0].is_ascii_lowercase() => (),
+            _ => return Err(ImageError::Unsupported("unknown critical chunk")),
+        }
+        pos += len + 12;
+    }
+    let header = header.ok_or(ImageError::Corrupt("missing IHDR"))?;
+    let scanlines = zlib_decompress(&compressed)?;
+
+    let (width, height) = (header.width as usize, header.height as usize);
+    let passes: &[_] = if header.interlaced {
+        &ADAM7_PASSES
+    } else {
+        &[(0, 0, 1, 1)]
+    };
+    // the header's size only gets trusted once the scanlines are known to cover it
+    let needed = scanlines_len(&header, passes).ok_or(ImageError::Unsupported("image size"))?;
+    if scanlines.len() < needed {
+        return Err(ImageError::Truncated);
+    }
+    let pixels_len = width
+        .checked_mul(height)
+        .and_then(|count| count.checked_mul(4))
+        .ok_or(ImageError::Unsupported("image size"))?;
+    let mut image = DecodedImage {
+        width: header.width,
+        height: header.height,
+        pixels: vec![0; pixels_len],
+    };
+
+    let mut consumed = 0;
+    for &(x0, y0, dx, dy) in passes {
+        let pass_width = width.saturating_sub(x0).div_ceil(dx);
+        let pass_height = height.saturating_sub(y0).div_ceil(dy);
+        if pass_width == 0 || pass_height == 0 {
+            continue;
+        }
+        let row_len = header.row_len(pass_width);
+        let filtered = scanlines.get(consumed..).ok_or(ImageError::Truncated)?;
+        let rows = unfilter(filtered, row_len, pass_height, header.bits_per_pixel())?;
+        consumed += (row_len + 1) * pass_height;
+
+        for (pass_y, row) in rows.chunks_exact(row_len).enumerate() {
+            for pass_x in 0..pass_width {
+                let rgba = read_pixel(&header, row, pass_x, palette, transparency)?;
+                let offset = ((y0 + pass_y * dy) * width + x0 + pass_x * dx) * 4;
+                image.pixels[offset..offset + 4].copy_from_slice(&rgba);
+            }
+        }
+    }
+    Ok(image)
+}
+
+/// how many bytes of filtered scanlines (filter bytes included) the passes take, `None` on overflow
+fn scanlines_len(header: &Header, passes: &[(usize, usize, usize, usize)]) -> Option<usize> {
+    let (width, height) = (header.width as usize, header.height as usize);
+    passes.iter().try_fold(0usize, |total, &(x0, y0, dx, dy)| {
+        let pass_width = width.saturating_sub(x0).div_ceil(dx);
+        let pass_height = height.saturating_sub(y0).div_ceil(dy);
+        if pass_width == 0 || pass_height == 0 {
+            return Some(total);
+        }
+        let row_len = pass_width.checked_mul(header.bits_per_pixel())?.div_ceil(8);
+        total.checked_add((row_len + 1).checked_mul(pass_height)?)
+    })
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> Result<u32, ImageError> {
+    let word = bytes.get(pos..pos + 4).ok_or(ImageError::Truncated)?;
+    Ok(u32::from_be_bytes([word[0], word[1], word[2], word[3]]))
+}
+
+/// undoes the per-scanline filters, `filtered` starts with the first filter byte
+fn unfilter(
+    filtered: &[u8],
+    row_len: usize,
+    height: usize,
+    bits_per_pixel: usize,
+) -> Result<Vec<u8>, ImageError> {
+    // filters work on whole bytes, sub-byte pixels count as one
+    let bpp = bits_per_pixel.div_ceil(8);
+    let mut rows = vec![0u8; row_len * height];
+    for y in 0..height {
+        let line = filtered
+            .get(y * (row_len + 1)..(y + 1) * (row_len + 1))
+            .ok_or(ImageError::Truncated)?;
+        let (previous_rows, current) = rows.split_at_mut(y * row_len);
+        let prior = (y > 0).then(|| &previous_rows[(y - 1) * row_len..]);
+        let current = &mut current[..row_len];
+
+        for x in 0..row_len {
+            let a = if x >= bpp { current[x - bpp] } else { 0 };
+            let b = prior.map_or(0, |prior| prior[x]);
+            let c = prior.filter(|_| x >= bpp).map_or(0, |prior| prior[x - bpp]);
+            let prediction = match line[0] {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return Err(ImageError::Corrupt("invalid filter type")),
+            };
+            current[x] = line[x + 1].wrapping_add(prediction);
+        }
+    }
+    Ok(rows)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// the raw value of sample `index` (channels of a pixel are consecutive samples) of an unfiltered row
+fn read_sample(row: &[u8], index: usize, bit_depth: u8) -> u16 {
+    match bit_depth {
+        16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+        8 => row[index] as u16,
+        _ => {
+            let bit = index * bit_depth as usize;
+            let shift = 8 - bit_depth as usize - bit % 8;
+            ((row[bit / 8] >> shift) & ((1 << bit_depth) - 1)) as u16
+        }
+    }
+}
+
+fn read_pixel(
+    header: &Header,
+    row: &[u8],
+    x: usize,
+    palette: &[u8],
+    transparency: &[u8],
+) -> Result<[u8; 4], ImageError> {
+    let channels = header.channels();
+    let depth = header.bit_depth;
+    let mut samples = [0u16; 4];
+    for (channel, sample) in samples.iter_mut().enumerate().take(channels) {
+        *sample = read_sample(row, x * channels + channel, depth);
+    }
+    let raw = &samples[..channels];
+    let to_u8 = |value: u16| match depth {
+        16 => (value >> 8) as u8,
+        8 => value as u8,
+        _ => (value as u32 * 255 / ((1u32 << depth) - 1)) as u8,
+    };
+    // tRNS holds a single transparent color for types without alpha, as 16-bit samples
+    let is_transparent_key = |samples: &[u16]| {
+        transparency.len() == samples.len() * 2
+            && samples
+                .iter()
+                .zip(transparency.chunks_exact(2))
+                .all(|(&sample, key)| sample == u16::from_be_bytes([key[0], key[1]]))
+    };
+    let key_alpha = |samples: &[u16]| if is_transparent_key(samples) { 0 } else { 255 };
+
+    Ok(match header.color_type {
+        0 => {
+            let gray = to_u8(raw[0]);
+            [gray, gray, gray, key_alpha(raw)]
+        }
+        2 => [to_u8(raw[0]), to_u8(raw[1]), to_u8(raw[2]), key_alpha(raw)],
+        3 => {
+            let index = raw[0] as usize;
+            let color = palette
+                .get(index * 3..index * 3 + 3)
+                .ok_or(ImageError::Corrupt("palette index out of range"))?;
+            let alpha = transparency.get(index).copied().unwrap_or(255);
+            [color[0], color[1], color[2], alpha]
+        }
+        4 => {
+            let gray = to_u8(raw[0]);
+            [gray, gray, gray, to_u8(raw[1])]
+        }
+        _ => [to_u8(raw[0]), to_u8(raw[1]), to_u8(raw[2]), to_u8(raw[3])],
+    })
+}
+
+#[test]
+fn png_checksums() {
+    assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+    assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+}
+
+#[test]
+fn png_encodes_rgba() {
+    let (width, height) = (17, 9);
+    let pixels: Vec<u8> = (0..width * height)
+        .flat_map(|i| [(i % 7) as u8 * 30, 255, (i / width) as u8, 255])
+        .collect();
+    let png = encode_rgba(width, height, &pixels);
+
+    assert_eq!(png[..8], SIGNATURE);
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(png[16..20], width.to_be_bytes());
+    assert_eq!(png[20..24], height.to_be_bytes());
+    assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+    // repetitive pixels should compress well below the raw size
+    assert!(png.len() < pixels.len());
+}
+
+#[cfg(test)]
+fn build_png(
+    width: u32,
+    height: u32,
+    ihdr_tail: [u8; 5],
+    chunks: &[(&[u8; 4], &[u8])],
+    scanlines: &[u8],
+) -> Vec<u8> {
+    let mut ihdr = Vec::new();
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&ihdr_tail);
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &ihdr);
+    for (kind, data) in chunks {
+        write_chunk(&mut png, kind, data);
+    }
+    write_chunk(&mut png, b"IDAT", &zlib_compress(scanlines));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+#[test]
+fn png_round_trips() {
+    let (width, height) = (13, 7);
+    let pixels: Vec<u8> = (0..width * height)
+        .flat_map(|i| {
+            [
+                (i * 7) as u8,
+                (i / width) as u8 * 20,
+                255 - i as u8,
+                (i % 3) as u8 * 100,
+            ]
+        })
+        .collect();
+    let image = decode(&encode_rgba(width, height, &pixels)).unwrap();
+    assert_eq!((image.width, image.height), (width, height));
+    assert_eq!(image.pixels, pixels);
+
+    let mut corrupt = encode_rgba(width, height, &pixels);
+    let last_data_byte = corrupt.len() - 13;
+    corrupt[last_data_byte] ^= 1;
+    assert_eq!(
+        decode(&corrupt),
+        Err(ImageError::Corrupt("chunk checksum mismatch"))
+    );
+    assert_eq!(decode(&corrupt[..40]), Err(ImageError::Truncated));
+}
+
+#[test]
+fn png_decodes_filters_palettes_and_interlacing() {
+    // 3x5 RGB, every row uses a different filter
+    let (width, height, bpp) = (3usize, 5usize, 3usize);
+    let raw: Vec<u8> = (0..width * height * bpp)
+        .map(|i| (i * 37 % 251) as u8)
+        .collect();
+    let mut scanlines = Vec::new();
+    for (y, row) in raw.chunks_exact(width * bpp).enumerate() {
+        let filter = y as u8;
+        scanlines.push(filter);
+        for x in 0..row.len() {
+            let a = if x >= bpp { row[x - bpp] } else { 0 };
+            let b = if y > 0 {
+                raw[(y - 1) * width * bpp + x]
+            } else {
+                0
+            };
+            let c = if y > 0 && x >= bpp {
+                raw[(y - 1) * width * bpp + x - bpp]
+            } else {
+                0
+            };
+            let prediction = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                _ => paeth(a, b, c),
+            };
+            scanlines.push(row[x].wrapping_sub(prediction));
+        }
+    }
+    let png = build_png(
+        width as u32,
+        height as u32,
+        [8, 2, 0, 0, 0],
+        &[],
+        &scanlines,
+    );
+    let image = decode(&png).unwrap();
+    for (pixel, expected) in image.pixels.chunks_exact(4).zip(raw.chunks_exact(3)) {
+        assert_eq!(pixel[..3], *expected);
+        assert_eq!(pixel[3], 255);
+    }
+
+    // 2-bit palette with a transparent entry, 5 pixels spill into a second byte
+    let palette = [255, 0, 0, 0, 255, 0, 0, 0, 255, 9, 9, 9];
+    let scanlines = [0, 0b00_01_10_11, 0b01_000000];
+    let png = build_png(
+        5,
+        1,
+        [2, 3, 0, 0, 0],
+        &[(b"PLTE", &palette), (b"tRNS", &[255, 128])],
+        &scanlines,
+    );
+    let image = decode(&png).unwrap();
+    assert_eq!(image.pixel(0, 0), [255, 0, 0, 255]);
+    assert_eq!(image.pixel(1, 0), [0, 255, 0, 128]);
+    assert_eq!(image.pixel(3, 0), [9, 9, 9, 255]);
+    assert_eq!(image.pixel(4, 0), [0, 255, 0, 128]);
+
+    // 16-bit grayscale with a color key, interlaced
+    let (width, height) = (3usize, 3usize);
+    let gray = |x: usize, y: usize| (x * 3 + y) as u16 * 0x1111;
+    let mut scanlines = Vec::new();
+    for &(x0, y0, dx, dy) in &ADAM7_PASSES {
+        for y in (y0..height).step_by(dy) {
+            let xs: Vec<_> = (x0..width).step_by(dx).collect();
+            if xs.is_empty() {
+                continue;
+            }
+            scanlines.push(0);
+            for x in xs {
+                scanlines.extend_from_slice(&gray(x, y).to_be_bytes());
+            }
+        }
+    }
+    let key = gray(1, 1).to_be_bytes();
+    let png = build_png(
+        width as u32,
+        height as u32,
+        [16, 0, 0, 0, 1],
+        &[(b"tRNS", &key)],
+        &scanlines,
+    );
+    let image = decode(&png).unwrap();
+    for y in 0..height {
+        for x in 0..width {
+            let expected = (gray(x, y) >> 8) as u8;
+            let alpha = if (x, y) == (1, 1) { 0 } else { 255 };
+            assert_eq!(
+                image.pixel(x as u32, y as u32),
+                [expected, expected, expected, alpha]
+            );
+        }
+    }
+
+    // a header claiming far more pixels than the data holds is rejected before allocating them
+    let huge = build_png(1 << 20, 1 << 20, [8, 6, 0, 0, 0], &[], &[0; 5]);
+    assert_eq!(decode(&huge), Err(ImageError::Truncated));
+}
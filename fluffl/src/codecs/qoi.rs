@@ -0,0 +1,135 @@
+//! A decoder for QOI, the "Quite OK Image" format (<https://qoiformat.org>).
+use super::image::{DecodedImage, ImageError};
+
+const HEADER_LEN: usize = 14;
+const OP_RGB: u8 = 0xfe;
+const OP_RGBA: u8 = 0xff;
+const OP_INDEX: u8 = 0x00;
+const OP_DIFF: u8 = 0x40;
+const OP_LUMA: u8 = 0x80;
+const OP_RUN: u8 = 0xc0;
+const TAG_MASK: u8 = 0xc0;
+
+/// ## Description
+/// decodes a QOI file to RGBA8
+/// ## Comments
+/// - 3-channel images get an opaque alpha channel, the colorspace byte is ignored
+pub fn decode(bytes: &[u8]) -> Result<DecodedImage, ImageError> {
+    let header = bytes.get(..HEADER_LEN).ok_or(ImageError::Truncated)?;
+    if &header[..4] != b"qoif" {
+        return Err(ImageError::Corrupt("missing QOI magic"));
+    }
+    let width = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    let height = u32::from_be_bytes([header[8], header[9], header[10], header[11]]);
+    if !matches!(header[12], 3 | 4) {
+        return Err(ImageError::Corrupt("invalid QOI channel count"));
+    }
+
+    // no op makes more than 62 pixels, so the header can't claim more than that per byte left
+    let pixel_count = (width as usize)
+        .checked_mul(height as usize)
+        .filter(|&count| count <= (bytes.len() - HEADER_LEN).saturating_mul(62))
+        .ok_or(ImageError::Truncated)?;
+    let mut pixels = Vec::with_capacity(pixel_count * 4);
+    let mut index = [[0u8; 4]; 64];
+    let mut pixel = [0, 0, 0, 255];
+    let mut pos = HEADER_LEN;
+    let mut next = || -> Result<u8, ImageError> {
+        let byte = *bytes.get(pos).ok_or(ImageError::Truncated)?;
+        pos += 1;
+        Ok(byte)
+    };
+
+    while pixels.len() < pixel_count * 4 {
+        let op = next()?;
+        let mut run = 1;
+        match op {
+            OP_RGB => {
+                pixel[0] = next()?;
+                pixel[1] = next()?;
+                pixel[2] = next()?;
+            }
+            OP_RGBA => {
+                for channel in pixel.iter_mut() {
+                    *channel = next()?;
+                }
+            }
+            _ => match op & TAG_MASK {
+                OP_INDEX => pixel = index[op as usize],
+                OP_DIFF => {
+                    pixel[0] = pixel[0].wrapping_add((op >> 4) & 3).wrapping_sub(2);
+                    pixel[1] = pixel[1].wrapping_add((op >> 2) & 3).wrapping_sub(2);
+                    pixel[2] = pixel[2].wrapping_add(op & 3).wrapping_sub(2);
+                }
+                OP_RUN => run = (op & 0x3f) as usize + 1,
+                OP_LUMA => {
+                    let green_diff = (op & 0x3f).wrapping_sub(32);
+                    let red_blue = next()?;
+                    let red_diff = green_diff.wrapping_add(red_blue >> 4).wrapping_sub(8);
+                    let blue_diff = green_diff.wrapping_add(red_blue & 0x0f).wrapping_sub(8);
+                    pixel[0] = pixel[0].wrapping_add(red_diff);
+                    pixel[1] = pixel[1].wrapping_add(green_diff);
+                    pixel[2] = pixel[2].wrapping_add(blue_diff);
+                }
+                _ => unreachable!("the tag is two bits"),
+            },
+        }
+
+        let hash = pixel[0] as usize * 3
+            + pixel[1] as usize * 5
+            + pixel[2] as usize * 7
+            + pixel[3] as usize * 11;
+        index[hash % 64] = pixel;
+        for _ in 0..run.min(pixel_count - pixels.len() / 4) {
+            pixels.extend_from_slice(&pixel);
+        }
+    }
+
+    Ok(DecodedImage {
+        width,
+        height,
+        pixels,
+    })
+}
+
+#[test]
+fn qoi_decodes_every_op() {
+    let mut qoi = b"qoif".to_vec();
+    qoi.extend_from_slice(&3u32.to_be_bytes());
+    qoi.extend_from_slice(&2u32.to_be_bytes());
+    qoi.extend_from_slice(&[4, 0]);
+    qoi.extend_from_slice(&[
+        OP_RGB,
+        10,
+        20,
+        30,
+        OP_RUN,
+        // red +1, green -1, blue +0
+        OP_DIFF | 3 << 4 | 1 << 2 | 2,
+        // green +5, red +3, blue +8
+        OP_LUMA | 37,
+        6 << 4 | 11,
+        OP_RGBA,
+        1,
+        2,
+        3,
+        4,
+        // (10,20,30,255) hashes to 9
+        OP_INDEX | 9,
+    ]);
+    qoi.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+
+    let image = decode(&qoi).unwrap();
+    assert_eq!((image.width, image.height), (3, 2));
+    assert_eq!(image.pixel(0, 0), [10, 20, 30, 255]);
+    assert_eq!(image.pixel(1, 0), [10, 20, 30, 255]);
+    assert_eq!(image.pixel(2, 0), [11, 19, 30, 255]);
+    assert_eq!(image.pixel(0, 1), [14, 24, 38, 255]);
+    assert_eq!(image.pixel(1, 1), [1, 2, 3, 4]);
+    assert_eq!(image.pixel(2, 1), [10, 20, 30, 255]);
+
+    assert_eq!(decode(&qoi[..20]), Err(ImageError::Truncated));
+
+    qoi[4..12].copy_from_slice(&[0xff; 8]);
+    assert_eq!(decode(&qoi), Err(ImageError::Truncated));
+}
@@ -5,6 +5,15 @@ pub mod audio;
 /// Lets you draw anti-aliased rectangles, circles, etc
 pub mod shapes;
 
+/// Draws lots of textured quads with few draw calls
+pub mod sprites;
+
+/// Packs images onto texture pages at runtime
+pub mod atlas;
+
+/// Loads OBJ and glTF meshes and draws them with cameras and basic lighting
+pub mod mesh;
+
 // A very simple packing/upacking library used to load SDF fonts.
 pub use hiero_pack;
 
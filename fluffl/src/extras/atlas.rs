@@ -0,0 +1,476 @@
+use super::sprites::UvRect;
+use crate::{codecs::image::DecodedImage, ogl::texture::*, GlowGL};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AtlasError {
+    /// two images were given the same name
+    DuplicateName(String),
+    /// the image (plus padding and extrusion) is bigger than a page
+    TooLarge(String),
+    /// a cached layout references an image that wasn't passed in
+    MissingImage(String),
+    /// a cached layout was packed with a different size for this image
+    SizeMismatch(String),
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateName(name) => write!(f, "atlas image \"{}\" was added twice", name),
+            Self::TooLarge(name) => write!(f, "atlas image \"{}\" doesn't fit on a page", name),
+            Self::MissingImage(name) => write!(f, "atlas layout needs image \"{}\"", name),
+            Self::SizeMismatch(name) => {
+                write!(
+                    f,
+                    "atlas image \"{}\" changed size since it was packed",
+                    name
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for AtlasError {}
+
+/// # Description
+/// How atlas pages get laid out
+/// # Comments
+/// - `padding` is the number of empty pixels between images (and between images and the page border)
+/// - `extrude` repeats the outermost pixels of every image this many times, so filtering and
+///   mipmapping near the edge of a sprite doesn't pull in its neighbours
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct AtlasOptions {
+    pub page_width: u32,
+    pub page_height: u32,
+    pub padding: u32,
+    pub extrude: u32,
+    /// used for every page texture
+    pub sampler: SamplerState,
+}
+
+impl AtlasOptions {
+    pub fn new(page_width: u32, page_height: u32) -> Self {
+        Self {
+            page_width,
+            page_height,
+            padding: 1,
+            extrude: 1,
+            sampler: SamplerState::default(),
+        }
+    }
+
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn with_extrude(mut self, extrude: u32) -> Self {
+        self.extrude = extrude;
+        self
+    }
+
+    pub fn with_sampler(mut self, sampler: SamplerState) -> Self {
+        self.sampler = sampler;
+        self
+    }
+}
+
+/// Where an image ended up, `x`/`y`/`width`/`height` cover the image itself without its extrusion
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct AtlasEntry {
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// # Description
+/// The result of packing a set of images, without any pixels or GL objects
+/// # Comments
+/// - serializes to JSON, so the packing can be cached and reused with `TextureAtlas::from_layout(..)`
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct AtlasLayout {
+    pub page_width: u32,
+    pub page_height: u32,
+    pub padding: u32,
+    pub extrude: u32,
+    pub num_pages: usize,
+    /// sorted by name so the JSON is stable
+    pub entries: BTreeMap<String, AtlasEntry>,
+}
+
+impl AtlasLayout {
+    /// ## Description
+    /// packs `images` onto as many pages as needed
+    /// ## Comments
+    /// - images get placed tallest first, with a bottom-left skyline heuristic
+    pub fn pack(
+        images: &[(&str, &DecodedImage)],
+        options: &AtlasOptions,
+    ) -> Result<Self, AtlasError> {
+        let mut layout = Self {
+            page_width: options.page_width,
+            page_height: options.page_height,
+            padding: options.padding,
+            extrude: options.extrude,
+            num_pages: 0,
+            entries: BTreeMap::new(),
+        };
+
+        let mut order: Vec<_> = images.iter().collect();
+        order.sort_by(|(name_a, a), (name_b, b)| {
+            (b.height, b.width, name_a).cmp(&(a.height, a.width, name_b))
+        });
+
+        // the packers work in a space shrunk by `padding`, which then gets added back as an offset
+        let padding = options.padding;
+        let packer_width = options.page_width.saturating_sub(padding);
+        let packer_height = options.page_height.saturating_sub(padding);
+        let mut pages: Vec<SkylinePacker> = Vec::new();
+        for &&(name, image) in &order {
+            if layout.entries.contains_key(name) {
+                return Err(AtlasError::DuplicateName(name.to_string()));
+            }
+            let slot_width = image.width + 2 * options.extrude + padding;
+            let slot_height = image.height + 2 * options.extrude + padding;
+
+            let placed = pages
+                .iter_mut()
+                .enumerate()
+                .find_map(|(page, packer)| Some((page, packer.insert(slot_width, slot_height)?)));
+            let (page, (x, y)) = match placed {
+                Some(placed) => placed,
+                None => {
+                    let mut packer = SkylinePacker::new(packer_width, packer_height);
+                    let position = packer
+                        .insert(slot_width, slot_height)
+                        .ok_or_else(|| AtlasError::TooLarge(name.to_string()))?;
+                    pages.push(packer);
+                    (pages.len() - 1, position)
+                }
+            };
+            layout.entries.insert(
+                name.to_string(),
+                AtlasEntry {
+                    page,
+                    x: x + padding + options.extrude,
+                    y: y + padding + options.extrude,
+                    width: image.width,
+                    height: image.height,
+                },
+            );
+        }
+        layout.num_pages = pages.len();
+        Ok(layout)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AtlasEntry> {
+        self.entries.get(name)
+    }
+
+    /// ## Returns
+    /// the page an image is on and its uv rectangle on that page
+    pub fn uv(&self, name: &str) -> Option<(usize, UvRect)> {
+        let entry = self.entries.get(name)?;
+        let uv = UvRect::from_pixels(
+            entry.x,
+            entry.y,
+            entry.width,
+            entry.height,
+            (self.page_width, self.page_height),
+        );
+        Some((entry.page, uv))
+    }
+
+    /// ## Description
+    /// draws `images` onto RGBA8 pages, extruding their edges
+    pub fn rasterize(
+        &self,
+        images: &[(&str, &DecodedImage)],
+    ) -> Result<Vec<DecodedImage>, AtlasError> {
+        let (page_width, page_height) = (self.page_width as usize, self.page_height as usize);
+        let mut pages = vec![
+            DecodedImage {
+                width: self.page_width,
+                height: self.page_height,
+                pixels: vec![0; page_width * page_height * 4],
+            };
+            self.num_pages
+        ];
+
+        for (name, entry) in &self.entries {
+            let &(_, image) = images
+                .iter()
+                .find(|(image_name, _)| image_name == name)
+                .ok_or_else(|| AtlasError::MissingImage(name.clone()))?;
+            if (image.width, image.height) != (entry.width, entry.height) {
+                return Err(AtlasError::SizeMismatch(name.clone()));
+            }
+            if image.width == 0 || image.height == 0 {
+                continue;
+            }
+
+            let page = &mut pages[entry.page];
+            let extrude = self.extrude as i64;
+            for dy in -extrude..entry.height as i64 + extrude {
+                let src_y = dy.clamp(0, entry.height as i64 - 1) as u32;
+                let dst_y = (entry.y as i64 + dy) as usize;
+                for dx in -extrude..entry.width as i64 + extrude {
+                    let src_x = dx.clamp(0, entry.width as i64 - 1) as u32;
+                    let dst = (dst_y * page_width + (entry.x as i64 + dx) as usize) * 4;
+                    page.pixels[dst..dst + 4].copy_from_slice(&image.pixel(src_x, src_y));
+                }
+            }
+        }
+        Ok(pages)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("atlas layouts should always serialize")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+/// # Description
+/// Images packed onto one or more textures at runtime
+/// # Comments
+/// - pairs with `SpriteBatch`: `region(..)` gives the texture and uv rectangle a `Sprite` needs
+pub struct TextureAtlas {
+    layout: AtlasLayout,
+    pages: Vec<OglTexture>,
+}
+
+impl TextureAtlas {
+    /// packs `images` and uploads the pages
+    pub fn new(
+        gl: &GlowGL,
+        images: &[(&str, &DecodedImage)],
+        options: &AtlasOptions,
+    ) -> Result<Self, AtlasError> {
+        let layout = AtlasLayout::pack(images, options)?;
+        Self::from_layout(gl, layout, images, options.sampler)
+    }
+
+    /// ## Description
+    /// uploads the pages of an already packed (usually cached) layout
+    pub fn from_layout(
+        gl: &GlowGL,
+        layout: AtlasLayout,
+        images: &[(&str, &DecodedImage)],
+        sampler: SamplerState,
+    ) -> Result<Self, AtlasError> {
+        let pages = layout
+            .rasterize(images)?
+            .iter()
+            .map(|page| {
+                TextureObj::<u8>::builder(gl)
+                    .with_image(page)
+                    .with_internal_format(glow::RGBA)
+                    .with_sampler(sampler)
+                    .build()
+                    .into()
+            })
+            .collect();
+        Ok(Self { layout, pages })
+    }
+
+    pub fn layout(&self) -> &AtlasLayout {
+        &self.layout
+    }
+
+    pub fn pages(&self) -> &[OglTexture] {
+        &self.pages
+    }
+
+    /// ## Returns
+    /// the texture of the page the image is on, and where on that page it is
+    pub fn region(&self, name: &str) -> Option<(Option<glow::Texture>, UvRect)> {
+        let (page, uv) = self.layout.uv(name)?;
+        Some((self.pages[page].texture_id(), uv))
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct SkylineNode {
+    x: u32,
+    y: u32,
+    width: u32,
+}
+
+/// Keeps track of the top edge of everything placed so far, rectangles go where that edge is lowest
+struct SkylinePacker {
+    width: u32,
+    height: u32,
+    skyline: Vec<SkylineNode>,
+}
+
+impl SkylinePacker {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            skyline: vec![SkylineNode { x: 0, y: 0, width }],
+        }
+    }
+
+    /// the lowest `y` a `width` x `height` rectangle can sit at when its left edge is on `node`
+    fn fit(&self, node: usize, width: u32, height: u32) -> Option<u32> {
+        let x = self.skyline[node].x;
+        if x + width > self.width {
+            return None;
+        }
+        let (mut y, mut remaining) = (0, width);
+        for segment in &self.skyline[node..] {
+            if remaining == 0 {
+                break;
+            }
+            y = y.max(segment.y);
+            remaining = remaining.saturating_sub(segment.width);
+        }
+        (y + height <= self.height).then_some(y)
+    }
+
+    fn insert(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (node, y) = (0..self.skyline.len())
+            .filter_map(|node| Some((node, self.fit(node, width, height)?)))
+            .min_by_key(|&(node, y)| (y + height, self.skyline[node].x))?;
+        let x = self.skyline[node].x;
+
+        self.skyline.insert(
+            node,
+            SkylineNode {
+                x,
+                y: y + height,
+                width,
+            },
+        );
+        // shrink or drop the segments the new one covers
+        let end = x + width;
+        let mut next = node + 1;
+        while next < self.skyline.len() && self.skyline[next].x < end {
+            let segment = &mut self.skyline[next];
+            let overlap = end - segment.x;
+            if segment.width <= overlap {
+                self.skyline.remove(next);
+            } else {
+                segment.x += overlap;
+                segment.width -= overlap;
+                next += 1;
+            }
+        }
+        // merge neighbours at the same height
+        let mut index = 0;
+        while index + 1 < self.skyline.len() {
+            if self.skyline[index].y == self.skyline[index + 1].y {
+                self.skyline[index].width += self.skyline[index + 1].width;
+                self.skyline.remove(index + 1);
+            } else {
+                index += 1;
+            }
+        }
+        Some((x, y))
+    }
+}
+
+#[cfg(test)]
+fn solid_image(width: u32, height: u32, color: [u8; 4]) -> DecodedImage {
+    DecodedImage {
+        width,
+        height,
+        pixels: color.repeat((width * height) as usize),
+    }
+}
+
+#[test]
+fn atlas_packs_without_overlap() {
+    let images: Vec<_> = (0..40)
+        .map(|i| {
+            (
+                format!("image{}", i),
+                solid_image(5 + i % 7 * 3, 4 + i % 5 * 4, [0; 4]),
+            )
+        })
+        .collect();
+    let named: Vec<_> = images
+        .iter()
+        .map(|(name, image)| (name.as_str(), image))
+        .collect();
+    let options = AtlasOptions::new(64, 64).with_padding(2).with_extrude(1);
+    let layout = AtlasLayout::pack(&named, &options).unwrap();
+    assert!(layout.num_pages > 1);
+    assert_eq!(layout.entries.len(), images.len());
+
+    // grow every entry by its extrusion and half the padding, none of those may overlap
+    let bounds: Vec<_> = layout
+        .entries
+        .values()
+        .map(|e| {
+            (
+                e.page,
+                e.x - 2,
+                e.y - 2,
+                e.x + e.width + 2,
+                e.y + e.height + 2,
+            )
+        })
+        .collect();
+    for (i, a) in bounds.iter().enumerate() {
+        assert!(a.1 >= 1 && a.2 >= 1 && a.3 <= 63 && a.4 <= 63);
+        for b in &bounds[i + 1..] {
+            let disjoint = a.0 != b.0 || a.3 <= b.1 || b.3 <= a.1 || a.4 <= b.2 || b.4 <= a.2;
+            assert!(disjoint, "{:?} overlaps {:?}", a, b);
+        }
+    }
+
+    let big = solid_image(63, 10, [0; 4]);
+    assert_eq!(
+        AtlasLayout::pack(&[("big", &big)], &options),
+        Err(AtlasError::TooLarge("big".to_string()))
+    );
+    assert_eq!(
+        AtlasLayout::pack(&[("a", &images[0].1), ("a", &images[1].1)], &options),
+        Err(AtlasError::DuplicateName("a".to_string()))
+    );
+}
+
+#[test]
+fn atlas_layouts_extrude_and_round_trip() {
+    let mut checker = solid_image(2, 2, [255, 0, 0, 255]);
+    checker.pixels[4..8].copy_from_slice(&[0, 255, 0, 255]);
+    let blue = solid_image(3, 1, [0, 0, 255, 255]);
+    let images = [("checker", &checker), ("blue", &blue)];
+    let options = AtlasOptions::new(16, 16).with_padding(1).with_extrude(2);
+
+    let layout = AtlasLayout::pack(&images, &options).unwrap();
+    let cached = AtlasLayout::from_json(&layout.to_json()).unwrap();
+    assert_eq!(cached, layout);
+
+    let pages = cached.rasterize(&images).unwrap();
+    assert_eq!(pages.len(), 1);
+    let entry = *cached.get("checker").unwrap();
+    let (x, y) = (entry.x, entry.y);
+    assert_eq!(pages[0].pixel(x, y), [255, 0, 0, 255]);
+    assert_eq!(pages[0].pixel(x + 1, y), [0, 255, 0, 255]);
+    // the extrusion repeats the edge pixels
+    assert_eq!(pages[0].pixel(x + 3, y - 2), [0, 255, 0, 255]);
+    assert_eq!(pages[0].pixel(x - 2, y + 3), [255, 0, 0, 255]);
+    assert_eq!(pages[0].pixel(x - 3, y - 3), [0; 4]);
+
+    let (page, uv) = cached.uv("blue").unwrap();
+    let blue_entry = cached.get("blue").unwrap();
+    assert_eq!(page, 0);
+    assert_eq!(uv.min[0], blue_entry.x as f32 / 16.0);
+    assert_eq!(uv.max[1], (blue_entry.y + 1) as f32 / 16.0);
+
+    let wrong_size = solid_image(1, 1, [0; 4]);
+    assert_eq!(
+        cached.rasterize(&[("checker", &checker), ("blue", &wrong_size)]),
+        Err(AtlasError::SizeMismatch("blue".to_string()))
+    );
+}
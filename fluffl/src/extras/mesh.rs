@@ -0,0 +1,178 @@
+use crate::{
+    math::*,
+    ogl::{array::*, buffer::*, *},
+    GlowGL,
+};
+use std::fmt;
+
+mod camera;
+mod gltf;
+mod material;
+mod obj;
+
+pub use self::{camera::*, gltf::*, material::*, obj::*};
+
+/// attribute locations `Mesh` uploads to, shaders drawing meshes have to use the same ones
+pub const POSITION_LOCATION: u32 = 0;
+pub const NORMAL_LOCATION: u32 = 1;
+pub const UV_LOCATION: u32 = 2;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum MeshError {
+    /// a line of an OBJ file couldn't be parsed, `line` starts at 1
+    Obj { line: usize, reason: String },
+    /// the glTF (or glb) file is malformed
+    Gltf(String),
+    /// the glTF file uses something the loader doesn't handle (external buffers, sparse accessors, ..)
+    UnsupportedGltf(String),
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Obj { line, reason } => write!(f, "obj line {}: {}", line, reason),
+            Self::Gltf(reason) => write!(f, "gltf: {}", reason),
+            Self::UnsupportedGltf(reason) => write!(f, "gltf: unsupported {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+/// # Description
+/// An indexed triangle mesh on the cpu, what the loaders produce
+/// # Comments
+/// - `normals` and `uvs` are either empty or as long as `positions`
+/// - `indices` index into all three attribute lists, three per triangle
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct MeshData {
+    pub name: String,
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// the smallest box holding every vertex, `None` for an empty mesh
+    pub fn bounds(&self) -> Option<AABB3<f32>> {
+        if self.positions.is_empty() {
+            return None;
+        }
+        let mut bounds = AABB3::flipped_infinity();
+        for &position in &self.positions {
+            bounds.merge(AABB3::from_segment(position, position));
+        }
+        Some(bounds)
+    }
+
+    /// ## Description
+    /// replaces `normals` with smooth normals, the area weighted average of the adjacent faces
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![Vec3::<f32>::zero(); self.positions.len()];
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| i as usize);
+            let corner = Vec3::from(self.positions[a]);
+            let face = (Vec3::from(self.positions[b]) - corner)
+                .cross(Vec3::from(self.positions[c]) - corner);
+            for index in [a, b, c] {
+                normals[index] += face;
+            }
+        }
+        self.normals = normals
+            .into_iter()
+            .map(|normal| {
+                let length = normal.length();
+                if length > f32::EPSILON {
+                    *(normal * (1.0 / length)).data()
+                } else {
+                    [0.0, 1.0, 0.0]
+                }
+            })
+            .collect();
+    }
+}
+
+/// # Description
+/// A `MeshData` uploaded into an `OglArray`
+/// # Comments
+/// - positions go to location 0, normals to 1 and uvs to 2 (see `POSITION_LOCATION` and friends),
+///   missing normals or uvs are filled with zeroes so every shader input has data
+pub struct Mesh {
+    name: String,
+    array: OglArray,
+    triangle_count: usize,
+}
+
+impl Mesh {
+    pub fn new(gl: &GlowGL, data: &MeshData) -> Self {
+        let vertex_count = data.vertex_count();
+        let attribute = |index: u32, num_comps: u32, values: Vec<f32>| -> Box<dyn HasBufferObj> {
+            OglBuf::new(gl)
+                .with_num_comps(num_comps)
+                .with_target(glow::ARRAY_BUFFER)
+                .with_usage(glow::STATIC_DRAW)
+                .with_index(index)
+                .with_data(values)
+                .build()
+                .into()
+        };
+        let padded = |values: Vec<f32>, num_comps: usize| {
+            if values.is_empty() {
+                vec![0.0; vertex_count * num_comps]
+            } else {
+                values
+            }
+        };
+        let positions = data.positions.iter().flatten().copied().collect();
+        let normals = padded(data.normals.iter().flatten().copied().collect(), 3);
+        let uvs = padded(data.uvs.iter().flatten().copied().collect(), 2);
+        // the vertex array doesn't exist yet, building an index buffer unbinds whatever array
+        // was bound so it can't end up with these indices
+        let indices: Box<dyn HasBufferObj> = OglBuf::new(gl)
+            .with_target(glow::ELEMENT_ARRAY_BUFFER)
+            .with_usage(glow::STATIC_DRAW)
+            .with_data(data.indices.clone())
+            .build()
+            .into();
+
+        let array = OglArray::new(gl).init(vec![
+            BufferPair::new("positions", attribute(POSITION_LOCATION, 3, positions)),
+            BufferPair::new("normals", attribute(NORMAL_LOCATION, 3, normals)),
+            BufferPair::new("uvs", attribute(UV_LOCATION, 2, uvs)),
+            BufferPair::new("indices", indices),
+        ]);
+        array.bind(false);
+
+        Self {
+            name: data.name.clone(),
+            array,
+            triangle_count: data.triangle_count(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangle_count
+    }
+
+    pub fn array(&self) -> &OglArray {
+        &self.array
+    }
+
+    /// draws the mesh with whatever program is bound
+    pub fn draw(&self) {
+        self.array.draw_elements(glow::TRIANGLES);
+    }
+}
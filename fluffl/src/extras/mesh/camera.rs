@@ -0,0 +1,202 @@
+use super::*;
+use std::f32::consts::FRAC_PI_2;
+
+/// keeps orbit cameras from flipping over when looking straight up or down
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+/// # Description
+/// Anything `PhongShader` can draw from
+/// # Comments
+/// - matrices are in row-major format, like the rest of `math`
+pub trait Camera {
+    fn view(&self) -> Mat4<f32>;
+    fn projection(&self) -> Mat4<f32>;
+    /// where the camera is in world space, used for specular highlights
+    fn position(&self) -> Vec3<f32>;
+
+    fn view_projection(&self) -> Mat4<f32> {
+        self.projection() * self.view()
+    }
+}
+
+/// # Description
+/// A symmetric perspective projection
+/// # Comments
+/// - `fov_y` is the vertical field of view in degrees
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Lens {
+    pub fov_y: f32,
+    pub aspect: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Lens {
+    pub fn new(fov_y: f32, aspect: f32, near: f32, far: f32) -> Self {
+        Self {
+            fov_y,
+            aspect,
+            near,
+            far,
+        }
+    }
+
+    /// sets `aspect` from the window (or framebuffer) size in pixels
+    pub fn with_viewport(mut self, width: u32, height: u32) -> Self {
+        self.aspect = width as f32 / height.max(1) as f32;
+        self
+    }
+
+    pub fn projection(&self) -> Mat4<f32> {
+        let top = self.near * (self.fov_y.to_radians() * 0.5).tan();
+        let right = top * self.aspect;
+        perspective(top, -top, -right, right, self.near, self.far)
+    }
+}
+
+impl Default for Lens {
+    fn default() -> Self {
+        Self::new(60.0, 4.0 / 3.0, 0.1, 1000.0)
+    }
+}
+
+/// # Description
+/// A camera at `eye` looking at `target`
+#[derive(Copy, Clone, Debug)]
+pub struct PerspectiveCamera {
+    pub eye: Vec3<f32>,
+    pub target: Vec3<f32>,
+    pub up: Vec3<f32>,
+    pub lens: Lens,
+}
+
+impl PerspectiveCamera {
+    pub fn new<V0, V1>(eye: V0, target: V1, lens: Lens) -> Self
+    where
+        V0: Into<Vec3<f32>>,
+        V1: Into<Vec3<f32>>,
+    {
+        Self {
+            eye: eye.into(),
+            target: target.into(),
+            up: Vec3::from([0.0, 1.0, 0.0]),
+            lens,
+        }
+    }
+}
+
+impl Camera for PerspectiveCamera {
+    fn view(&self) -> Mat4<f32> {
+        look_at(self.eye, self.target, self.up)
+    }
+
+    fn projection(&self) -> Mat4<f32> {
+        self.lens.projection()
+    }
+
+    fn position(&self) -> Vec3<f32> {
+        self.eye
+    }
+}
+
+/// # Description
+/// A camera circling `target`, the usual model viewer controls
+/// # Comments
+/// - `yaw` turns around the y axis and `pitch` tilts up and down, both in radians
+/// - at a `yaw` and `pitch` of 0 the camera sits on the +z axis looking down -z
+#[derive(Copy, Clone, Debug)]
+pub struct OrbitCamera {
+    pub target: Vec3<f32>,
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub lens: Lens,
+}
+
+impl OrbitCamera {
+    pub fn new<V>(target: V, distance: f32, lens: Lens) -> Self
+    where
+        V: Into<Vec3<f32>>,
+    {
+        Self {
+            target: target.into(),
+            distance,
+            yaw: 0.0,
+            pitch: 0.0,
+            min_distance: lens.near * 2.0,
+            max_distance: lens.far * 0.5,
+            lens,
+        }
+    }
+
+    /// ## Description
+    /// turns the camera around `target`, `pitch` stops just short of straight up or down
+    pub fn rotate(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        self.yaw = (self.yaw + delta_yaw) % std::f32::consts::TAU;
+        self.pitch = (self.pitch + delta_pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// ## Description
+    /// multiplies the distance to `target` by `factor`, values below 1 move closer
+    pub fn zoom(&mut self, factor: f32) {
+        self.distance = (self.distance * factor).clamp(self.min_distance, self.max_distance);
+    }
+
+    /// ## Description
+    /// moves `target` (and with it the camera) along the camera's right and up axes
+    pub fn pan(&mut self, right: f32, up: f32) {
+        let forward = self.target - self.position();
+        let side = forward.cross(Vec3::from([0.0, 1.0, 0.0]));
+        let side = side * (1.0 / side.length().max(f32::EPSILON));
+        let camera_up = side.cross(forward) * (1.0 / forward.length().max(f32::EPSILON));
+        self.target += side * right + camera_up * up;
+    }
+}
+
+impl Camera for OrbitCamera {
+    fn view(&self) -> Mat4<f32> {
+        look_at(self.position(), self.target, Vec3::from([0.0, 1.0, 0.0]))
+    }
+
+    fn projection(&self) -> Mat4<f32> {
+        self.lens.projection()
+    }
+
+    fn position(&self) -> Vec3<f32> {
+        let (yaw_sin, yaw_cos) = self.yaw.sin_cos();
+        let (pitch_sin, pitch_cos) = self.pitch.sin_cos();
+        let offset = Vec3::from([pitch_cos * yaw_sin, pitch_sin, pitch_cos * yaw_cos]);
+        self.target + offset * self.distance
+    }
+}
+
+#[test]
+fn orbit_camera_keeps_its_target_centered() {
+    let mut camera = OrbitCamera::new(
+        [0.0, 1.0, 0.0],
+        5.0,
+        Lens::default().with_viewport(800, 600),
+    );
+    let close = |a: Vec4<f32>, b: [f32; 4]| (0..4).all(|k| (a[k] - b[k]).abs() < 1e-4);
+
+    let eye = camera.position();
+    assert!(close(Vec4::convert(eye), [0.0, 1.0, 5.0, 0.0]));
+    let target_in_view = camera
+        .view()
+        .transform(Vec4::from_array([0.0, 1.0, 0.0, 1.0]));
+    assert!(close(target_in_view, [0.0, 0.0, -5.0, 1.0]));
+
+    camera.rotate(1.0, 10.0);
+    assert!(camera.pitch < FRAC_PI_2);
+    camera.zoom(0.5);
+    assert!((camera.distance - 2.5).abs() < 1e-5);
+
+    // wherever the camera is, the target projects to the middle of the screen
+    let clip = camera
+        .view_projection()
+        .transform(Vec4::from_array([0.0, 1.0, 0.0, 1.0]));
+    assert!(clip[0].abs() < 1e-4 && clip[1].abs() < 1e-4);
+    assert!(clip[2] / clip[3] > -1.0 && clip[2] / clip[3] < 1.0);
+}
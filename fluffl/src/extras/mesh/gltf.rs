@@ -0,0 +1,601 @@
+use super::*;
+use crate::codecs::base64;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+const GLB_MAGIC: &[u8] = b"glTF";
+const GLB_CHUNK_JSON: u32 = 0x4E4F534A;
+const GLB_CHUNK_BIN: u32 = 0x004E4942;
+
+/// ## Description
+/// loads the meshes of a glTF 2.0 file, either a `.gltf` (json) or a `.glb` (binary) one
+/// ## Comments
+/// - buffers have to be embedded, as base64 `data:` uris or the binary chunk of a `.glb`
+/// - the nodes of the default scene are walked and their transforms baked into the vertices,
+///   files without scenes get every mesh untransformed
+/// - all triangle primitives of a mesh end up in one `MeshData`, other primitive modes are skipped
+/// - materials, skins and morph targets are ignored
+pub fn load_gltf(bytes: &[u8]) -> Result<Vec<MeshData>, MeshError> {
+    let (json, binary_chunk) = if bytes.starts_with(GLB_MAGIC) {
+        split_glb(bytes)?
+    } else {
+        (bytes, None)
+    };
+    let document: GltfDocument =
+        serde_json::from_slice(json).map_err(|err| MeshError::Gltf(err.to_string()))?;
+    let buffers = document
+        .buffers
+        .iter()
+        .enumerate()
+        .map(|(index, buffer)| load_buffer(buffer, index, binary_chunk))
+        .collect::<Result<Vec<_>, _>>()?;
+    let loader = GltfLoader {
+        document: &document,
+        buffers,
+    };
+
+    let roots = document
+        .scene
+        .or_else(|| (!document.scenes.is_empty()).then_some(0))
+        .map(|scene| {
+            document
+                .scenes
+                .get(scene)
+                .map(|scene| scene.nodes.clone())
+                .ok_or_else(|| MeshError::Gltf(format!("scene {} doesn't exist", scene)))
+        })
+        .transpose()?;
+
+    let mut meshes = Vec::new();
+    match roots {
+        Some(roots) => {
+            for node in roots {
+                loader.walk(node, Mat4::identity(), 0, &mut meshes)?;
+            }
+        }
+        None => {
+            for (index, mesh) in document.meshes.iter().enumerate() {
+                let name = mesh
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("mesh{}", index));
+                meshes.push(loader.mesh(index, name, None)?);
+            }
+        }
+    }
+    Ok(meshes)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GltfDocument {
+    scene: Option<usize>,
+    #[serde(default)]
+    scenes: Vec<GltfScene>,
+    #[serde(default)]
+    nodes: Vec<GltfNode>,
+    #[serde(default)]
+    meshes: Vec<GltfMesh>,
+    #[serde(default)]
+    accessors: Vec<GltfAccessor>,
+    #[serde(default)]
+    buffer_views: Vec<GltfBufferView>,
+    #[serde(default)]
+    buffers: Vec<GltfBuffer>,
+}
+
+#[derive(Deserialize)]
+struct GltfScene {
+    #[serde(default)]
+    nodes: Vec<usize>,
+}
+
+#[derive(Deserialize)]
+struct GltfNode {
+    name: Option<String>,
+    mesh: Option<usize>,
+    #[serde(default)]
+    children: Vec<usize>,
+    /// column-major
+    matrix: Option<[f32; 16]>,
+    translation: Option<[f32; 3]>,
+    /// quaternion as `[x, y, z, w]`
+    rotation: Option<[f32; 4]>,
+    scale: Option<[f32; 3]>,
+}
+
+#[derive(Deserialize)]
+struct GltfMesh {
+    name: Option<String>,
+    primitives: Vec<GltfPrimitive>,
+}
+
+#[derive(Deserialize)]
+struct GltfPrimitive {
+    attributes: HashMap<String, usize>,
+    indices: Option<usize>,
+    mode: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GltfAccessor {
+    buffer_view: Option<usize>,
+    #[serde(default)]
+    byte_offset: usize,
+    component_type: u32,
+    #[serde(default)]
+    normalized: bool,
+    count: usize,
+    #[serde(rename = "type")]
+    kind: String,
+    sparse: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GltfBufferView {
+    buffer: usize,
+    #[serde(default)]
+    byte_offset: usize,
+    byte_length: usize,
+    byte_stride: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GltfBuffer {
+    uri: Option<String>,
+    byte_length: usize,
+}
+
+/// the json chunk and the (optional) binary chunk of a `.glb`
+fn split_glb(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>), MeshError> {
+    let read_u32 = |offset: usize| {
+        bytes
+            .get(offset..offset + 4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .ok_or_else(|| MeshError::Gltf(String::from("glb is truncated")))
+    };
+    if read_u32(4)? != 2 {
+        return Err(MeshError::UnsupportedGltf(String::from("glb version")));
+    }
+    let length = (read_u32(8)? as usize).min(bytes.len());
+
+    let mut json = None;
+    let mut binary = None;
+    let mut cursor = 12;
+    while cursor + 8 <= length {
+        let chunk_length = read_u32(cursor)? as usize;
+        let chunk_type = read_u32(cursor + 4)?;
+        let chunk = bytes
+            .get(cursor + 8..cursor + 8 + chunk_length)
+            .ok_or_else(|| MeshError::Gltf(String::from("glb chunk is truncated")))?;
+        match chunk_type {
+            GLB_CHUNK_JSON if json.is_none() => json = Some(chunk),
+            GLB_CHUNK_BIN if binary.is_none() => binary = Some(chunk),
+            _ => (),
+        }
+        cursor += 8 + chunk_length;
+    }
+    let json = json.ok_or_else(|| MeshError::Gltf(String::from("glb has no json chunk")))?;
+    Ok((json, binary))
+}
+
+fn load_buffer(
+    buffer: &GltfBuffer,
+    index: usize,
+    binary_chunk: Option<&[u8]>,
+) -> Result<Vec<u8>, MeshError> {
+    let data = match (&buffer.uri, binary_chunk) {
+        (None, Some(chunk)) if index == 0 => chunk.to_vec(),
+        (None, _) => {
+            return Err(MeshError::Gltf(format!("buffer {} has no data", index)));
+        }
+        (Some(uri), _) => {
+            let encoded = uri
+                .strip_prefix("data:")
+                .and_then(|uri| uri.split_once(";base64,"))
+                .map(|(_, encoded)| encoded)
+                .ok_or_else(|| MeshError::UnsupportedGltf(format!("external buffer '{}'", uri)))?;
+            base64::decode(encoded).map_err(|err| MeshError::Gltf(err.to_string()))?
+        }
+    };
+    if data.len() < buffer.byte_length {
+        return Err(MeshError::Gltf(format!("buffer {} is truncated", index)));
+    }
+    Ok(data)
+}
+
+struct GltfLoader<'a> {
+    document: &'a GltfDocument,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl<'a> GltfLoader<'a> {
+    fn walk(
+        &self,
+        node_index: usize,
+        parent: Mat4<f32>,
+        depth: usize,
+        meshes: &mut Vec<MeshData>,
+    ) -> Result<(), MeshError> {
+        // a valid file is a forest, this only stops malformed ones from recursing forever
+        if depth > self.document.nodes.len() {
+            return Err(MeshError::Gltf(String::from("node hierarchy has a cycle")));
+        }
+        let node = self
+            .document
+            .nodes
+            .get(node_index)
+            .ok_or_else(|| MeshError::Gltf(format!("node {} doesn't exist", node_index)))?;
+        let world = parent * local_transform(node);
+
+        if let Some(mesh) = node.mesh {
+            let name = node
+                .name
+                .clone()
+                .or_else(|| self.document.meshes.get(mesh)?.name.clone())
+                .unwrap_or_else(|| format!("node{}", node_index));
+            meshes.push(self.mesh(mesh, name, Some(world))?);
+        }
+        for &child in &node.children {
+            self.walk(child, world, depth + 1, meshes)?;
+        }
+        Ok(())
+    }
+
+    fn mesh(
+        &self,
+        mesh_index: usize,
+        name: String,
+        transform: Option<Mat4<f32>>,
+    ) -> Result<MeshData, MeshError> {
+        let mesh = self
+            .document
+            .meshes
+            .get(mesh_index)
+            .ok_or_else(|| MeshError::Gltf(format!("mesh {} doesn't exist", mesh_index)))?;
+        let mut data = MeshData {
+            name,
+            ..MeshData::default()
+        };
+        let mut has_normals = false;
+        let mut has_uvs = false;
+
+        for primitive in &mesh.primitives {
+            if primitive.mode.unwrap_or(glow::TRIANGLES) != glow::TRIANGLES {
+                continue;
+            }
+            let attribute = |name: &str| primitive.attributes.get(name).copied();
+            let position_accessor = attribute("POSITION")
+                .ok_or_else(|| MeshError::Gltf(String::from("primitive has no POSITION")))?;
+            let positions = self.read_floats::<3>(position_accessor)?;
+            let vertex_count = positions.len();
+            let normals = attribute("NORMAL")
+                .map(|accessor| self.read_floats::<3>(accessor))
+                .transpose()?;
+            let uvs = attribute("TEXCOORD_0")
+                .map(|accessor| self.read_floats::<2>(accessor))
+                .transpose()?;
+            let indices = match primitive.indices {
+                Some(accessor) => self.read_indices(accessor, vertex_count)?,
+                None => (0..vertex_count as u32).collect(),
+            };
+
+            let base = data.positions.len() as u32;
+            has_normals |= normals.is_some();
+            has_uvs |= uvs.is_some();
+            data.positions.extend(positions);
+            data.normals
+                .extend(normals.unwrap_or_else(|| vec![[0.0; 3]; vertex_count]));
+            data.uvs
+                .extend(uvs.unwrap_or_else(|| vec![[0.0; 2]; vertex_count]));
+            data.indices.extend(indices.into_iter().map(|i| base + i));
+        }
+
+        if let Some(transform) = transform {
+            bake_transform(&mut data, transform);
+        }
+        if !has_uvs {
+            data.uvs.clear();
+        }
+        if !has_normals {
+            data.compute_normals();
+        }
+        Ok(data)
+    }
+
+    fn read_floats<const N: usize>(&self, accessor: usize) -> Result<Vec<[f32; N]>, MeshError> {
+        let view = self.view(accessor, N)?;
+        Ok((0..view.count)
+            .map(|element| {
+                let mut value = [0.0; N];
+                for (component, out) in value.iter_mut().enumerate() {
+                    *out = view.float(element, component);
+                }
+                value
+            })
+            .collect())
+    }
+
+    fn read_indices(&self, accessor: usize, vertex_count: usize) -> Result<Vec<u32>, MeshError> {
+        let view = self.view(accessor, 1)?;
+        if ![
+            glow::UNSIGNED_BYTE,
+            glow::UNSIGNED_SHORT,
+            glow::UNSIGNED_INT,
+        ]
+        .contains(&view.component_type)
+        {
+            return Err(MeshError::Gltf(String::from("indices have to be unsigned")));
+        }
+        let indices: Vec<u32> = (0..view.count).map(|i| view.unsigned(i, 0)).collect();
+        if indices.iter().any(|&index| index as usize >= vertex_count) {
+            return Err(MeshError::Gltf(String::from("index is out of range")));
+        }
+        Ok(indices)
+    }
+
+    /// checks that accessor `index` has `components` components and fits inside its buffer
+    fn view(&self, index: usize, components: usize) -> Result<AccessorView<'_>, MeshError> {
+        let accessor = self
+            .document
+            .accessors
+            .get(index)
+            .ok_or_else(|| MeshError::Gltf(format!("accessor {} doesn't exist", index)))?;
+        if accessor.sparse.is_some() {
+            return Err(MeshError::UnsupportedGltf(String::from("sparse accessor")));
+        }
+        let expected = match components {
+            1 => "SCALAR",
+            2 => "VEC2",
+            3 => "VEC3",
+            _ => "VEC4",
+        };
+        if accessor.kind != expected {
+            return Err(MeshError::Gltf(format!(
+                "accessor {} is a {}, expected a {}",
+                index, accessor.kind, expected
+            )));
+        }
+        let component_size = match accessor.component_type {
+            glow::BYTE | glow::UNSIGNED_BYTE => 1,
+            glow::SHORT | glow::UNSIGNED_SHORT => 2,
+            glow::UNSIGNED_INT | glow::FLOAT => 4,
+            other => {
+                return Err(MeshError::Gltf(format!("unknown component type {}", other)));
+            }
+        };
+        let buffer_view = accessor
+            .buffer_view
+            .and_then(|view| self.document.buffer_views.get(view))
+            .ok_or_else(|| MeshError::UnsupportedGltf(String::from("accessor without a view")))?;
+        let buffer = self.buffers.get(buffer_view.buffer).ok_or_else(|| {
+            MeshError::Gltf(format!("buffer {} doesn't exist", buffer_view.buffer))
+        })?;
+        let bytes = buffer_view
+            .byte_offset
+            .checked_add(buffer_view.byte_length)
+            .and_then(|view_end| buffer.get(buffer_view.byte_offset..view_end))
+            .ok_or_else(|| MeshError::Gltf(String::from("buffer view is out of bounds")))?;
+
+        let element_size = component_size * components;
+        let stride = buffer_view.byte_stride.unwrap_or(element_size);
+        if stride < element_size {
+            return Err(MeshError::Gltf(format!(
+                "accessor {} has elements overlapping each other",
+                index
+            )));
+        }
+        // every element has to fit inside the view, which also keeps a bogus `count` from
+        // allocating more than the file could ever hold
+        let end = match accessor.count {
+            0 => Some(0),
+            count => stride
+                .checked_mul(count - 1)
+                .and_then(|last| last.checked_add(accessor.byte_offset))
+                .and_then(|last| last.checked_add(element_size)),
+        };
+        if !matches!(end, Some(end) if end <= bytes.len()) {
+            return Err(MeshError::Gltf(format!(
+                "accessor {} is out of bounds",
+                index
+            )));
+        }
+        Ok(AccessorView {
+            bytes,
+            offset: accessor.byte_offset,
+            stride,
+            count: accessor.count,
+            component_type: accessor.component_type,
+            component_size,
+            normalized: accessor.normalized,
+        })
+    }
+}
+
+struct AccessorView<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    stride: usize,
+    count: usize,
+    component_type: u32,
+    component_size: usize,
+    normalized: bool,
+}
+
+impl<'a> AccessorView<'a> {
+    fn raw(&self, element: usize, component: usize) -> [u8; 4] {
+        let start = self.offset + self.stride * element + self.component_size * component;
+        let mut raw = [0; 4];
+        raw[..self.component_size].copy_from_slice(&self.bytes[start..start + self.component_size]);
+        raw
+    }
+
+    fn unsigned(&self, element: usize, component: usize) -> u32 {
+        let [a, b, c, d] = self.raw(element, component);
+        match self.component_type {
+            glow::UNSIGNED_BYTE => a as u32,
+            glow::UNSIGNED_SHORT => u16::from_le_bytes([a, b]) as u32,
+            _ => u32::from_le_bytes([a, b, c, d]),
+        }
+    }
+
+    /// reads a component as a float, normalized integers are mapped to `[0,1]` or `[-1,1]`
+    fn float(&self, element: usize, component: usize) -> f32 {
+        let [a, b, c, d] = self.raw(element, component);
+        let (value, max) = match self.component_type {
+            glow::FLOAT => return f32::from_le_bytes([a, b, c, d]),
+            glow::BYTE => (a as i8 as f32, i8::MAX as f32),
+            glow::UNSIGNED_BYTE => (a as f32, u8::MAX as f32),
+            glow::SHORT => (i16::from_le_bytes([a, b]) as f32, i16::MAX as f32),
+            glow::UNSIGNED_SHORT => (u16::from_le_bytes([a, b]) as f32, u16::MAX as f32),
+            _ => (u32::from_le_bytes([a, b, c, d]) as f32, u32::MAX as f32),
+        };
+        if self.normalized {
+            (value / max).max(-1.0)
+        } else {
+            value
+        }
+    }
+}
+
+/// the transform of `node` relative to its parent, in row-major like the rest of `math`
+#[rustfmt::skip]
+fn local_transform(node: &GltfNode) -> Mat4<f32> {
+    if let Some(m) = node.matrix {
+        return Mat4::new().with_data([
+            [m[0], m[4], m[8] , m[12]],
+            [m[1], m[5], m[9] , m[13]],
+            [m[2], m[6], m[10], m[14]],
+            [m[3], m[7], m[11], m[15]],
+        ]);
+    }
+    let [tx, ty, tz] = node.translation.unwrap_or([0.0; 3]);
+    let [x, y, z, w] = node.rotation.unwrap_or([0.0, 0.0, 0.0, 1.0]);
+    let [sx, sy, sz] = node.scale.unwrap_or([1.0; 3]);
+    let rotation = Mat4::new().with_data([
+        [1.0 - 2.0*(y*y + z*z), 2.0*(x*y - z*w)      , 2.0*(x*z + y*w)      , 0.0],
+        [2.0*(x*y + z*w)      , 1.0 - 2.0*(x*x + z*z), 2.0*(y*z - x*w)      , 0.0],
+        [2.0*(x*z - y*w)      , 2.0*(y*z + x*w)      , 1.0 - 2.0*(x*x + y*y), 0.0],
+        [0.0                  , 0.0                  , 0.0                  , 1.0],
+    ]);
+    translate4(Vec4::from_array([tx, ty, tz, 1.0]))
+        * rotation
+        * scale4(Vec4::from_array([sx, sy, sz, 1.0]))
+}
+
+/// moves the vertices of `data` into world space, normals go through the inverse transpose
+fn bake_transform(data: &mut MeshData, transform: Mat4<f32>) {
+    let normal_matrix = transform
+        .decomp_plu(None)
+        .map(|plu| plu.invert().transpose())
+        .unwrap_or(transform);
+    for position in data.positions.iter_mut() {
+        let [x, y, z] = *position;
+        let moved = transform.transform(Vec4::from_array([x, y, z, 1.0]));
+        *position = [moved[0], moved[1], moved[2]];
+    }
+    for normal in data.normals.iter_mut() {
+        let [x, y, z] = *normal;
+        let turned =
+            Vec3::<f32>::convert(normal_matrix.transform(Vec4::from_array([x, y, z, 0.0])));
+        let length = turned.length();
+        if length > f32::EPSILON {
+            *normal = *(turned * (1.0 / length)).data();
+        }
+    }
+}
+
+#[cfg(test)]
+fn triangle_gltf(uri: Option<&str>, byte_length: usize) -> String {
+    let uri = uri
+        .map(|uri| format!(r#""uri": "{}","#, uri))
+        .unwrap_or_default();
+    format!(
+        r#"{{
+            "asset": {{ "version": "2.0" }},
+            "scene": 0,
+            "scenes": [{{ "nodes": [0] }}],
+            "nodes": [{{ "name": "root", "translation": [0, 0, -5], "children": [1] }},
+                      {{ "mesh": 0, "scale": [2, 2, 2] }}],
+            "meshes": [{{ "name": "triangle", "primitives": [{{
+                "attributes": {{ "POSITION": 0, "TEXCOORD_0": 1 }},
+                "indices": 2
+            }}] }}],
+            "accessors": [
+                {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" }},
+                {{ "bufferView": 1, "componentType": 5121, "normalized": true, "count": 3, "type": "VEC2" }},
+                {{ "bufferView": 2, "componentType": 5123, "count": 3, "type": "SCALAR" }}
+            ],
+            "bufferViews": [
+                {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+                {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }},
+                {{ "buffer": 0, "byteOffset": 42, "byteLength": 6 }}
+            ],
+            "buffers": [{{ {} "byteLength": {} }}]
+        }}"#,
+        uri, byte_length
+    )
+}
+
+#[test]
+fn gltf_and_glb_load_the_same_triangle() {
+    let mut buffer = Vec::new();
+    for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+        buffer.extend(value.to_le_bytes());
+    }
+    buffer.extend([0, 0, 255, 0, 0, 255]);
+    for index in [0u16, 1, 2] {
+        buffer.extend(index.to_le_bytes());
+    }
+
+    let data_uri = format!(
+        "data:application/octet-stream;base64,{}",
+        base64::encode(&buffer)
+    );
+    let text = triangle_gltf(Some(&data_uri), buffer.len());
+    let from_text = load_gltf(text.as_bytes()).unwrap();
+
+    let mut json = triangle_gltf(None, buffer.len()).into_bytes();
+    json.resize(json.len().next_multiple_of(4), b' ');
+    buffer.resize(buffer.len().next_multiple_of(4), 0);
+    let mut glb = Vec::new();
+    glb.extend(GLB_MAGIC);
+    glb.extend(2u32.to_le_bytes());
+    glb.extend((12 + 8 + json.len() as u32 + 8 + buffer.len() as u32).to_le_bytes());
+    glb.extend((json.len() as u32).to_le_bytes());
+    glb.extend(GLB_CHUNK_JSON.to_le_bytes());
+    glb.extend(&json);
+    glb.extend((buffer.len() as u32).to_le_bytes());
+    glb.extend(GLB_CHUNK_BIN.to_le_bytes());
+    glb.extend(&buffer);
+    let from_glb = load_gltf(&glb).unwrap();
+
+    assert_eq!(from_text, from_glb);
+    assert_eq!(from_text.len(), 1);
+    let triangle = &from_text[0];
+    // the child node has no name, so the mesh's is used
+    assert_eq!(triangle.name, "triangle");
+    assert_eq!(triangle.indices, vec![0, 1, 2]);
+    assert_eq!(
+        triangle.positions,
+        vec![[0.0, 0.0, -5.0], [2.0, 0.0, -5.0], [0.0, 2.0, -5.0]]
+    );
+    assert_eq!(triangle.uvs[1], [1.0, 0.0]);
+    assert_eq!(triangle.normals[0], [0.0, 0.0, 1.0]);
+
+    // accessors claiming more elements than their view holds are rejected before reading
+    let huge = text.replacen("\"count\": 3", "\"count\": 18446744073709551615", 1);
+    assert!(matches!(
+        load_gltf(huge.as_bytes()),
+        Err(MeshError::Gltf(_))
+    ));
+
+    let external = triangle_gltf(Some("triangle.bin"), buffer.len());
+    assert!(matches!(
+        load_gltf(external.as_bytes()),
+        Err(MeshError::UnsupportedGltf(_))
+    ));
+}
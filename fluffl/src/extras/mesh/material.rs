@@ -0,0 +1,188 @@
+use super::*;
+use crate::ogl::reflection::*;
+
+static PHONG_PROGRAM_SOURCE: &str = "
+    #ifndef HEADER
+        #version 300 es
+        precision mediump float;
+    #endif
+
+    #ifndef UNIFORMS
+        uniform mat4 model;
+        uniform mat4 view_projection;
+        uniform mat4 normal_matrix;
+        uniform vec3 camera_position;
+        uniform vec3 light_direction;
+        uniform vec3 light_color;
+        uniform vec3 ambient_color;
+        uniform vec3 diffuse_color;
+        uniform vec3 specular_color;
+        uniform float shininess;
+    #endif
+
+    #ifndef VERTEX_ATTRIBUTES
+        layout (location = 0) in vec3 position_in;
+        layout (location = 1) in vec3 normal_in;
+    #endif
+
+    #ifndef VERTEX_SHADER
+        out vec3 world_position;
+        out vec3 normal;
+        void main(){
+            vec4 world = model*vec4(position_in,1.);
+            world_position = world.xyz;
+            normal = mat3(normal_matrix)*normal_in;
+            gl_Position = view_projection*world;
+        }
+    #endif
+
+    #ifndef FRAGMENT_SHADER
+        in vec3 world_position;
+        in vec3 normal;
+        out vec4 color;
+        void main(){
+            vec3 n = normalize(normal);
+            vec3 to_light = normalize(-light_direction);
+            vec3 to_camera = normalize(camera_position - world_position);
+            vec3 halfway = normalize(to_light + to_camera);
+
+            float diffuse = max(dot(n,to_light),0.);
+            float specular = diffuse > 0. ? pow(max(dot(n,halfway),0.),shininess) : 0.;
+            vec3 lit = ambient_color*diffuse_color
+                + light_color*(diffuse_color*diffuse + specular_color*specular);
+            color = vec4(lit,1.);
+        }
+    #endif
+";
+
+/// # Description
+/// The surface of a mesh lit by `PhongShader`
+/// # Comments
+/// - colors are linear rgb
+/// - `shininess` is the specular exponent, higher values give smaller, sharper highlights
+#[derive(Copy, Clone, Debug)]
+pub struct PhongMaterial {
+    pub diffuse: Vec3<f32>,
+    pub specular: Vec3<f32>,
+    pub shininess: f32,
+}
+
+impl PhongMaterial {
+    pub fn new<V>(diffuse: V) -> Self
+    where
+        V: Into<Vec3<f32>>,
+    {
+        Self {
+            diffuse: diffuse.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_specular<V>(mut self, specular: V, shininess: f32) -> Self
+    where
+        V: Into<Vec3<f32>>,
+    {
+        self.specular = specular.into();
+        self.shininess = shininess;
+        self
+    }
+}
+
+impl Default for PhongMaterial {
+    fn default() -> Self {
+        Self {
+            diffuse: Vec3::from([0.8, 0.8, 0.8]),
+            specular: Vec3::from([0.5, 0.5, 0.5]),
+            shininess: 32.0,
+        }
+    }
+}
+
+/// # Description
+/// A light infinitely far away, like the sun
+/// # Comments
+/// - `direction` is the way the light travels, it doesn't need to be normalized
+/// - `ambient` is added to every surface regardless of where it faces
+#[derive(Copy, Clone, Debug)]
+pub struct DirectionalLight {
+    pub direction: Vec3<f32>,
+    pub color: Vec3<f32>,
+    pub ambient: Vec3<f32>,
+}
+
+impl Default for DirectionalLight {
+    fn default() -> Self {
+        Self {
+            direction: Vec3::from([-0.3, -1.0, -0.5]),
+            color: Vec3::from([1.0, 1.0, 1.0]),
+            ambient: Vec3::from([0.1, 0.1, 0.1]),
+        }
+    }
+}
+
+/// # Description
+/// Draws meshes with Blinn-Phong shading from a single directional light
+/// # Comments
+/// - depth testing is turned on while drawing and restored afterwards, blending is left alone
+/// - shaders this uses will need opengl 3.0 / webgl 2 in order to work
+pub struct PhongShader {
+    gl: GlowGL,
+    program: OglProg,
+}
+
+impl PhongShader {
+    pub fn new(gl: &GlowGL) -> Self {
+        let program = match OglProg::compile_program(gl, PHONG_PROGRAM_SOURCE) {
+            Ok(a) => a,
+            Err(err) => panic!("{}", err),
+        };
+        Self {
+            gl: gl.clone(),
+            program,
+        }
+    }
+
+    pub fn program(&self) -> &OglProg {
+        &self.program
+    }
+
+    /// ## Description
+    /// draws `mesh` placed in the world by `model` (row-major)
+    /// ## Comments
+    /// - only fails if a uniform can't be set, which means the program was swapped out underneath
+    pub fn draw<C>(
+        &self,
+        mesh: &Mesh,
+        model: Mat4<f32>,
+        material: &PhongMaterial,
+        light: &DirectionalLight,
+        camera: &C,
+    ) -> Result<(), UniformError>
+    where
+        C: Camera,
+    {
+        let normal_matrix = model
+            .decomp_plu(None)
+            .map(|plu| plu.invert().transpose())
+            .unwrap_or(model);
+        let program = &self.program;
+        program.set_uniform("model", model)?;
+        program.set_uniform("view_projection", camera.view_projection())?;
+        program.set_uniform("normal_matrix", normal_matrix)?;
+        program.set_uniform("camera_position", camera.position())?;
+        program.set_uniform("light_direction", light.direction)?;
+        program.set_uniform("light_color", light.color)?;
+        program.set_uniform("ambient_color", light.ambient)?;
+        program.set_uniform("diffuse_color", material.diffuse)?;
+        program.set_uniform("specular_color", material.specular)?;
+        program.set_uniform("shininess", material.shininess)?;
+
+        GlStateCache::with(&self.gl, |state| {
+            state.push();
+            state.set_depth(DepthState::LESS_EQUAL);
+            mesh.draw();
+            state.pop();
+        });
+        Ok(())
+    }
+}
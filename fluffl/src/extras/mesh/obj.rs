@@ -0,0 +1,226 @@
+use super::*;
+use std::collections::HashMap;
+
+/// ## Description
+/// parses a Wavefront OBJ file, every `o`/`g` section becomes its own mesh
+/// ## Comments
+/// - polygons are triangulated as fans, negative (relative) indices are supported
+/// - texture coordinates get flipped vertically so they match glTF (origin in the top-left corner)
+/// - sections without normals get smooth ones from `MeshData::compute_normals()`
+/// - materials (`mtllib`, `usemtl`), lines and points are ignored
+pub fn load_obj(source: &str) -> Result<Vec<MeshData>, MeshError> {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut meshes = Vec::new();
+    let mut current = ObjSection::new("");
+
+    for (line_index, line) in source.lines().enumerate() {
+        let error = |reason: String| MeshError::Obj {
+            line: line_index + 1,
+            reason,
+        };
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        match keyword {
+            "v" => {
+                let [x, y, z] = parse_floats(tokens).map_err(error)?;
+                positions.push([x, y, z]);
+            }
+            "vt" => {
+                let [u, v] = parse_floats(tokens).map_err(error)?;
+                uvs.push([u, 1.0 - v]);
+            }
+            "vn" => {
+                let [x, y, z] = parse_floats(tokens).map_err(error)?;
+                normals.push([x, y, z]);
+            }
+            "f" => {
+                let corners = tokens
+                    .map(|token| parse_corner(token, &positions, &uvs, &normals))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(error)?;
+                if corners.len() < 3 {
+                    return Err(error(String::from("faces need at least 3 vertices")));
+                }
+                let corners: Vec<u32> = corners
+                    .into_iter()
+                    .map(|corner| current.vertex(corner, &positions, &uvs, &normals))
+                    .collect();
+                for k in 1..corners.len() - 1 {
+                    current
+                        .data
+                        .indices
+                        .extend([corners[0], corners[k], corners[k + 1]]);
+                }
+            }
+            "o" | "g" => {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                let previous = std::mem::replace(&mut current, ObjSection::new(&name));
+                meshes.extend(previous.finish());
+            }
+            _ => (),
+        }
+    }
+    meshes.extend(current.finish());
+    Ok(meshes)
+}
+
+/// a face corner, indices into the position, uv and normal lists
+type Corner = (usize, Option<usize>, Option<usize>);
+
+struct ObjSection {
+    data: MeshData,
+    vertices: HashMap<Corner, u32>,
+    has_uvs: bool,
+    has_normals: bool,
+}
+
+impl ObjSection {
+    fn new(name: &str) -> Self {
+        Self {
+            data: MeshData {
+                name: String::from(name),
+                ..MeshData::default()
+            },
+            vertices: HashMap::new(),
+            has_uvs: false,
+            has_normals: false,
+        }
+    }
+
+    /// index of the vertex for `corner`, corners sharing all three indices share a vertex
+    fn vertex(
+        &mut self,
+        corner: Corner,
+        positions: &[[f32; 3]],
+        uvs: &[[f32; 2]],
+        normals: &[[f32; 3]],
+    ) -> u32 {
+        let data = &mut self.data;
+        let (position, uv, normal) = corner;
+        self.has_uvs |= uv.is_some();
+        self.has_normals |= normal.is_some();
+        *self.vertices.entry(corner).or_insert_with(|| {
+            data.positions.push(positions[position]);
+            data.uvs.push(uv.map(|uv| uvs[uv]).unwrap_or_default());
+            data.normals
+                .push(normal.map(|normal| normals[normal]).unwrap_or_default());
+            data.positions.len() as u32 - 1
+        })
+    }
+
+    fn finish(mut self) -> Option<MeshData> {
+        if self.data.indices.is_empty() {
+            return None;
+        }
+        if !self.has_uvs {
+            self.data.uvs.clear();
+        }
+        if !self.has_normals {
+            self.data.compute_normals();
+        }
+        Some(self.data)
+    }
+}
+
+fn parse_floats<'a, I, const N: usize>(mut tokens: I) -> Result<[f32; N], String>
+where
+    I: Iterator<Item = &'a str>,
+{
+    // extra values (the `w` of positions, vertex colors) are skipped
+    let mut values = [0.0; N];
+    for value in values.iter_mut() {
+        let token = tokens
+            .next()
+            .ok_or_else(|| format!("expected {} numbers", N))?;
+        *value = token
+            .parse::<f32>()
+            .map_err(|_| format!("'{}' isn't a number", token))?;
+    }
+    Ok(values)
+}
+
+/// parses `v`, `v/vt`, `v//vn` or `v/vt/vn`
+fn parse_corner(
+    token: &str,
+    positions: &[[f32; 3]],
+    uvs: &[[f32; 2]],
+    normals: &[[f32; 3]],
+) -> Result<Corner, String> {
+    let mut parts = token.split('/');
+    let position = parts
+        .next()
+        .map(|index| resolve_index(index, positions.len()))
+        .unwrap_or_else(|| Err(format!("bad face vertex '{}'", token)))?;
+    let mut optional = |len: usize| match parts.next() {
+        Some(index) if !index.is_empty() => resolve_index(index, len).map(Some),
+        _ => Ok(None),
+    };
+    let uv = optional(uvs.len())?;
+    let normal = optional(normals.len())?;
+    Ok((position, uv, normal))
+}
+
+/// OBJ indices start at 1, negative ones count back from the last element
+fn resolve_index(index: &str, len: usize) -> Result<usize, String> {
+    let value = index
+        .parse::<i64>()
+        .map_err(|_| format!("'{}' isn't an index", index))?;
+    let resolved = if value < 0 {
+        len as i64 + value
+    } else {
+        value - 1
+    };
+    if resolved < 0 || resolved >= len as i64 {
+        return Err(format!("index {} is out of range", value));
+    }
+    Ok(resolved as usize)
+}
+
+#[test]
+fn obj_faces_are_triangulated_and_deduplicated() {
+    let source = "
+        # a quad and a triangle
+        o quad
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        vt 0 0
+        vt 1 0
+        vt 1 1
+        vt 0 1
+        vn 0 0 1
+        f 1/1/1 2/2/1 3/3/1 4/4/1
+        o triangle
+        v 0 0 1
+        f -3 -2 -1
+    ";
+    let meshes = load_obj(source).unwrap();
+    assert_eq!(meshes.len(), 2);
+
+    let quad = &meshes[0];
+    assert_eq!(quad.name, "quad");
+    assert_eq!(quad.vertex_count(), 4);
+    assert_eq!(quad.indices, vec![0, 1, 2, 0, 2, 3]);
+    assert_eq!(quad.uvs[0], [0.0, 1.0]);
+    assert_eq!(quad.normals[2], [0.0, 0.0, 1.0]);
+
+    // no normals in the file, so they're computed from the winding
+    let triangle = &meshes[1];
+    assert_eq!(
+        triangle.positions,
+        vec![[1., 1., 0.], [0., 1., 0.], [0., 0., 1.]]
+    );
+    assert!(triangle.uvs.is_empty());
+    let normal = triangle.normals[0];
+    assert!((normal[1] - normal[2]).abs() < 1e-5 && normal[1] > 0.5);
+
+    let bad = load_obj("v 0 0 0\nf 1 2 3");
+    assert!(matches!(bad, Err(MeshError::Obj { line: 2, .. })));
+}
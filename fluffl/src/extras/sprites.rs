@@ -0,0 +1,512 @@
+use super::math_util::*;
+use crate::{
+    ogl::{array::*, buffer::*, program::*, texture::*, *},
+    GlowGL,
+};
+use glow::*;
+
+static SPRITE_PROGRAM_SOURCE: &str = "
+    #ifndef HEADER
+        #version 300 es
+        precision mediump float;
+    #endif
+
+    #ifndef UNIFORMS
+        uniform mat4 projection;
+        uniform sampler2D sprite_texture;
+    #endif
+
+    #ifndef VERTEX_ATTRIBUTES
+        layout (location = 0) in vec2 position_in;
+        layout (location = 1) in vec2 uv_in;
+        layout (location = 2) in vec4 tint_in;
+    #endif
+
+    #ifndef VERTEX_SHADER
+        out vec2 uv;
+        out vec4 tint;
+        void main(){
+            uv = uv_in;
+            tint = tint_in;
+            gl_Position = projection*vec4(position_in,0.,1.);
+        }
+    #endif
+
+    #ifndef FRAGMENT_SHADER
+        in vec2 uv;
+        in vec4 tint;
+        out vec4 color;
+        void main(){
+            color = texture(sprite_texture,uv)*tint;
+        }
+    #endif
+";
+
+/// how many sprites fit into the vertex buffers before the batch has to be flushed
+pub const SPRITES_PER_BATCH: usize = 2048;
+
+const VERTICES_PER_SPRITE: usize = 6;
+
+/// # Description
+/// A rectangle inside a texture, in uv coordinates where `(0,0)` is the top-left corner of the texture
+/// and `(1,1)` the bottom-right one
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct UvRect {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+impl UvRect {
+    /// the whole texture
+    pub const FULL: Self = Self {
+        min: [0.0, 0.0],
+        max: [1.0, 1.0],
+    };
+
+    /// ## Description
+    /// converts a region of an atlas given in pixels to uv coordinates
+    pub fn from_pixels(x: u32, y: u32, width: u32, height: u32, atlas_dims: (u32, u32)) -> Self {
+        let (atlas_width, atlas_height) = (atlas_dims.0 as f32, atlas_dims.1 as f32);
+        Self {
+            min: [x as f32 / atlas_width, y as f32 / atlas_height],
+            max: [
+                (x + width) as f32 / atlas_width,
+                (y + height) as f32 / atlas_height,
+            ],
+        }
+    }
+
+    /// swaps left and right
+    pub fn flipped_x(self) -> Self {
+        Self {
+            min: [self.max[0], self.min[1]],
+            max: [self.min[0], self.max[1]],
+        }
+    }
+}
+
+impl Default for UvRect {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+/// # Description
+/// A textured, tinted quad in screen space (top-left origin, y pointing down)
+/// # Comments
+/// - `origin` is the pivot, in fractions of `size`. `position` is where the pivot ends up and
+///   rotation happens around it. `[0.5,0.5]` (the default) is the center of the sprite
+/// - sprites with a higher `z` get drawn on top, sprites with equal `z` get drawn in the order they
+///   were submitted
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Sprite {
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    pub origin: [f32; 2],
+    /// in radians, clockwise on screen
+    pub rotation: f32,
+    pub uv: UvRect,
+    /// multiplied with the texture color
+    pub tint: [f32; 4],
+    pub z: f32,
+}
+
+impl Sprite {
+    pub fn new(position: [f32; 2], size: [f32; 2]) -> Self {
+        Self {
+            position,
+            size,
+            origin: [0.5, 0.5],
+            rotation: 0.0,
+            uv: UvRect::FULL,
+            tint: [1.0; 4],
+            z: 0.0,
+        }
+    }
+
+    pub fn with_uv(mut self, uv: UvRect) -> Self {
+        self.uv = uv;
+        self
+    }
+
+    pub fn with_tint(mut self, tint: [f32; 4]) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_origin(mut self, origin: [f32; 2]) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn with_z(mut self, z: f32) -> Self {
+        self.z = z;
+        self
+    }
+
+    /// the corners in screen space, in the order top-left, top-right, bottom-right, bottom-left
+    pub fn corners(&self) -> [Vec2; 4] {
+        let (sin, cos) = self.rotation.sin_cos();
+        let local = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+        local.map(|[x, y]| {
+            let dx = (x - self.origin[0]) * self.size[0];
+            let dy = (y - self.origin[1]) * self.size[1];
+            [
+                self.position[0] + dx * cos - dy * sin,
+                self.position[1] + dx * sin + dy * cos,
+            ]
+        })
+    }
+}
+
+/// Counts what the last frame cost, for profiling
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct BatchStats {
+    pub sprites: u32,
+    pub draw_calls: u32,
+    pub vertices: u32,
+}
+
+/// `projection` and `sprite_texture` of a program, as cached by `OglProg::uniform_location(..)`
+#[derive(Clone, Default)]
+struct SpriteUniforms {
+    projection: Option<glow::UniformLocation>,
+    sprite_texture: Option<glow::UniformLocation>,
+}
+
+impl SpriteUniforms {
+    fn of(program: &OglProg) -> Self {
+        Self {
+            projection: program.uniform_location("projection").cloned(),
+            sprite_texture: program.uniform_location("sprite_texture").cloned(),
+        }
+    }
+}
+
+/// a submitted sprite along with the state it has to be drawn with
+#[derive(Copy, Clone)]
+struct QueuedSprite {
+    sprite: Sprite,
+    texture: Option<glow::Texture>,
+    program: Option<glow::Program>,
+}
+
+impl QueuedSprite {
+    fn same_state(&self, other: &Self) -> bool {
+        self.texture == other.texture && self.program == other.program
+    }
+}
+
+/// # Description
+/// Draws lots of sprites with few draw calls by collecting them into big dynamic vertex buffers
+/// # Comments
+/// - call `Self::begin(..)`, submit sprites with `Self::draw(..)` and then call `Self::end()`, which
+///   sorts the sprites by `z` and issues one draw call per run of sprites sharing texture and program
+/// - `texture` is the raw GL texture (see `HasTextureObj::texture_id()`), `None` draws a plain
+///   colored quad
+/// - blending is left alone, enable it yourself (`SRC_ALPHA, ONE_MINUS_SRC_ALPHA` for straight alpha)
+/// - shaders this uses will need opengl 3.0 / webgl 2 in order to work
+pub struct SpriteBatch {
+    gl: GlowGL,
+    program: OglProg,
+    vertex_array: OglArray,
+    white_texture: TextureObj<u8>,
+    custom_program: Option<glow::Program>,
+    /// the uniforms of every program passed to `Self::set_program(..)` since `Self::begin(..)`
+    custom_uniforms: Vec<(glow::Program, SpriteUniforms)>,
+    queue: Vec<QueuedSprite>,
+    positions: Vec<f32>,
+    uvs: Vec<f32>,
+    tints: Vec<f32>,
+    stats: BatchStats,
+    window_width: f32,
+    window_height: f32,
+}
+
+impl SpriteBatch {
+    pub fn new(gl: &GlowGL) -> Self {
+        let program = match OglProg::compile_program(gl, SPRITE_PROGRAM_SOURCE) {
+            Ok(a) => a,
+            Err(err) => panic!("{}", err),
+        };
+
+        let capacity = SPRITES_PER_BATCH * VERTICES_PER_SPRITE;
+        let attribute = |index: u32, num_comps: u32| -> Box<dyn HasBufferObj> {
+            OglBuf::new(gl)
+                .with_num_comps(num_comps)
+                .with_target(glow::ARRAY_BUFFER)
+                .with_usage(glow::DYNAMIC_DRAW)
+                .with_index(index)
+                .with_data(vec![0.0f32; capacity * num_comps as usize])
+                .build()
+                .into()
+        };
+        let vertex_array = OglArray::new(gl).init(vec![
+            BufferPair::new("positions", attribute(0, 2)),
+            BufferPair::new("uvs", attribute(1, 2)),
+            BufferPair::new("tints", attribute(2, 4)),
+        ]);
+        vertex_array.bind(false);
+
+        let white_texture = TextureObj::<u8>::builder(gl)
+            .with_width(1)
+            .with_height(1)
+            .with_format(glow::RGBA)
+            .with_pixels(vec![255; 4])
+            .build();
+
+        Self {
+            gl: gl.clone(),
+            program,
+            vertex_array,
+            white_texture,
+            custom_program: None,
+            custom_uniforms: Vec::new(),
+            queue: Vec::new(),
+            positions: Vec::with_capacity(capacity * 2),
+            uvs: Vec::with_capacity(capacity * 2),
+            tints: Vec::with_capacity(capacity * 4),
+            stats: BatchStats::default(),
+            window_width: 800.0,
+            window_height: 600.0,
+        }
+    }
+
+    /// ## Description
+    /// starts a frame, `bounds` are the window dimensions in pixels
+    pub fn begin(&mut self, bounds: (u32, u32)) {
+        self.window_width = bounds.0 as f32;
+        self.window_height = bounds.1 as f32;
+        self.queue.clear();
+        self.custom_program = None;
+        self.custom_uniforms.clear();
+        self.stats = BatchStats::default();
+    }
+
+    /// ## Description
+    /// draws the sprites submitted after this call with `program` instead of the built-in one,
+    /// `None` switches back
+    /// ## Comments
+    /// - `program` has to take the same attributes as the built-in shader (`position_in` at
+    ///   location 0, `uv_in` at 1 and `tint_in` at 2), `projection` and `sprite_texture` are set if
+    ///   it has them
+    pub fn set_program(&mut self, program: Option<&OglProg>) {
+        self.custom_program = program.map(|program| program.prog());
+        if let Some(program) = program {
+            let known = self
+                .custom_uniforms
+                .iter()
+                .any(|(known, _)| *known == program.prog());
+            if !known {
+                let uniforms = SpriteUniforms::of(program);
+                self.custom_uniforms.push((program.prog(), uniforms));
+            }
+        }
+    }
+
+    /// queues `sprite`, nothing is drawn until `Self::end()`
+    pub fn draw(&mut self, texture: Option<glow::Texture>, sprite: &Sprite) {
+        self.queue.push(QueuedSprite {
+            sprite: *sprite,
+            texture,
+            program: self.custom_program,
+        });
+    }
+
+    /// ## Description
+    /// draws everything submitted since `Self::begin(..)`
+    pub fn end(&mut self) {
+        let mut queue = std::mem::take(&mut self.queue);
+        queue.sort_by(|a, b| a.sprite.z.total_cmp(&b.sprite.z));
+
+        self.vertex_array.bind(true);
+        for (start, end) in plan_batches(&queue, SPRITES_PER_BATCH) {
+            self.flush(&queue[start..end]);
+        }
+        self.vertex_array.bind(false);
+        self.program.bind(false);
+        self.stats.sprites += queue.len() as u32;
+
+        // hand the allocation back so the next frame doesn't have to grow it again
+        queue.clear();
+        self.queue = queue;
+    }
+
+    /// what the last `Self::end()` cost
+    pub fn stats(&self) -> BatchStats {
+        self.stats
+    }
+
+    /// draws a run of sprites that share texture and program with one draw call
+    fn flush(&mut self, run: &[QueuedSprite]) {
+        let first = match run.first() {
+            Some(first) => first,
+            None => return,
+        };
+
+        self.positions.clear();
+        self.uvs.clear();
+        self.tints.clear();
+        for queued in run {
+            push_sprite_vertices(
+                &queued.sprite,
+                &mut self.positions,
+                &mut self.uvs,
+                &mut self.tints,
+            );
+        }
+
+        for (name, data) in [
+            ("positions", &self.positions),
+            ("uvs", &self.uvs),
+            ("tints", &self.tints),
+        ] {
+            if let Some(buffer) = self.vertex_array.get(name) {
+                buffer.bind(true);
+                unsafe {
+                    self.gl
+                        .buffer_sub_data_u8_slice(glow::ARRAY_BUFFER, 0, data.raw_bytes());
+                }
+            }
+        }
+
+        let gl = &self.gl;
+        let program = first.program.unwrap_or_else(|| self.program.prog());
+        let uniforms = match first.program {
+            Some(custom) => self
+                .custom_uniforms
+                .iter()
+                .find(|(known, _)| *known == custom)
+                .map(|(_, uniforms)| uniforms.clone())
+                .unwrap_or_default(),
+            None => SpriteUniforms::of(&self.program),
+        };
+        let texture = first.texture.or_else(|| self.white_texture.texture_id());
+        let vertex_count = run.len() * VERTICES_PER_SPRITE;
+        GlBindings::with(gl, |bindings| bindings.use_program(Some(program)));
+        unsafe {
+            let projection = calc_proj(self.window_width, self.window_height);
+            gl.uniform_matrix_4_f32_slice(uniforms.projection.as_ref(), false, &projection[..]);
+            gl.uniform_1_i32(uniforms.sprite_texture.as_ref(), 0);
+        }
+        GlBindings::with(gl, |bindings| {
+            bindings.bind_texture(0, glow::TEXTURE_2D, texture)
+        });
+        unsafe {
+            gl.draw_arrays(glow::TRIANGLES, 0, vertex_count as i32);
+        }
+        GlBindings::with(gl, |bindings| {
+            bindings.bind_texture(0, glow::TEXTURE_2D, None)
+        });
+
+        self.stats.draw_calls += 1;
+        self.stats.vertices += vertex_count as u32;
+    }
+}
+
+/// ## Description
+/// splits the (sorted) queue into runs that can each be drawn with a single draw call
+/// ## Returns
+/// `(start, end)` index pairs, `end` is exclusive
+fn plan_batches(queue: &[QueuedSprite], capacity: usize) -> Vec<(usize, usize)> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    for index in 1..=queue.len() {
+        let is_break = index == queue.len()
+            || index - start == capacity
+            || !queue[index].same_state(&queue[start]);
+        if is_break {
+            batches.push((start, index));
+            start = index;
+        }
+    }
+    batches
+}
+
+/// writes the two triangles of `sprite`
+fn push_sprite_vertices(
+    sprite: &Sprite,
+    positions: &mut Vec<f32>,
+    uvs: &mut Vec<f32>,
+    tints: &mut Vec<f32>,
+) {
+    let corners = sprite.corners();
+    let UvRect { min, max } = sprite.uv;
+    let corner_uvs = [
+        [min[0], min[1]],
+        [max[0], min[1]],
+        [max[0], max[1]],
+        [min[0], max[1]],
+    ];
+
+    // same winding as the quads of `ShapePainter2D`
+    let (tl, tr, br, bl) = (0, 1, 2, 3);
+    for corner in [tl, bl, br, br, tr, tl] {
+        positions.extend_from_slice(&corners[corner]);
+        uvs.extend_from_slice(&corner_uvs[corner]);
+        tints.extend_from_slice(&sprite.tint);
+    }
+}
+
+#[test]
+fn sprites_batch_by_state() {
+    let sprite = |z: f32| Sprite::new([0.0, 0.0], [1.0, 1.0]).with_z(z);
+    let queued = |texture: u32, program: Option<u32>, z: f32| QueuedSprite {
+        sprite: sprite(z),
+        texture: Some(texture),
+        program,
+    };
+    let mut queue = vec![
+        queued(1, None, 0.0),
+        queued(2, None, 1.0),
+        queued(1, None, 0.0),
+        queued(1, Some(7), 0.0),
+        queued(1, None, 2.0),
+        queued(1, None, 2.0),
+    ];
+    queue.sort_by(|a, b| a.sprite.z.total_cmp(&b.sprite.z));
+    assert_eq!(
+        plan_batches(&queue, SPRITES_PER_BATCH),
+        vec![(0, 2), (2, 3), (3, 4), (4, 6)]
+    );
+    assert_eq!(plan_batches(&queue[4..], 1), vec![(0, 1), (1, 2)]);
+    assert!(plan_batches(&[], 4).is_empty());
+}
+
+#[test]
+fn sprite_vertices_follow_rotation_and_uvs() {
+    let uv = UvRect::from_pixels(32, 0, 32, 16, (64, 64));
+    assert_eq!(uv.min, [0.5, 0.0]);
+    assert_eq!(uv.max, [1.0, 0.25]);
+
+    let sprite = Sprite::new([10.0, 20.0], [4.0, 2.0])
+        .with_uv(uv)
+        .with_tint([1.0, 0.0, 0.0, 1.0]);
+    assert_eq!(
+        sprite.corners(),
+        [[8.0, 19.0], [12.0, 19.0], [12.0, 21.0], [8.0, 21.0]]
+    );
+
+    // a quarter turn around the top-left corner
+    let rotated = sprite
+        .with_origin([0.0, 0.0])
+        .with_rotation(std::f32::consts::FRAC_PI_2);
+    let corners = rotated.corners();
+    let expected = [[10.0, 20.0], [10.0, 24.0], [8.0, 24.0], [8.0, 20.0]];
+    for (corner, expected) in corners.iter().zip(expected) {
+        assert!((corner[0] - expected[0]).abs() < 1e-5 && (corner[1] - expected[1]).abs() < 1e-5);
+    }
+
+    let (mut positions, mut uvs, mut tints) = (Vec::new(), Vec::new(), Vec::new());
+    push_sprite_vertices(&sprite, &mut positions, &mut uvs, &mut tints);
+    assert_eq!(positions.len(), VERTICES_PER_SPRITE * 2);
+    assert_eq!(tints.len(), VERTICES_PER_SPRITE * 4);
+    // the second vertex is the bottom-left corner
+    assert_eq!(positions[2..4], [8.0, 21.0]);
+    assert_eq!(uvs[2..4], [0.5, 0.25]);
+}
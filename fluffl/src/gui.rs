@@ -5,8 +5,6 @@ use std::{
     vec,
 };
 
-use glow::HasContext;
-
 use crate::{
     collections::{
         fixed_stack::FixedStack,
@@ -14,19 +12,33 @@ use crate::{
     },
     math::{self, translate4, ComponentWriter, Mat4, MatStack, Vec2, Vec4, AABB2},
     mem::force_borrow_mut,
-    ogl::{self, ArrayBuilder, Bindable, BufferPair, HasBufferBuilder, OglProg},
+    ogl::{
+        self, ArrayBuilder, Bindable, BlendState, BufferPair, GlStateCache, HasBufferBuilder,
+        OglProg, OglTexture, StencilState,
+    },
     text_writer::TextWriter,
-    window::event_util::{EventKind, KeyCode},
+    window::{
+        event_util::{EventKind, KeyCode, MouseCode},
+        gesture::{self, GestureClock, GestureRecognizer},
+    },
     FlufflState, GlowGL,
 };
 
+mod accessibility;
 mod builder;
 mod components;
+mod focus;
 mod gui_key;
 mod handler_block;
+mod harness;
 mod renderer;
+mod theme;
+mod tween;
 
-pub use self::{builder::*, components::*, gui_key::*, handler_block::*, renderer::*};
+pub use self::{
+    accessibility::*, builder::*, components::*, focus::*, gui_key::*, handler_block::*,
+    harness::*, renderer::*, theme::*, tween::*,
+};
 pub type ListenerCallBack<ProgramState> =
     Box<dyn FnMut(EventListenerInfo<'_, ProgramState>) -> Option<()>>;
 
@@ -64,10 +76,11 @@ impl<ProgramState> MutationRequestQueue<ProgramState> {
 }
 
 pub struct GuiManager<ProgramState> {
-    gl: GlowGL,
+    /// `None` when the manager is headless
+    gl: Option<GlowGL>,
 
-    /// lets us actually draw stuff
-    renderer: GuiRenderer,
+    /// lets us actually draw stuff, `None` when the manager is headless
+    renderer: Option<GuiRenderer>,
 
     /// used to compute global coordinates from scene-graph
     component_transform_stack: MatStack<f32>,
@@ -106,12 +119,41 @@ pub struct GuiManager<ProgramState> {
     window_events: VecDeque<EventKind>,
 
     mutation_queue: MutationRequestQueue<ProgramState>,
+
+    /// styles every component that doesn't explicitly override its look
+    theme: Option<Theme>,
+
+    /// set when focus was last moved with the keyboard, cleared on mouse clicks
+    focus_ring_visible: bool,
+
+    /// animations that are currently running, advanced with `Self::advance_animations(..)`
+    tween_manager: TweenManager,
+
+    /// turns touch events into `EventKind::Gesture` events, which are then routed like any other event
+    gesture_recognizer: GestureRecognizer,
+
+    /// the time gestures are measured with, see `Self::set_gesture_clock(..)`
+    gesture_clock: GestureClock,
 }
 
 impl<ProgramState> GuiManager<ProgramState> {
     pub fn new(gl: GlowGL) -> Self {
+        let renderer = GuiRenderer::new(&gl);
+        Self {
+            gl: Some(gl),
+            renderer: Some(renderer),
+            ..Self::new_headless()
+        }
+    }
+
+    /// ## Description
+    /// creates a manager that has no GL context, meaning it never draws anything.
+    /// ## Comments
+    /// - events, layout and hit-testing all work the same, so this is meant for testing UI logic
+    /// - `Self::render(..)` still processes events but skips drawing, see `Self::update()`
+    pub fn new_headless() -> Self {
         Self {
-            renderer: GuiRenderer::new(&gl),
+            renderer: None,
             focused_component: None,
             clicked_component: None,
             hover_component: None,
@@ -125,11 +167,37 @@ impl<ProgramState> GuiManager<ProgramState> {
             visibility_table: Vec::new(),
             visibility_intersection_stack: FixedStack::new(),
             _clipboard: String::new(),
-            gl,
+            gl: None,
             mutation_queue: MutationRequestQueue::new(),
+            theme: None,
+            focus_ring_visible: false,
+            tween_manager: TweenManager::new(),
+            gesture_recognizer: GestureRecognizer::default(),
+            gesture_clock: gesture::wall_clock(),
         }
     }
 
+    /// ## Description
+    /// sets the theme every component gets drawn with
+    /// ## Comments
+    /// - can be called at any time, the next `Self::render(..)` restyles everything
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = Some(theme);
+    }
+
+    /// removes the current theme, components go back to being drawn with their own colors
+    pub fn clear_theme(&mut self) -> Option<Theme> {
+        self.theme.take()
+    }
+
+    pub fn theme(&self) -> Option<&Theme> {
+        self.theme.as_ref()
+    }
+
+    pub fn theme_mut(&mut self) -> Option<&mut Theme> {
+        self.theme.as_mut()
+    }
+
     pub fn clear_listeners(&mut self, key: GuiComponentKey, event: GuiEventKind) {
         self.key_to_handler_block_table
             .get_mut(&key)
@@ -153,19 +221,74 @@ impl<ProgramState> GuiManager<ProgramState> {
         self.window_events.push_back(event);
     }
 
-    pub fn render(&mut self, text_writer: &mut TextWriter, window_width: f32, window_height: f32) {
+    /// ## Description
+    /// processes every event pushed since the last update and recomputes layout (visibility and aabbs)
+    /// ## Comments
+    /// - doesn't touch GL at all, `Self::render(..)` calls this before drawing
+    pub fn update(&mut self) {
+        // long presses fire while the finger is down, so they can't wait for another touch event
+        let now = (self.gesture_clock)();
+        self.gesture_recognizer.poll(now);
+        let window_events = &mut self.window_events;
+        self.gesture_recognizer
+            .flush_iter_mut()
+            .for_each(|gesture| window_events.push_back(EventKind::Gesture { gesture }));
+
         self.handle_incoming_events();
+    }
+
+    /// the recognizer that turns touch events into `GuiEventKind::OnGesture` signals, use it to tune thresholds
+    pub fn gestures(&self) -> &GestureRecognizer {
+        &self.gesture_recognizer
+    }
+
+    pub fn gestures_mut(&mut self) -> &mut GestureRecognizer {
+        &mut self.gesture_recognizer
+    }
+
+    /// ## Description
+    /// replaces the clock gestures are timed with, by default it's `gesture::wall_clock()`
+    /// ## Comments
+    /// - `GuiHarness` installs a clock that only moves when told to, see `GuiHarness::advance_time(..)`
+    pub fn set_gesture_clock<F>(&mut self, clock: F)
+    where
+        F: FnMut() -> f64 + 'static,
+    {
+        self.gesture_clock = Box::new(clock);
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.gl.is_none()
+    }
+
+    /// the GL context the manager draws with, `None` when headless
+    pub fn gl(&self) -> Option<&GlowGL> {
+        self.gl.as_ref()
+    }
+
+    pub fn render(&mut self, text_writer: &mut TextWriter, window_width: f32, window_height: f32) {
+        self.update();
+
+        let (gl, renderer) = match (&self.gl, &self.renderer) {
+            (Some(gl), Some(renderer)) => (gl, renderer),
+            _ => return,
+        };
 
         let mut level_stack = LevelStack::new();
         let mut node_stack = NodeStack::new();
 
-        let gl = &mut self.gl;
         let transform_stack = &mut self.component_transform_stack;
 
-        let renderer = &mut self.renderer;
         let gui_component_tree = &mut self.gui_component_tree;
         let visibility_table = &mut self.visibility_table;
         let key_to_aabb_table = &mut self.key_to_aabb_table;
+        let theme = self.theme.as_ref();
+        let focused_component = self.focused_component;
+        let hover_component = self.hover_component;
+
+        let compute_style_state = |key: GuiComponentKey, comp: &dyn GuiComponent| {
+            StyleState::compute(key, *comp.flags(), focused_component, hover_component)
+        };
 
         let compute_global_position = |rel_pos, stack: &MatStack<f32>| {
             let s = stack;
@@ -183,10 +306,11 @@ impl<ProgramState> GuiManager<ProgramState> {
             }
         };
 
-        unsafe {
-            gl.enable(glow::BLEND);
-            gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
-        }
+        // whatever the gui changes gets undone once it's done drawing
+        GlStateCache::with(gl, |state| {
+            state.push();
+            state.set_blend(BlendState::ALPHA);
+        });
 
         // forced a clone the gui_component_tree because it is actually very safe mutate
         // there are very few ways in which this tree could invalidate keys
@@ -231,6 +355,9 @@ impl<ProgramState> GuiManager<ProgramState> {
                             let tree =
                                 unsafe { force_borrow_mut(gui_component_tree_borrowed_by_force) };
                             if visibility {
+                                let node_comp = tree.get_mut(node).unwrap();
+                                let style_state =
+                                    compute_style_state(node.into(), node_comp.as_ref());
                                 let state = RenderState::new(
                                     key.into(),
                                     global_position,
@@ -240,10 +367,10 @@ impl<ProgramState> GuiManager<ProgramState> {
                                     key_to_aabb_table,
                                     window_width,
                                     window_height,
+                                    theme,
+                                    style_state,
                                 );
-                                tree.get_mut(node)
-                                    .unwrap()
-                                    .render_exit(gl, state, text_writer);
+                                node_comp.render_exit(gl, state, text_writer);
                             }
                         }
                     }
@@ -269,6 +396,7 @@ impl<ProgramState> GuiManager<ProgramState> {
             };
 
             if visibility_table[key.as_usize()] {
+                let style_state = compute_style_state(key.into(), comp.as_ref());
                 comp.render_entry(
                     gl,
                     RenderState::new(
@@ -280,15 +408,69 @@ impl<ProgramState> GuiManager<ProgramState> {
                         key_to_aabb_table,
                         window_width,
                         window_height,
+                        theme,
+                        style_state,
                     ),
                     text_writer,
                 );
             }
         }
 
-        unsafe {
-            gl.disable(glow::BLEND);
+        if self.focus_ring_visible {
+            if let Some(focused_key) = focused_component {
+                Self::render_focus_ring(
+                    gl,
+                    renderer,
+                    key_to_aabb_table,
+                    visibility_table,
+                    theme,
+                    focused_key,
+                    window_width,
+                    window_height,
+                );
+            }
         }
+
+        GlStateCache::with(gl, |state| state.pop());
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_focus_ring(
+        gl: &GlowGL,
+        renderer: &GuiRenderer,
+        key_to_aabb_table: &HashMap<GuiComponentKey, AABB2<f32>>,
+        visibility_table: &[bool],
+        theme: Option<&Theme>,
+        focused_key: GuiComponentKey,
+        window_width: f32,
+        window_height: f32,
+    ) {
+        const RING_MARGIN: f32 = 3.0;
+        const RING_THICKNESS: f32 = 2.0;
+
+        let aabb = match key_to_aabb_table.get(&focused_key) {
+            Some(&aabb) if visibility_table[focused_key.as_usize()] => aabb,
+            _ => return,
+        };
+
+        let ring_color = theme
+            .and_then(|theme| theme.focus_ring_color())
+            .unwrap_or_else(|| Vec4::rgb_u32(0x2F80ED));
+        let position = aabb.min_pos - Vec2::from([RING_MARGIN; 2]);
+        let bounds = aabb.dims() + Vec2::from([RING_MARGIN * 2.0; 2]);
+
+        // the ring is drawn on top of everything so no stencil testing is done here
+        components::layer_unlock(gl);
+        renderer
+            .builder(gl, GuiShaderKind::RoundedBox)
+            .set_window(window_width, window_height)
+            .set_roundness_vec([RING_MARGIN * 2.0; 4])
+            .set_edge_color(ring_color)
+            .set_background_color(Vec4::from([0.0; 4]))
+            .set_edge_thickness(RING_THICKNESS)
+            .set_bounds(bounds)
+            .set_position(Vec4::to_pos(position), Vec4::to_pos(bounds))
+            .render();
     }
 }
 
@@ -354,6 +536,7 @@ impl<ProgramState> GuiManager<ProgramState> {
         let key_to_aabb_table = &mut self.key_to_aabb_table;
         let key_to_handler_block_table = &mut self.key_to_handler_block_table;
         let mutation_queue = &mut self.mutation_queue;
+        let tweens = &mut self.tween_manager;
 
         while let Some(signal) = component_signal_queue.pop_front() {
             let key = signal.component_key;
@@ -369,6 +552,7 @@ impl<ProgramState> GuiManager<ProgramState> {
                     gui_comp_tree: gui_component_tree,
                     key_to_aabb_table,
                     mutation_queue,
+                    tweens,
                 },
             );
         }
@@ -386,6 +570,9 @@ impl<ProgramState> GuiManager<ProgramState> {
         let visibility_table = &mut self.visibility_table;
         let visibility_intersection_stack = &mut self.visibility_intersection_stack;
         let key_to_handler_block_table = &mut self.key_to_handler_block_table;
+        let focus_ring_visible = &mut self.focus_ring_visible;
+        let gesture_recognizer = &mut self.gesture_recognizer;
+        let gesture_time = (self.gesture_clock)();
 
         while let Some(event) = window_events.pop_front() {
             let _old_signal_len = component_signal_bus.len();
@@ -404,18 +591,56 @@ impl<ProgramState> GuiManager<ProgramState> {
                     // }
 
                     if !key_down_table.contains(&code) {
-                        if let &mut Some(fkey) = focused_component {
+                        if let KeyCode::TAB = code {
+                            // Tab is consumed by the manager and never reaches components
+                            let reverse = key_down_table.contains(&KeyCode::SHIFT_L)
+                                || key_down_table.contains(&KeyCode::SHIFT_R);
+                            let tab_order = Self::compute_tab_order_from_tree(
+                                gui_component_tree,
+                                visibility_table,
+                            );
+                            let prev_focused_component = *focused_component;
+                            *focused_component =
+                                focus::step_focus(&tab_order, prev_focused_component, reverse);
+                            *focus_ring_visible = true;
+
+                            Self::push_focus_change_signals(
+                                component_signal_bus,
+                                prev_focused_component,
+                                *focused_component,
+                                event,
+                            );
+                        } else if let &mut Some(fkey) = focused_component {
                             component_signal_bus.push_back(ComponentEventSignal::new(
                                 GuiEventKind::OnKeyDown,
                                 fkey,
                                 event,
                             ));
+
+                            let is_activation_key =
+                                matches!(code, KeyCode::ENTER | KeyCode::KP_ENTER | KeyCode::SPACE);
+                            let can_activate = gui_component_tree
+                                .get(fkey)
+                                .map(|comp| comp.is_focusable() && comp.is_enabled())
+                                .unwrap_or(false);
+                            if is_activation_key && can_activate {
+                                Self::push_activation_signals(
+                                    component_signal_bus,
+                                    gui_component_tree,
+                                    key_to_handler_block_table,
+                                    key_to_aabb_table,
+                                    fkey,
+                                    event,
+                                );
+                            }
                         }
                         key_down_table.insert(code);
                     }
                 }
                 EventKind::KeyUp { code } => {
-                    if key_down_table.contains(&code) && focused_component.is_some() {
+                    let is_consumed = matches!(code, KeyCode::TAB);
+                    if key_down_table.contains(&code) && focused_component.is_some() && !is_consumed
+                    {
                         component_signal_bus.push_back(ComponentEventSignal::new(
                             GuiEventKind::OnKeyRelease,
                             focused_component.expect("focused key should exist"),
@@ -439,6 +664,7 @@ impl<ProgramState> GuiManager<ProgramState> {
                     let mouse_pos = Vec2::from([x, y]);
                     let prev_focused_component = *focused_component;
 
+                    *focus_ring_visible = false;
                     *clicked_component = None;
                     *focused_component = None;
 
@@ -454,41 +680,12 @@ impl<ProgramState> GuiManager<ProgramState> {
                         },
                     );
 
-                    // handle focused events
-                    match (prev_focused_component, *focused_component) {
-                        (None, Some(cur_key)) => {
-                            component_signal_bus.push_back(ComponentEventSignal::new(
-                                GuiEventKind::OnFocusIn,
-                                cur_key,
-                                event,
-                            ));
-                        }
-                        (Some(prev_key), None) => {
-                            component_signal_bus.push_back(ComponentEventSignal::new(
-                                GuiEventKind::OnFocusOut,
-                                prev_key,
-                                event,
-                            ));
-                        }
-                        (Some(prev_key), Some(cur_key)) => {
-                            if prev_key != cur_key {
-                                component_signal_bus.push_back(ComponentEventSignal::new(
-                                    GuiEventKind::OnFocusOut,
-                                    prev_key,
-                                    event,
-                                ));
-                                component_signal_bus.push_back(ComponentEventSignal::new(
-                                    GuiEventKind::OnFocusIn,
-                                    cur_key,
-                                    event,
-                                ));
-                            }
-                        }
-
-                        (None, None) => {
-                            // do nothing
-                        }
-                    }
+                    Self::push_focus_change_signals(
+                        component_signal_bus,
+                        prev_focused_component,
+                        *focused_component,
+                        event,
+                    );
 
                     if let &mut Some(clicked) = clicked_component {
                         component_signal_bus.push_back(ComponentEventSignal::new(
@@ -544,6 +741,54 @@ impl<ProgramState> GuiManager<ProgramState> {
                         );
                     }
                 }
+                EventKind::TextInput { .. } | EventKind::ImeCommit { .. } => {
+                    if let &mut Some(focused_key) = focused_component {
+                        component_signal_bus.push_back(ComponentEventSignal::new(
+                            GuiEventKind::OnTextInput,
+                            focused_key,
+                            event,
+                        ));
+                    }
+                }
+                EventKind::ImePreeditStart | EventKind::ImePreeditUpdate { .. } => {
+                    if let &mut Some(focused_key) = focused_component {
+                        component_signal_bus.push_back(ComponentEventSignal::new(
+                            GuiEventKind::OnComposition,
+                            focused_key,
+                            event,
+                        ));
+                    }
+                }
+                EventKind::TouchDown { .. }
+                | EventKind::TouchMove { .. }
+                | EventKind::TouchUp { .. } => {
+                    // recognized gestures go to the back of the queue and get routed below
+                    gesture_recognizer.handle_event(&event, gesture_time);
+                    gesture_recognizer.flush_iter_mut().for_each(|gesture| {
+                        window_events.push_back(EventKind::Gesture { gesture })
+                    });
+                }
+                EventKind::Gesture { gesture } => {
+                    let mut target = None;
+                    Self::point_in_aabb_cumulative_intersections(
+                        gui_component_tree,
+                        key_to_aabb_table,
+                        visibility_table,
+                        visibility_intersection_stack,
+                        Vec2::from(gesture.pos()),
+                        |key| target = Some(key),
+                    );
+                    if let Some(target) = target {
+                        Self::push_signal_to_bus_and_bubble(
+                            component_signal_bus,
+                            gui_component_tree,
+                            key_to_handler_block_table,
+                            target,
+                            GuiEventKind::OnGesture,
+                            event,
+                        );
+                    }
+                }
                 EventKind::MouseWheel { .. } => {
                     if let &mut Some(focused_key) = focused_component {
                         component_signal_bus.push_back(ComponentEventSignal::new(
@@ -571,6 +816,101 @@ impl<ProgramState> GuiManager<ProgramState> {
         }
     }
 
+    /// ## Description
+    /// queues `OnActivate` for `key`, a component without `OnActivate` listeners gets clicked in its
+    /// middle instead (`OnMouseDown` followed by `OnMouseRelease`), so buttons work from the keyboard
+    /// ## Comments
+    /// - text inputs never get the click, Enter and Space are typing there
+    fn push_activation_signals(
+        component_signal_bus: &mut VecDeque<ComponentEventSignal>,
+        gui_component_tree: &LinearTree<Box<dyn GuiComponent>>,
+        key_to_handler_block_table: &HashMap<GuiComponentKey, ComponentHandlerBlock<ProgramState>>,
+        key_to_aabb_table: &HashMap<GuiComponentKey, AABB2<f32>>,
+        key: GuiComponentKey,
+        event: EventKind,
+    ) {
+        let has_listener = |kind: GuiEventKind| {
+            key_to_handler_block_table
+                .get(&key)
+                .and_then(|block| block.get(kind as usize))
+                .map(|handlers| !handlers.is_empty())
+                .unwrap_or(false)
+        };
+        let is_text_input = gui_component_tree
+            .get(key)
+            .map(|comp| comp.accessible_role() == AccessibleRole::TextInput)
+            .unwrap_or(false);
+        let aabb = key_to_aabb_table.get(&key);
+
+        match aabb {
+            Some(aabb) if !has_listener(GuiEventKind::OnActivate) && !is_text_input => {
+                let center = (aabb.min_pos + aabb.max_pos) * 0.5;
+                let (x, y) = (center.x(), center.y());
+                let button_code = MouseCode::LEFT_BUTTON;
+                let down = EventKind::MouseDown { button_code, x, y };
+                let up = EventKind::MouseUp { button_code, x, y };
+                component_signal_bus.push_back(ComponentEventSignal::new(
+                    GuiEventKind::OnMouseDown,
+                    key,
+                    down,
+                ));
+                component_signal_bus.push_back(ComponentEventSignal::new(
+                    GuiEventKind::OnMouseRelease,
+                    key,
+                    up,
+                ));
+            }
+            _ => component_signal_bus.push_back(ComponentEventSignal::new(
+                GuiEventKind::OnActivate,
+                key,
+                event,
+            )),
+        }
+    }
+
+    /// queues `OnFocusOut`/`OnFocusIn` signals if focus moved from `prev_focused` to `cur_focused`
+    fn push_focus_change_signals(
+        component_signal_bus: &mut VecDeque<ComponentEventSignal>,
+        prev_focused: Option<GuiComponentKey>,
+        cur_focused: Option<GuiComponentKey>,
+        event: EventKind,
+    ) {
+        match (prev_focused, cur_focused) {
+            (None, Some(cur_key)) => {
+                component_signal_bus.push_back(ComponentEventSignal::new(
+                    GuiEventKind::OnFocusIn,
+                    cur_key,
+                    event,
+                ));
+            }
+            (Some(prev_key), None) => {
+                component_signal_bus.push_back(ComponentEventSignal::new(
+                    GuiEventKind::OnFocusOut,
+                    prev_key,
+                    event,
+                ));
+            }
+            (Some(prev_key), Some(cur_key)) => {
+                if prev_key != cur_key {
+                    component_signal_bus.push_back(ComponentEventSignal::new(
+                        GuiEventKind::OnFocusOut,
+                        prev_key,
+                        event,
+                    ));
+                    component_signal_bus.push_back(ComponentEventSignal::new(
+                        GuiEventKind::OnFocusIn,
+                        cur_key,
+                        event,
+                    ));
+                }
+            }
+
+            (None, None) => {
+                // do nothing
+            }
+        }
+    }
+
     fn print_signals_queued_to_bus(
         _component_signal_bus: &mut VecDeque<ComponentEventSignal>,
         _old_signal_len: usize,
@@ -851,6 +1191,85 @@ impl<ProgramState> GuiManager<ProgramState> {
     pub fn poll_mutation_requsts(&mut self) -> Option<GuiMutation<ProgramState>> {
         self.mutation_queue.dequeue()
     }
+
+    /// recomputes visibility and aabbs without processing any events
+    fn recompute_layout(&mut self) {
+        self.recompute_visibility();
+        self.recompute_aabb_table();
+    }
+
+    pub fn component(&self, key: GuiComponentKey) -> Option<&dyn GuiComponent> {
+        self.gui_component_tree.get(key).map(|comp| comp.as_ref())
+    }
+
+    pub fn component_mut(
+        &mut self,
+        key: GuiComponentKey,
+    ) -> Option<&mut (dyn GuiComponent + 'static)> {
+        self.gui_component_tree
+            .get_mut(key)
+            .map(|comp| comp.as_mut())
+    }
+
+    /// fetches the component at `key` if it is of type `T`
+    pub fn component_as<T: 'static>(&self, key: GuiComponentKey) -> Option<&T> {
+        self.component(key)?.as_any().downcast_ref::<T>()
+    }
+
+    pub fn component_as_mut<T: 'static>(&mut self, key: GuiComponentKey) -> Option<&mut T> {
+        self.component_mut(key)?.as_any_mut().downcast_mut::<T>()
+    }
+
+    /// returns the key of the first component (in pre-order) named `name`
+    pub fn find_component_by_name(&self, name: &str) -> Option<GuiComponentKey> {
+        self.gui_component_tree
+            .iter()
+            .find(|node| node.val.name() == name)
+            .map(|node| GuiComponentKey::from(node.id))
+    }
+
+    pub fn key_to_aabb_table(&self) -> &HashMap<GuiComponentKey, AABB2<f32>> {
+        &self.key_to_aabb_table
+    }
+
+    /// the global aabb of `key`, as computed during the last update
+    pub fn component_aabb(&self, key: GuiComponentKey) -> Option<AABB2<f32>> {
+        self.key_to_aabb_table.get(&key).copied()
+    }
+
+    /// true if `key` and all of its ancestors were visible during the last update
+    pub fn is_globally_visible(&self, key: GuiComponentKey) -> bool {
+        self.visibility_table
+            .get(key.as_usize())
+            .copied()
+            .unwrap_or(false)
+    }
+
+    pub fn hovered_component(&self) -> Option<GuiComponentKey> {
+        self.hover_component
+    }
+
+    pub fn clicked_component(&self) -> Option<GuiComponentKey> {
+        self.clicked_component
+    }
+
+    /// ## Description
+    /// returns the component a mouse click at `pos` would land on
+    /// ## Comments
+    /// - uses the layout computed during the last update
+    pub fn component_at(&self, pos: Vec2<f32>) -> Option<GuiComponentKey> {
+        let mut visibility_stack = VisibilityStack::new();
+        let mut hit = None;
+        Self::point_in_aabb_cumulative_intersections(
+            &self.gui_component_tree,
+            &self.key_to_aabb_table,
+            &self.visibility_table,
+            &mut visibility_stack,
+            pos,
+            |key| hit = Some(key),
+        );
+        hit
+    }
 }
 
 impl<State> GuiManager<FlufflState<State>> {
@@ -0,0 +1,142 @@
+use super::*;
+
+/// What a component is, from the point of view of assistive tech and automated UI tests
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[rustfmt::skip]
+pub enum AccessibleRole {
+    Root      = 0,
+    Group     = 1,
+    Label     = 2,
+    TextInput = 3,
+    Slider    = 4,
+    Image     = 5,
+    Unknown   = 6,
+}
+
+/// A snapshot of a single component and its descendants
+#[derive(Clone, Debug)]
+pub struct AccessibilityNode {
+    pub key: GuiComponentKey,
+    pub role: AccessibleRole,
+    pub name: String,
+    pub value: Option<String>,
+    pub bounds: AABB2<f32>,
+    pub is_visible: bool,
+    pub is_focused: bool,
+    pub is_enabled: bool,
+    pub is_focusable: bool,
+    pub children: Vec<AccessibilityNode>,
+}
+
+impl AccessibilityNode {
+    /// iterates through this node and all of its descendants in pre-order
+    pub fn iter(&self) -> impl Iterator<Item = &AccessibilityNode> + '_ {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+
+    /// finds the first node (in pre-order) with the name `name`
+    pub fn find_by_name(&self, name: &str) -> Option<&AccessibilityNode> {
+        self.iter().find(|node| node.name == name)
+    }
+
+    pub fn find_by_key(&self, key: GuiComponentKey) -> Option<&AccessibilityNode> {
+        self.iter().find(|node| node.key == key)
+    }
+}
+
+impl<ProgramState> GuiManager<ProgramState> {
+    /// ## Description
+    /// walks the component tree and returns a snapshot of roles, names, values and states
+    /// ## Comments
+    /// - returns `None` if no components have been added yet
+    /// - visibility and bounds are recomputed, so this works before the first `Self::render(..)`
+    pub fn accessibility_tree(&mut self) -> Option<AccessibilityNode> {
+        self.recompute_visibility();
+        self.recompute_aabb_table();
+
+        let gui_component_tree = &self.gui_component_tree;
+
+        // every node gets built bottom-up, so children are popped off of their slots in reverse pre-order
+        let mut slots: Vec<Option<AccessibilityNode>> = Vec::new();
+        let mut node_info = Vec::new();
+        for node in gui_component_tree.iter() {
+            let key = GuiComponentKey::from(node.id);
+            let comp = node.val;
+            slots.push(Some(AccessibilityNode {
+                key,
+                role: comp.accessible_role(),
+                name: String::from(comp.name()),
+                value: comp.accessible_value(),
+                bounds: self
+                    .key_to_aabb_table
+                    .get(&key)
+                    .copied()
+                    .unwrap_or_else(AABB2::zero),
+                is_visible: self.visibility_table[key.as_usize()],
+                is_focused: self.focused_component == Some(key),
+                is_enabled: comp.is_enabled(),
+                is_focusable: comp.is_focusable(),
+                children: Vec::new(),
+            }));
+            node_info.push((key, node.parent.map(GuiComponentKey::from)));
+        }
+
+        let slot_of = |key: GuiComponentKey| node_info.iter().position(|&(k, _)| k == key);
+
+        let mut root = None;
+        for slot_idx in (0..slots.len()).rev() {
+            let mut node = slots[slot_idx]
+                .take()
+                .expect("slot should only be taken once");
+            // walking backwards pushed the children last one first
+            node.children.reverse();
+            match node_info[slot_idx].1.and_then(slot_of) {
+                Some(parent_slot) => slots[parent_slot]
+                    .as_mut()
+                    .expect("parents come before children in pre-order")
+                    .children
+                    .push(node),
+                None => root = Some(node),
+            }
+        }
+
+        root
+    }
+}
+
+#[test]
+fn accessibility_node_iter_is_preorder() {
+    let leaf = |key: usize, name: &str| AccessibilityNode {
+        key: GuiComponentKey(key),
+        role: AccessibleRole::Label,
+        name: String::from(name),
+        value: None,
+        bounds: AABB2::zero(),
+        is_visible: true,
+        is_focused: false,
+        is_enabled: true,
+        is_focusable: false,
+        children: vec![],
+    };
+
+    let mut group = leaf(1, "group");
+    group.role = AccessibleRole::Group;
+    group.children = vec![leaf(2, "a"), leaf(3, "b")];
+
+    let mut root = leaf(0, "root");
+    root.role = AccessibleRole::Root;
+    root.children = vec![group, leaf(4, "c")];
+
+    let names: Vec<_> = root.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(names, vec!["root", "group", "a", "b", "c"]);
+    assert_eq!(
+        root.find_by_name("b").map(|n| n.key),
+        Some(GuiComponentKey(3))
+    );
+    assert!(root.find_by_key(GuiComponentKey(7)).is_none());
+}
@@ -16,6 +16,44 @@ pub trait HasComponentBuilder<ProgramState>: Sized {
         self
     }
 
+    /// ## Description
+    /// makes the component use the named `Theme` style `name` instead of its class default
+    fn with_style<T: AsRef<str>>(mut self, name: T) -> Self {
+        self.state()
+            .as_mut()
+            .expect("state not initalized")
+            .set_style_name(Some(name.as_ref()));
+        self
+    }
+
+    fn with_enabled(mut self, enabled: bool) -> Self {
+        self.state()
+            .as_mut()
+            .expect("state not initalized")
+            .set_enabled(enabled);
+        self
+    }
+
+    /// ## Description
+    /// lets the component be reached with Tab/Shift+Tab
+    fn with_focusable(mut self, is_focusable: bool) -> Self {
+        self.state()
+            .as_mut()
+            .expect("state not initalized")
+            .set_focusable(is_focusable);
+        self
+    }
+
+    /// ## Description
+    /// overrides where the component shows up in the Tab order, see `GuiComponent::tab_index(..)`
+    fn with_tab_index(mut self, tab_index: i32) -> Self {
+        self.state()
+            .as_mut()
+            .expect("state not initalized")
+            .set_tab_index(Some(tab_index));
+        self
+    }
+
     fn with_parent(mut self, parent: GuiComponentKey) -> Self {
         *self.parent() = Some(parent);
         self
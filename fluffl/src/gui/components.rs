@@ -1,17 +1,18 @@
 use super::{builder::*, *};
 
 use crate::{math::AABB2, text_writer::TextWriter};
-use std::any::Any;
+use std::{any::Any, cell::Cell};
 
 pub mod component_flags;
 mod frame;
+mod image;
 mod label;
 mod origin;
 mod slider;
 mod textbox;
 
 use self::component_flags::ComponentFlags;
-pub use self::{frame::*, label::*, origin::*, slider::*, textbox::*};
+pub use self::{frame::*, image::*, label::*, origin::*, slider::*, textbox::*};
 
 pub struct TextAligner2D {
     alignment_mode_per_axis: [TextAlignment; 2],
@@ -75,6 +76,10 @@ pub enum GuiEventKind {
     OnFocusOut          =  9,
     OnWheelWhileFocused = 10, 
     OnWheelWhileHovered = 11,
+    OnActivate          = 12,
+    OnTextInput         = 13,
+    OnComposition       = 14,
+    OnGesture           = 15,
 }
 
 pub struct ComponentEventListener<ProgramState> {
@@ -113,6 +118,8 @@ pub struct RenderState<'a> {
     pub key_to_aabb_table: &'a HashMap<GuiComponentKey, AABB2<f32>>,
     pub win_w: f32,
     pub win_h: f32,
+    pub theme: Option<&'a Theme>,
+    pub style_state: StyleState,
 }
 
 impl<'a> Clone for RenderState<'a> {
@@ -133,6 +140,8 @@ impl<'a> RenderState<'a> {
         key_to_aabb_table: &'a HashMap<GuiComponentKey, math::AABB<2, f32>>,
         window_width: f32,
         window_height: f32,
+        theme: Option<&'a Theme>,
+        style_state: StyleState,
     ) -> Self {
         Self {
             key,
@@ -143,8 +152,37 @@ impl<'a> RenderState<'a> {
             key_to_aabb_table,
             win_h: window_height,
             win_w: window_width,
+            theme,
+            style_state,
         }
     }
+
+    /// ## Description
+    /// computes the style a component should be drawn with
+    /// ## Comments
+    /// - if no theme is set, `instance` is returned untouched
+    /// - properties flagged in the components `StyleOverrides` always come from `instance`
+    pub fn resolve_style(
+        &self,
+        class: StyleClass,
+        common: &GuiCommonState,
+        instance: ComponentStyle,
+    ) -> ComponentStyle {
+        let themed = self
+            .theme
+            .and_then(|theme| theme.resolve(class, common.style_name(), self.style_state));
+        common.resolve_style(themed, instance)
+    }
+
+    /// ## Description
+    /// the color text should be drawn with
+    /// ## Comments
+    /// - precedence is: `instance` > the theme's text color > white
+    pub fn resolve_text_color(&self, instance: Option<Vec4<f32>>) -> Vec4<f32> {
+        instance
+            .or_else(|| self.theme.and_then(|theme| theme.text_color()))
+            .unwrap_or_else(|| Vec4::from([1.0; 4]))
+    }
 }
 
 pub struct EventListenerInfo<'a, ProgramState> {
@@ -153,6 +191,8 @@ pub struct EventListenerInfo<'a, ProgramState> {
     pub gui_comp_tree: &'a mut LinearTree<Box<dyn GuiComponent>>,
     pub key_to_aabb_table: &'a HashMap<GuiComponentKey, AABB2<f32>>,
     pub mutation_queue: &'a mut MutationRequestQueue<ProgramState>,
+    /// lets handlers start animations, like press feedback on a button
+    pub tweens: &'a mut TweenManager,
 }
 
 #[derive(Clone)]
@@ -161,6 +201,13 @@ pub struct GuiCommonState {
     bounds: Vec2<f32>,
     flags: ComponentFlags,
     name: String,
+    style_name: Option<String>,
+    style_overrides: StyleOverrides,
+    /// the color a running tween shows in place of the themed one
+    animated_color: Option<Vec4<f32>>,
+    /// what the color resolved to the last time the component was drawn
+    drawn_color: Cell<Option<Vec4<f32>>>,
+    tab_index: Option<i32>,
 }
 impl Default for GuiCommonState {
     fn default() -> Self {
@@ -174,6 +221,11 @@ impl GuiCommonState {
             name: String::new(),
             rel_pos: Vec2::zero(),
             bounds: Vec2::zero(),
+            style_name: None,
+            style_overrides: StyleOverrides::default(),
+            animated_color: None,
+            drawn_color: Cell::new(None),
+            tab_index: None,
         }
     }
 
@@ -186,6 +238,35 @@ impl GuiCommonState {
         self.bounds = bounds.into();
         self
     }
+
+    pub fn style_name(&self) -> Option<&str> {
+        self.style_name.as_deref()
+    }
+
+    /// ## Description
+    /// the style to draw with, given what the theme says (see `RenderState::resolve_style(..)`)
+    /// ## Comments
+    /// - the color of a running tween wins over both the theme and `instance`
+    /// - the resulting color is remembered for `GuiComponent::drawn_color()`
+    pub fn resolve_style(
+        &self,
+        themed: Option<ComponentStyle>,
+        instance: ComponentStyle,
+    ) -> ComponentStyle {
+        let mut style = match themed {
+            Some(themed) => self.style_overrides.merge(themed, instance),
+            None => instance,
+        };
+        if let Some(color) = self.animated_color {
+            style.color = color;
+        }
+        self.drawn_color.set(Some(style.color));
+        style
+    }
+
+    pub fn set_animated_color(&mut self, color: Option<Vec4<f32>>) {
+        self.animated_color = color;
+    }
 }
 
 pub trait GuiComponent {
@@ -204,6 +285,23 @@ pub trait GuiComponent {
         common.name.push_str(name);
     }
 
+    /// the named `Theme` style this component uses instead of its class default
+    fn style_name(&self) -> Option<&str> {
+        self.common().style_name()
+    }
+
+    fn set_style_name(&mut self, name: Option<&str>) {
+        self.common_mut().style_name = name.map(String::from);
+    }
+
+    fn style_overrides(&self) -> &StyleOverrides {
+        &self.common().style_overrides
+    }
+
+    fn style_overrides_mut(&mut self) -> &mut StyleOverrides {
+        &mut self.common_mut().style_overrides
+    }
+
     fn flags(&self) -> &ComponentFlags {
         &self.common().flags
     }
@@ -228,6 +326,32 @@ pub trait GuiComponent {
         self.common_mut().bounds = bounds;
     }
 
+    /// the main color of this component, `None` if it doesn't have one
+    fn color(&self) -> Option<Vec4<f32>> {
+        None
+    }
+
+    /// does nothing if the component has no color, see `Self::color()`
+    fn set_color(&mut self, _color: Vec4<f32>) {}
+
+    /// ## Description
+    /// the color the component was last drawn with, the themed one if a theme applies
+    /// ## Comments
+    /// - falls back to `Self::color()` before the first draw
+    fn drawn_color(&self) -> Option<Vec4<f32>> {
+        self.common().drawn_color.get().or_else(|| self.color())
+    }
+
+    /// ## Description
+    /// how tweens change the color, unlike `Self::set_color(..)` it doesn't override the theme while
+    /// the tween runs
+    /// ## Comments
+    /// - `color` is drawn in place of the themed color until the tween is `done`, the final color
+    ///   gets set like `Self::set_color(..)` would, so it stays
+    fn set_tweened_color(&mut self, color: Vec4<f32>, _done: bool) {
+        self.set_color(color)
+    }
+
     fn is_visible(&self) -> bool {
         self.flags().is_set(component_flags::VISIBLE)
     }
@@ -238,6 +362,49 @@ pub trait GuiComponent {
             .set(component_flags::VISIBLE & ComponentFlags::as_mask(is_visible))
     }
 
+    fn is_enabled(&self) -> bool {
+        !self.flags().is_set(component_flags::DISABLED)
+    }
+
+    fn set_enabled(&mut self, is_enabled: bool) {
+        self.flags_mut().unset(component_flags::DISABLED);
+        self.flags_mut()
+            .set(component_flags::DISABLED & ComponentFlags::as_mask(!is_enabled))
+    }
+
+    fn is_focusable(&self) -> bool {
+        self.flags().is_set(component_flags::FOCUSABLE)
+    }
+
+    fn set_focusable(&mut self, is_focusable: bool) {
+        self.flags_mut().unset(component_flags::FOCUSABLE);
+        self.flags_mut()
+            .set(component_flags::FOCUSABLE & ComponentFlags::as_mask(is_focusable))
+    }
+
+    /// ## Description
+    /// overrides where this component shows up in the Tab order
+    /// ## Comments
+    /// - positive indices are visited first (ascending), `None`/`0` follow in tree order
+    /// - negative indices are never visited
+    fn tab_index(&self) -> Option<i32> {
+        self.common().tab_index
+    }
+
+    fn set_tab_index(&mut self, tab_index: Option<i32>) {
+        self.common_mut().tab_index = tab_index;
+    }
+
+    /// what this component is, as reported by `GuiManager::accessibility_tree(..)`
+    fn accessible_role(&self) -> AccessibleRole {
+        AccessibleRole::Unknown
+    }
+
+    /// the current value of this component (the text of a textbox, the percentage of a slider, etc)
+    fn accessible_value(&self) -> Option<String> {
+        None
+    }
+
     fn set_overflowable(&mut self, overflowable: bool) {
         self.flags_mut().unset(component_flags::OVERFLOWABLE);
         self.flags_mut()
@@ -281,44 +448,61 @@ pub trait GuiComponent {
 
 const LAYER_BIAS: i32 = 128;
 
-#[allow(clippy::identity_op)]
 pub fn layer_lock(gl: &GlowGL, layer_id: i32, flags: ComponentFlags) {
     if !flags.is_set(component_flags::OVERFLOWABLE) {
         layer_lock_always(gl, layer_id);
     } else {
-        unsafe {
-            gl.enable(glow::STENCIL_TEST);
-            gl.stencil_mask(0xff);
-            gl.stencil_func(glow::ALWAYS, (layer_id + 0) + LAYER_BIAS, 0xff);
-            gl.stencil_op(glow::REPLACE, glow::REPLACE, glow::REPLACE);
-        }
+        let stencil = StencilState {
+            enabled: true,
+            func: glow::ALWAYS,
+            reference: layer_id + LAYER_BIAS,
+            value_mask: 0xff,
+            write_mask: 0xff,
+            fail: glow::REPLACE,
+            depth_fail: glow::REPLACE,
+            pass: glow::REPLACE,
+        };
+        GlStateCache::with(gl, |state| state.set_stencil(stencil));
     }
 }
 
-#[allow(clippy::identity_op)]
 pub fn layer_lock_always(gl: &GlowGL, layer_id: i32) {
-    if layer_id == 1 {
+    let stencil = if layer_id == 1 {
         //initalize the stencil buffer for the first layer
-        unsafe {
-            gl.enable(glow::STENCIL_TEST);
-            gl.stencil_mask(0xff);
-            gl.stencil_func(glow::ALWAYS, (layer_id + 0) + LAYER_BIAS, 0xff);
-            gl.stencil_op(glow::REPLACE, glow::REPLACE, glow::REPLACE);
+        StencilState {
+            enabled: true,
+            func: glow::ALWAYS,
+            reference: layer_id + LAYER_BIAS,
+            value_mask: 0xff,
+            write_mask: 0xff,
+            fail: glow::REPLACE,
+            depth_fail: glow::REPLACE,
+            pass: glow::REPLACE,
         }
     } else {
         // layer_id -1 is the parent of the current layer.
         // the goal i  to clip away pixels OUTSIDE of the parents domain
-        unsafe {
-            gl.enable(glow::STENCIL_TEST);
-            gl.stencil_mask(0xff);
-            gl.stencil_func(glow::LEQUAL, (layer_id - 1) + LAYER_BIAS, 0xff);
-            gl.stencil_op(glow::KEEP, glow::INCR, glow::INCR);
+        StencilState {
+            enabled: true,
+            func: glow::LEQUAL,
+            reference: (layer_id - 1) + LAYER_BIAS,
+            value_mask: 0xff,
+            write_mask: 0xff,
+            fail: glow::KEEP,
+            depth_fail: glow::INCR,
+            pass: glow::INCR,
         }
-    }
+    };
+    GlStateCache::with(gl, |state| state.set_stencil(stencil));
 }
 
 pub fn layer_unlock(gl: &GlowGL) {
-    unsafe {
-        gl.disable(glow::STENCIL_TEST);
-    }
+    GlStateCache::with(gl, |state| {
+        // only the test gets turned off, the next lock likely wants the same masks again
+        let stencil = state.stencil().unwrap_or(StencilState::DISABLED);
+        state.set_stencil(StencilState {
+            enabled: false,
+            ..stencil
+        });
+    });
 }
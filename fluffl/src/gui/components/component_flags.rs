@@ -9,6 +9,12 @@ pub const OVERFLOWABLE: ComponentFlags = ComponentFlags(1 << 1);
 /// the component is marked as "visible"
 pub const VISIBLE: ComponentFlags = ComponentFlags(1 << 2);
 
+/// the component is marked as "disabled" and gets styled accordingly
+pub const DISABLED: ComponentFlags = ComponentFlags(1 << 3);
+
+/// the component can receive keyboard focus through Tab/Shift+Tab
+pub const FOCUSABLE: ComponentFlags = ComponentFlags(1 << 4);
+
 #[derive(Default, Copy, Clone)]
 pub struct ComponentFlags(u64);
 
@@ -55,6 +55,7 @@ pub struct FrameState {
     pub edge_color: Vec4<f32>,
     pub edge_thickness: f32,
     pub roundness: Vec4<f32>,
+    pub style_class: StyleClass,
 
     is_scrollbars_enabled: bool,
     camera: Vec2<f32>,
@@ -83,6 +84,7 @@ impl FrameState {
             edge_color: Vec4::rgb_u32(0x89CFFD),
             edge_thickness: 0.02,
             roundness: Vec4::from([1.0, 1.0, 1.0, 1.0]),
+            style_class: StyleClass::Frame,
             is_scrollbars_enabled: false,
             camera: Vec2::zero(),
             components_aabb: AABB2::zero(),
@@ -95,6 +97,26 @@ impl FrameState {
         }
     }
 
+    pub fn with_style_class(mut self, class: StyleClass) -> Self {
+        self.style_class = class;
+        self
+    }
+
+    pub fn with_focusable(mut self, is_focusable: bool) -> Self {
+        self.set_focusable(is_focusable);
+        self
+    }
+
+    /// the style this frame would have if no theme was set
+    pub fn instance_style(&self) -> ComponentStyle {
+        ComponentStyle {
+            color: self.color,
+            edge_color: self.edge_color,
+            edge_thickness: self.edge_thickness,
+            roundness: self.roundness,
+        }
+    }
+
     fn draw_rectangle<T>(
         gl: &GlowGL,
         r: &GuiRenderer,
@@ -122,9 +144,7 @@ impl FrameState {
             .set_position(position, Vec4::convert(rect.dims()))
             .render();
 
-        unsafe {
-            gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
-        }
+        GlStateCache::with(gl, |state| state.set_blend(BlendState::ALPHA));
     }
 
     fn update_component_bounds_assuming_new_bounds_already_set(&mut self) {
@@ -171,6 +191,32 @@ impl GuiComponent for FrameState {
         self
     }
 
+    fn accessible_role(&self) -> AccessibleRole {
+        AccessibleRole::Group
+    }
+
+    fn color(&self) -> Option<Vec4<f32>> {
+        Some(self.color)
+    }
+
+    /// also marks the color as overridden, so a theme can't undo it
+    fn set_color(&mut self, color: Vec4<f32>) {
+        self.color = color;
+        self.style_overrides_mut().color = true;
+        self.common.set_animated_color(None);
+    }
+
+    fn set_tweened_color(&mut self, color: Vec4<f32>, done: bool) {
+        if done {
+            // the final color sticks, a fade-out mustn't pop back to the themed color
+            self.set_color(color);
+            return;
+        }
+        // without a theme the instance color is what gets drawn, so it has to end up there too
+        self.color = color;
+        self.common.set_animated_color(Some(color));
+    }
+
     fn render_entry(
         &mut self,
         gl: &GlowGL,
@@ -187,14 +233,16 @@ impl GuiComponent for FrameState {
         let win_w = state.win_w;
         let win_h = state.win_h;
 
+        let style = state.resolve_style(self.style_class, &self.common, self.instance_style());
+
         layer_lock(gl, level, *self.flags());
 
         r.builder(gl, GuiShaderKind::RoundedBox)
             .set_window(win_w, win_h)
-            .set_roundness_vec(self.roundness)
-            .set_edge_color(self.edge_color)
-            .set_background_color(self.color)
-            .set_edge_thickness(self.edge_thickness)
+            .set_roundness_vec(style.roundness)
+            .set_edge_color(style.edge_color)
+            .set_background_color(style.color)
+            .set_edge_thickness(style.edge_thickness)
             .set_bounds(self.bounds())
             .set_position(state.global_position, Vec4::to_pos(self.bounds()))
             .render();
@@ -362,7 +410,9 @@ impl<'a, ProgramState> FrameBuilder<'a, ProgramState> {
     where
         Vec4<f32>: From<T>,
     {
-        self.state.as_mut().unwrap().color = Vec4::from(color);
+        let state = self.state.as_mut().unwrap();
+        state.color = Vec4::from(color);
+        state.style_overrides_mut().color = true;
         self
     }
 
@@ -370,12 +420,16 @@ impl<'a, ProgramState> FrameBuilder<'a, ProgramState> {
     where
         Vec4<f32>: From<T>,
     {
-        self.state.as_mut().unwrap().edge_color = Vec4::from(color);
+        let state = self.state.as_mut().unwrap();
+        state.edge_color = Vec4::from(color);
+        state.style_overrides_mut().edge_color = true;
         self
     }
 
     pub fn with_edge_thickness<T: Into<f32>>(mut self, edge_thickness: T) -> Self {
-        self.state.as_mut().unwrap().edge_thickness = edge_thickness.into();
+        let state = self.state.as_mut().unwrap();
+        state.edge_thickness = edge_thickness.into();
+        state.style_overrides_mut().edge_thickness = true;
         self
     }
 
@@ -383,7 +437,9 @@ impl<'a, ProgramState> FrameBuilder<'a, ProgramState> {
     where
         Vec4<f32>: From<T>,
     {
-        self.state.as_mut().unwrap().roundness = Vec4::from(r);
+        let state = self.state.as_mut().unwrap();
+        state.roundness = Vec4::from(r);
+        state.style_overrides_mut().roundness = true;
         self
     }
 
@@ -0,0 +1,481 @@
+use super::*;
+use crate::{
+    codecs::{
+        ico::IcoEntry,
+        image::{DecodedImage, ImageError},
+    },
+    ogl::texture::{HasTextureBuilder, TextureObj},
+};
+use std::rc::Rc;
+
+/// How an image gets mapped onto the bounds of its component
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ImageScaleMode {
+    /// the image covers the bounds exactly, aspect ratio is not preserved
+    Stretch,
+    /// the whole image is visible and centered, aspect ratio is preserved
+    Fit,
+    /// the image covers the bounds and gets cropped, aspect ratio is preserved
+    Fill,
+    /// corners are kept unscaled, edges are stretched along one axis and the center along both.
+    /// Insets are in texture pixels.
+    NineSlice {
+        left: f32,
+        right: f32,
+        top: f32,
+        bottom: f32,
+    },
+}
+
+/// A single textured quad in window coordinates
+#[derive(Copy, Clone, Debug)]
+pub struct ImageQuad {
+    pub position: Vec2<f32>,
+    pub bounds: Vec2<f32>,
+    pub uv_min: Vec2<f32>,
+    pub uv_max: Vec2<f32>,
+}
+
+/// The quads needed to draw an image plus the region they get clipped against
+#[derive(Clone, Debug)]
+pub struct ImageLayout {
+    pub mask: AABB2<f32>,
+    pub quads: Vec<ImageQuad>,
+}
+
+impl ImageScaleMode {
+    /// ## Description
+    /// computes the quads needed to draw an image of size `image_dims` into the rectangle at
+    /// `position` with size `bounds`
+    /// ## Comments
+    /// - returns no quads if either the image or the bounds are empty
+    pub fn layout(
+        &self,
+        position: Vec2<f32>,
+        bounds: Vec2<f32>,
+        image_dims: Vec2<f32>,
+    ) -> ImageLayout {
+        let full_rect = AABB2::from_point_and_lengths(position, bounds);
+        let is_empty = |v: Vec2<f32>| v.x() <= 0.0 || v.y() <= 0.0;
+        if is_empty(bounds) || is_empty(image_dims) {
+            return ImageLayout {
+                mask: full_rect,
+                quads: vec![],
+            };
+        }
+
+        let full_quad = |position, bounds, uv_min, uv_max| ImageQuad {
+            position,
+            bounds,
+            uv_min,
+            uv_max,
+        };
+
+        match *self {
+            Self::Stretch => ImageLayout {
+                mask: full_rect,
+                quads: vec![full_quad(
+                    position,
+                    bounds,
+                    Vec2::zero(),
+                    Vec2::from([1.0; 2]),
+                )],
+            },
+            Self::Fit => {
+                let scale = (bounds.x() / image_dims.x()).min(bounds.y() / image_dims.y());
+                let dims = image_dims * scale;
+                let fitted_position = position + (bounds - dims) * 0.5;
+                ImageLayout {
+                    mask: AABB2::from_point_and_lengths(fitted_position, dims),
+                    quads: vec![full_quad(
+                        fitted_position,
+                        dims,
+                        Vec2::zero(),
+                        Vec2::from([1.0; 2]),
+                    )],
+                }
+            }
+            Self::Fill => {
+                let scale = (bounds.x() / image_dims.x()).max(bounds.y() / image_dims.y());
+                let scaled_dims = image_dims * scale;
+                // fraction of the image that is actually visible along each axis
+                let uv_extent =
+                    Vec2::from([bounds.x() / scaled_dims.x(), bounds.y() / scaled_dims.y()]);
+                let uv_min = (Vec2::from([1.0; 2]) - uv_extent) * 0.5;
+                ImageLayout {
+                    mask: full_rect,
+                    quads: vec![full_quad(position, bounds, uv_min, uv_min + uv_extent)],
+                }
+            }
+            Self::NineSlice {
+                left,
+                right,
+                top,
+                bottom,
+            } => {
+                let xs = nine_slice_stops(position.x(), bounds.x(), left, right);
+                let ys = nine_slice_stops(position.y(), bounds.y(), top, bottom);
+                let us = [
+                    0.0,
+                    left / image_dims.x(),
+                    1.0 - right / image_dims.x(),
+                    1.0,
+                ];
+                let vs = [
+                    0.0,
+                    top / image_dims.y(),
+                    1.0 - bottom / image_dims.y(),
+                    1.0,
+                ];
+
+                let mut quads = Vec::with_capacity(9);
+                for row in 0..3 {
+                    for col in 0..3 {
+                        let quad_position = Vec2::from([xs[col], ys[row]]);
+                        let quad_bounds =
+                            Vec2::from([xs[col + 1] - xs[col], ys[row + 1] - ys[row]]);
+                        if is_empty(quad_bounds) {
+                            continue;
+                        }
+                        quads.push(full_quad(
+                            quad_position,
+                            quad_bounds,
+                            Vec2::from([us[col], vs[row]]),
+                            Vec2::from([us[col + 1], vs[row + 1]]),
+                        ));
+                    }
+                }
+
+                ImageLayout {
+                    mask: full_rect,
+                    quads,
+                }
+            }
+        }
+    }
+}
+
+/// splits `[start, start+length]` into three spans, shrinking the insets if they don't fit
+fn nine_slice_stops(start: f32, length: f32, inset_lo: f32, inset_hi: f32) -> [f32; 4] {
+    let total_inset = inset_lo + inset_hi;
+    let shrink = if total_inset > length && total_inset > 0.0 {
+        length / total_inset
+    } else {
+        1.0
+    };
+    let lo = inset_lo.max(0.0) * shrink;
+    let hi = inset_hi.max(0.0) * shrink;
+    [start, start + lo, start + length - hi, start + length]
+}
+
+/// ## Description
+/// converts an icon entry to tightly packed RGBA, same as `IcoEntry::decode()`
+pub fn decode_ico_entry(entry: &IcoEntry) -> Result<DecodedImage, ImageError> {
+    entry.decode()
+}
+
+/// ## Description
+/// uploads an icon entry into an RGBA texture, see `decode_ico_entry(..)` for what can fail
+pub fn texture_from_ico_entry(gl: &GlowGL, entry: &IcoEntry) -> Result<OglTexture, ImageError> {
+    let image = decode_ico_entry(entry)?;
+    Ok(TextureObj::<u8>::builder(gl)
+        .with_width(image.width)
+        .with_height(image.height)
+        .with_format(glow::RGBA)
+        .with_internal_format(glow::RGBA)
+        .with_pixels(image.pixels)
+        .build()
+        .into())
+}
+
+pub struct ImageState {
+    common: GuiCommonState,
+    texture: Option<Rc<OglTexture>>,
+    pub scale_mode: ImageScaleMode,
+    pub tint: Vec4<f32>,
+    pub roundness: Vec4<f32>,
+}
+
+impl Default for ImageState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ImageState {
+    pub fn new() -> Self {
+        Self {
+            common: GuiCommonState::new()
+                .with_flags(component_flags::VISIBLE)
+                .with_bounds([64.; 2]),
+            texture: None,
+            scale_mode: ImageScaleMode::Fit,
+            tint: Vec4::from([1.0; 4]),
+            roundness: Vec4::zero(),
+        }
+    }
+
+    pub fn texture(&self) -> Option<&Rc<OglTexture>> {
+        self.texture.as_ref()
+    }
+
+    pub fn set_texture(&mut self, texture: Option<Rc<OglTexture>>) {
+        self.texture = texture;
+    }
+
+    /// the size of the texture in pixels, `None` if no texture is set
+    pub fn image_dims(&self) -> Option<Vec2<f32>> {
+        self.texture.as_ref().map(|tex| {
+            let info = tex.get_info();
+            Vec2::from([info.width() as f32, info.height() as f32])
+        })
+    }
+}
+
+impl GuiComponent for ImageState {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn common(&self) -> &GuiCommonState {
+        &self.common
+    }
+
+    fn common_mut(&mut self) -> &mut GuiCommonState {
+        &mut self.common
+    }
+
+    fn accessible_role(&self) -> AccessibleRole {
+        AccessibleRole::Image
+    }
+
+    /// the tint the texture gets multiplied by
+    fn color(&self) -> Option<Vec4<f32>> {
+        Some(self.tint)
+    }
+
+    fn set_color(&mut self, color: Vec4<f32>) {
+        self.tint = color;
+    }
+
+    fn render_entry(&mut self, gl: &GlowGL, state: RenderState<'_>, _text_writer: &mut TextWriter) {
+        let (texture, image_dims) = match (self.texture.as_ref(), self.image_dims()) {
+            (Some(texture), Some(dims)) => (texture, dims),
+            _ => return,
+        };
+
+        let r = state.renderer;
+        let position = Vec2::convert(state.global_position);
+        let layout = self.scale_mode.layout(position, self.bounds(), image_dims);
+
+        layer_lock(gl, state.level, *self.flags());
+
+        for quad in layout.quads.iter() {
+            r.builder(gl, GuiShaderKind::Image)
+                .set_window(state.win_w, state.win_h)
+                .set_roundness_vec(self.roundness)
+                .set_background_color(self.tint)
+                .set_mask(layout.mask.min_pos, layout.mask.dims())
+                .set_uv_rect(quad.uv_min, quad.uv_max)
+                .set_texture(texture)
+                .set_position(Vec4::to_pos(quad.position), Vec4::to_pos(quad.bounds))
+                .render();
+        }
+
+        layer_unlock(gl);
+    }
+
+    fn render_exit(
+        &mut self,
+        _gl: &GlowGL,
+        _state: RenderState<'_>,
+        _text_writer: &mut TextWriter,
+    ) {
+        /* not implemented on purpose */
+    }
+}
+
+pub struct ImageBuilder<'a, ProgramState> {
+    image_key: Option<GuiComponentKey>,
+    parent: Option<GuiComponentKey>,
+    state: Option<ImageState>,
+    manager: &'a mut GuiManager<ProgramState>,
+}
+
+impl<'a, ProgramState> ImageBuilder<'a, ProgramState> {
+    pub fn new(manager: &'a mut GuiManager<ProgramState>) -> Self {
+        let image_key =
+            Some(unsafe { manager.add_component_deferred(GuiComponentKey::default(), None) });
+        Self {
+            image_key,
+            parent: Some(GuiComponentKey::default()),
+            state: Some(ImageState::new()),
+            manager,
+        }
+    }
+
+    pub fn with_position<T: Into<Vec2<f32>>>(mut self, pos: T) -> Self {
+        self.state.as_mut().unwrap().set_rel_position(pos.into());
+        self
+    }
+
+    pub fn with_bounds<T: Into<Vec2<f32>>>(mut self, bounds: T) -> Self {
+        self.state.as_mut().unwrap().set_bounds(bounds.into());
+        self
+    }
+
+    pub fn with_texture(mut self, texture: Rc<OglTexture>) -> Self {
+        self.state.as_mut().unwrap().texture = Some(texture);
+        self
+    }
+
+    /// ## Description
+    /// uploads `entry` and uses it as the texture
+    /// ## Comments
+    /// - does nothing on a headless manager or when the entry can't be decoded
+    pub fn with_ico_entry(mut self, entry: &IcoEntry) -> Self {
+        let texture = self
+            .manager
+            .gl()
+            .and_then(|gl| texture_from_ico_entry(gl, entry).ok())
+            .map(Rc::new);
+        if texture.is_some() {
+            self.state.as_mut().unwrap().texture = texture;
+        }
+        self
+    }
+
+    pub fn with_scale_mode(mut self, mode: ImageScaleMode) -> Self {
+        self.state.as_mut().unwrap().scale_mode = mode;
+        self
+    }
+
+    pub fn with_tint<T>(mut self, tint: T) -> Self
+    where
+        Vec4<f32>: From<T>,
+    {
+        self.state.as_mut().unwrap().tint = Vec4::from(tint);
+        self
+    }
+
+    pub fn with_roundness<T>(mut self, roundness: T) -> Self
+    where
+        Vec4<f32>: From<T>,
+    {
+        self.state.as_mut().unwrap().roundness = Vec4::from(roundness);
+        self
+    }
+}
+
+impl<'a, ProgramState> HasComponentBuilder<ProgramState> for ImageBuilder<'a, ProgramState> {
+    type ComponentKind = ImageState;
+
+    fn key(&mut self) -> &mut Option<GuiComponentKey> {
+        &mut self.image_key
+    }
+
+    fn manager(&mut self) -> &mut GuiManager<ProgramState> {
+        self.manager
+    }
+
+    fn parent(&mut self) -> &mut Option<GuiComponentKey> {
+        &mut self.parent
+    }
+
+    fn state(&mut self) -> &mut Option<Self::ComponentKind> {
+        &mut self.state
+    }
+
+    fn build(mut self) -> GuiComponentKey {
+        let image_key = self.image_key.expect("image key should always exist");
+        let parent = self.parent.unwrap_or_default();
+        let image_state = self.state.take().expect("image state should exist");
+
+        let gui_component_tree = &mut self.manager.gui_component_tree;
+        gui_component_tree.set_parent(image_key, parent);
+
+        *gui_component_tree.get_mut_uninit(image_key) = MaybeUninit::new(Box::new(image_state));
+        gui_component_tree.reconstruct_preorder();
+
+        image_key
+    }
+}
+
+impl<ProgramState> GuiManager<ProgramState> {
+    pub fn builder_image(&mut self) -> ImageBuilder<'_, ProgramState> {
+        ImageBuilder::new(self)
+    }
+}
+
+#[cfg(test)]
+fn assert_vec2_eq(a: Vec2<f32>, b: [f32; 2]) {
+    assert!(
+        (a.x() - b[0]).abs() < 0.0001 && (a.y() - b[1]).abs() < 0.0001,
+        "{:?} != {:?}",
+        [a.x(), a.y()],
+        b
+    );
+}
+
+#[test]
+fn image_layout_modes() {
+    let position = Vec2::from([10.0, 20.0]);
+    let bounds = Vec2::from([200.0, 100.0]);
+    let image_dims = Vec2::from([50.0, 50.0]);
+
+    let fit = ImageScaleMode::Fit.layout(position, bounds, image_dims);
+    assert_eq!(fit.quads.len(), 1);
+    assert_vec2_eq(fit.quads[0].bounds, [100.0, 100.0]);
+    assert_vec2_eq(fit.quads[0].position, [60.0, 20.0]);
+    assert_vec2_eq(fit.mask.min_pos, [60.0, 20.0]);
+
+    let fill = ImageScaleMode::Fill.layout(position, bounds, image_dims);
+    assert_vec2_eq(fill.quads[0].bounds, [200.0, 100.0]);
+    assert_vec2_eq(fill.quads[0].uv_min, [0.0, 0.25]);
+    assert_vec2_eq(fill.quads[0].uv_max, [1.0, 0.75]);
+
+    let stretch = ImageScaleMode::Stretch.layout(position, bounds, image_dims);
+    assert_vec2_eq(stretch.quads[0].uv_max, [1.0, 1.0]);
+
+    let empty = ImageScaleMode::Fit.layout(position, bounds, Vec2::zero());
+    assert!(empty.quads.is_empty());
+}
+
+#[test]
+fn image_layout_nine_slice() {
+    let mode = ImageScaleMode::NineSlice {
+        left: 10.0,
+        right: 10.0,
+        top: 5.0,
+        bottom: 5.0,
+    };
+    let layout = mode.layout(
+        Vec2::zero(),
+        Vec2::from([100.0, 40.0]),
+        Vec2::from([40.0, 20.0]),
+    );
+    assert_eq!(layout.quads.len(), 9);
+
+    // corners keep their size in pixels
+    let top_left = layout.quads[0];
+    assert_vec2_eq(top_left.bounds, [10.0, 5.0]);
+    assert_vec2_eq(top_left.uv_max, [0.25, 0.25]);
+
+    // the center stretches
+    let center = layout.quads[4];
+    assert_vec2_eq(center.position, [10.0, 5.0]);
+    assert_vec2_eq(center.bounds, [80.0, 30.0]);
+
+    // insets get shrunk if the component is too small, center disappears
+    let layout = mode.layout(
+        Vec2::zero(),
+        Vec2::from([10.0, 40.0]),
+        Vec2::from([40.0, 20.0]),
+    );
+    assert_eq!(layout.quads.len(), 6);
+    assert_vec2_eq(layout.quads[0].bounds, [5.0, 5.0]);
+}
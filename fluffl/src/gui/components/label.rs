@@ -10,6 +10,8 @@ pub struct LabelState {
     aligner: TextAligner2D,
     caption: String,
     size: TextSize,
+    /// overrides the theme's text color when set
+    text_color: Option<Vec4<f32>>,
 }
 
 impl Default for LabelState {
@@ -25,6 +27,7 @@ impl LabelState {
             caption: String::new(),
             size: TextSize::Dynamic,
             aligner: TextAligner2D::new(),
+            text_color: None,
         }
     }
 }
@@ -44,6 +47,14 @@ impl GuiComponent for LabelState {
         &mut self.common
     }
 
+    fn accessible_role(&self) -> AccessibleRole {
+        AccessibleRole::Label
+    }
+
+    fn accessible_value(&self) -> Option<String> {
+        Some(self.caption.clone())
+    }
+
     fn render_entry(
         &mut self,
         gl: &GlowGL,
@@ -68,6 +79,7 @@ impl GuiComponent for LabelState {
             self.bounds(),
         );
 
+        let old_color = text_writer.set_text_color(state.resolve_text_color(self.text_color));
         text_writer.draw_text_line(
             &self.caption,
             aligned_position.x(),
@@ -75,11 +87,9 @@ impl GuiComponent for LabelState {
             text_height,
             Some((win_w as u32, win_h as u32)),
         );
+        text_writer.set_text_color(old_color);
 
-        unsafe {
-            gl.enable(glow::BLEND);
-            gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
-        }
+        GlStateCache::with(gl, |state| state.set_blend(BlendState::ALPHA));
 
         layer_unlock(gl);
     }
@@ -146,6 +156,14 @@ impl<'a, ProgramState> LabelBuilder<'a, ProgramState> {
         self.state.as_mut().unwrap().size = size;
         self
     }
+
+    pub fn with_text_color<T>(mut self, color: T) -> Self
+    where
+        Vec4<f32>: From<T>,
+    {
+        self.state.as_mut().unwrap().text_color = Some(Vec4::from(color));
+        self
+    }
 }
 
 impl<'a, ProgramState> HasComponentBuilder<ProgramState> for LabelBuilder<'a, ProgramState> {
@@ -30,6 +30,10 @@ impl GuiComponent for OriginState {
         self
     }
 
+    fn accessible_role(&self) -> AccessibleRole {
+        AccessibleRole::Root
+    }
+
     fn render_entry(
         &mut self,
         _gl: &GlowGL,
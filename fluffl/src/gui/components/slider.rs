@@ -10,10 +10,73 @@ impl SliderState {
     fn new() -> Self {
         Self {
             slider_button_key: GuiComponentKey::default(),
-            slider_frame: FrameState::new(),
+            slider_frame: FrameState::new()
+                .with_style_class(StyleClass::Slider)
+                .with_focusable(true),
             percentage: 0.0,
         }
     }
+
+    /// ## Description
+    /// moves the button of the slider at `slider_frame_key` by `steps` increments of 5%
+    /// and recomputes the percentage
+    fn nudge_button(
+        tree: &mut LinearTree<Box<dyn GuiComponent>>,
+        slider_frame_key: GuiComponentKey,
+        steps: f32,
+    ) {
+        let slider_button_key = tree
+            .get_mut(slider_frame_key)
+            .unwrap()
+            .as_any_mut()
+            .downcast_mut::<SliderState>()
+            .unwrap()
+            .slider_button_key;
+
+        let frame_bounds = tree
+            .get(slider_frame_key)
+            .expect("slider_frame component")
+            .bounds();
+
+        let button_bounds = tree
+            .get(slider_button_key)
+            .expect("slider_button component")
+            .bounds();
+
+        //move slider button,horizontally by increments of 5% of the parent bounds width
+        let dx = (frame_bounds[0] * 0.05) * steps;
+
+        //translate the slider like normal
+        tree.get_mut(slider_button_key)
+            .expect("slider_button")
+            .translate(Vec2::from([dx, 0.0]));
+
+        //vertically center the slider on drag
+        let max_horizontal_rel_pos = frame_bounds[0] - button_bounds[0];
+        let button_slider_pos = *tree
+            .get(slider_button_key)
+            .expect("slider_button_key invalid")
+            .rel_position();
+
+        let vertically_centered_and_horizontally_clamped_relative_position = Vec2::from([
+            button_slider_pos.x().clamp(0.0, max_horizontal_rel_pos),
+            frame_bounds.y() * 0.5 - button_bounds.y() * 0.5,
+        ]);
+
+        //set newly computed position
+        tree.get_mut(slider_button_key)
+            .expect("slider_button_key invalid")
+            .set_rel_position(vertically_centered_and_horizontally_clamped_relative_position);
+
+        //update percentage
+        let new_percentage = (button_slider_pos.x() / max_horizontal_rel_pos).clamp(0.0, 1.0);
+        tree.get_mut(slider_frame_key)
+            .expect("slider_frame_key invalid")
+            .as_any_mut()
+            .downcast_mut::<SliderState>()
+            .expect("slider_frame_key should alias SliderState")
+            .percentage = new_percentage;
+    }
 }
 
 impl GuiComponent for SliderState {
@@ -40,6 +103,30 @@ impl GuiComponent for SliderState {
         self
     }
 
+    fn accessible_role(&self) -> AccessibleRole {
+        AccessibleRole::Slider
+    }
+
+    fn color(&self) -> Option<Vec4<f32>> {
+        self.slider_frame.color()
+    }
+
+    fn set_color(&mut self, color: Vec4<f32>) {
+        self.slider_frame.set_color(color)
+    }
+
+    fn drawn_color(&self) -> Option<Vec4<f32>> {
+        self.slider_frame.drawn_color()
+    }
+
+    fn set_tweened_color(&mut self, color: Vec4<f32>, done: bool) {
+        self.slider_frame.set_tweened_color(color, done)
+    }
+
+    fn accessible_value(&self) -> Option<String> {
+        Some(format!("{:.2}", self.percentage))
+    }
+
     fn bounds(&self) -> Vec2<f32> {
         self.slider_frame.bounds()
     }
@@ -57,6 +144,12 @@ impl GuiComponent for SliderState {
         let win_w = state.win_w;
         let win_h = state.win_h;
 
+        let style = state.resolve_style(
+            self.slider_frame.style_class,
+            self.slider_frame.common(),
+            self.slider_frame.instance_style(),
+        );
+
         //makes sure whatever gets render is bound within the parent
         layer_lock(gl, state.level, *self.flags());
 
@@ -64,10 +157,11 @@ impl GuiComponent for SliderState {
             .renderer
             .builder(gl, GuiShaderKind::RoundedBox)
             .set_window(win_w, win_h)
-            .set_background_color(self.slider_frame.color)
+            .set_background_color(style.color)
             .set_bounds(self.slider_frame.bounds())
-            .set_edge_color(self.slider_frame.edge_color)
-            .set_roundness_vec(self.slider_frame.roundness)
+            .set_edge_color(style.edge_color)
+            .set_edge_thickness(style.edge_thickness)
+            .set_roundness_vec(style.roundness)
             .set_position(
                 state.global_position,
                 Vec4::convert(self.slider_frame.bounds()),
@@ -111,7 +205,7 @@ impl<'a, ProgramState> SliderBuilder<'a, ProgramState> {
             manager,
             parent: None,
             slider_frame_state: Some(slider_state),
-            slider_button_state: Some(FrameState::new()),
+            slider_button_state: Some(FrameState::new().with_style_class(StyleClass::SliderButton)),
             slider_frame_key,
             slider_button_key,
             key: slider_frame_key,
@@ -142,25 +236,23 @@ impl<'a, ProgramState> SliderBuilder<'a, ProgramState> {
     }
 
     pub fn with_color<T: Into<Vec4<f32>>>(mut self, color: T) -> Self {
-        self.slider_frame_state.as_mut().unwrap().slider_frame.color = color.into();
+        let state = self.slider_frame_state.as_mut().unwrap();
+        state.slider_frame.color = color.into();
+        state.style_overrides_mut().color = true;
         self
     }
 
     pub fn with_edge_color<T: Into<Vec4<f32>>>(mut self, color: T) -> Self {
-        self.slider_frame_state
-            .as_mut()
-            .unwrap()
-            .slider_frame
-            .edge_color = color.into();
+        let state = self.slider_frame_state.as_mut().unwrap();
+        state.slider_frame.edge_color = color.into();
+        state.style_overrides_mut().edge_color = true;
         self
     }
 
     pub fn with_roundness<T: Into<Vec4<f32>>>(mut self, roundness: T) -> Self {
-        self.slider_frame_state
-            .as_mut()
-            .unwrap()
-            .slider_frame
-            .roundness = roundness.into();
+        let state = self.slider_frame_state.as_mut().unwrap();
+        state.slider_frame.roundness = roundness.into();
+        state.style_overrides_mut().roundness = true;
         self
     }
 
@@ -173,17 +265,32 @@ impl<'a, ProgramState> SliderBuilder<'a, ProgramState> {
     }
 
     pub fn with_button_color<T: Into<Vec4<f32>>>(mut self, color: T) -> Self {
-        self.slider_button_state.as_mut().unwrap().color = color.into();
+        let button = self.slider_button_state.as_mut().unwrap();
+        button.color = color.into();
+        button.style_overrides_mut().color = true;
         self
     }
 
     pub fn with_button_edge_color<T: Into<Vec4<f32>>>(mut self, color: T) -> Self {
-        self.slider_button_state.as_mut().unwrap().edge_color = color.into();
+        let button = self.slider_button_state.as_mut().unwrap();
+        button.edge_color = color.into();
+        button.style_overrides_mut().edge_color = true;
         self
     }
 
     pub fn with_button_roundness<T: Into<Vec4<f32>>>(mut self, roundness: T) -> Self {
-        self.slider_button_state.as_mut().unwrap().roundness = roundness.into();
+        let button = self.slider_button_state.as_mut().unwrap();
+        button.roundness = roundness.into();
+        button.style_overrides_mut().roundness = true;
+        self
+    }
+
+    /// uses the named `Theme` style `name` for the slider button
+    pub fn with_button_style<T: AsRef<str>>(mut self, name: T) -> Self {
+        self.slider_button_state
+            .as_mut()
+            .unwrap()
+            .set_style_name(Some(name.as_ref()));
         self
     }
 
@@ -377,67 +484,27 @@ impl<'a, ProgramState> HasComponentBuilder<ProgramState> for SliderBuilder<'a, P
                 GuiEventKind::OnWheelWhileHovered,
                 Box::new(|info| {
                     let wheel = info.event.wheel();
+                    SliderState::nudge_button(info.gui_comp_tree, info.key, wheel);
+                    None
+                }),
+            ),
+        );
 
-                    let tree = info.gui_comp_tree;
-
-                    let slider_frame_key = info.key;
-
-                    let slider_button_key = tree
-                        .get_mut(slider_frame_key)
-                        .unwrap()
-                        .as_any_mut()
-                        .downcast_mut::<SliderState>()
-                        .unwrap()
-                        .slider_button_key;
-
-                    let frame_bounds = tree
-                        .get(slider_frame_key)
-                        .expect("slider_frame component")
-                        .bounds();
-
-                    let button_bounds = tree
-                        .get(slider_button_key)
-                        .expect("slider_button component")
-                        .bounds();
-
-                    //move slider button,horizontally by increments of 5% of the parent bounds width
-                    let wheel_dx = (frame_bounds[0] * 0.05) * wheel;
-
-                    //translate the slider like normal
-                    tree.get_mut(slider_button_key)
-                        .expect("slider_button")
-                        .translate(Vec2::from([wheel_dx, 0.0]));
-
-                    //vertically center the slider on drag
-                    let max_horizontal_rel_pos = frame_bounds[0] - button_bounds[0];
-                    let button_slider_pos = *tree
-                        .get(slider_button_key)
-                        .expect("slider_button_key invalid")
-                        .rel_position();
-
-                    let vertically_centered_and_horizontally_clamped_relative_position =
-                        Vec2::from([
-                            button_slider_pos.x().clamp(0.0, max_horizontal_rel_pos),
-                            frame_bounds.y() * 0.5 - button_bounds.y() * 0.5,
-                        ]);
-
-                    //set newly computed position
-                    tree.get_mut(slider_button_key)
-                        .expect("slider_button_key invalid")
-                        .set_rel_position(
-                            vertically_centered_and_horizontally_clamped_relative_position,
-                        );
-
-                    //update percentage
-                    let new_percentage =
-                        (button_slider_pos.x() / max_horizontal_rel_pos).clamp(0.0, 1.0);
-                    tree.get_mut(slider_frame_key)
-                        .expect("slider_frame_key invalid")
-                        .as_any_mut()
-                        .downcast_mut::<Self::ComponentKind>()
-                        .expect("slider_frame_key should alias SliderState")
-                        .percentage = new_percentage;
-
+        self.manager.push_listener(
+            slider_frame_key,
+            ComponentEventListener::new(
+                GuiEventKind::OnKeyDown,
+                Box::new(|info| {
+                    let steps = match info.event {
+                        EventKind::KeyDown {
+                            code: KeyCode::ARROW_L | KeyCode::ARROW_D,
+                        } => -1.0,
+                        EventKind::KeyDown {
+                            code: KeyCode::ARROW_R | KeyCode::ARROW_U,
+                        } => 1.0,
+                        _ => return None,
+                    };
+                    SliderState::nudge_button(info.gui_comp_tree, info.key, steps);
                     None
                 }),
             ),
@@ -2,7 +2,23 @@ use super::*;
 
 use crate::{slice::IdxSlice, time::Instant};
 
+/// rounds `idx` down to the nearest char boundary of `text` (`str::floor_char_boundary` is unstable)
+fn floor_char_boundary(text: &str, idx: usize) -> usize {
+    let mut idx = idx.min(text.len());
+    while !text.is_char_boundary(idx) {
+        idx -= 1;
+    }
+    idx
+}
+
+/// length in bytes of the first char in `text`, `0` if `text` is empty
+fn first_char_len(text: &str) -> usize {
+    text.chars().next().map(char::len_utf8).unwrap_or(0)
+}
+
 /// Given a string of text, this code figures out what substring can fit inside of rectangle
+/// ## Comments
+/// - all indices are byte indices into the text, but the clipper only ever stops on char boundaries
 pub struct CaptionClipper {
     prev_cap_len: usize,
     visible_text: String,
@@ -81,33 +97,40 @@ impl CaptionClipper {
 
         let mut clipped_text;
         let mut aabb;
-        let byte_slice = text.as_bytes();
-        let num_bytes = byte_slice.len() as isize;
+        let num_bytes = text.len() as isize;
         let cursor_ubound = (num_bytes - self.scroll_cursor).clamp(0, num_bytes) as usize;
+        let cursor_ubound = floor_char_boundary(text, cursor_ubound);
         let max_text_width = (frame_bounds.x() - margin_right).max(0.0);
 
-        for _ in &byte_slice[..cursor_ubound] {
+        for c in text[..cursor_ubound].chars() {
             clipped_text = visible_slice.get_slice(text);
             aabb = text_writer.calc_text_aabb(clipped_text, 0.0, 0.0, text_size);
 
             if aabb.w() < max_text_width {
-                visible_slice.push_rear(1);
+                visible_slice.push_rear(c.len_utf8());
             } else {
                 //on the first overflow record text positions
                 if visible_slice_first_overflow.is_none() {
                     *visible_slice_first_overflow = Some(*visible_slice);
                 }
 
-                visible_slice.shift(1);
+                // same as shifting by one char, but the char leaving and the char entering
+                // don't have to be the same number of bytes
+                if clipped_text.is_empty() {
+                    visible_slice.shift(c.len_utf8());
+                } else {
+                    visible_slice.pop_front(first_char_len(clipped_text));
+                    visible_slice.push_rear(c.len_utf8());
+                }
             }
         }
 
         while {
             clipped_text = visible_slice.get_slice(text);
             aabb = text_writer.calc_text_aabb(clipped_text, 0.0, 0.0, text_size);
-            aabb.w() >= max_text_width
+            aabb.w() >= max_text_width && !clipped_text.is_empty()
         } {
-            visible_slice.pop_front(1);
+            visible_slice.pop_front(first_char_len(clipped_text));
         }
 
         clipped_text = visible_slice.get_slice(text);
@@ -166,8 +189,8 @@ impl CaptionClipper {
         let clipped_text = self.visible_text.as_str();
         self.visible_text_dx.clear();
 
-        for k in 1..=clipped_text.len() {
-            let cumulative_text = &clipped_text[..k];
+        for (idx, c) in clipped_text.char_indices() {
+            let cumulative_text = &clipped_text[..idx + c.len_utf8()];
             let cur_w = text_writer
                 .calc_text_aabb(cumulative_text, 0.0, 0.0, font_size)
                 .w();
@@ -214,11 +237,10 @@ impl CaptionClipper {
         const MARGIN_SCALING_TO_MAKE_SURE_CURSOR_REACES_THE_START_OF_THE_TEXT: f32 = 1.5;
 
         if let Some(off) = self.can_off_cursor.take() {
-            let byte_slice = text.as_bytes();
-            let num_bytes = byte_slice.len() as isize;
+            let num_bytes = text.len() as isize;
             let cursor_ubound =
                 (num_bytes - (self.scroll_cursor + off)).clamp(0, num_bytes) as usize;
-            let clipped_text = &text[..cursor_ubound];
+            let clipped_text = &text[..floor_char_boundary(text, cursor_ubound)];
             let clipped_text_aabb = text_writer.calc_text_aabb(clipped_text, 0.0, 0.0, font_size);
             let is_overflow_on_x = || {
                 let clipped_max_width = frame_bounds.x()
@@ -239,10 +261,11 @@ impl CaptionClipper {
         let mut global_idx = self.visible_slice.lbound();
 
         // let visible_text_len = self.visible_text.len();
+        let mut visible_chars = self.visible_text.chars();
         while total_len < disp_x && local_idx < self.visible_text_dx.len() {
             total_len += self.visible_text_dx[local_idx];
             local_idx += 1;
-            global_idx += 1;
+            global_idx += visible_chars.next().map(char::len_utf8).unwrap_or(1);
         }
         // println!(
         //     "c = {}",
@@ -252,9 +275,16 @@ impl CaptionClipper {
     }
 
     pub fn get_visible_cursor_displacement(&self, global_text_index: usize) -> f32 {
+        // `visible_text_dx` has one entry per char, not per byte
+        let local_idx = global_text_index.saturating_sub(self.visible_slice.lbound());
+        let num_chars = self
+            .visible_text
+            .get(..local_idx)
+            .map(|text| text.chars().count())
+            .unwrap_or(self.visible_text_dx.len());
         self.visible_text_dx
             .iter()
-            .take(global_text_index - self.visible_slice.lbound())
+            .take(num_chars)
             .fold(0.0, |acc, &e| acc + e)
     }
 
@@ -282,6 +312,12 @@ pub struct TextBoxState {
     text_area: AABB2<f32>,
     cursor_area: AABB2<f32>,
     t0: Instant,
+    /// text an input method is still composing, drawn at the cursor but not part of `text` yet
+    preedit: String,
+    /// byte offset of the cursor inside of `preedit`
+    preedit_cursor: usize,
+    /// overrides the theme's text color when set
+    text_color: Option<Vec4<f32>>,
 }
 
 impl Default for TextBoxState {
@@ -293,7 +329,9 @@ impl Default for TextBoxState {
 impl TextBoxState {
     pub fn new() -> Self {
         Self {
-            frame: FrameState::new(),
+            frame: FrameState::new()
+                .with_style_class(StyleClass::TextBox)
+                .with_focusable(true),
             aligner: TextAligner2D::new(),
             text: String::new(),
             text_size: 12.0,
@@ -302,6 +340,9 @@ impl TextBoxState {
             text_area: AABB2::zero(),
             text_cursor: 0,
             t0: Instant::now(),
+            preedit: String::new(),
+            preedit_cursor: 0,
+            text_color: None,
         }
     }
 }
@@ -312,26 +353,83 @@ impl TextBoxState {
         let new_text_cursor_position = self
             .clipper
             .get_text_postion_given_horizontal_disp(relative_horizontal_postion);
-        self.text_cursor = new_text_cursor_position;
+        self.text_cursor = floor_char_boundary(&self.text, new_text_cursor_position);
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
     }
 
+    /// the cursor position, as a byte index into `Self::text()`
+    pub fn cursor(&self) -> usize {
+        self.text_cursor
+    }
+
+    /// moves the cursor `off` chars to the right (or left if negative)
     pub fn offset_cursor(&mut self, off: isize) {
-        self.text_cursor =
-            (self.text_cursor as isize + off).clamp(0, self.text.len() as isize) as usize;
+        let text = self.text.as_str();
+        let mut cursor = floor_char_boundary(text, self.text_cursor);
+        let num_chars = off.unsigned_abs();
+        if off < 0 {
+            for c in text[..cursor].chars().rev().take(num_chars) {
+                cursor -= c.len_utf8();
+            }
+        } else {
+            for c in text[cursor..].chars().take(num_chars) {
+                cursor += c.len_utf8();
+            }
+        }
+        self.text_cursor = cursor;
     }
 
     pub fn push_char_at_cursor(&mut self, c: char) {
-        self.text_cursor = self.text_cursor.clamp(0, self.text.len());
+        self.text_cursor = floor_char_boundary(&self.text, self.text_cursor);
         self.text.insert(self.text_cursor, c);
-        self.text_cursor += 1;
+        self.text_cursor += c.len_utf8();
+    }
+
+    /// inserts `text` at the cursor, control characters are skipped
+    pub fn insert_text_at_cursor(&mut self, text: &str) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            self.push_char_at_cursor(c);
+        }
     }
 
+    /// removes the char right before the cursor
     pub fn remove_char_at_cursor(&mut self) {
-        if self.text_cursor > 0 && self.text_cursor <= self.text.len() {
-            self.text.remove(self.text_cursor - 1);
-            self.text_cursor -= 1;
+        self.text_cursor = floor_char_boundary(&self.text, self.text_cursor);
+        if let Some(c) = self.text[..self.text_cursor].chars().next_back() {
+            self.text_cursor -= c.len_utf8();
+            self.text.remove(self.text_cursor);
         }
     }
+
+    /// the text an input method is composing, empty when nothing is being composed
+    pub fn preedit(&self) -> &str {
+        &self.preedit
+    }
+
+    pub fn is_composing(&self) -> bool {
+        !self.preedit.is_empty()
+    }
+
+    /// replaces the preedit text, `cursor` is in chars like `EventKind::ImePreeditUpdate`
+    pub fn set_preedit(&mut self, preedit: &str, cursor: i32) {
+        self.preedit.clear();
+        self.preedit
+            .extend(preedit.chars().filter(|c| !c.is_control()));
+        self.preedit_cursor = self
+            .preedit
+            .char_indices()
+            .map(|(idx, _)| idx)
+            .nth(cursor.max(0) as usize)
+            .unwrap_or(self.preedit.len());
+    }
+
+    pub fn clear_preedit(&mut self) {
+        self.preedit.clear();
+        self.preedit_cursor = 0;
+    }
 }
 
 impl GuiComponent for TextBoxState {
@@ -351,6 +449,30 @@ impl GuiComponent for TextBoxState {
         self
     }
 
+    fn accessible_role(&self) -> AccessibleRole {
+        AccessibleRole::TextInput
+    }
+
+    fn color(&self) -> Option<Vec4<f32>> {
+        self.frame.color()
+    }
+
+    fn set_color(&mut self, color: Vec4<f32>) {
+        self.frame.set_color(color)
+    }
+
+    fn drawn_color(&self) -> Option<Vec4<f32>> {
+        self.frame.drawn_color()
+    }
+
+    fn set_tweened_color(&mut self, color: Vec4<f32>, done: bool) {
+        self.frame.set_tweened_color(color, done)
+    }
+
+    fn accessible_value(&self) -> Option<String> {
+        Some(self.text.clone())
+    }
+
     fn render_entry(
         &mut self,
         gl: &GlowGL,
@@ -367,9 +489,19 @@ impl GuiComponent for TextBoxState {
 
         let &old_sf = text_writer.horizontal_scaling_factor();
         *text_writer.horizontal_scaling_factor_mut() = 1.3;
+        let text_color = state.resolve_text_color(self.text_color);
+
+        // while an input method is composing, the preedit text is drawn inline at the cursor
+        let composed_caption;
+        let (caption, display_cursor) = if self.preedit.is_empty() {
+            (self.text.as_str(), self.text_cursor)
+        } else {
+            let split = floor_char_boundary(&self.text, self.text_cursor);
+            composed_caption = [&self.text[..split], &self.preedit, &self.text[split..]].concat();
+            (composed_caption.as_str(), split + self.preedit_cursor)
+        };
 
         let clipper = &mut self.clipper;
-        let caption = &self.text;
         let frame_bounds = self.frame.bounds();
         let text_size = self.text_size;
 
@@ -392,6 +524,7 @@ impl GuiComponent for TextBoxState {
                 self.frame.bounds(),
             );
 
+            let old_color = text_writer.set_text_color(text_color);
             text_writer.draw_text_line(
                 clipped_text,
                 aligned_global_position.x() + HORIZONTAL_MARGIN,
@@ -399,6 +532,7 @@ impl GuiComponent for TextBoxState {
                 text_size,
                 Some((win_w as u32, win_h as u32)),
             );
+            text_writer.set_text_color(old_color);
 
             self.text_area = AABB2::from_point_and_lengths(
                 Vec2::from([
@@ -408,10 +542,7 @@ impl GuiComponent for TextBoxState {
                 text_aabb.dims(),
             );
 
-            unsafe {
-                //re-enable
-                gl.enable(glow::BLEND);
-            }
+            GlStateCache::with(gl, |state| state.set_blend(BlendState::ALPHA));
 
             //draw scroll bar
             let scroll_bar_bounds = Vec2::from([50.0, 12.0]);
@@ -448,9 +579,7 @@ impl GuiComponent for TextBoxState {
                 ]),
             );
 
-            unsafe {
-                gl.blend_func(glow::ONE, glow::ONE);
-            }
+            GlStateCache::with(gl, |state| state.set_blend(BlendState::ADDITIVE));
             // // render cursor_area bounding box
             // state
             //     .renderer
@@ -485,16 +614,15 @@ impl GuiComponent for TextBoxState {
             let is_text_cursor_visible = self
                 .clipper
                 .visible_slice
-                .is_in_range_include_upper_bound(self.text_cursor);
+                .is_in_range_include_upper_bound(display_cursor);
 
             let elapsed_time_ms = self.t0.elapsed().as_millis();
             //blink every 1024ms
             let cursor_blink_index = elapsed_time_ms >> 10;
 
             if is_text_cursor_visible && cursor_blink_index % 2 == 0 {
-                let visible_cursor_displacement = self
-                    .clipper
-                    .get_visible_cursor_displacement(self.text_cursor);
+                let visible_cursor_displacement =
+                    self.clipper.get_visible_cursor_displacement(display_cursor);
 
                 let cursor_pos = Vec2::from([
                     aligned_global_position.x() + HORIZONTAL_MARGIN + visible_cursor_displacement,
@@ -514,10 +642,35 @@ impl GuiComponent for TextBoxState {
                     .render();
             }
 
-            unsafe {
-                gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
+            // underline whatever is still being composed
+            if !self.preedit.is_empty() {
+                let preedit_start = floor_char_boundary(&self.text, self.text_cursor);
+                let preedit_end = preedit_start + self.preedit.len();
+                let x0 = self.clipper.get_visible_cursor_displacement(preedit_start);
+                let x1 = self.clipper.get_visible_cursor_displacement(preedit_end);
+
+                let underline_pos = Vec2::from([
+                    aligned_global_position.x() + HORIZONTAL_MARGIN + x0,
+                    aligned_global_position.y() + text_aabb.h() - 1.0,
+                ]);
+                let underline_bounds = Vec2::from([(x1 - x0).max(1.0), 1.0]);
+                state
+                    .renderer
+                    .builder(gl, GuiShaderKind::Rectangle)
+                    .set_window(win_w, win_h)
+                    .set_position(
+                        Vec4::convert(underline_pos),
+                        Vec4::convert(underline_bounds),
+                    )
+                    .set_background_color(text_color)
+                    .set_edge_color(text_color)
+                    .set_roundness_vec([1.; 4])
+                    .set_bounds(underline_bounds)
+                    .render();
             }
 
+            GlStateCache::with(gl, |state| state.set_blend(BlendState::ALPHA));
+
             layer_unlock(gl);
 
             //restore previous sf
@@ -566,7 +719,9 @@ impl<'a, ProgramState> TextBoxBuilder<'a, ProgramState> {
     where
         Vec4<f32>: From<T>,
     {
-        self.state.as_mut().unwrap().frame.color = Vec4::from(color);
+        let state = self.state.as_mut().unwrap();
+        state.frame.color = Vec4::from(color);
+        state.style_overrides_mut().color = true;
         self
     }
 
@@ -574,7 +729,9 @@ impl<'a, ProgramState> TextBoxBuilder<'a, ProgramState> {
     where
         Vec4<f32>: From<T>,
     {
-        self.state.as_mut().unwrap().frame.edge_color = Vec4::from(color);
+        let state = self.state.as_mut().unwrap();
+        state.frame.edge_color = Vec4::from(color);
+        state.style_overrides_mut().edge_color = true;
         self
     }
 
@@ -582,7 +739,9 @@ impl<'a, ProgramState> TextBoxBuilder<'a, ProgramState> {
     where
         Vec4<f32>: From<T>,
     {
-        self.state.as_mut().unwrap().frame.roundness = Vec4::from(r);
+        let state = self.state.as_mut().unwrap();
+        state.frame.roundness = Vec4::from(r);
+        state.style_overrides_mut().roundness = true;
         self
     }
 
@@ -616,6 +775,14 @@ impl<'a, ProgramState> TextBoxBuilder<'a, ProgramState> {
         self.state.as_mut().unwrap().text_size = size;
         self
     }
+
+    pub fn with_text_color<T>(mut self, color: T) -> Self
+    where
+        Vec4<f32>: From<T>,
+    {
+        self.state.as_mut().unwrap().text_color = Some(Vec4::from(color));
+        self
+    }
 }
 
 impl<'a, ProgramState> HasComponentBuilder<ProgramState> for TextBoxBuilder<'a, ProgramState> {
@@ -689,7 +856,12 @@ impl<'a, ProgramState> HasComponentBuilder<ProgramState> for TextBoxBuilder<'a,
                     .request_offset_of_scroll_cursor(wheel_dir as isize);
             })
             .with_listener(GuiEventKind::OnKeyDown, |comp, e, _mrq| {
+                // characters arrive through OnTextInput, only editing keys are handled here.
+                // While composing, the input method owns these keys.
                 if let EventKind::KeyDown { code } = e {
+                    if comp.is_composing() {
+                        return;
+                    }
                     match code {
                         KeyCode::BACKSPACE => {
                             comp.remove_char_at_cursor();
@@ -700,16 +872,31 @@ impl<'a, ProgramState> HasComponentBuilder<ProgramState> for TextBoxBuilder<'a,
                         KeyCode::ARROW_R => {
                             comp.offset_cursor(1);
                         }
-                        KeyCode::SHIFT_L | KeyCode::SHIFT_R | KeyCode::CTRL_L | KeyCode::CTRL_R => {
-                        }
-                        _ => {
-                            let c = code.key_val().unwrap_or_default();
-                            if c.is_ascii() {
-                                comp.push_char_at_cursor(c);
-                            }
-                        }
+                        _ => (),
+                    }
+                }
+            })
+            .with_listener(GuiEventKind::OnTextInput, |comp, e, _mrq| {
+                if let EventKind::TextInput { text } | EventKind::ImeCommit { text } = e {
+                    comp.clear_preedit();
+                    comp.insert_text_at_cursor(text.as_str());
+                }
+            })
+            .with_listener(GuiEventKind::OnComposition, |comp, e, _mrq| match e {
+                EventKind::ImePreeditStart => comp.clear_preedit(),
+                EventKind::ImePreeditUpdate {
+                    text,
+                    cursor,
+                    continued,
+                } => {
+                    if continued {
+                        let preedit = [comp.preedit(), text.as_str()].concat();
+                        comp.set_preedit(&preedit, cursor)
+                    } else {
+                        comp.set_preedit(text.as_str(), cursor)
                     }
                 }
+                _ => (),
             });
 
         let manager = self.manager;
@@ -0,0 +1,117 @@
+use super::*;
+
+/// ## Description
+/// computes the order Tab visits components in
+/// ## Comments
+/// - `candidates` must be given in tree pre-order
+/// - components with a positive tab index come first (in ascending order)
+/// - components with no tab index (or a tab index of `0`) follow in pre-order
+/// - components with a negative tab index are skipped
+pub fn compute_tab_order<I>(candidates: I) -> Vec<GuiComponentKey>
+where
+    I: IntoIterator<Item = (GuiComponentKey, Option<i32>)>,
+{
+    let mut explicit = Vec::new();
+    let mut natural = Vec::new();
+
+    for (key, tab_index) in candidates {
+        match tab_index.unwrap_or(0) {
+            idx if idx > 0 => explicit.push((idx, key)),
+            0 => natural.push(key),
+            _ => (),
+        }
+    }
+
+    // sort is stable so ties stay in pre-order
+    explicit.sort_by_key(|&(idx, _)| idx);
+
+    explicit
+        .into_iter()
+        .map(|(_, key)| key)
+        .chain(natural)
+        .collect()
+}
+
+/// ## Description
+/// picks the component that gets focus after `current` when moving through `tab_order`
+/// ## Comments
+/// - wraps around at both ends
+/// - if `current` isn't in `tab_order` traversal starts at the beginning (or the end if `reverse` is set)
+pub fn step_focus(
+    tab_order: &[GuiComponentKey],
+    current: Option<GuiComponentKey>,
+    reverse: bool,
+) -> Option<GuiComponentKey> {
+    let len = tab_order.len();
+    if len == 0 {
+        return None;
+    }
+
+    let current_idx = current.and_then(|key| tab_order.iter().position(|&k| k == key));
+    let next_idx = match (current_idx, reverse) {
+        (Some(idx), false) => (idx + 1) % len,
+        (Some(idx), true) => (idx + len - 1) % len,
+        (None, false) => 0,
+        (None, true) => len - 1,
+    };
+
+    Some(tab_order[next_idx])
+}
+
+impl<ProgramState> GuiManager<ProgramState> {
+    /// ## Description
+    /// returns the components Tab currently cycles through, in the order they get visited
+    /// ## Comments
+    /// - only visible, enabled and focusable components are included
+    pub fn tab_order(&mut self) -> Vec<GuiComponentKey> {
+        self.recompute_visibility();
+        Self::compute_tab_order_from_tree(&self.gui_component_tree, &self.visibility_table)
+    }
+
+    /// the component that currently has keyboard focus
+    pub fn focused_component(&self) -> Option<GuiComponentKey> {
+        self.focused_component
+    }
+
+    pub(super) fn compute_tab_order_from_tree(
+        gui_component_tree: &LinearTree<Box<dyn GuiComponent>>,
+        visibility_table: &[bool],
+    ) -> Vec<GuiComponentKey> {
+        let candidates = gui_component_tree
+            .iter()
+            .filter(|node| visibility_table[node.id.as_usize()])
+            .filter(|node| node.val.is_focusable() && node.val.is_enabled())
+            .map(|node| (GuiComponentKey::from(node.id), node.val.tab_index()));
+        compute_tab_order(candidates)
+    }
+}
+
+#[test]
+fn tab_order_sanity() {
+    let k = GuiComponentKey;
+    let candidates = vec![
+        (k(1), None),
+        (k(2), Some(2)),
+        (k(3), Some(-1)),
+        (k(4), Some(1)),
+        (k(5), Some(0)),
+        (k(6), Some(2)),
+    ];
+
+    let order = compute_tab_order(candidates);
+    assert_eq!(order, vec![k(4), k(2), k(6), k(1), k(5)]);
+}
+
+#[test]
+fn step_focus_wraps() {
+    let k = GuiComponentKey;
+    let order = vec![k(4), k(2), k(6)];
+
+    assert_eq!(step_focus(&order, None, false), Some(k(4)));
+    assert_eq!(step_focus(&order, None, true), Some(k(6)));
+    assert_eq!(step_focus(&order, Some(k(2)), false), Some(k(6)));
+    assert_eq!(step_focus(&order, Some(k(6)), false), Some(k(4)));
+    assert_eq!(step_focus(&order, Some(k(4)), true), Some(k(6)));
+    assert_eq!(step_focus(&order, Some(k(9)), false), Some(k(4)));
+    assert_eq!(step_focus(&[], Some(k(9)), false), None);
+}
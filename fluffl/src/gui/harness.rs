@@ -0,0 +1,599 @@
+use super::*;
+use crate::window::event_util::{MouseCode, TextChunk};
+use std::{cell::Cell, rc::Rc};
+
+/// ## Description
+/// Drives a headless `GuiManager` with synthetic input so UI logic can be tested without a window
+/// ## Comments
+/// - every input method pushes its events and immediately steps the manager
+/// - layout is refreshed after each step, so `GuiManager::component_aabb(..)` already reflects
+///   any movement caused by the events that were just processed
+/// - time stands still unless `Self::advance_time(..)` is called, the manager's gesture clock reads it
+pub struct GuiHarness<ProgramState> {
+    manager: GuiManager<ProgramState>,
+    origin: GuiComponentKey,
+    mouse_pos: Vec2<f32>,
+    is_composing: bool,
+    /// last known position of every finger that is down
+    fingers: HashMap<i32, Vec2<f32>>,
+    time_ms: Rc<Cell<f64>>,
+}
+
+impl<ProgramState> Default for GuiHarness<ProgramState> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<ProgramState> GuiHarness<ProgramState> {
+    /// creates a headless manager with an `OriginState` already added as the root
+    pub fn new() -> Self {
+        let mut manager = GuiManager::new_headless();
+        let origin =
+            manager.add_component(GuiComponentKey::default(), Box::new(OriginState::new()));
+        Self::from_manager(manager, origin)
+    }
+
+    pub fn from_manager(mut manager: GuiManager<ProgramState>, origin: GuiComponentKey) -> Self {
+        let time_ms = Rc::new(Cell::new(0.0));
+        let clock = time_ms.clone();
+        manager.set_gesture_clock(move || clock.get());
+        Self {
+            manager,
+            origin,
+            mouse_pos: Vec2::zero(),
+            is_composing: false,
+            fingers: HashMap::new(),
+            time_ms,
+        }
+    }
+
+    pub fn origin(&self) -> GuiComponentKey {
+        self.origin
+    }
+
+    pub fn manager(&self) -> &GuiManager<ProgramState> {
+        &self.manager
+    }
+
+    pub fn manager_mut(&mut self) -> &mut GuiManager<ProgramState> {
+        &mut self.manager
+    }
+
+    pub fn into_manager(self) -> GuiManager<ProgramState> {
+        self.manager
+    }
+
+    /// milliseconds passed since the harness was created, according to the harness
+    pub fn time(&self) -> f64 {
+        self.time_ms.get()
+    }
+
+    /// moves the clock forward by `ms` and steps, so anything waiting on time (like long presses) fires
+    pub fn advance_time(&mut self, ms: f64) -> &mut Self {
+        self.time_ms.set(self.time_ms.get() + ms);
+        self.step()
+    }
+
+    /// the last position the mouse was moved to
+    pub fn mouse_pos(&self) -> Vec2<f32> {
+        self.mouse_pos
+    }
+
+    /// queues `event` without processing it, call `Self::step()` to process
+    pub fn push_event(&mut self, event: EventKind) -> &mut Self {
+        if let EventKind::MouseMove { x, y, .. }
+        | EventKind::MouseDown { x, y, .. }
+        | EventKind::MouseUp { x, y, .. } = event
+        {
+            self.mouse_pos = Vec2::from([x, y]);
+        }
+        self.manager.push_event(event);
+        self
+    }
+
+    /// processes all queued events and then refreshes layout
+    pub fn step(&mut self) -> &mut Self {
+        self.manager.update();
+        self.manager.recompute_layout();
+        self
+    }
+
+    pub fn mouse_move_to<T: Into<Vec2<f32>>>(&mut self, pos: T) -> &mut Self {
+        let pos = pos.into();
+        let disp = pos - self.mouse_pos;
+        self.push_event(EventKind::MouseMove {
+            x: pos.x(),
+            y: pos.y(),
+            dx: disp.x(),
+            dy: disp.y(),
+        })
+        .step()
+    }
+
+    pub fn mouse_down<T: Into<Vec2<f32>>>(&mut self, pos: T) -> &mut Self {
+        let pos = pos.into();
+        self.mouse_move_to(pos)
+            .push_event(EventKind::MouseDown {
+                button_code: MouseCode::LEFT_BUTTON,
+                x: pos.x(),
+                y: pos.y(),
+            })
+            .step()
+    }
+
+    pub fn mouse_up(&mut self) -> &mut Self {
+        let pos = self.mouse_pos;
+        self.push_event(EventKind::MouseUp {
+            button_code: MouseCode::LEFT_BUTTON,
+            x: pos.x(),
+            y: pos.y(),
+        })
+        .step()
+    }
+
+    pub fn click<T: Into<Vec2<f32>>>(&mut self, pos: T) -> &mut Self {
+        self.mouse_down(pos).mouse_up()
+    }
+
+    /// presses at `from`, moves to `to` in `num_moves` equal increments and then releases
+    pub fn drag<A, B>(&mut self, from: A, to: B, num_moves: usize) -> &mut Self
+    where
+        A: Into<Vec2<f32>>,
+        B: Into<Vec2<f32>>,
+    {
+        let from = from.into();
+        let to = to.into();
+        let num_moves = num_moves.max(1);
+
+        self.mouse_down(from);
+        for k in 1..=num_moves {
+            let t = k as f32 / num_moves as f32;
+            self.mouse_move_to(from + (to - from) * t);
+        }
+        self.mouse_up()
+    }
+
+    pub fn wheel(&mut self, direction: i32) -> &mut Self {
+        self.push_event(EventKind::MouseWheel {
+            button_code: MouseCode::WHEEL { direction },
+        })
+        .step()
+    }
+
+    pub fn key_down(&mut self, code: KeyCode) -> &mut Self {
+        self.push_event(EventKind::KeyDown { code }).step()
+    }
+
+    pub fn key_up(&mut self, code: KeyCode) -> &mut Self {
+        self.push_event(EventKind::KeyUp { code }).step()
+    }
+
+    pub fn press_key(&mut self, code: KeyCode) -> &mut Self {
+        self.key_down(code).key_up(code)
+    }
+
+    /// presses every key in `codes`, one after the other
+    pub fn press_keys<I>(&mut self, codes: I) -> &mut Self
+    where
+        I: IntoIterator<Item = KeyCode>,
+    {
+        for code in codes {
+            self.press_key(code);
+        }
+        self
+    }
+
+    /// presses `code` while holding shift
+    pub fn press_key_shifted(&mut self, code: KeyCode) -> &mut Self {
+        self.key_down(KeyCode::SHIFT_L)
+            .press_key(code)
+            .key_up(KeyCode::SHIFT_L)
+    }
+
+    /// sends `text` as `TextInput` events, the way a backend would after a user typed it
+    pub fn type_text(&mut self, text: &str) -> &mut Self {
+        for text in TextChunk::split(text) {
+            self.push_event(EventKind::TextInput { text });
+        }
+        self.step()
+    }
+
+    /// ## Description
+    /// sends the preedit text an input method would show while composing
+    /// ## Comments
+    /// - starts a composition first if one isn't already going
+    /// - the cursor is placed at the end of `preedit`
+    pub fn ime_preedit(&mut self, preedit: &str) -> &mut Self {
+        if !self.is_composing {
+            self.is_composing = true;
+            self.push_event(EventKind::ImePreeditStart);
+        }
+        for event in EventKind::preedit_updates(preedit, preedit.chars().count() as i32) {
+            self.push_event(event);
+        }
+        self.step()
+    }
+
+    pub fn touch_down<T: Into<Vec2<f32>>>(&mut self, finger_id: i32, pos: T) -> &mut Self {
+        let pos = pos.into();
+        self.fingers.insert(finger_id, pos);
+        self.push_event(EventKind::TouchDown {
+            finger_id,
+            x: pos.x(),
+            y: pos.y(),
+            dx: 0.0,
+            dy: 0.0,
+        })
+        .step()
+    }
+
+    pub fn touch_move<T: Into<Vec2<f32>>>(&mut self, finger_id: i32, pos: T) -> &mut Self {
+        let pos = pos.into();
+        let disp = pos - self.fingers.insert(finger_id, pos).unwrap_or(pos);
+        self.push_event(EventKind::TouchMove {
+            finger_id,
+            x: pos.x(),
+            y: pos.y(),
+            dx: disp.x(),
+            dy: disp.y(),
+        })
+        .step()
+    }
+
+    /// lifts `finger_id` wherever it was last moved to
+    pub fn touch_up(&mut self, finger_id: i32) -> &mut Self {
+        let pos = self.fingers.remove(&finger_id).unwrap_or_else(Vec2::zero);
+        self.push_event(EventKind::TouchUp {
+            finger_id,
+            x: pos.x(),
+            y: pos.y(),
+            dx: 0.0,
+            dy: 0.0,
+        })
+        .step()
+    }
+
+    pub fn tap<T: Into<Vec2<f32>>>(&mut self, pos: T) -> &mut Self {
+        self.touch_down(0, pos).touch_up(0)
+    }
+
+    /// ends the current composition by committing `text`, an empty `text` cancels it
+    pub fn ime_commit(&mut self, text: &str) -> &mut Self {
+        self.is_composing = false;
+        self.push_event(EventKind::ImeCommit {
+            text: TextChunk::new(text),
+        })
+        .step()
+    }
+}
+
+#[test]
+fn harness_click_focuses_and_hit_tests() {
+    let mut harness = GuiHarness::<()>::new();
+    let origin = harness.origin();
+
+    let frame = harness
+        .manager_mut()
+        .builder_frame()
+        .with_parent(origin)
+        .with_position([10.0, 10.0])
+        .with_bounds([200.0, 100.0])
+        .build();
+
+    let child = harness
+        .manager_mut()
+        .builder_frame()
+        .with_parent(frame)
+        .with_position([20.0, 20.0])
+        .with_bounds([50.0, 50.0])
+        .with_name("child")
+        .build();
+
+    harness.step();
+
+    let manager = harness.manager();
+    let child_aabb = manager.component_aabb(child).unwrap();
+    assert_eq!(child_aabb.min_pos.x(), 30.0);
+    assert_eq!(child_aabb.min_pos.y(), 30.0);
+    assert_eq!(manager.find_component_by_name("child"), Some(child));
+    assert_eq!(manager.component_at(Vec2::from([40.0, 40.0])), Some(child));
+    assert_eq!(manager.component_at(Vec2::from([150.0, 90.0])), Some(frame));
+    assert_eq!(
+        manager.component_at(Vec2::from([500.0, 500.0])),
+        Some(origin)
+    );
+
+    harness.click([40.0, 40.0]);
+    assert_eq!(harness.manager().focused_component(), Some(child));
+    assert_eq!(harness.manager().clicked_component(), None);
+
+    harness.mouse_down([150.0, 90.0]);
+    assert_eq!(harness.manager().focused_component(), Some(frame));
+    assert_eq!(harness.manager().clicked_component(), Some(frame));
+}
+
+#[test]
+fn harness_drag_moves_components() {
+    let mut harness = GuiHarness::<()>::new();
+    let origin = harness.origin();
+
+    let frame = harness
+        .manager_mut()
+        .builder_frame()
+        .with_parent(origin)
+        .with_position([0.0, 0.0])
+        .with_bounds([100.0, 100.0])
+        .with_drag(true)
+        .build();
+
+    harness.step().drag([50.0, 50.0], [80.0, 60.0], 3);
+
+    let aabb = harness.manager().component_aabb(frame).unwrap();
+    assert!((aabb.min_pos.x() - 30.0).abs() < 0.001);
+    assert!((aabb.min_pos.y() - 10.0).abs() < 0.001);
+}
+
+#[test]
+fn harness_tab_traversal_and_typing() {
+    let mut harness = GuiHarness::<()>::new();
+    let origin = harness.origin();
+
+    let first = harness
+        .manager_mut()
+        .builder_textbox()
+        .with_parent(origin)
+        .with_position([0.0, 0.0])
+        .with_bounds([100.0, 20.0])
+        .build();
+
+    let hidden = harness
+        .manager_mut()
+        .builder_textbox()
+        .with_parent(origin)
+        .with_position([0.0, 30.0])
+        .with_bounds([100.0, 20.0])
+        .with_visibility(false)
+        .build();
+
+    let last = harness
+        .manager_mut()
+        .builder_textbox()
+        .with_parent(origin)
+        .with_position([0.0, 60.0])
+        .with_bounds([100.0, 20.0])
+        .build();
+
+    let prioritized = harness
+        .manager_mut()
+        .builder_textbox()
+        .with_parent(origin)
+        .with_position([0.0, 90.0])
+        .with_bounds([100.0, 20.0])
+        .with_tab_index(1)
+        .build();
+
+    assert_eq!(
+        harness.manager_mut().tab_order(),
+        vec![prioritized, first, last]
+    );
+
+    harness.step().press_key(KeyCode::TAB);
+    assert_eq!(harness.manager().focused_component(), Some(prioritized));
+
+    harness.press_key(KeyCode::TAB);
+    assert_eq!(harness.manager().focused_component(), Some(first));
+    assert!(!harness.manager().is_globally_visible(hidden));
+
+    harness.type_text("hi");
+    let text = harness
+        .manager()
+        .component(first)
+        .and_then(|comp| comp.accessible_value());
+    assert_eq!(text.as_deref(), Some("hi"));
+
+    harness.press_key_shifted(KeyCode::TAB);
+    assert_eq!(harness.manager().focused_component(), Some(prioritized));
+
+    let tree = harness.manager_mut().accessibility_tree().unwrap();
+    let first_node = tree.find_by_key(first).unwrap();
+    assert_eq!(first_node.role, AccessibleRole::TextInput);
+    assert_eq!(first_node.value.as_deref(), Some("hi"));
+    assert!(tree.find_by_key(prioritized).unwrap().is_focused);
+    assert!(!tree.find_by_key(hidden).unwrap().is_visible);
+}
+
+#[test]
+fn harness_text_input_and_ime_composition() {
+    let mut harness = GuiHarness::<()>::new();
+    let origin = harness.origin();
+
+    let textbox = harness
+        .manager_mut()
+        .builder_textbox()
+        .with_parent(origin)
+        .with_position([0.0, 0.0])
+        .with_bounds([200.0, 20.0])
+        .build();
+
+    let text_of = |harness: &GuiHarness<()>| {
+        let tb = harness
+            .manager()
+            .component_as::<TextBoxState>(textbox)
+            .unwrap();
+        (String::from(tb.text()), String::from(tb.preedit()))
+    };
+
+    // key codes on their own no longer produce characters
+    harness.click([10.0, 10.0]).press_key(KeyCode::KEY_Q);
+    assert_eq!(text_of(&harness).0, "");
+
+    harness.type_text("Ünï!");
+    assert_eq!(text_of(&harness).0, "Ünï!");
+
+    harness
+        .press_keys([KeyCode::ARROW_L, KeyCode::ARROW_L])
+        .press_key(KeyCode::BACKSPACE);
+    assert_eq!(text_of(&harness).0, "Üï!");
+
+    harness.ime_preedit("に").ime_preedit("にほ");
+    assert_eq!(
+        text_of(&harness),
+        (String::from("Üï!"), String::from("にほ"))
+    );
+
+    // editing keys belong to the input method while composing
+    harness.press_key(KeyCode::BACKSPACE);
+    assert_eq!(text_of(&harness).0, "Üï!");
+
+    harness.ime_commit("日本");
+    assert_eq!(text_of(&harness), (String::from("Ü日本ï!"), String::new()));
+
+    harness.ime_preedit("x").ime_commit("");
+    assert_eq!(text_of(&harness), (String::from("Ü日本ï!"), String::new()));
+
+    // preedit text too long for a single event arrives in pieces
+    let long_preedit = "にほんごのぶんしょうをにゅうりょくする";
+    harness.ime_preedit(long_preedit);
+    assert_eq!(text_of(&harness).1, long_preedit);
+    harness.ime_commit("");
+
+    // nothing happens to unfocused components
+    harness.click([500.0, 500.0]).type_text("lost");
+    assert_eq!(text_of(&harness).0, "Ü日本ï!");
+}
+
+#[test]
+fn harness_gestures_reach_the_touched_component() {
+    use crate::window::gesture::Gesture;
+    use std::{cell::RefCell, rc::Rc};
+
+    let mut harness = GuiHarness::<()>::new();
+    let origin = harness.origin();
+    let received = Rc::new(RefCell::new(Vec::new()));
+
+    let frame = harness
+        .manager_mut()
+        .builder_frame()
+        .with_parent(origin)
+        .with_position([0.0, 0.0])
+        .with_bounds([100.0, 100.0])
+        .build();
+
+    // gestures bubble up to the first ancestor listening for them
+    let received_by_frame = received.clone();
+    harness
+        .manager_mut()
+        .builder_frame()
+        .with_parent(frame)
+        .with_position([10.0, 10.0])
+        .with_bounds([20.0, 20.0])
+        .build();
+    harness.manager_mut().push_listener(
+        frame,
+        ComponentEventListener::new(
+            GuiEventKind::OnGesture,
+            Box::new(move |info| {
+                if let EventKind::Gesture { gesture } = info.event {
+                    received_by_frame.borrow_mut().push(gesture);
+                }
+                None
+            }),
+        ),
+    );
+    harness.step();
+
+    harness.tap([20.0, 20.0]).tap([21.0, 20.0]);
+    assert!(matches!(
+        received.borrow()[..],
+        [
+            Gesture::Tap { .. },
+            Gesture::Tap { .. },
+            Gesture::DoubleTap { .. }
+        ]
+    ));
+
+    // outside of the frame nobody is listening
+    received.borrow_mut().clear();
+    harness.tap([300.0, 300.0]);
+    assert!(received.borrow().is_empty());
+
+    // holding still long enough is a long press, no matter how long the test takes to run
+    harness.touch_down(0, [20.0, 20.0]).advance_time(400.0);
+    assert!(received.borrow().is_empty());
+    harness.advance_time(200.0).touch_up(0);
+    assert!(matches!(received.borrow()[..], [Gesture::LongPress { .. }]));
+    received.borrow_mut().clear();
+
+    // pinching inside the frame
+    harness
+        .touch_down(0, [40.0, 50.0])
+        .touch_down(1, [60.0, 50.0])
+        .touch_move(0, [30.0, 50.0])
+        .touch_move(1, [70.0, 50.0])
+        .touch_up(0)
+        .touch_up(1);
+    let last_scale = received.borrow().iter().rev().find_map(|g| match *g {
+        Gesture::Pinch { scale, .. } => Some(scale),
+        _ => None,
+    });
+    assert_eq!(last_scale, Some(2.0));
+}
+
+#[test]
+fn harness_enter_and_space_click_buttons() {
+    let mut harness = GuiHarness::<()>::new();
+    let origin = harness.origin();
+    let clicks = Rc::new(Cell::new(0));
+    let activations = Rc::new(Cell::new(0));
+
+    // only listens for clicks, so the keyboard clicks it
+    let counter = clicks.clone();
+    let button = harness
+        .manager_mut()
+        .builder_frame()
+        .with_parent(origin)
+        .with_position([0.0, 0.0])
+        .with_bounds([100.0, 20.0])
+        .with_focusable(true)
+        .with_listener(GuiEventKind::OnMouseRelease, move |_, event, _| {
+            // in the middle of the button
+            let pos = event.mouse_pos();
+            assert_eq!((pos.x(), pos.y()), (50.0, 10.0));
+            counter.set(counter.get() + 1);
+        })
+        .build();
+
+    // has its own activation, which wins over the click
+    let counter = activations.clone();
+    let clicked = clicks.clone();
+    let custom = harness
+        .manager_mut()
+        .builder_frame()
+        .with_parent(origin)
+        .with_position([0.0, 30.0])
+        .with_bounds([100.0, 20.0])
+        .with_focusable(true)
+        .with_listener(GuiEventKind::OnActivate, move |_, _, _| {
+            counter.set(counter.get() + 1)
+        })
+        .with_listener(GuiEventKind::OnMouseRelease, move |_, _, _| {
+            clicked.set(clicked.get() + 1)
+        })
+        .build();
+
+    harness.step().press_key(KeyCode::TAB);
+    assert_eq!(harness.manager().focused_component(), Some(button));
+    harness.press_key(KeyCode::ENTER).press_key(KeyCode::SPACE);
+    assert_eq!(clicks.get(), 2);
+
+    harness.press_key(KeyCode::TAB);
+    assert_eq!(harness.manager().focused_component(), Some(custom));
+    harness.press_key(KeyCode::ENTER);
+    assert_eq!((clicks.get(), activations.get()), (2, 1));
+
+    // siblings keep the order they were added in
+    let tree = harness.manager_mut().accessibility_tree().unwrap();
+    let children: Vec<_> = tree.children.iter().map(|node| node.key).collect();
+    assert_eq!(children, [button, custom]);
+}
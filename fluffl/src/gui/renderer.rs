@@ -8,6 +8,7 @@ use shader_sources::*;
 pub enum GuiShaderKind {
     RoundedBox = 0,
     Rectangle = 1,
+    Image = 2,
 }
 
 struct ShaderUniforms {
@@ -20,6 +21,9 @@ struct ShaderUniforms {
     roundness_loc: Option<glow::UniformLocation>,
     edge_thickness_loc: Option<glow::UniformLocation>,
     edge_color_loc: Option<glow::UniformLocation>,
+    uv_rect_loc: Option<glow::UniformLocation>,
+    mask_rect_loc: Option<glow::UniformLocation>,
+    image_loc: Option<glow::UniformLocation>,
 }
 
 impl ShaderUniforms {
@@ -33,6 +37,9 @@ impl ShaderUniforms {
             roundness_loc: None,
             edge_thickness_loc: None,
             edge_color_loc: None,
+            uv_rect_loc: None,
+            mask_rect_loc: None,
+            image_loc: None,
         }
     }
     pub fn with_location_hooks(mut self, gl: &GlowGL, prog: &OglProg) -> Self {
@@ -46,6 +53,9 @@ impl ShaderUniforms {
             self.roundness_loc = gl.get_uniform_location(prog, "roundness");
             self.edge_color_loc = gl.get_uniform_location(prog, "edge_color");
             self.edge_thickness_loc = gl.get_uniform_location(prog, "edge_thickness");
+            self.uv_rect_loc = gl.get_uniform_location(prog, "uv_rect");
+            self.mask_rect_loc = gl.get_uniform_location(prog, "mask_rect");
+            self.image_loc = gl.get_uniform_location(prog, "image");
         }
         self
     }
@@ -99,6 +109,27 @@ impl ShaderUniforms {
         }
     }
 
+    fn set_uv_rect(&self, gl: &GlowGL, prog: &OglProg, uv_min: Vec2<f32>, uv_max: Vec2<f32>) {
+        prog.bind(true);
+        let uv_rect = Vec4::from_array([uv_min[0], uv_min[1], uv_max[0], uv_max[1]]);
+        unsafe {
+            gl.uniform_4_f32_slice(self.uv_rect_loc.as_ref(), uv_rect.as_slice());
+        }
+    }
+
+    fn set_mask(&self, gl: &GlowGL, prog: &OglProg, pos: Vec2<f32>, bounds: Vec2<f32>) {
+        prog.bind(true);
+        let mask_rect = Vec4::from_array([pos[0], pos[1], bounds[0], bounds[1]]);
+        unsafe {
+            gl.uniform_4_f32_slice(self.mask_rect_loc.as_ref(), mask_rect.as_slice());
+        }
+    }
+
+    fn set_texture(&self, prog: &OglProg, texture: &OglTexture) {
+        prog.bind(true);
+        texture.bind(0, self.image_loc.as_ref());
+    }
+
     fn set_position(&self, gl: &GlowGL, prog: &OglProg, pos: Vec4<f32>, bounds: Vec4<f32>) {
         prog.bind(true);
         let position = pos;
@@ -179,6 +210,32 @@ impl<'a> RenderBuilder<'a> {
         self
     }
 
+    /// the region of the texture that gets mapped onto the quad, in normalized texture coordinates
+    pub fn set_uv_rect<A, B>(self, uv_min: A, uv_max: B) -> Self
+    where
+        Vec2<f32>: From<A> + From<B>,
+    {
+        self.uniforms
+            .set_uv_rect(self.gl, self.prog, Vec2::from(uv_min), Vec2::from(uv_max));
+        self
+    }
+
+    /// the rounded box (in window coordinates) pixels get clipped against, the roundness is shared
+    /// with `Self::set_roundness(..)`
+    pub fn set_mask<A, B>(self, pos: A, bounds: B) -> Self
+    where
+        Vec2<f32>: From<A> + From<B>,
+    {
+        self.uniforms
+            .set_mask(self.gl, self.prog, Vec2::from(pos), Vec2::from(bounds));
+        self
+    }
+
+    pub fn set_texture(self, texture: &OglTexture) -> Self {
+        self.uniforms.set_texture(self.prog, texture);
+        self
+    }
+
     pub fn set_window(self, window_width: f32, window_height: f32) -> Self {
         self.uniforms
             .recompute_proj(self.gl, self.prog, window_width, window_height);
@@ -245,6 +302,7 @@ impl GuiRenderer {
             shader_program_table: vec![
                 (GuiShaderKind::RoundedBox, OglProgramBlock::new(gl, ROUNDED_BOX_SHADER_SOURCE)),
                 (GuiShaderKind::Rectangle, OglProgramBlock::new(gl, RECTANGLE_SHADER_SOURCE)),
+                (GuiShaderKind::Image, OglProgramBlock::new(gl, IMAGE_SHADER_SOURCE)),
             ]
             .into_iter()
             .collect::<HashMap<_, _>>(),
@@ -112,3 +112,61 @@ pub const RECTANGLE_SHADER_SOURCE: &str = r"
         }
     #endif
 ";
+
+pub const IMAGE_SHADER_SOURCE: &str = r"
+    #ifndef HEADER
+        #version 300 es
+        precision mediump float;
+        uniform vec4  roundness;
+        uniform vec4  background_color;
+        uniform vec4  uv_rect;
+        uniform vec4  mask_rect;
+        uniform mat4  modelview;
+        uniform mat4  proj;
+        uniform sampler2D image;
+    #endif
+
+    #ifndef VERTEX_SHADER
+        layout(location = 0) in vec4 attr_pos;
+        out vec4 world_space_pos;
+        out vec2 uv;
+        void main(){
+            vec4 world_space = modelview*attr_pos;
+            world_space_pos = world_space;
+            //the unit square spans [0,1] so it doubles as an interpolation parameter
+            uv = mix(uv_rect.xy, uv_rect.zw, attr_pos.xy);
+            gl_Position = proj*world_space;
+        }
+    #endif
+
+    #ifndef FRAGMENT_SHADER
+        in vec4 world_space_pos;
+        in vec2 uv;
+        out vec4 final_color;
+
+        float sdRoundBox( in vec2 p, in vec2 b, in vec4 r )
+        {
+            r.xy = (p.x>0.0)?r.xy : r.zw;
+            r.x  = (p.y>0.0)?r.x  : r.y;
+            vec2 q = abs(p)-b+r.x;
+            return min(max(q.x,q.y),0.0) + length(max(q,0.0)) - r.x;
+        }
+
+        void main(){
+            //the mask is a rounded box that can be bigger than the quad being drawn (nine-slice)
+            vec2 half_mask = mask_rect.zw*0.5;
+            float d = sdRoundBox(world_space_pos.xy - mask_rect.xy - half_mask, half_mask, roundness);
+            float d_epsilon = length(vec2(dFdx(d),dFdy(d)));
+
+            if (d > d_epsilon){
+                discard;
+            }
+
+            float coverage = smoothstep(d_epsilon, -d_epsilon, d);
+
+            //background_color acts as a tint
+            final_color = texture(image, uv)*background_color;
+            final_color.a *= coverage;
+        }
+    #endif
+";
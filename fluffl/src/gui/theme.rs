@@ -0,0 +1,428 @@
+use super::{component_flags::ComponentFlags, *};
+
+/// The kinds of components a `Theme` can provide defaults for
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[rustfmt::skip]
+pub enum StyleClass {
+    Frame        = 0,
+    Slider       = 1,
+    SliderButton = 2,
+    TextBox      = 3,
+}
+
+/// The interaction state a component is currently in, used to pick a variant from a `StyleSheet`
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[rustfmt::skip]
+pub enum StyleState {
+    Normal   = 0,
+    Hovered  = 1,
+    Focused  = 2,
+    Disabled = 3,
+}
+
+impl StyleState {
+    /// ## Description
+    /// figures out what state `key` is in given what the manager currently considers focused/hovered
+    /// ## Comments
+    /// - precedence is: `Disabled` > `Focused` > `Hovered` > `Normal`
+    pub fn compute(
+        key: GuiComponentKey,
+        flags: ComponentFlags,
+        focused: Option<GuiComponentKey>,
+        hovered: Option<GuiComponentKey>,
+    ) -> Self {
+        if flags.is_set(component_flags::DISABLED) {
+            Self::Disabled
+        } else if focused == Some(key) {
+            Self::Focused
+        } else if hovered == Some(key) {
+            Self::Hovered
+        } else {
+            Self::Normal
+        }
+    }
+}
+
+/// The visual properties shared by every component that draws a rounded box
+#[derive(Copy, Clone, Debug)]
+pub struct ComponentStyle {
+    pub color: Vec4<f32>,
+    pub edge_color: Vec4<f32>,
+    pub edge_thickness: f32,
+    pub roundness: Vec4<f32>,
+}
+
+impl Default for ComponentStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ComponentStyle {
+    /// the style components had before themes existed
+    pub fn new() -> Self {
+        Self {
+            color: Vec4::rgb_u32(0xF94892),
+            edge_color: Vec4::rgb_u32(0x89CFFD),
+            edge_thickness: 0.02,
+            roundness: Vec4::from([1.0; 4]),
+        }
+    }
+
+    pub fn with_color<T>(mut self, color: T) -> Self
+    where
+        Vec4<f32>: From<T>,
+    {
+        self.color = Vec4::from(color);
+        self
+    }
+
+    pub fn with_edge_color<T>(mut self, color: T) -> Self
+    where
+        Vec4<f32>: From<T>,
+    {
+        self.edge_color = Vec4::from(color);
+        self
+    }
+
+    pub fn with_edge_thickness(mut self, edge_thickness: f32) -> Self {
+        self.edge_thickness = edge_thickness;
+        self
+    }
+
+    pub fn with_roundness<T>(mut self, roundness: T) -> Self
+    where
+        Vec4<f32>: From<T>,
+    {
+        self.roundness = Vec4::from(roundness);
+        self
+    }
+}
+
+/// Records which style properties were set explicitly on a component.
+/// Overridden properties always win over whatever the active `Theme` says.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct StyleOverrides {
+    pub color: bool,
+    pub edge_color: bool,
+    pub edge_thickness: bool,
+    pub roundness: bool,
+}
+
+impl StyleOverrides {
+    /// picks `instance` properties that are overridden and `themed` properties everywhere else
+    pub fn merge(&self, themed: ComponentStyle, instance: ComponentStyle) -> ComponentStyle {
+        ComponentStyle {
+            color: if self.color {
+                instance.color
+            } else {
+                themed.color
+            },
+            edge_color: if self.edge_color {
+                instance.edge_color
+            } else {
+                themed.edge_color
+            },
+            edge_thickness: if self.edge_thickness {
+                instance.edge_thickness
+            } else {
+                themed.edge_thickness
+            },
+            roundness: if self.roundness {
+                instance.roundness
+            } else {
+                themed.roundness
+            },
+        }
+    }
+}
+
+/// A style with optional per-state variants. Missing variants fall back to `normal`.
+#[derive(Copy, Clone, Debug)]
+pub struct StyleSheet {
+    pub normal: ComponentStyle,
+    pub hovered: Option<ComponentStyle>,
+    pub focused: Option<ComponentStyle>,
+    pub disabled: Option<ComponentStyle>,
+}
+
+impl StyleSheet {
+    pub fn new(normal: ComponentStyle) -> Self {
+        Self {
+            normal,
+            hovered: None,
+            focused: None,
+            disabled: None,
+        }
+    }
+
+    pub fn with_hovered(mut self, style: ComponentStyle) -> Self {
+        self.hovered = Some(style);
+        self
+    }
+
+    pub fn with_focused(mut self, style: ComponentStyle) -> Self {
+        self.focused = Some(style);
+        self
+    }
+
+    pub fn with_disabled(mut self, style: ComponentStyle) -> Self {
+        self.disabled = Some(style);
+        self
+    }
+
+    pub fn resolve(&self, state: StyleState) -> ComponentStyle {
+        let variant = match state {
+            StyleState::Normal => None,
+            StyleState::Hovered => self.hovered,
+            StyleState::Focused => self.focused,
+            StyleState::Disabled => self.disabled,
+        };
+        variant.unwrap_or(self.normal)
+    }
+}
+
+/// ## Description
+/// A collection of per-component-type defaults and user-named styles.
+/// ## Comments
+/// - set it with `GuiManager::set_theme(..)`, every component picks it up through `RenderState`
+/// - named styles (see `HasComponentBuilder::with_style(..)`) take priority over the class defaults
+pub struct Theme {
+    name: String,
+    class_table: HashMap<StyleClass, StyleSheet>,
+    named_table: HashMap<String, StyleSheet>,
+    focus_ring_color: Option<Vec4<f32>>,
+    text_color: Option<Vec4<f32>>,
+}
+
+impl Theme {
+    pub fn new<T: AsRef<str>>(name: T) -> Self {
+        Self {
+            name: String::from(name.as_ref()),
+            class_table: HashMap::new(),
+            named_table: HashMap::new(),
+            focus_ring_color: None,
+            text_color: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn with_class(mut self, class: StyleClass, sheet: StyleSheet) -> Self {
+        self.set_class(class, sheet);
+        self
+    }
+
+    pub fn with_named<T: AsRef<str>>(mut self, name: T, sheet: StyleSheet) -> Self {
+        self.set_named(name, sheet);
+        self
+    }
+
+    pub fn set_class(&mut self, class: StyleClass, sheet: StyleSheet) {
+        self.class_table.insert(class, sheet);
+    }
+
+    pub fn set_named<T: AsRef<str>>(&mut self, name: T, sheet: StyleSheet) {
+        self.named_table.insert(String::from(name.as_ref()), sheet);
+    }
+
+    /// the color of the ring drawn around the component focused with the keyboard
+    pub fn with_focus_ring_color<T>(mut self, color: T) -> Self
+    where
+        Vec4<f32>: From<T>,
+    {
+        self.focus_ring_color = Some(Vec4::from(color));
+        self
+    }
+
+    pub fn focus_ring_color(&self) -> Option<Vec4<f32>> {
+        self.focus_ring_color
+    }
+
+    /// the color labels and text boxes draw their text with
+    pub fn with_text_color<T>(mut self, color: T) -> Self
+    where
+        Vec4<f32>: From<T>,
+    {
+        self.text_color = Some(Vec4::from(color));
+        self
+    }
+
+    pub fn text_color(&self) -> Option<Vec4<f32>> {
+        self.text_color
+    }
+
+    pub fn class(&self, class: StyleClass) -> Option<&StyleSheet> {
+        self.class_table.get(&class)
+    }
+
+    pub fn named(&self, name: &str) -> Option<&StyleSheet> {
+        self.named_table.get(name)
+    }
+
+    /// ## Description
+    /// looks up the sheet for a component, trying `style_name` first and then `class`
+    pub fn resolve(
+        &self,
+        class: StyleClass,
+        style_name: Option<&str>,
+        state: StyleState,
+    ) -> Option<ComponentStyle> {
+        style_name
+            .and_then(|name| self.named(name))
+            .or_else(|| self.class(class))
+            .map(|sheet| sheet.resolve(state))
+    }
+
+    pub fn light() -> Self {
+        let frame = ComponentStyle::new()
+            .with_color(Vec4::rgb_u32(0xF2F2F2))
+            .with_edge_color(Vec4::rgb_u32(0xB0B0B0))
+            .with_edge_thickness(1.0)
+            .with_roundness([4.0; 4]);
+        let button = frame
+            .with_color(Vec4::rgb_u32(0xDADADA))
+            .with_edge_color(Vec4::rgb_u32(0x8A8A8A));
+        let accent = Vec4::rgb_u32(0x2F80ED);
+        let disabled = frame
+            .with_color(Vec4::rgb_u32(0xE6E6E6))
+            .with_edge_color(Vec4::rgb_u32(0xCFCFCF));
+
+        Self::new("light")
+            .with_focus_ring_color(accent)
+            .with_text_color(Vec4::rgb_u32(0x1E1E1E))
+            .with_class(
+                StyleClass::Frame,
+                StyleSheet::new(frame).with_disabled(disabled),
+            )
+            .with_class(
+                StyleClass::Slider,
+                StyleSheet::new(frame.with_color(Vec4::rgb_u32(0xE0E0E0)))
+                    .with_focused(frame.with_edge_color(accent))
+                    .with_disabled(disabled),
+            )
+            .with_class(
+                StyleClass::SliderButton,
+                StyleSheet::new(button)
+                    .with_hovered(button.with_color(Vec4::rgb_u32(0xC8C8C8)))
+                    .with_focused(button.with_edge_color(accent))
+                    .with_disabled(disabled),
+            )
+            .with_class(
+                StyleClass::TextBox,
+                StyleSheet::new(frame.with_color(Vec4::rgb_u32(0xFFFFFF)))
+                    .with_focused(
+                        frame
+                            .with_color(Vec4::rgb_u32(0xFFFFFF))
+                            .with_edge_color(accent),
+                    )
+                    .with_disabled(disabled),
+            )
+    }
+
+    pub fn dark() -> Self {
+        let frame = ComponentStyle::new()
+            .with_color(Vec4::rgb_u32(0x2B2B2B))
+            .with_edge_color(Vec4::rgb_u32(0x4A4A4A))
+            .with_edge_thickness(1.0)
+            .with_roundness([4.0; 4]);
+        let button = frame
+            .with_color(Vec4::rgb_u32(0x3C3C3C))
+            .with_edge_color(Vec4::rgb_u32(0x5C5C5C));
+        let accent = Vec4::rgb_u32(0x56A0FF);
+        let disabled = frame
+            .with_color(Vec4::rgb_u32(0x232323))
+            .with_edge_color(Vec4::rgb_u32(0x333333));
+
+        Self::new("dark")
+            .with_focus_ring_color(accent)
+            .with_text_color(Vec4::rgb_u32(0xE6E6E6))
+            .with_class(
+                StyleClass::Frame,
+                StyleSheet::new(frame).with_disabled(disabled),
+            )
+            .with_class(
+                StyleClass::Slider,
+                StyleSheet::new(frame.with_color(Vec4::rgb_u32(0x333333)))
+                    .with_focused(frame.with_edge_color(accent))
+                    .with_disabled(disabled),
+            )
+            .with_class(
+                StyleClass::SliderButton,
+                StyleSheet::new(button)
+                    .with_hovered(button.with_color(Vec4::rgb_u32(0x4C4C4C)))
+                    .with_focused(button.with_edge_color(accent))
+                    .with_disabled(disabled),
+            )
+            .with_class(
+                StyleClass::TextBox,
+                StyleSheet::new(frame.with_color(Vec4::rgb_u32(0x1E1E1E)))
+                    .with_focused(
+                        frame
+                            .with_color(Vec4::rgb_u32(0x1E1E1E))
+                            .with_edge_color(accent),
+                    )
+                    .with_disabled(disabled),
+            )
+    }
+}
+
+#[test]
+fn theme_resolution_sanity() {
+    let theme = Theme::dark().with_named(
+        "warning",
+        StyleSheet::new(ComponentStyle::new().with_color(Vec4::rgb_u32(0xff0000)))
+            .with_hovered(ComponentStyle::new().with_color(Vec4::rgb_u32(0x00ff00))),
+    );
+
+    let named_hovered = theme
+        .resolve(StyleClass::Frame, Some("warning"), StyleState::Hovered)
+        .unwrap();
+    assert!((named_hovered.color[1] - 1.0).abs() < 0.001);
+
+    // missing variants fall back to normal
+    let named_focused = theme
+        .resolve(StyleClass::Frame, Some("warning"), StyleState::Focused)
+        .unwrap();
+    assert!((named_focused.color[0] - 1.0).abs() < 0.001);
+
+    // unknown names fall back to the class default
+    let class_default = theme
+        .resolve(StyleClass::Frame, Some("missing"), StyleState::Normal)
+        .unwrap();
+    let frame_normal = theme.class(StyleClass::Frame).unwrap().normal;
+    assert!((class_default.color[0] - frame_normal.color[0]).abs() < 0.001);
+
+    // overridden properties come from the instance
+    let overrides = StyleOverrides {
+        edge_thickness: true,
+        ..StyleOverrides::default()
+    };
+    let instance = ComponentStyle::new().with_edge_thickness(7.0);
+    let merged = overrides.merge(frame_normal, instance);
+    assert_eq!(merged.edge_thickness, 7.0);
+    assert!((merged.color[0] - frame_normal.color[0]).abs() < 0.001);
+
+    // the built-in themes pick text that reads on their backgrounds, custom ones start without
+    assert!(theme.text_color().unwrap()[0] > 0.5);
+    assert!(Theme::light().text_color().unwrap()[0] < 0.5);
+    assert!(Theme::new("custom").text_color().is_none());
+}
+
+#[test]
+fn style_state_precedence() {
+    let key = GuiComponentKey(3);
+    let other = GuiComponentKey(4);
+
+    let state = StyleState::compute(key, component_flags::VISIBLE, Some(key), Some(key));
+    assert_eq!(state, StyleState::Focused);
+
+    let state = StyleState::compute(key, component_flags::VISIBLE, Some(other), Some(key));
+    assert_eq!(state, StyleState::Hovered);
+
+    let flags = component_flags::VISIBLE | component_flags::DISABLED;
+    let state = StyleState::compute(key, flags, Some(key), Some(key));
+    assert_eq!(state, StyleState::Disabled);
+}
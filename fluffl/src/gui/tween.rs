@@ -0,0 +1,615 @@
+use super::*;
+
+/// Maps normalized time `t` in `[0,1]` to normalized progress
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    ElasticIn,
+    ElasticOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+}
+
+impl Easing {
+    /// ## Description
+    /// evaluates the curve at `t`
+    /// ## Comments
+    /// - `t` is clamped to `[0,1]`
+    /// - every curve maps `0` to `0` and `1` to `1`, but elastic and back curves overshoot in between
+    pub fn apply(self, t: f32) -> f32 {
+        use std::f32::consts::PI;
+        const BACK_C1: f32 = 1.70158;
+        const BACK_C2: f32 = BACK_C1 * 1.525;
+        const BACK_C3: f32 = BACK_C1 + 1.0;
+        const ELASTIC_C4: f32 = (2.0 * PI) / 3.0;
+
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) * 0.5
+                }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) * 0.5
+                }
+            }
+            Easing::ElasticIn => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    -(2.0f32.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * ELASTIC_C4).sin()
+                }
+            }
+            Easing::ElasticOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2.0f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * ELASTIC_C4).sin() + 1.0
+                }
+            }
+            Easing::BackIn => BACK_C3 * t * t * t - BACK_C1 * t * t,
+            Easing::BackOut => {
+                let u = t - 1.0;
+                1.0 + BACK_C3 * u * u * u + BACK_C1 * u * u
+            }
+            Easing::BackInOut => {
+                if t < 0.5 {
+                    let u = 2.0 * t;
+                    (u * u * ((BACK_C2 + 1.0) * u - BACK_C2)) * 0.5
+                } else {
+                    let u = 2.0 * t - 2.0;
+                    (u * u * ((BACK_C2 + 1.0) * u + BACK_C2) + 2.0) * 0.5
+                }
+            }
+        }
+    }
+}
+
+/// A component property a `Tween` can drive, holding the value it ends at
+#[derive(Copy, Clone, Debug)]
+pub enum TweenProperty {
+    /// the relative position of the component
+    Position(Vec2<f32>),
+    Bounds(Vec2<f32>),
+    /// see `GuiComponent::color()`, components without a color are left alone.
+    /// Starts from the color the component was drawn with and only overrides the theme once the
+    /// tween completes, with the color it ends at
+    Color(Vec4<f32>),
+    /// not interpolated, the flag flips once the tween completes
+    Visibility(bool),
+}
+
+impl TweenProperty {
+    /// reads the current value of this property off of `comp`
+    fn read(&self, comp: &dyn GuiComponent) -> Option<TweenProperty> {
+        match self {
+            TweenProperty::Position(_) => Some(TweenProperty::Position(*comp.rel_position())),
+            TweenProperty::Bounds(_) => Some(TweenProperty::Bounds(comp.bounds())),
+            TweenProperty::Color(_) => comp.drawn_color().map(TweenProperty::Color),
+            TweenProperty::Visibility(_) => Some(TweenProperty::Visibility(comp.is_visible())),
+        }
+    }
+
+    /// interpolates between `from` and `self`, where `progress` is already eased
+    fn write(&self, from: &TweenProperty, progress: f32, done: bool, comp: &mut dyn GuiComponent) {
+        let lerp2 = |a: Vec2<f32>, b: Vec2<f32>| a + (b - a) * progress;
+        match (*from, *self) {
+            (TweenProperty::Position(a), TweenProperty::Position(b)) => {
+                comp.set_rel_position(lerp2(a, b))
+            }
+            (TweenProperty::Bounds(a), TweenProperty::Bounds(b)) => {
+                let bounds = lerp2(a, b);
+                comp.set_bounds(Vec2::from([bounds.x().max(0.0), bounds.y().max(0.0)]))
+            }
+            (TweenProperty::Color(a), TweenProperty::Color(b)) => {
+                let mut color = a + (b - a) * progress;
+                for k in 0..4 {
+                    color[k] = color[k].clamp(0.0, 1.0);
+                }
+                comp.set_tweened_color(color, done)
+            }
+            (TweenProperty::Visibility(_), TweenProperty::Visibility(b)) if done => {
+                comp.set_visible(b)
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Drives a single property of a single component from its current value to a target value
+#[derive(Copy, Clone, Debug)]
+pub struct Tween {
+    pub key: GuiComponentKey,
+    pub property: TweenProperty,
+    pub from: Option<TweenProperty>,
+    /// in seconds
+    pub duration: f32,
+    pub easing: Easing,
+}
+
+impl Tween {
+    pub fn new(key: GuiComponentKey, property: TweenProperty) -> Self {
+        Self {
+            key,
+            property,
+            from: None,
+            duration: 0.25,
+            easing: Easing::Linear,
+        }
+    }
+
+    pub fn position<T: Into<Vec2<f32>>>(key: GuiComponentKey, to: T) -> Self {
+        Self::new(key, TweenProperty::Position(to.into()))
+    }
+
+    pub fn bounds<T: Into<Vec2<f32>>>(key: GuiComponentKey, to: T) -> Self {
+        Self::new(key, TweenProperty::Bounds(to.into()))
+    }
+
+    pub fn color<T: Into<Vec4<f32>>>(key: GuiComponentKey, to: T) -> Self {
+        Self::new(key, TweenProperty::Color(to.into()))
+    }
+
+    pub fn visibility(key: GuiComponentKey, is_visible: bool) -> Self {
+        Self::new(key, TweenProperty::Visibility(is_visible)).with_duration(0.0)
+    }
+
+    pub fn with_duration(mut self, seconds: f32) -> Self {
+        self.duration = seconds.max(0.0);
+        self
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// ## Description
+    /// sets the value the tween starts at
+    /// ## Comments
+    /// - by default the starting value is read off of the component when the tween starts running
+    /// - must be the same kind of property as the target or it gets ignored
+    pub fn with_from(mut self, from: TweenProperty) -> Self {
+        self.from = Some(from);
+        self
+    }
+}
+
+/// A tree of tweens that run one after the other or side by side
+#[derive(Clone, Debug)]
+pub enum Animation {
+    Tween(Tween),
+    /// does nothing for the given number of seconds
+    Wait(f32),
+    /// runs every animation to completion, one after the other
+    Sequence(Vec<Animation>),
+    /// runs every animation at the same time, completes when the longest one does
+    Parallel(Vec<Animation>),
+}
+
+impl Animation {
+    pub fn sequence<I: IntoIterator<Item = Animation>>(animations: I) -> Self {
+        Self::Sequence(animations.into_iter().collect())
+    }
+
+    pub fn parallel<I: IntoIterator<Item = Animation>>(animations: I) -> Self {
+        Self::Parallel(animations.into_iter().collect())
+    }
+
+    /// runs `self` and then `next`
+    pub fn then<T: Into<Animation>>(self, next: T) -> Self {
+        match self {
+            Animation::Sequence(mut animations) => {
+                animations.push(next.into());
+                Animation::Sequence(animations)
+            }
+            anim => Animation::Sequence(vec![anim, next.into()]),
+        }
+    }
+
+    /// runs `self` and `other` at the same time
+    pub fn with<T: Into<Animation>>(self, other: T) -> Self {
+        match self {
+            Animation::Parallel(mut animations) => {
+                animations.push(other.into());
+                Animation::Parallel(animations)
+            }
+            anim => Animation::Parallel(vec![anim, other.into()]),
+        }
+    }
+
+    /// the number of seconds it takes this animation to complete
+    pub fn duration(&self) -> f32 {
+        match self {
+            Animation::Tween(tween) => tween.duration,
+            &Animation::Wait(seconds) => seconds.max(0.0),
+            Animation::Sequence(animations) => animations.iter().map(Animation::duration).sum(),
+            Animation::Parallel(animations) => animations
+                .iter()
+                .map(Animation::duration)
+                .fold(0.0, f32::max),
+        }
+    }
+}
+
+impl From<Tween> for Animation {
+    fn from(tween: Tween) -> Self {
+        Self::Tween(tween)
+    }
+}
+
+/// Identifies an animation started with `TweenManager::play(..)`
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct AnimationHandle(u64);
+
+/// the running version of `Animation`
+enum AnimationNode {
+    Tween { tween: Tween, elapsed: f32 },
+    Wait { duration: f32, elapsed: f32 },
+    Sequence { children: VecDeque<AnimationNode> },
+    Parallel { children: Vec<AnimationNode> },
+}
+
+impl AnimationNode {
+    fn new(animation: Animation) -> Self {
+        match animation {
+            Animation::Tween(tween) => AnimationNode::Tween {
+                tween,
+                elapsed: 0.0,
+            },
+            Animation::Wait(duration) => AnimationNode::Wait {
+                duration: duration.max(0.0),
+                elapsed: 0.0,
+            },
+            Animation::Sequence(animations) => AnimationNode::Sequence {
+                children: animations.into_iter().map(AnimationNode::new).collect(),
+            },
+            Animation::Parallel(animations) => AnimationNode::Parallel {
+                children: animations.into_iter().map(AnimationNode::new).collect(),
+            },
+        }
+    }
+
+    /// ## Description
+    /// steps the animation forward by `dt` seconds
+    /// ## Comments
+    /// - returns `Some(leftover_dt)` once the node has completed, so sequences can hand the
+    ///   leftover time to the next node instead of dropping it
+    fn advance(
+        &mut self,
+        dt: f32,
+        gui_component_tree: &mut LinearTree<Box<dyn GuiComponent>>,
+    ) -> Option<f32> {
+        match self {
+            AnimationNode::Tween { tween, elapsed } => {
+                let comp = match gui_component_tree.get_mut(tween.key) {
+                    Some(comp) => comp.as_mut(),
+                    // the component is gone so there is nothing left to animate
+                    None => return Some(dt),
+                };
+
+                // the starting value gets captured on the first step
+                if tween.from.is_none() {
+                    tween.from = tween.property.read(comp);
+                }
+                let from = match tween.from {
+                    Some(from) => from,
+                    None => return Some(dt),
+                };
+
+                *elapsed += dt;
+                let t = if tween.duration > 0.0 {
+                    (*elapsed / tween.duration).min(1.0)
+                } else {
+                    1.0
+                };
+                let done = *elapsed >= tween.duration;
+                tween
+                    .property
+                    .write(&from, tween.easing.apply(t), done, comp);
+
+                done.then_some(*elapsed - tween.duration)
+            }
+            AnimationNode::Wait { duration, elapsed } => {
+                *elapsed += dt;
+                (*elapsed >= *duration).then_some(*elapsed - *duration)
+            }
+            AnimationNode::Sequence { children } => {
+                let mut dt = dt;
+                while let Some(child) = children.front_mut() {
+                    dt = child.advance(dt, gui_component_tree)?;
+                    children.pop_front();
+                }
+                Some(dt)
+            }
+            AnimationNode::Parallel { children } => {
+                let mut min_leftover = dt;
+                children.retain_mut(|child| match child.advance(dt, gui_component_tree) {
+                    Some(leftover) => {
+                        min_leftover = min_leftover.min(leftover);
+                        false
+                    }
+                    None => true,
+                });
+                children.is_empty().then_some(min_leftover)
+            }
+        }
+    }
+
+    fn targets(&self, key: GuiComponentKey) -> bool {
+        match self {
+            AnimationNode::Tween { tween, .. } => tween.key == key,
+            AnimationNode::Wait { .. } => false,
+            AnimationNode::Sequence { children } => children.iter().any(|c| c.targets(key)),
+            AnimationNode::Parallel { children } => children.iter().any(|c| c.targets(key)),
+        }
+    }
+}
+
+/// Owns every running animation, see `GuiManager::advance_animations(..)`
+#[derive(Default)]
+pub struct TweenManager {
+    next_handle: u64,
+    running: Vec<(AnimationHandle, AnimationNode)>,
+}
+
+impl TweenManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ## Description
+    /// starts `animation`, it begins moving on the next `Self::advance(..)`
+    /// ## Comments
+    /// - animations are stepped in the order they were started, so when two of them drive the
+    ///   same property the newest one wins
+    pub fn play<T: Into<Animation>>(&mut self, animation: T) -> AnimationHandle {
+        let handle = AnimationHandle(self.next_handle);
+        self.next_handle += 1;
+        self.running
+            .push((handle, AnimationNode::new(animation.into())));
+        handle
+    }
+
+    /// ## Description
+    /// stops the animation where it is, returns false if it had already completed
+    /// ## Comments
+    /// - a cancelled color tween keeps showing its last color until the next one completes or
+    ///   `GuiComponent::set_color(..)` is called
+    pub fn cancel(&mut self, handle: AnimationHandle) -> bool {
+        let old_len = self.running.len();
+        self.running.retain(|(h, _)| *h != handle);
+        self.running.len() != old_len
+    }
+
+    /// stops every animation that drives any property of `key`
+    pub fn cancel_component(&mut self, key: GuiComponentKey) {
+        self.running.retain(|(_, node)| !node.targets(key));
+    }
+
+    pub fn clear(&mut self) {
+        self.running.clear();
+    }
+
+    pub fn is_running(&self, handle: AnimationHandle) -> bool {
+        self.running.iter().any(|(h, _)| *h == handle)
+    }
+
+    pub fn is_animating(&self, key: GuiComponentKey) -> bool {
+        self.running.iter().any(|(_, node)| node.targets(key))
+    }
+
+    pub fn num_running(&self) -> usize {
+        self.running.len()
+    }
+
+    /// steps every running animation forward by `dt` seconds and drops the completed ones
+    pub fn advance(&mut self, dt: f32, gui_component_tree: &mut LinearTree<Box<dyn GuiComponent>>) {
+        let dt = dt.max(0.0);
+        self.running
+            .retain_mut(|(_, node)| node.advance(dt, gui_component_tree).is_none());
+    }
+}
+
+impl<ProgramState> GuiManager<ProgramState> {
+    /// shorthand for `self.tweens_mut().play(animation)`
+    pub fn animate<T: Into<Animation>>(&mut self, animation: T) -> AnimationHandle {
+        self.tween_manager.play(animation)
+    }
+
+    /// ## Description
+    /// moves every running animation forward by `dt` seconds
+    /// ## Comments
+    /// - meant to be called once per frame, before `Self::render(..)`, with the frame time
+    pub fn advance_animations(&mut self, dt: f32) {
+        self.tween_manager.advance(dt, &mut self.gui_component_tree);
+    }
+
+    pub fn tweens(&self) -> &TweenManager {
+        &self.tween_manager
+    }
+
+    pub fn tweens_mut(&mut self) -> &mut TweenManager {
+        &mut self.tween_manager
+    }
+}
+
+#[test]
+fn easing_endpoints() {
+    let curves = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::ElasticIn,
+        Easing::ElasticOut,
+        Easing::BackIn,
+        Easing::BackOut,
+        Easing::BackInOut,
+    ];
+    for curve in curves {
+        assert!(curve.apply(0.0).abs() < 0.001, "{:?} at 0", curve);
+        assert!((curve.apply(1.0) - 1.0).abs() < 0.001, "{:?} at 1", curve);
+        assert!((curve.apply(2.0) - 1.0).abs() < 0.001, "{:?} past 1", curve);
+    }
+
+    assert!((Easing::QuadIn.apply(0.5) - 0.25).abs() < 0.001);
+    assert!((Easing::CubicOut.apply(0.5) - 0.875).abs() < 0.001);
+    assert!(Easing::BackIn.apply(0.2) < 0.0);
+    assert!(Easing::BackOut.apply(0.8) > 1.0);
+}
+
+#[test]
+fn tween_sequence_and_parallel() {
+    let mut harness = GuiHarness::<()>::new();
+    let origin = harness.origin();
+
+    let panel = harness
+        .manager_mut()
+        .builder_frame()
+        .with_parent(origin)
+        .with_position([0.0, 0.0])
+        .with_bounds([100.0, 100.0])
+        .build();
+
+    let slide = Tween::position(panel, [100.0, 0.0]).with_duration(1.0);
+    let grow = Tween::bounds(panel, [200.0, 50.0]).with_duration(0.5);
+    let hide = Tween::visibility(panel, false);
+
+    let manager = harness.manager_mut();
+    let handle = manager.animate(Animation::from(slide).with(grow).then(hide));
+    assert_eq!(manager.tweens().num_running(), 1);
+
+    manager.advance_animations(0.25);
+    let comp = manager.component(panel).unwrap();
+    assert!((comp.rel_position().x() - 25.0).abs() < 0.001);
+    assert!((comp.bounds().x() - 150.0).abs() < 0.001);
+    assert!((comp.bounds().y() - 75.0).abs() < 0.001);
+
+    manager.advance_animations(0.5);
+    let comp = manager.component(panel).unwrap();
+    assert!((comp.rel_position().x() - 75.0).abs() < 0.001);
+    assert!((comp.bounds().x() - 200.0).abs() < 0.001);
+    assert!(comp.is_visible());
+    assert!(manager.tweens().is_running(handle));
+    assert!(manager.tweens().is_animating(panel));
+
+    manager.advance_animations(0.5);
+    let comp = manager.component(panel).unwrap();
+    assert!((comp.rel_position().x() - 100.0).abs() < 0.001);
+    assert!(!comp.is_visible());
+    assert!(!manager.tweens().is_running(handle));
+
+    harness.step();
+    assert!(!harness.manager().is_globally_visible(panel));
+}
+
+#[test]
+fn tween_color_and_cancel() {
+    let mut manager = GuiManager::<()>::new_headless();
+    let origin = manager.add_component(GuiComponentKey::default(), Box::new(OriginState::new()));
+    let label = manager.builder_label().with_parent(origin).build();
+    let frame = manager
+        .builder_frame()
+        .with_parent(origin)
+        .with_color([1.0, 0.0, 0.0, 1.0])
+        .build();
+
+    // labels have no color so the tween completes without touching anything
+    let label_handle = manager.animate(Tween::color(label, [0.0; 4]).with_duration(1.0));
+    let frame_handle = manager.animate(
+        Tween::color(frame, [0.0, 0.0, 1.0, 0.0])
+            .with_duration(1.0)
+            .with_easing(Easing::QuadIn),
+    );
+
+    manager.advance_animations(0.5);
+    assert!(!manager.tweens().is_running(label_handle));
+    let color = manager.component(frame).unwrap().color().unwrap();
+    assert!((color.x() - 0.75).abs() < 0.001);
+    assert!((color.z() - 0.25).abs() < 0.001);
+
+    assert!(manager.tweens_mut().cancel(frame_handle));
+    assert!(!manager.tweens_mut().cancel(frame_handle));
+    manager.advance_animations(0.5);
+    let color = manager.component(frame).unwrap().color().unwrap();
+    assert!((color.x() - 0.75).abs() < 0.001);
+}
+
+#[test]
+fn tween_color_of_a_themed_frame_keeps_the_final_color() {
+    let mut harness = GuiHarness::<()>::new();
+    let origin = harness.origin();
+    let frame = harness
+        .manager_mut()
+        .builder_frame()
+        .with_parent(origin)
+        .build();
+    let themed = ComponentStyle::new().with_color([0.0, 1.0, 0.0, 1.0]);
+    let theme = Theme::new("green").with_class(StyleClass::Frame, StyleSheet::new(themed));
+    harness.manager_mut().set_theme(theme);
+
+    // what the frame gets drawn with, the way `render_entry` resolves it
+    let draw = |harness: &GuiHarness<()>| {
+        let manager = harness.manager();
+        let themed = manager
+            .theme()
+            .and_then(|theme| theme.resolve(StyleClass::Frame, None, StyleState::Normal));
+        let comp = manager.component(frame).unwrap();
+        let instance = ComponentStyle::new().with_color(comp.color().unwrap());
+        comp.common().resolve_style(themed, instance).color
+    };
+    draw(&harness);
+
+    // fade out
+    let manager = harness.manager_mut();
+    manager.animate(Tween::color(frame, [0.0, 1.0, 0.0, 0.0]).with_duration(1.0));
+    manager.advance_animations(0.5);
+    harness.step();
+    // the tween started from the themed green, not the frame's own color
+    let color = draw(&harness);
+    assert!((color.y() - 1.0).abs() < 0.001 && (color.w() - 0.5).abs() < 0.001);
+    assert!(
+        !harness
+            .manager()
+            .component(frame)
+            .unwrap()
+            .style_overrides()
+            .color
+    );
+
+    harness.manager_mut().advance_animations(0.5);
+    harness.step();
+    let color = draw(&harness);
+    assert!(color.w().abs() < 0.001);
+    assert!(
+        harness
+            .manager()
+            .component(frame)
+            .unwrap()
+            .style_overrides()
+            .color
+    );
+}
@@ -84,6 +84,8 @@ pub enum Error {
     FromUtf8ParseError(String),
     WindowInitError(String),
     IOError(String),
+    /// the platform/backend can't do what was asked
+    UnsupportedError(String),
 }
 
 impl From<std::io::Error> for Error {
@@ -82,3 +82,22 @@ pub fn resize_region(src: AABB2<f32>, dst: AABB2<f32>) -> Mat4<f32>{
         [0.       ,  0.      ,   0.  ,1.                          ],
     ])
 }
+
+/// # Description
+/// A view matrix for a camera at `eye` looking at `target`, `up` picks the roll
+/// # Comments
+/// - this matrix is in row-major format so a `transpose` is needed to pass into opengl
+/// - the camera looks down its -z axis, like `perspective(..)` expects
+#[rustfmt::skip]
+pub fn look_at(eye: Vec3<f32>, target: Vec3<f32>, up: Vec3<f32>) -> Mat4<f32> {
+    let normalize = |v: Vec3<f32>| v * (1.0 / v.length().max(f32::EPSILON));
+    let forward = normalize(target - eye);
+    let side = normalize(forward.cross(up));
+    let up = side.cross(forward);
+    Mat4::new().with_data([
+        [ side[0]    ,  side[1]    ,  side[2]    , -Vec3::dot(side, eye)   ],
+        [ up[0]      ,  up[1]      ,  up[2]      , -Vec3::dot(up, eye)     ],
+        [-forward[0] , -forward[1] , -forward[2] ,  Vec3::dot(forward, eye)],
+        [ 0.0        ,  0.0        ,  0.0        ,  1.0                    ],
+    ])
+}
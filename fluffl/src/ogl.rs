@@ -4,16 +4,33 @@ use glow::*;
 
 pub mod array;
 pub mod buffer;
+pub mod capabilities;
+pub mod capture;
+pub mod framebuffer;
+pub mod hot_reload;
+pub mod preprocessor;
 pub mod program;
+pub mod reflection;
+pub mod state;
 pub mod texture;
 
 use std::collections::HashMap;
 
 pub use self::{
-    array::{ArrayBuilder, BufferPair, OglArray},
+    array::{ArrayBuilder, BufferKind, BufferPair, OglArray},
     buffer::{HasBufferBuilder, HasBufferObj, OglBuf},
-    program::OglProg,
-    texture::OglTexture,
+    capabilities::{GlCapabilities, InstancingSupport},
+    capture::{Capture, CaptureFormat, FrameSequence},
+    framebuffer::{
+        DepthStencilFormat, FramebufferAttachment, HasFramebufferBuilder, OglFramebuffer,
+        OglRenderbuffer,
+    },
+    hot_reload::HotProgram,
+    preprocessor::{PreprocessError, ShaderOptions, ShaderTarget, SourceMap},
+    program::{CompilationError, OglProg},
+    reflection::{GlslType, ProgramReflection, TextureUnit, UniformError, UniformValue},
+    state::{BlendState, DepthState, GlBindings, GlStateCache, ScissorState, StencilState},
+    texture::{ColorSpace, OglTexture, SamplerState, TextureFilter, TextureWrap},
 };
 
 pub struct OglIncomplete<T> {
@@ -1,16 +1,37 @@
 use super::{buffer::*, *};
 use crate::*;
 
+/// What a buffer of a vertex array is used for
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BufferKind {
+    /// feeds a vertex attribute, per vertex or per instance when it has a divisor
+    Attribute,
+    /// the index buffer used by `draw_elements`, an array has at most one
+    Elements,
+}
+
 /// `buffer_name` should be a unique string to name your buffer.
 /// `buffer_object` is just the buffer object
 pub struct BufferPair {
     name: &'static str,
+    kind: BufferKind,
     object: Box<dyn HasBufferObj>,
 }
 
 impl BufferPair {
+    /// ## Comments
+    /// - the kind is worked out from the buffer's target, `ELEMENT_ARRAY_BUFFER` is `BufferKind::Elements`
     pub fn new(name: &'static str, object: Box<dyn HasBufferObj>) -> Self {
-        Self { name, object }
+        let kind = if object.info().target == glow::ELEMENT_ARRAY_BUFFER {
+            BufferKind::Elements
+        } else {
+            BufferKind::Attribute
+        };
+        Self { name, kind, object }
+    }
+
+    pub fn kind(&self) -> BufferKind {
+        self.kind
     }
 }
 
@@ -30,16 +51,24 @@ impl ArrayBuilder for OglIncomplete<OglArray> {
             self.inner.gl_array = Some(gl.create_vertex_array().unwrap());
             self.inner.bind(true);
         }
+        let instancing = GlCapabilities::of(&gl).instancing;
         for BufferPair {
             name: buffer_name,
+            kind,
             object: buffer_object,
         } in buffer_list
         {
             //bind buffer object
             buffer_object.bind(true);
             let index = buffer_object.info().index;
+            let divisor = buffer_object.info().divisor;
 
-            if buffer_object.info().target == glow::ARRAY_BUFFER {
+            if kind == BufferKind::Elements {
+                self.inner.element_buffer = Some(buffer_name.to_string());
+            } else if buffer_object.info().target == glow::ARRAY_BUFFER {
+                if divisor > 0 {
+                    self.inner.instance_buffers.push(buffer_name.to_string());
+                }
                 unsafe {
                     //define attrib pointers
                     gl.vertex_attrib_pointer_f32(
@@ -53,6 +82,10 @@ impl ArrayBuilder for OglIncomplete<OglArray> {
 
                     //enable attrib pointer
                     gl.enable_vertex_attrib_array(index);
+
+                    if divisor > 0 && instancing != InstancingSupport::Unsupported {
+                        gl.vertex_attrib_divisor(index, divisor);
+                    }
                 }
             }
 
@@ -60,6 +93,8 @@ impl ArrayBuilder for OglIncomplete<OglArray> {
                 .buf_table
                 .insert(buffer_name.to_string(), buffer_object);
         }
+        // leave nothing bound, buffers bound later on shouldn't end up in this array
+        self.inner.bind(false);
         self.inner
     }
 }
@@ -68,6 +103,8 @@ pub struct OglArray {
     gl: GlowGL,
     gl_array: Option<glow::VertexArray>,
     buf_table: HashMap<String, Box<dyn HasBufferObj>>,
+    element_buffer: Option<String>,
+    instance_buffers: Vec<String>,
 }
 
 impl OglArray {
@@ -76,6 +113,8 @@ impl OglArray {
             gl: gl.clone(),
             gl_array: None,
             buf_table: HashMap::new(),
+            element_buffer: None,
+            instance_buffers: Vec::new(),
         })
     }
 
@@ -93,22 +132,151 @@ impl OglArray {
             .get_mut(buffer_name)
             .map(|box_ptr| box_ptr.as_mut())
     }
+
+    /// the index buffer of the array, if one was given to `init`
+    pub fn elements(&self) -> Option<&dyn HasBufferObj> {
+        self.element_buffer
+            .as_ref()
+            .and_then(|name| self.buf_table.get(name))
+            .map(|box_ptr| box_ptr.as_ref())
+    }
+
+    /// ## Description
+    /// binds the array and draws `count` vertices starting at `first`
+    pub fn draw_arrays(&self, mode: u32, first: i32, count: i32) {
+        self.bind(true);
+        unsafe {
+            self.gl.draw_arrays(mode, first, count);
+        }
+    }
+
+    /// ## Description
+    /// draws `count` vertices `instances` times, buffers with a divisor advance per instance
+    /// ## Comments
+    /// - without instancing support every instance is its own draw call
+    pub fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instances: i32) {
+        self.bind(true);
+        if self.instancing() != InstancingSupport::Unsupported {
+            unsafe {
+                self.gl.draw_arrays_instanced(mode, first, count, instances);
+            }
+            return;
+        }
+        self.emulate_instances(instances, || unsafe {
+            self.gl.draw_arrays(mode, first, count);
+        });
+    }
+
+    /// ## Description
+    /// draws every index of the element buffer
+    /// ## Comments
+    /// - does nothing when the array has no element buffer
+    pub fn draw_elements(&self, mode: u32) {
+        if let Some((count, element_type)) = self.element_range() {
+            self.bind(true);
+            unsafe {
+                self.gl.draw_elements(mode, count, element_type, 0);
+            }
+        }
+    }
+
+    /// ## Description
+    /// draws every index of the element buffer `instances` times
+    /// ## Comments
+    /// - does nothing when the array has no element buffer
+    /// - without instancing support every instance is its own draw call
+    pub fn draw_elements_instanced(&self, mode: u32, instances: i32) {
+        let (count, element_type) = match self.element_range() {
+            Some(range) => range,
+            None => return,
+        };
+        self.bind(true);
+        if self.instancing() != InstancingSupport::Unsupported {
+            unsafe {
+                self.gl
+                    .draw_elements_instanced(mode, count, element_type, 0, instances);
+            }
+            return;
+        }
+        self.emulate_instances(instances, || unsafe {
+            self.gl.draw_elements(mode, count, element_type, 0);
+        });
+    }
+
+    fn instancing(&self) -> InstancingSupport {
+        GlCapabilities::of(&self.gl).instancing
+    }
+
+    fn element_range(&self) -> Option<(i32, u32)> {
+        self.elements().map(|elements| {
+            let info = elements.info();
+            let count = elements.raw_bytes().len() / info.item_size.max(1) as usize;
+            (count as i32, info.element_type())
+        })
+    }
+
+    /// swaps the per-instance arrays for constant attributes read back from the cpu copy,
+    /// assumes the instance buffers hold `f32`s
+    fn emulate_instances<F: Fn()>(&self, instances: i32, draw: F) {
+        let gl = &self.gl;
+        let instance_buffers: Vec<_> = self
+            .instance_buffers
+            .iter()
+            .filter_map(|name| self.buf_table.get(name))
+            .collect();
+
+        for buffer in &instance_buffers {
+            unsafe {
+                gl.disable_vertex_attrib_array(buffer.info().index);
+            }
+        }
+        for instance in 0..instances.max(0) as usize {
+            for buffer in &instance_buffers {
+                let info = buffer.info();
+                let comps = info.num_comps as usize;
+                let start = (instance / info.divisor.max(1) as usize) * comps;
+                let values: Vec<f32> = buffer
+                    .raw_bytes()
+                    .chunks_exact(4)
+                    .skip(start)
+                    .take(comps)
+                    .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                    .collect();
+                unsafe {
+                    match values.len() {
+                        1 => gl.vertex_attrib_1_f32_slice(info.index, &values),
+                        2 => gl.vertex_attrib_2_f32_slice(info.index, &values),
+                        3 => gl.vertex_attrib_3_f32_slice(info.index, &values),
+                        4 => gl.vertex_attrib_4_f32_slice(info.index, &values),
+                        _ => (),
+                    }
+                }
+            }
+            draw();
+        }
+        for buffer in &instance_buffers {
+            unsafe {
+                gl.enable_vertex_attrib_array(buffer.info().index);
+            }
+        }
+    }
 }
 
 impl Bindable for OglArray {
     fn bind(&self, ok: bool) {
-        unsafe {
-            self.gl
-                .bind_vertex_array(ok.map(|| self.gl_array).flatten());
-        }
+        GlBindings::with(&self.gl, |bindings| {
+            bindings.bind_vertex_array(ok.map(|| self.gl_array).flatten())
+        });
     }
 }
 
 impl Drop for OglArray {
     fn drop(&mut self) {
         let gl = self.gl.clone();
+        let gl_array = self.gl_array.unwrap();
+        GlBindings::with(&gl, |bindings| bindings.forget_vertex_array(gl_array));
         unsafe {
-            gl.delete_vertex_array(self.gl_array.unwrap());
+            gl.delete_vertex_array(gl_array);
         }
     }
 }
@@ -9,6 +9,24 @@ pub struct BufferInfo {
     pub usage: u32,
     pub index: u32,
     pub num_comps: u32,
+    /// advance the attribute once every `divisor` instances, 0 means once per vertex
+    pub divisor: u32,
+    /// size of one item of the buffer in bytes
+    pub item_size: u32,
+}
+
+impl BufferInfo {
+    /// ## Description
+    /// the `glDrawElements` type of an index buffer
+    /// ## Returns
+    /// `UNSIGNED_BYTE`, `UNSIGNED_SHORT` or `UNSIGNED_INT` depending on `item_size`
+    pub fn element_type(&self) -> u32 {
+        match self.item_size {
+            1 => glow::UNSIGNED_BYTE,
+            2 => glow::UNSIGNED_SHORT,
+            _ => glow::UNSIGNED_INT,
+        }
+    }
 }
 
 impl Default for BufferInfo {
@@ -18,6 +36,8 @@ impl Default for BufferInfo {
             usage: glow::DYNAMIC_DRAW,
             index: 0,
             num_comps: 1,
+            divisor: 0,
+            item_size: 4,
         }
     }
 }
@@ -34,6 +54,11 @@ pub trait HasBufferObj: HasData + Bindable {
     fn as_any_mut(&mut self) -> &mut dyn Any;
     fn info(&self) -> &BufferInfo;
     fn update(&self);
+    /// ## Description
+    /// binds a `UNIFORM_BUFFER` to the uniform block binding point `binding`
+    /// ## Comments
+    /// - does nothing on contexts without uniform buffers (WebGL1, GL < 3.1)
+    fn bind_base(&self, binding: u32);
 }
 
 pub trait HasBufferBuilder {
@@ -43,6 +68,7 @@ pub trait HasBufferBuilder {
     fn with_usage(self, usage: u32) -> OglIncomplete<Self::InnerStruct>;
     fn with_index(self, index: u32) -> OglIncomplete<Self::InnerStruct>;
     fn with_num_comps(self, comps: u32) -> OglIncomplete<Self::InnerStruct>;
+    fn with_divisor(self, divisor: u32) -> OglIncomplete<Self::InnerStruct>;
     fn with_data(self, data: Vec<Self::VecItemType>) -> OglIncomplete<Self::InnerStruct>;
     fn build(self) -> Self::InnerStruct;
 }
@@ -73,6 +99,11 @@ where
         self
     }
 
+    fn with_divisor(mut self, divisor: u32) -> Self {
+        self.inner.info.divisor = divisor;
+        self
+    }
+
     fn with_data(mut self, data: Vec<Self::VecItemType>) -> Self {
         self.inner.data = data;
         self
@@ -85,7 +116,12 @@ where
         let target = new_self.info.target;
         unsafe {
             new_self.gl_buf = Some(gl.create_buffer().unwrap());
-            gl.bind_buffer(target, new_self.gl_buf);
+        }
+        GlBindings::with(&gl, |bindings| {
+            detach_vertex_array(bindings, target);
+            bindings.bind_buffer(target, new_self.gl_buf);
+        });
+        unsafe {
             gl.buffer_data_u8_slice(target, new_self.data.raw_bytes(), new_self.info.usage);
         }
         new_self
@@ -102,17 +138,19 @@ where
             data: Vec::new(),
             gl_buf: None,
             gl: gl.clone(),
-            info: BufferInfo::default(),
+            info: BufferInfo {
+                item_size: std::mem::size_of::<T>() as u32,
+                ..BufferInfo::default()
+            },
         })
     }
 }
 
 impl<T> Bindable for OglBuf<T> {
     fn bind(&self, ok: bool) {
-        let gl = self.gl.clone();
-        unsafe {
-            gl.bind_buffer(self.info.target, ok.map(|| self.gl_buf).flatten());
-        }
+        GlBindings::with(&self.gl, |bindings| {
+            bindings.bind_buffer(self.info.target, ok.map(|| self.gl_buf).flatten())
+        });
     }
 }
 
@@ -135,11 +173,24 @@ where
 
     fn update(&self) {
         let gl = &self.gl;
-        self.bind(true);
+        GlBindings::with(gl, |bindings| {
+            detach_vertex_array(bindings, self.info.target);
+            bindings.bind_buffer(self.info.target, self.gl_buf);
+        });
         unsafe {
             gl.buffer_sub_data_u8_slice(self.info.target, 0, self.raw_bytes());
         }
     }
+
+    fn bind_base(&self, binding: u32) {
+        let gl = &self.gl;
+        if !GlCapabilities::of(gl).uniform_buffers {
+            return;
+        }
+        GlBindings::with(gl, |bindings| {
+            bindings.bind_buffer_base(glow::UNIFORM_BUFFER, binding, self.gl_buf)
+        });
+    }
 }
 
 impl<T> HasData for OglBuf<Vec<T>>
@@ -156,8 +207,10 @@ where
 
 impl<T> Drop for OglBuf<T> {
     fn drop(&mut self) {
+        let gl_buf = self.gl_buf.unwrap();
+        GlBindings::with(&self.gl, |bindings| bindings.forget_buffer(gl_buf));
         unsafe {
-            self.gl.delete_buffer(self.gl_buf.unwrap());
+            self.gl.delete_buffer(gl_buf);
         }
     }
 }
@@ -171,3 +224,14 @@ where
         Box::new(bo)
     }
 }
+
+/// ## Description
+/// unbinds the vertex array before an element buffer gets bound on its own
+/// ## Comments
+/// - the `ELEMENT_ARRAY_BUFFER` binding belongs to the bound vertex array, so building or updating
+///   an index buffer would otherwise swap out the indices of whatever array was drawn last
+fn detach_vertex_array(bindings: &mut GlBindings, target: u32) {
+    if target == glow::ELEMENT_ARRAY_BUFFER {
+        bindings.bind_vertex_array(None);
+    }
+}
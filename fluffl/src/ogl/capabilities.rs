@@ -0,0 +1,141 @@
+use super::*;
+use crate::GlowGL;
+use std::{cell::RefCell, sync::Arc};
+
+thread_local! {
+    /// capabilities of every context asked about so far, kept apart from `GlStateCache` so
+    /// looking them up never needs the cache
+    static KNOWN_CAPABILITIES: RefCell<Vec<(GlowGL, GlCapabilities)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// How (and if) instanced drawing and attribute divisors are available
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum InstancingSupport {
+    /// GL 3.x, GLES 3 and WebGL2
+    Core,
+    /// WebGL1 with `ANGLE_instanced_arrays`, glow routes the calls through the extension
+    AngleExtension,
+    /// instanced draws get emulated with one draw call per instance
+    Unsupported,
+}
+
+/// # Description
+/// The optional features the `ogl` wrappers adapt to
+/// # Comments
+/// - `GlCapabilities::of(..)` queries them once per context and remembers them
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct GlCapabilities {
+    pub instancing: InstancingSupport,
+    pub uniform_buffers: bool,
+}
+
+impl GlCapabilities {
+    /// ## Description
+    /// what `gl`'s context supports, queried the first time it's asked for
+    /// ## Comments
+    /// - safe to call from anywhere, including inside of `GlStateCache::with(..)`
+    pub fn of(gl: &GlowGL) -> Self {
+        KNOWN_CAPABILITIES.with(|known| {
+            let mut known = known.borrow_mut();
+            // forget contexts only kept alive by this list
+            known.retain(|(gl, _)| Arc::strong_count(gl) > 1);
+            match known.iter().find(|(known_gl, _)| Arc::ptr_eq(known_gl, gl)) {
+                Some(&(_, capabilities)) => capabilities,
+                None => {
+                    let capabilities = Self::query(gl);
+                    known.push((gl.clone(), capabilities));
+                    capabilities
+                }
+            }
+        })
+    }
+
+    pub fn query(gl: &GlowGL) -> Self {
+        let version = unsafe { gl.get_parameter_string(glow::VERSION) };
+        Self::from_version(&version, Self::has_angle_instanced_arrays)
+    }
+
+    /// ## Description
+    /// works the capabilities out from a `GL_VERSION` string
+    /// ## Comments
+    /// - `has_angle` is only asked for WebGL1 contexts
+    fn from_version<F>(version: &str, has_angle: F) -> Self
+    where
+        F: FnOnce() -> bool,
+    {
+        if version.starts_with("WebGL 1") || version.starts_with("OpenGL ES 2") {
+            let instancing = if version.starts_with("WebGL") && has_angle() {
+                InstancingSupport::AngleExtension
+            } else {
+                InstancingSupport::Unsupported
+            };
+            return Self {
+                instancing,
+                uniform_buffers: false,
+            };
+        }
+        if version.starts_with("WebGL") || version.starts_with("OpenGL ES") {
+            return Self {
+                instancing: InstancingSupport::Core,
+                uniform_buffers: true,
+            };
+        }
+
+        // desktop GL starts with "major.minor", followed by vendor specific stuff
+        let mut numbers = version
+            .split(|c: char| !c.is_ascii_digit())
+            .filter_map(|number| number.parse::<u32>().ok());
+        let major = numbers.next().unwrap_or(0);
+        let minor = numbers.next().unwrap_or(0);
+        Self {
+            // divisors are core since 3.3, but every 3.x driver has them through ARB_instanced_arrays
+            instancing: if major >= 3 {
+                InstancingSupport::Core
+            } else {
+                InstancingSupport::Unsupported
+            },
+            uniform_buffers: (major, minor) >= (3, 1),
+        }
+    }
+
+    #[cfg(all(target_family = "wasm", not(target_os = "wasi")))]
+    fn has_angle_instanced_arrays() -> bool {
+        crate::window::webgl_extension_supported("ANGLE_instanced_arrays")
+    }
+
+    #[cfg(not(all(target_family = "wasm", not(target_os = "wasi"))))]
+    fn has_angle_instanced_arrays() -> bool {
+        false
+    }
+}
+
+#[test]
+fn capabilities_follow_the_version_string() {
+    let query =
+        |version: &str, has_angle: bool| GlCapabilities::from_version(version, || has_angle);
+
+    let webgl2 = query("WebGL 2.0 (OpenGL ES 3.0 Chromium)", false);
+    assert_eq!(webgl2.instancing, InstancingSupport::Core);
+    assert!(webgl2.uniform_buffers);
+
+    let webgl1 = query("WebGL 1.0 (OpenGL ES 2.0 Chromium)", true);
+    assert_eq!(webgl1.instancing, InstancingSupport::AngleExtension);
+    assert!(!webgl1.uniform_buffers);
+    assert_eq!(
+        query("WebGL 1.0", false).instancing,
+        InstancingSupport::Unsupported
+    );
+    assert_eq!(
+        query("OpenGL ES 2.0 Mesa", true).instancing,
+        InstancingSupport::Unsupported
+    );
+
+    let desktop = query("3.0 Mesa 23.2.1", false);
+    assert_eq!(desktop.instancing, InstancingSupport::Core);
+    assert!(!desktop.uniform_buffers);
+    assert!(query("4.6.0 NVIDIA 535.54", false).uniform_buffers);
+    assert_eq!(
+        query("2.1 Mesa", false).instancing,
+        InstancingSupport::Unsupported
+    );
+}
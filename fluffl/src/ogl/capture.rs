@@ -0,0 +1,305 @@
+use super::*;
+use crate::{
+    codecs::png,
+    math::{Vec2, AABB2},
+    Error, GlowGL,
+};
+use std::path::{Path, PathBuf};
+
+/// ## Description
+/// An RGBA8 image read back from a framebuffer
+/// ## Comments
+/// - rows go from top to bottom, unlike what `glReadPixels` returns
+#[derive(Clone, PartialEq, Debug)]
+pub struct Capture {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Capture {
+    /// panics if `pixels` doesn't hold exactly `width*height*4` bytes
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            width as usize * height as usize * 4,
+            "pixel data doesn't match the dimensions"
+        );
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// ## Description
+    /// reads a `width`x`height` region, whose bottom-left corner is at `(x,y)`, out of the framebuffer
+    /// currently bound for reading
+    /// ## Comments
+    /// - that's the default framebuffer unless a render target was bound, bind one to capture it instead
+    /// - call this before swapping buffers, the back buffer is undefined afterwards
+    pub fn from_framebuffer(gl: &GlowGL, x: i32, y: i32, width: u32, height: u32) -> Self {
+        let (w, h) = (width as usize, height as usize);
+        let mut pixels = vec![0u8; w * h * 4];
+        unsafe {
+            // rows are tightly packed, the caller's alignment is put back afterwards
+            let alignment = gl.get_parameter_i32(glow::PACK_ALIGNMENT);
+            gl.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
+            gl.read_pixels(
+                x,
+                y,
+                width as i32,
+                height as i32,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                glow::PixelPackData::Slice(&mut pixels),
+            );
+            gl.pixel_store_i32(glow::PACK_ALIGNMENT, alignment);
+        }
+
+        let row_len = w * 4;
+        for row in 0..h / 2 {
+            let (top, bottom) = pixels.split_at_mut((h - row - 1) * row_len);
+            top[row * row_len..(row + 1) * row_len].swap_with_slice(&mut bottom[..row_len]);
+        }
+        Self::from_rgba(width, height, pixels)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn into_rgba(self) -> Vec<u8> {
+        self.pixels
+    }
+
+    /// `(0,0)` is the top-left pixel
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let offset = (y as usize * self.width as usize + x as usize) * 4;
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&self.pixels[offset..offset + 4]);
+        pixel
+    }
+
+    pub fn to_png(&self) -> Vec<u8> {
+        png::encode_rgba(self.width, self.height, &self.pixels)
+    }
+
+    /// a binary (P6) ppm, alpha is dropped
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for pixel in self.pixels.chunks_exact(4) {
+            ppm.extend_from_slice(&pixel[..3]);
+        }
+        ppm
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        std::fs::write(path, self.to_png())?;
+        Ok(())
+    }
+
+    pub fn save_ppm<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        std::fs::write(path, self.to_ppm())?;
+        Ok(())
+    }
+
+    /// ## Description
+    /// compares two captures, returning the smallest region containing every pixel where some channel
+    /// differs by more than `tolerance`
+    /// ## Comments
+    /// - returns `None` if the captures match
+    /// - `max_pos` of the region is exclusive
+    /// - captures of different sizes always differ, the region then covers both of them
+    pub fn diff(&self, other: &Capture, tolerance: u8) -> Option<AABB2<usize>> {
+        if (self.width, self.height) != (other.width, other.height) {
+            let width = self.width.max(other.width) as usize;
+            let height = self.height.max(other.height) as usize;
+            return Some(AABB2::from_segment(
+                Vec2::from_array([0usize, 0]),
+                Vec2::from_array([width, height]),
+            ));
+        }
+
+        let width = self.width as usize;
+        let mut region: Option<([usize; 2], [usize; 2])> = None;
+        let pixel_pairs = self
+            .pixels
+            .chunks_exact(4)
+            .zip(other.pixels.chunks_exact(4));
+        for (index, (a, b)) in pixel_pairs.enumerate() {
+            let differs = a.iter().zip(b).any(|(&a, &b)| a.abs_diff(b) > tolerance);
+            if !differs {
+                continue;
+            }
+            let (x, y) = (index % width, index / width);
+            region = Some(match region {
+                None => ([x, y], [x + 1, y + 1]),
+                Some((min, max)) => (
+                    [min[0].min(x), min[1].min(y)],
+                    [max[0].max(x + 1), max[1].max(y + 1)],
+                ),
+            });
+        }
+        region.map(|(min, max)| AABB2::from_segment(Vec2::from_array(min), Vec2::from_array(max)))
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CaptureFormat {
+    Png,
+    Ppm,
+}
+
+impl CaptureFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Ppm => "ppm",
+        }
+    }
+}
+
+/// ## Description
+/// Saves every Nth frame as a numbered image (`frame_00000.png`, `frame_00001.png`, ...)
+/// ## Comments
+/// - meant for recording trailers and collecting frames for visual regression tests
+/// - call `Self::on_frame(..)` once per frame, after drawing but before the buffers get swapped
+pub struct FrameSequence {
+    directory: PathBuf,
+    prefix: String,
+    format: CaptureFormat,
+    every_nth: u64,
+    frame: u64,
+    next_index: u64,
+}
+
+impl FrameSequence {
+    /// `every_nth` is clamped to at least `1`, which saves every frame
+    pub fn new<P: Into<PathBuf>>(directory: P, every_nth: u64) -> Self {
+        Self {
+            directory: directory.into(),
+            prefix: String::from("frame"),
+            format: CaptureFormat::Png,
+            every_nth: every_nth.max(1),
+            frame: 0,
+            next_index: 0,
+        }
+    }
+
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = String::from(prefix);
+        self
+    }
+
+    pub fn with_format(mut self, format: CaptureFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// how many frames were seen so far, saved or not
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// ## Description
+    /// captures the bottom-left `width`x`height` region of the bound framebuffer if this frame is due
+    /// ## Returns
+    /// the path the frame got saved to, or `None` if this frame got skipped
+    pub fn on_frame(
+        &mut self,
+        gl: &GlowGL,
+        width: u32,
+        height: u32,
+    ) -> Result<Option<PathBuf>, Error> {
+        let is_due = self.frame.is_multiple_of(self.every_nth);
+        self.frame += 1;
+        if !is_due {
+            return Ok(None);
+        }
+        let capture = Capture::from_framebuffer(gl, 0, 0, width, height);
+        self.save(&capture).map(Some)
+    }
+
+    /// ## Description
+    /// saves `capture` as the next image of the sequence, regardless of the frame counter
+    pub fn save(&mut self, capture: &Capture) -> Result<PathBuf, Error> {
+        std::fs::create_dir_all(&self.directory)?;
+        let path = self.directory.join(format!(
+            "{}_{:05}.{}",
+            self.prefix,
+            self.next_index,
+            self.format.extension()
+        ));
+        match self.format {
+            CaptureFormat::Png => capture.save_png(&path)?,
+            CaptureFormat::Ppm => capture.save_ppm(&path)?,
+        }
+        self.next_index += 1;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+fn checkerboard(width: u32, height: u32) -> Capture {
+    let pixels = (0..width * height)
+        .flat_map(|i| {
+            let v = if (i % width + i / width).is_multiple_of(2) {
+                255
+            } else {
+                0
+            };
+            [v, v, v, 255]
+        })
+        .collect();
+    Capture::from_rgba(width, height, pixels)
+}
+
+#[test]
+fn capture_diff_reports_region() {
+    let a = checkerboard(8, 6);
+    let mut b = a.clone();
+    assert!(a.diff(&b, 0).is_none());
+
+    // nudge two pixels, one of them only slightly
+    b.pixels[(2 * 8 + 3) * 4] ^= 0xff;
+    b.pixels[(4 * 8 + 5) * 4 + 1] = b.pixels[(4 * 8 + 5) * 4 + 1].wrapping_add(3);
+
+    let region = a.diff(&b, 0).unwrap();
+    assert_eq!(region.min_pos.data, [3, 2]);
+    assert_eq!(region.max_pos.data, [6, 5]);
+
+    let region = a.diff(&b, 5).unwrap();
+    assert_eq!(region.min_pos.data, [3, 2]);
+    assert_eq!(region.max_pos.data, [4, 3]);
+
+    let region = a.diff(&checkerboard(9, 6), 255).unwrap();
+    assert_eq!(region.max_pos.data, [9, 6]);
+}
+
+#[test]
+fn capture_encodes_and_saves_sequences() {
+    let capture = checkerboard(4, 2);
+    assert_eq!(capture.pixel(1, 0), [0, 0, 0, 255]);
+    let ppm = capture.to_ppm();
+    assert!(ppm.starts_with(b"P6\n4 2\n255\n"));
+    assert_eq!(ppm.len(), 11 + 4 * 2 * 3);
+
+    let directory = std::env::temp_dir().join("fluffl_frame_sequence_test");
+    let _ = std::fs::remove_dir_all(&directory);
+    let mut sequence = FrameSequence::new(&directory, 3).with_format(CaptureFormat::Ppm);
+    let first = sequence.save(&capture).unwrap();
+    let second = sequence.save(&capture).unwrap();
+    assert!(first.ends_with("frame_00000.ppm"));
+    assert!(second.ends_with("frame_00001.ppm"));
+    assert_eq!(std::fs::read(second).unwrap(), ppm);
+    let _ = std::fs::remove_dir_all(&directory);
+}
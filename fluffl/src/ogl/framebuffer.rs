@@ -0,0 +1,640 @@
+use super::{
+    texture::{HasTextureBuilder, TextureObj},
+    *,
+};
+use crate::*;
+
+/// The storage behind a depth (and possibly stencil) attachment
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DepthStencilFormat {
+    Depth16,
+    Depth24,
+    Depth32F,
+    Depth24Stencil8,
+    Depth32FStencil8,
+}
+
+impl DepthStencilFormat {
+    pub fn internal_format(self) -> u32 {
+        match self {
+            Self::Depth16 => glow::DEPTH_COMPONENT16,
+            Self::Depth24 => glow::DEPTH_COMPONENT24,
+            Self::Depth32F => glow::DEPTH_COMPONENT32F,
+            Self::Depth24Stencil8 => glow::DEPTH24_STENCIL8,
+            Self::Depth32FStencil8 => glow::DEPTH32F_STENCIL8,
+        }
+    }
+
+    pub fn has_stencil(self) -> bool {
+        matches!(self, Self::Depth24Stencil8 | Self::Depth32FStencil8)
+    }
+
+    /// the attachment point the format goes to
+    pub fn attachment(self) -> u32 {
+        if self.has_stencil() {
+            glow::DEPTH_STENCIL_ATTACHMENT
+        } else {
+            glow::DEPTH_ATTACHMENT
+        }
+    }
+
+    /// `(format, component type)` for uploading into a texture of this format
+    fn pixel_format(self) -> (u32, u32) {
+        match self {
+            Self::Depth16 => (glow::DEPTH_COMPONENT, glow::UNSIGNED_SHORT),
+            Self::Depth24 => (glow::DEPTH_COMPONENT, glow::UNSIGNED_INT),
+            Self::Depth32F => (glow::DEPTH_COMPONENT, glow::FLOAT),
+            Self::Depth24Stencil8 => (glow::DEPTH_STENCIL, glow::UNSIGNED_INT_24_8),
+            Self::Depth32FStencil8 => (glow::DEPTH_STENCIL, glow::FLOAT_32_UNSIGNED_INT_24_8_REV),
+        }
+    }
+}
+
+/// ## Description
+/// maps a sized color format (like `glow::RGBA8`) to the `(format, component type)` textures of it
+/// get created with
+/// ## Comments
+/// - returns `None` for formats GL 3.x and WebGL2 can't render into without extensions
+pub fn color_pixel_format(internal_format: u32) -> Option<(u32, u32)> {
+    let format = match internal_format {
+        glow::R8 => (glow::RED, glow::UNSIGNED_BYTE),
+        glow::RG8 => (glow::RG, glow::UNSIGNED_BYTE),
+        glow::RGB8 => (glow::RGB, glow::UNSIGNED_BYTE),
+        glow::RGBA8 | glow::SRGB8_ALPHA8 => (glow::RGBA, glow::UNSIGNED_BYTE),
+        glow::R16F => (glow::RED, glow::HALF_FLOAT),
+        glow::RG16F => (glow::RG, glow::HALF_FLOAT),
+        glow::RGBA16F => (glow::RGBA, glow::HALF_FLOAT),
+        glow::R32F => (glow::RED, glow::FLOAT),
+        glow::RG32F => (glow::RG, glow::FLOAT),
+        glow::RGBA32F => (glow::RGBA, glow::FLOAT),
+        glow::R32UI => (glow::RED_INTEGER, glow::UNSIGNED_INT),
+        _ => return None,
+    };
+    Some(format)
+}
+
+pub struct OglRenderbuffer {
+    gl: GlowGL,
+    obj_id: glow::Renderbuffer,
+    internal_format: u32,
+    samples: u32,
+}
+
+impl OglRenderbuffer {
+    /// `samples` above `1` makes a multisampled renderbuffer
+    pub fn new(
+        gl: &GlowGL,
+        internal_format: u32,
+        width: u32,
+        height: u32,
+        samples: u32,
+    ) -> Result<Self, Error> {
+        unsafe {
+            let obj_id = gl.create_renderbuffer().map_err(Error::GenericError)?;
+            gl.bind_renderbuffer(glow::RENDERBUFFER, Some(obj_id));
+            if samples > 1 {
+                gl.renderbuffer_storage_multisample(
+                    glow::RENDERBUFFER,
+                    samples as i32,
+                    internal_format,
+                    width as i32,
+                    height as i32,
+                );
+            } else {
+                gl.renderbuffer_storage(
+                    glow::RENDERBUFFER,
+                    internal_format,
+                    width as i32,
+                    height as i32,
+                );
+            }
+            gl.bind_renderbuffer(glow::RENDERBUFFER, None);
+            Ok(Self {
+                gl: gl.clone(),
+                obj_id,
+                internal_format,
+                samples,
+            })
+        }
+    }
+
+    pub fn renderbuffer(&self) -> glow::Renderbuffer {
+        self.obj_id
+    }
+
+    pub fn internal_format(&self) -> u32 {
+        self.internal_format
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+}
+
+impl Drop for OglRenderbuffer {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_renderbuffer(self.obj_id);
+        }
+    }
+}
+
+/// What a framebuffer attachment is stored in
+pub enum FramebufferAttachment {
+    Texture(OglTexture),
+    Renderbuffer(OglRenderbuffer),
+}
+
+impl FramebufferAttachment {
+    pub fn texture(&self) -> Option<&OglTexture> {
+        match self {
+            Self::Texture(texture) => Some(texture),
+            Self::Renderbuffer(_) => None,
+        }
+    }
+}
+
+/// The settings an `OglFramebuffer` gets (re)built from
+#[derive(Clone, Debug)]
+pub struct FramebufferInfo {
+    pub width: u32,
+    pub height: u32,
+    /// sized internal formats of the color attachments, in attachment order
+    pub color_formats: Vec<u32>,
+    pub depth_stencil: Option<DepthStencilFormat>,
+    /// stores depth/stencil in a texture instead of a renderbuffer, so it can be sampled (shadow maps)
+    pub depth_stencil_texture: bool,
+    /// `1` means no multisampling
+    pub samples: u32,
+}
+
+impl Default for FramebufferInfo {
+    fn default() -> Self {
+        Self {
+            width: 1,
+            height: 1,
+            color_formats: Vec::new(),
+            depth_stencil: None,
+            depth_stencil_texture: false,
+            samples: 1,
+        }
+    }
+}
+
+/// # Description
+/// A render target: draws go into its attachments instead of the window while it is bound
+/// # Comments
+/// - with multisampling on, the color attachments are multisampled renderbuffers. Call
+///   `Self::resolve()` after drawing, which copies them into single sampled textures that
+///   `Self::texture(..)` hands out
+/// - `Bindable::bind(true)` also sets the viewport to cover the framebuffer, `bind(false)` goes back to
+///   the window but leaves the viewport alone
+pub struct OglFramebuffer {
+    gl: GlowGL,
+    obj_id: Option<glow::Framebuffer>,
+    info: FramebufferInfo,
+    color_attachments: Vec<FramebufferAttachment>,
+    depth_stencil_attachment: Option<FramebufferAttachment>,
+    /// single sampled copy of the color attachments when multisampling
+    resolve_target: Option<Box<OglFramebuffer>>,
+}
+
+impl OglFramebuffer {
+    pub fn new(gl: &GlowGL) -> OglIncomplete<Self> {
+        OglIncomplete::new(Self {
+            gl: gl.clone(),
+            obj_id: None,
+            info: FramebufferInfo::default(),
+            color_attachments: Vec::new(),
+            depth_stencil_attachment: None,
+            resolve_target: None,
+        })
+    }
+
+    pub fn info(&self) -> &FramebufferInfo {
+        &self.info
+    }
+
+    pub fn width(&self) -> u32 {
+        self.info.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.info.height
+    }
+
+    /// the sample count actually used, which can be lower than the one asked for
+    pub fn samples(&self) -> u32 {
+        self.info.samples
+    }
+
+    pub fn framebuffer(&self) -> Option<glow::Framebuffer> {
+        self.obj_id
+    }
+
+    /// ## Description
+    /// the texture holding color attachment `index`
+    /// ## Comments
+    /// - when multisampling this is the resolved texture, so call `Self::resolve()` first
+    pub fn texture(&self, index: usize) -> Option<&OglTexture> {
+        match &self.resolve_target {
+            Some(resolved) => resolved.texture(index),
+            None => self
+                .color_attachments
+                .get(index)
+                .and_then(FramebufferAttachment::texture),
+        }
+    }
+
+    /// the depth/stencil texture, only there if the framebuffer was built with one
+    pub fn depth_stencil_texture(&self) -> Option<&OglTexture> {
+        self.depth_stencil_attachment
+            .as_ref()
+            .and_then(FramebufferAttachment::texture)
+    }
+
+    /// ## Description
+    /// copies the multisampled color attachments into the textures `Self::texture(..)` returns
+    /// ## Comments
+    /// - does nothing when multisampling is off
+    /// - leaves the window's framebuffer bound
+    pub fn resolve(&self) {
+        let resolved = match &self.resolve_target {
+            Some(resolved) => resolved,
+            None => return,
+        };
+        let (width, height) = (self.info.width as i32, self.info.height as i32);
+        let num_colors = self.color_attachments.len();
+        unsafe {
+            let gl = &self.gl;
+            gl.bind_framebuffer(glow::READ_FRAMEBUFFER, self.obj_id);
+            gl.bind_framebuffer(glow::DRAW_FRAMEBUFFER, resolved.obj_id);
+            // blits only go from one read buffer to the enabled draw buffers, so go one attachment at a time
+            for index in 0..num_colors {
+                let attachment = glow::COLOR_ATTACHMENT0 + index as u32;
+                let mut draw_buffers = vec![glow::NONE; num_colors];
+                draw_buffers[index] = attachment;
+                gl.read_buffer(attachment);
+                gl.draw_buffers(&draw_buffers);
+                gl.blit_framebuffer(
+                    0,
+                    0,
+                    width,
+                    height,
+                    0,
+                    0,
+                    width,
+                    height,
+                    glow::COLOR_BUFFER_BIT,
+                    glow::NEAREST,
+                );
+            }
+            gl.draw_buffers(&color_attachment_points(num_colors));
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        }
+    }
+
+    /// ## Description
+    /// stretches color attachment `index` over the `(x,y,width,height)` region of the window
+    /// ## Comments
+    /// - multisampled framebuffers get resolved first, GL can't scale a multisampled blit
+    /// - leaves the window's framebuffer bound
+    pub fn blit_to_window(&self, index: usize, x: i32, y: i32, width: u32, height: u32) {
+        self.resolve();
+        let source = self.resolve_target.as_deref().unwrap_or(self);
+        unsafe {
+            let gl = &self.gl;
+            gl.bind_framebuffer(glow::READ_FRAMEBUFFER, source.obj_id);
+            gl.bind_framebuffer(glow::DRAW_FRAMEBUFFER, None);
+            gl.read_buffer(glow::COLOR_ATTACHMENT0 + index as u32);
+            let filter = if (width, height) == (self.info.width, self.info.height) {
+                glow::NEAREST
+            } else {
+                glow::LINEAR
+            };
+            gl.blit_framebuffer(
+                0,
+                0,
+                self.info.width as i32,
+                self.info.height as i32,
+                x,
+                y,
+                x + width as i32,
+                y + height as i32,
+                glow::COLOR_BUFFER_BIT,
+                filter,
+            );
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        }
+    }
+
+    /// ## Description
+    /// reads color attachment `index` back into memory
+    /// ## Comments
+    /// - multisampled framebuffers get resolved first
+    /// - only works for `RGBA8`-ish attachments, since the pixels are read as RGBA8
+    pub fn capture(&self, index: usize) -> Capture {
+        self.resolve();
+        let source = self.resolve_target.as_deref().unwrap_or(self);
+        unsafe {
+            let gl = &self.gl;
+            gl.bind_framebuffer(glow::FRAMEBUFFER, source.obj_id);
+            gl.read_buffer(glow::COLOR_ATTACHMENT0 + index as u32);
+        }
+        let capture = Capture::from_framebuffer(&self.gl, 0, 0, self.info.width, self.info.height);
+        unsafe {
+            self.gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        }
+        capture
+    }
+
+    /// ## Description
+    /// reallocates every attachment at the new size, the old contents are lost
+    /// ## Comments
+    /// - meant for render targets that follow the window size, call it on `EventKind::Resize`
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        if (width, height) == (self.info.width, self.info.height) {
+            return Ok(());
+        }
+        let mut info = self.info.clone();
+        info.width = width;
+        info.height = height;
+        *self = build_framebuffer(&self.gl, info)?;
+        Ok(())
+    }
+}
+
+impl Bindable for OglFramebuffer {
+    fn bind(&self, ok: bool) {
+        unsafe {
+            self.gl
+                .bind_framebuffer(glow::FRAMEBUFFER, ok.map(|| self.obj_id).flatten());
+            if ok {
+                self.gl
+                    .viewport(0, 0, self.info.width as i32, self.info.height as i32);
+            }
+        }
+    }
+}
+
+impl Drop for OglFramebuffer {
+    fn drop(&mut self) {
+        if let Some(id) = self.obj_id {
+            unsafe {
+                self.gl.delete_framebuffer(id);
+            }
+        }
+    }
+}
+
+pub trait HasFramebufferBuilder {
+    fn with_size(self, width: u32, height: u32) -> Self;
+    /// adds a texture backed color attachment of the sized format `internal_format` (like `glow::RGBA8`)
+    fn with_color_attachment(self, internal_format: u32) -> Self;
+    fn with_depth_stencil(self, format: DepthStencilFormat) -> Self;
+    /// like `with_depth_stencil(..)` but the attachment can be sampled, which multisampling doesn't allow
+    fn with_depth_stencil_texture(self, format: DepthStencilFormat) -> Self;
+    /// samples per pixel, clamped to what the driver supports. `1` turns multisampling off
+    fn with_samples(self, samples: u32) -> Self;
+    fn build(self) -> Result<OglFramebuffer, Error>;
+}
+
+impl HasFramebufferBuilder for OglIncomplete<OglFramebuffer> {
+    fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.inner.info.width = width;
+        self.inner.info.height = height;
+        self
+    }
+
+    fn with_color_attachment(mut self, internal_format: u32) -> Self {
+        self.inner.info.color_formats.push(internal_format);
+        self
+    }
+
+    fn with_depth_stencil(mut self, format: DepthStencilFormat) -> Self {
+        self.inner.info.depth_stencil = Some(format);
+        self.inner.info.depth_stencil_texture = false;
+        self
+    }
+
+    fn with_depth_stencil_texture(mut self, format: DepthStencilFormat) -> Self {
+        self.inner.info.depth_stencil = Some(format);
+        self.inner.info.depth_stencil_texture = true;
+        self
+    }
+
+    fn with_samples(mut self, samples: u32) -> Self {
+        self.inner.info.samples = samples.max(1);
+        self
+    }
+
+    fn build(self) -> Result<OglFramebuffer, Error> {
+        let gl = self.inner.gl.clone();
+        let info = self.inner.info.clone();
+        build_framebuffer(&gl, info)
+    }
+}
+
+fn build_framebuffer(gl: &GlowGL, mut info: FramebufferInfo) -> Result<OglFramebuffer, Error> {
+    if info.width == 0 || info.height == 0 {
+        return Err(Error::GenericError(String::from(
+            "framebuffers need a non-zero size",
+        )));
+    }
+    if let Some(&format) = info
+        .color_formats
+        .iter()
+        .find(|&&format| color_pixel_format(format).is_none())
+    {
+        return Err(Error::UnsupportedError(format!(
+            "0x{:x} is not a renderable color format",
+            format
+        )));
+    }
+    if info.samples > 1 {
+        let max_samples = unsafe { gl.get_parameter_i32(glow::MAX_SAMPLES) }.max(1) as u32;
+        info.samples = info.samples.min(max_samples);
+    }
+    let is_multisampled = info.samples > 1;
+    if is_multisampled && info.depth_stencil_texture {
+        return Err(Error::UnsupportedError(String::from(
+            "multisampled depth/stencil can't be stored in a texture",
+        )));
+    }
+
+    let mut framebuffer = OglFramebuffer {
+        gl: gl.clone(),
+        obj_id: None,
+        info: info.clone(),
+        color_attachments: Vec::new(),
+        depth_stencil_attachment: None,
+        resolve_target: None,
+    };
+
+    unsafe {
+        let obj_id = gl.create_framebuffer().map_err(Error::GenericError)?;
+        framebuffer.obj_id = Some(obj_id);
+        gl.bind_framebuffer(glow::FRAMEBUFFER, Some(obj_id));
+
+        for (index, &internal_format) in info.color_formats.iter().enumerate() {
+            let attachment_point = glow::COLOR_ATTACHMENT0 + index as u32;
+            let attachment = if is_multisampled {
+                let renderbuffer = OglRenderbuffer::new(
+                    gl,
+                    internal_format,
+                    info.width,
+                    info.height,
+                    info.samples,
+                )?;
+                attach_renderbuffer(gl, attachment_point, &renderbuffer);
+                FramebufferAttachment::Renderbuffer(renderbuffer)
+            } else {
+                let (format, comp_type) =
+                    color_pixel_format(internal_format).expect("color formats are checked above");
+                let texture = empty_texture(gl, &info, internal_format, format, comp_type);
+                attach_texture(gl, attachment_point, &texture);
+                FramebufferAttachment::Texture(texture)
+            };
+            framebuffer.color_attachments.push(attachment);
+        }
+
+        if let Some(depth_stencil) = info.depth_stencil {
+            let attachment = if info.depth_stencil_texture {
+                let (format, comp_type) = depth_stencil.pixel_format();
+                let texture = empty_texture(
+                    gl,
+                    &info,
+                    depth_stencil.internal_format(),
+                    format,
+                    comp_type,
+                );
+                // depth can't be filtered everywhere, and shadow lookups want exact texels anyway
+                texture.set_param_i32(glow::TEXTURE_MIN_FILTER, glow::NEAREST as i32);
+                texture.set_param_i32(glow::TEXTURE_MAG_FILTER, glow::NEAREST as i32);
+                attach_texture(gl, depth_stencil.attachment(), &texture);
+                FramebufferAttachment::Texture(texture)
+            } else {
+                let renderbuffer = OglRenderbuffer::new(
+                    gl,
+                    depth_stencil.internal_format(),
+                    info.width,
+                    info.height,
+                    info.samples,
+                )?;
+                attach_renderbuffer(gl, depth_stencil.attachment(), &renderbuffer);
+                FramebufferAttachment::Renderbuffer(renderbuffer)
+            };
+            framebuffer.depth_stencil_attachment = Some(attachment);
+        }
+
+        // a framebuffer without color attachments (depth only) must not draw into COLOR_ATTACHMENT0
+        let draw_buffers = color_attachment_points(info.color_formats.len());
+        if draw_buffers.is_empty() {
+            gl.draw_buffers(&[glow::NONE]);
+            gl.read_buffer(glow::NONE);
+        } else {
+            gl.draw_buffers(&draw_buffers);
+        }
+
+        let status = gl.check_framebuffer_status(glow::FRAMEBUFFER);
+        gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        if status != glow::FRAMEBUFFER_COMPLETE {
+            return Err(Error::GenericError(format!(
+                "framebuffer is incomplete: {}",
+                framebuffer_status_name(status)
+            )));
+        }
+    }
+
+    if is_multisampled && !info.color_formats.is_empty() {
+        let resolve_info = FramebufferInfo {
+            depth_stencil: None,
+            depth_stencil_texture: false,
+            samples: 1,
+            ..info
+        };
+        framebuffer.resolve_target = Some(Box::new(build_framebuffer(gl, resolve_info)?));
+    }
+
+    Ok(framebuffer)
+}
+
+fn empty_texture(
+    gl: &GlowGL,
+    info: &FramebufferInfo,
+    internal_format: u32,
+    format: u32,
+    comp_type: u32,
+) -> OglTexture {
+    TextureObj::<u8>::builder(gl)
+        .with_width(info.width)
+        .with_height(info.height)
+        .with_internal_format(internal_format)
+        .with_format(format)
+        .with_component_type(comp_type)
+        .build()
+        .into()
+}
+
+unsafe fn attach_texture(gl: &GlowGL, attachment_point: u32, texture: &OglTexture) {
+    gl.framebuffer_texture_2d(
+        glow::FRAMEBUFFER,
+        attachment_point,
+        glow::TEXTURE_2D,
+        texture.texture_id(),
+        0,
+    );
+}
+
+unsafe fn attach_renderbuffer(gl: &GlowGL, attachment_point: u32, renderbuffer: &OglRenderbuffer) {
+    gl.framebuffer_renderbuffer(
+        glow::FRAMEBUFFER,
+        attachment_point,
+        glow::RENDERBUFFER,
+        Some(renderbuffer.renderbuffer()),
+    );
+}
+
+fn color_attachment_points(num_colors: usize) -> Vec<u32> {
+    (0..num_colors as u32)
+        .map(|index| glow::COLOR_ATTACHMENT0 + index)
+        .collect()
+}
+
+fn framebuffer_status_name(status: u32) -> String {
+    let name = match status {
+        glow::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => "incomplete attachment",
+        glow::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => "missing attachment",
+        glow::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => "mismatched sample counts",
+        glow::FRAMEBUFFER_UNSUPPORTED => "unsupported combination of formats",
+        _ => return format!("status 0x{:x}", status),
+    };
+    String::from(name)
+}
+
+#[test]
+fn framebuffer_formats() {
+    assert_eq!(
+        color_pixel_format(glow::RGBA8),
+        Some((glow::RGBA, glow::UNSIGNED_BYTE))
+    );
+    assert_eq!(
+        color_pixel_format(glow::RGBA16F),
+        Some((glow::RGBA, glow::HALF_FLOAT))
+    );
+    assert_eq!(color_pixel_format(glow::RGB5_A1), None);
+
+    assert!(DepthStencilFormat::Depth24Stencil8.has_stencil());
+    assert_eq!(
+        DepthStencilFormat::Depth24Stencil8.attachment(),
+        glow::DEPTH_STENCIL_ATTACHMENT
+    );
+    assert_eq!(
+        DepthStencilFormat::Depth32F.attachment(),
+        glow::DEPTH_ATTACHMENT
+    );
+    assert_eq!(
+        color_attachment_points(2),
+        vec![glow::COLOR_ATTACHMENT0, glow::COLOR_ATTACHMENT1]
+    );
+}
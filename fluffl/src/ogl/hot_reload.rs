@@ -0,0 +1,235 @@
+use super::{preprocessor::*, program::*, reflection::*, *};
+use crate::{console::*, console_log, GlowGL};
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// # Description
+/// An `OglProg` loaded from a file on disk that gets recompiled whenever the file, or anything it
+/// `#include`s, changes
+/// # Comments
+/// - includes are resolved relative to the directory of the main file
+/// - uniforms written through `Self::set_uniform(..)` are remembered and written into every reloaded
+///   program, so tweaked values survive a reload
+/// - meant for tuning effects on desktop, on the web `Self::poll()` never reloads anything
+pub struct HotProgram {
+    gl: GlowGL,
+    program: OglProg,
+    path: PathBuf,
+    target: ShaderTarget,
+    defines: Vec<(String, String)>,
+    watched: WatchedFiles,
+    uniforms: HashMap<String, UniformValue>,
+    reloads: u32,
+}
+
+impl HotProgram {
+    pub fn load<P: Into<PathBuf>>(gl: &GlowGL, path: P) -> Result<Self, CompilationError> {
+        Self::load_with(gl, path, ShaderTarget::default(), &[])
+    }
+
+    /// ## Description
+    /// loads the program, preprocessing it for `target` with `defines` (pairs of name and value)
+    pub fn load_with<P: Into<PathBuf>>(
+        gl: &GlowGL,
+        path: P,
+        target: ShaderTarget,
+        defines: &[(&str, &str)],
+    ) -> Result<Self, CompilationError> {
+        let path = path.into();
+        let defines: Vec<_> = defines
+            .iter()
+            .map(|&(name, value)| (String::from(name), String::from(value)))
+            .collect();
+        let mut files = Vec::new();
+        let program = compile_file(gl, &path, target, &defines, &mut files)?;
+        Ok(Self {
+            gl: gl.clone(),
+            program,
+            path,
+            target,
+            defines,
+            watched: WatchedFiles::new(files),
+            uniforms: HashMap::new(),
+            reloads: 0,
+        })
+    }
+
+    /// the current program, don't hold on to it across calls to `Self::poll()`
+    pub fn program(&self) -> &OglProg {
+        &self.program
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// how many times the program got successfully recompiled
+    pub fn reloads(&self) -> u32 {
+        self.reloads
+    }
+
+    /// ## Description
+    /// same as `OglProg::set_uniform(..)` but the value also gets written into reloaded programs
+    pub fn set_uniform<V>(&mut self, name: &str, value: V) -> Result<(), UniformError>
+    where
+        V: Into<UniformValue>,
+    {
+        let value = value.into();
+        self.program.set_uniform(name, value)?;
+        self.uniforms.insert(String::from(name), value);
+        Ok(())
+    }
+
+    /// ## Description
+    /// recompiles the program if one of its files changed since the last call
+    /// ## Returns
+    /// `true` if the program got replaced, uniform locations looked up before then are stale
+    /// ## Comments
+    /// - only compares modification times, so calling it once a frame is fine
+    /// - when compilation fails the error gets logged and the previous program stays in use, the
+    ///   files it tried to include are still watched so fixing any of them triggers another reload
+    pub fn poll(&mut self) -> bool {
+        if cfg!(all(target_family = "wasm", not(target_os = "wasi"))) || !self.watched.changed() {
+            return false;
+        }
+        self.reload()
+    }
+
+    /// ## Description
+    /// recompiles the program right away, whether its files changed or not
+    /// ## Returns
+    /// `true` if the program got replaced
+    pub fn reload(&mut self) -> bool {
+        let mut files = Vec::new();
+        let result = compile_file(&self.gl, &self.path, self.target, &self.defines, &mut files);
+        // the includes may have changed even if the new source doesn't compile
+        self.watched = WatchedFiles::new(files);
+        match result {
+            Ok(program) => {
+                // uniforms that went away or changed type in the new source are simply dropped
+                self.uniforms
+                    .retain(|name, value| program.set_uniform(name, *value).is_ok());
+                self.program = program;
+                self.reloads += 1;
+                true
+            }
+            Err(err) => {
+                console_log!("failed to reload {}: {}\n", self.path.display(), err);
+                false
+            }
+        }
+    }
+}
+
+impl Bindable for HotProgram {
+    fn bind(&self, opt: bool) {
+        self.program.bind(opt)
+    }
+}
+
+/// ## Description
+/// compiles the program in `path`
+/// ## Comments
+/// - `files` gets `path` and every file it tried to include, whether compiling worked or not
+fn compile_file(
+    gl: &GlowGL,
+    path: &Path,
+    target: ShaderTarget,
+    defines: &[(String, String)],
+    files: &mut Vec<PathBuf>,
+) -> Result<OglProg, CompilationError> {
+    files.push(path.to_path_buf());
+    let source =
+        std::fs::read_to_string(path).map_err(|err| CompilationError::PreprocessError {
+            message: format!("{}: {}", path.display(), err),
+        })?;
+
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    let included = RefCell::new(Vec::new());
+    let mut options = ShaderOptions::new()
+        .with_target(target)
+        .with_file_name(&path.to_string_lossy())
+        .with_loader(|include: &str| {
+            let include_path = directory.join(include);
+            let source = std::fs::read_to_string(&include_path).ok();
+            included.borrow_mut().push(include_path);
+            source
+        });
+    for (name, value) in defines {
+        options = options.with_define(name, value);
+    }
+    let program = OglProg::compile_program_with(gl, &source, &options);
+    drop(options);
+
+    files.extend(included.into_inner());
+    program
+}
+
+/// Remembers the modification time of a set of files
+struct WatchedFiles {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl WatchedFiles {
+    fn new(mut paths: Vec<PathBuf>) -> Self {
+        paths.sort();
+        paths.dedup();
+        let files = paths
+            .into_iter()
+            .map(|path| {
+                let modified = modified_time(&path);
+                (path, modified)
+            })
+            .collect();
+        Self { files }
+    }
+
+    /// true if some file got modified, created or deleted since the last call
+    fn changed(&mut self) -> bool {
+        let mut changed = false;
+        for (path, last_modified) in &mut self.files {
+            let modified = modified_time(path);
+            if modified != *last_modified {
+                *last_modified = modified;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[test]
+fn watched_files_notice_changes() {
+    let directory = std::env::temp_dir().join("fluffl_hot_reload_test");
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    let shader = directory.join("effect.glsl");
+    let include = directory.join("common.glsl");
+    std::fs::write(&shader, "#include \"common.glsl\"").unwrap();
+
+    let mut watched = WatchedFiles::new(vec![shader.clone(), include.clone(), shader.clone()]);
+    assert_eq!(watched.files.len(), 2);
+    assert!(!watched.changed());
+
+    // a missing include showing up counts as a change
+    std::fs::write(&include, "uniform float t;").unwrap();
+    assert!(watched.changed());
+    assert!(!watched.changed());
+
+    let later = SystemTime::now() + std::time::Duration::from_secs(5);
+    let file = std::fs::File::options().write(true).open(&shader).unwrap();
+    file.set_modified(later).unwrap();
+    assert!(watched.changed());
+    assert!(!watched.changed());
+
+    let _ = std::fs::remove_dir_all(&directory);
+}
@@ -0,0 +1,564 @@
+//! The preprocessor `OglProg::compile_program(..)` runs before handing sources to GL.
+//! It splits a combined source into the `HEADER`, `UNIFORMS`, `VERTEX_ATTRIBUTES`, `VERTEX_SHADER` and
+//! `FRAGMENT_SHADER` blocks, resolves `#include`s, injects `#define`s and the `#version` header, and
+//! keeps track of where every line came from so compiler errors can point at the original file.
+use std::fmt;
+
+/// Decides which `#version`/precision header gets injected into sources that don't declare one
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ShaderTarget {
+    /// desktop GL 3.3 core
+    Gl33,
+    WebGl1,
+    WebGl2,
+}
+
+impl ShaderTarget {
+    /// the header lines that go in front of every stage
+    pub fn header(self) -> &'static [&'static str] {
+        match self {
+            Self::Gl33 => &["#version 330 core"],
+            Self::WebGl1 => &["#version 100", "precision mediump float;"],
+            Self::WebGl2 => &["#version 300 es", "precision highp float;"],
+        }
+    }
+}
+
+impl Default for ShaderTarget {
+    /// WebGL2 on the web, GL 3.3 everywhere else
+    fn default() -> Self {
+        if cfg!(all(target_family = "wasm", not(target_os = "wasi"))) {
+            Self::WebGl2
+        } else {
+            Self::Gl33
+        }
+    }
+}
+
+/// Resolves an `#include` path to the contents of the file, `None` if there is no such file
+pub type IncludeLoader<'a> = Box<dyn Fn(&str) -> Option<String> + 'a>;
+
+/// # Description
+/// Settings for `OglProg::compile_program_with(..)`
+/// # Comments
+/// - every combination of defines is a separate permutation of the program, compile one per combination
+pub struct ShaderOptions<'a> {
+    target: ShaderTarget,
+    defines: Vec<(String, String)>,
+    loader: Option<IncludeLoader<'a>>,
+    file_name: String,
+}
+
+impl<'a> ShaderOptions<'a> {
+    pub fn new() -> Self {
+        Self {
+            target: ShaderTarget::default(),
+            defines: Vec::new(),
+            loader: None,
+            file_name: String::from("<source>"),
+        }
+    }
+
+    pub fn with_target(mut self, target: ShaderTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// adds `#define name value` to every stage, `value` may be empty
+    pub fn with_define(mut self, name: &str, value: &str) -> Self {
+        self.defines.push((String::from(name), String::from(value)));
+        self
+    }
+
+    /// ## Description
+    /// sets the function `#include "path"` gets resolved with
+    /// ## Comments
+    /// - included files get preprocessed too, so they can include files of their own
+    pub fn with_loader<F>(mut self, loader: F) -> Self
+    where
+        F: Fn(&str) -> Option<String> + 'a,
+    {
+        self.loader = Some(Box::new(loader));
+        self
+    }
+
+    /// the name errors in the top level source get reported with
+    pub fn with_file_name(mut self, file_name: &str) -> Self {
+        self.file_name = String::from(file_name);
+        self
+    }
+
+    pub fn target(&self) -> ShaderTarget {
+        self.target
+    }
+}
+
+impl<'a> Default for ShaderOptions<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PreprocessError {
+    /// `#include` was used but `ShaderOptions::with_loader(..)` wasn't
+    NoLoader {
+        file: String,
+        line: u32,
+    },
+    IncludeNotFound {
+        path: String,
+        file: String,
+        line: u32,
+    },
+    /// a file ended up including itself
+    IncludeCycle {
+        path: String,
+    },
+    BadDirective {
+        directive: String,
+        file: String,
+        line: u32,
+    },
+    /// a block was opened but its `#endif` is missing
+    UnterminatedBlock {
+        block: String,
+    },
+    MissingBlock {
+        block: String,
+    },
+}
+
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoLoader { file, line } => {
+                write!(
+                    f,
+                    "{}:{}: #include used without an include loader",
+                    file, line
+                )
+            }
+            Self::IncludeNotFound { path, file, line } => {
+                write!(f, "{}:{}: can't find include \"{}\"", file, line, path)
+            }
+            Self::IncludeCycle { path } => write!(f, "\"{}\" includes itself", path),
+            Self::BadDirective {
+                directive,
+                file,
+                line,
+            } => write!(f, "{}:{}: malformed directive '{}'", file, line, directive),
+            Self::UnterminatedBlock { block } => write!(f, "block {} has no #endif", block),
+            Self::MissingBlock { block } => write!(f, "block {} is missing", block),
+        }
+    }
+}
+
+impl std::error::Error for PreprocessError {}
+
+/// Maps lines of a preprocessed stage back to the file and line they came from
+#[derive(Clone, Default, Debug)]
+pub struct SourceMap {
+    files: Vec<String>,
+    /// indexed by line - 1, `None` for generated lines
+    lines: Vec<Option<(usize, u32)>>,
+}
+
+impl SourceMap {
+    /// ## Description
+    /// where line `line` (1-based, like compilers count) of the preprocessed source came from
+    /// ## Returns
+    /// the file name and 1-based line, `None` for lines the preprocessor generated
+    pub fn locate(&self, line: u32) -> Option<(&str, u32)> {
+        let (file, line) = (*self.lines.get((line as usize).checked_sub(1)?)?)?;
+        Some((self.files[file].as_str(), line))
+    }
+
+    /// ## Description
+    /// prefixes every line of a compiler log that mentions a line number with the original location
+    /// ## Comments
+    /// - understands the `0:12`, `0:12(5)` and `0(12)` styles of Mesa, ANGLE and NVIDIA
+    pub fn remap_log(&self, log: &str) -> String {
+        let mut remapped = String::with_capacity(log.len());
+        for entry in log.lines() {
+            let location = log_line_number(entry).and_then(|line| self.locate(line));
+            if let Some((file, line)) = location {
+                remapped.push_str(&format!("{}:{}: ", file, line));
+            }
+            remapped.push_str(entry);
+            remapped.push('\n');
+        }
+        remapped
+    }
+}
+
+/// A single stage, ready for `glShaderSource`
+#[derive(Clone, Debug)]
+pub struct PreprocessedStage {
+    /// `glow::VERTEX_SHADER` or `glow::FRAGMENT_SHADER`
+    pub stage: u32,
+    pub source: String,
+    pub source_map: SourceMap,
+}
+
+/// ## Description
+/// runs the whole preprocessor over a combined source
+/// ## Returns
+/// the vertex stage followed by the fragment stage
+pub fn preprocess(
+    raw_source: &str,
+    options: &ShaderOptions,
+) -> Result<Vec<PreprocessedStage>, PreprocessError> {
+    let mut files = vec![options.file_name.clone()];
+    let mut lines = Vec::new();
+    expand_includes(
+        raw_source,
+        0,
+        options,
+        &mut files,
+        &mut vec![options.file_name.clone()],
+        &mut lines,
+    )?;
+
+    let header = find_block(&lines, "HEADER")?;
+    let uniforms = find_block(&lines, "UNIFORMS")?;
+    let attributes = find_block(&lines, "VERTEX_ATTRIBUTES")?;
+    let vertex = find_block(&lines, "VERTEX_SHADER")?.ok_or(PreprocessError::MissingBlock {
+        block: String::from("VERTEX_SHADER"),
+    })?;
+    let fragment = find_block(&lines, "FRAGMENT_SHADER")?.ok_or(PreprocessError::MissingBlock {
+        block: String::from("FRAGMENT_SHADER"),
+    })?;
+
+    let stages = [
+        (
+            glow::VERTEX_SHADER,
+            [header, uniforms, attributes, Some(vertex)],
+        ),
+        (
+            glow::FRAGMENT_SHADER,
+            [header, uniforms, None, Some(fragment)],
+        ),
+    ];
+    let preprocessed = stages
+        .iter()
+        .map(|(stage, blocks)| {
+            let body = blocks.iter().flatten().flat_map(|block| block.iter());
+            assemble_stage(*stage, body, options, &files)
+        })
+        .collect();
+    Ok(preprocessed)
+}
+
+#[derive(Clone, Debug)]
+struct SourceLine {
+    text: String,
+    /// index into the file list and 1-based line
+    origin: (usize, u32),
+}
+
+/// splits `#name rest` into `("name", "rest")`
+fn directive(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start().strip_prefix('#')?.trim_start();
+    let end = line
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(line.len());
+    Some((&line[..end], line[end..].trim()))
+}
+
+fn expand_includes(
+    source: &str,
+    file: usize,
+    options: &ShaderOptions,
+    files: &mut Vec<String>,
+    include_stack: &mut Vec<String>,
+    out: &mut Vec<SourceLine>,
+) -> Result<(), PreprocessError> {
+    for (index, text) in source.lines().enumerate() {
+        let line = index as u32 + 1;
+        let path = match directive(text) {
+            Some(("include", rest)) => {
+                include_path(rest).ok_or_else(|| PreprocessError::BadDirective {
+                    directive: String::from(text.trim()),
+                    file: files[file].clone(),
+                    line,
+                })?
+            }
+            _ => {
+                out.push(SourceLine {
+                    text: String::from(text),
+                    origin: (file, line),
+                });
+                continue;
+            }
+        };
+
+        if include_stack.iter().any(|included| included == path) {
+            return Err(PreprocessError::IncludeCycle {
+                path: String::from(path),
+            });
+        }
+        let loader = options.loader.as_ref().ok_or(PreprocessError::NoLoader {
+            file: files[file].clone(),
+            line,
+        })?;
+        let contents = loader(path).ok_or_else(|| PreprocessError::IncludeNotFound {
+            path: String::from(path),
+            file: files[file].clone(),
+            line,
+        })?;
+
+        files.push(String::from(path));
+        include_stack.push(String::from(path));
+        expand_includes(
+            &contents,
+            files.len() - 1,
+            options,
+            files,
+            include_stack,
+            out,
+        )?;
+        include_stack.pop();
+    }
+    Ok(())
+}
+
+/// accepts both `"path"` and `<path>`
+fn include_path(rest: &str) -> Option<&str> {
+    let path = rest
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .or_else(|| {
+            rest.strip_prefix('<')
+                .and_then(|rest| rest.strip_suffix('>'))
+        })?;
+    (!path.is_empty()).then_some(path)
+}
+
+/// ## Description
+/// finds the lines between `#ifndef name` and the `#endif` closing it
+/// ## Comments
+/// - conditionals nested inside the block are skipped over, so blocks can use `#ifdef` for permutations
+fn find_block<'a>(
+    lines: &'a [SourceLine],
+    name: &str,
+) -> Result<Option<&'a [SourceLine]>, PreprocessError> {
+    let start = match lines
+        .iter()
+        .position(|line| directive(&line.text) == Some(("ifndef", name)))
+    {
+        Some(start) => start,
+        None => return Ok(None),
+    };
+
+    let mut depth = 1;
+    for (offset, line) in lines[start + 1..].iter().enumerate() {
+        match directive(&line.text) {
+            Some(("if" | "ifdef" | "ifndef", _)) => depth += 1,
+            Some(("endif", _)) => depth -= 1,
+            _ => (),
+        }
+        if depth == 0 {
+            return Ok(Some(&lines[start + 1..start + 1 + offset]));
+        }
+    }
+    Err(PreprocessError::UnterminatedBlock {
+        block: String::from(name),
+    })
+}
+
+fn assemble_stage<'a, I>(
+    stage: u32,
+    body: I,
+    options: &ShaderOptions,
+    files: &[String],
+) -> PreprocessedStage
+where
+    I: Iterator<Item = &'a SourceLine>,
+{
+    let mut source = String::new();
+    let mut source_map = SourceMap {
+        files: files.to_vec(),
+        lines: Vec::new(),
+    };
+    let mut push_line = |text: &str, origin: Option<(usize, u32)>| {
+        source.push_str(text);
+        source.push('\n');
+        source_map.lines.push(origin);
+    };
+
+    // `#version` has to come first, so a declared one gets hoisted above the injected defines
+    let body: Vec<_> = body.collect();
+    let declared_version = body
+        .iter()
+        .find(|line| matches!(directive(&line.text), Some(("version", _))));
+    match declared_version {
+        Some(line) => push_line(line.text.trim(), Some(line.origin)),
+        None => {
+            for header_line in options.target.header() {
+                push_line(header_line, None);
+            }
+        }
+    }
+    for (name, value) in &options.defines {
+        push_line(format!("#define {} {}", name, value).trim_end(), None);
+    }
+    for line in body
+        .iter()
+        .filter(|line| !matches!(directive(&line.text), Some(("version", _))))
+    {
+        push_line(&line.text, Some(line.origin));
+    }
+
+    PreprocessedStage {
+        stage,
+        source,
+        source_map,
+    }
+}
+
+/// pulls the line number out of a compiler log line like `ERROR: 0:12: ...` or `0(12) : error ...`
+fn log_line_number(entry: &str) -> Option<u32> {
+    let bytes = entry.as_bytes();
+    (0..bytes.len().saturating_sub(2)).find_map(|index| {
+        let is_source_zero =
+            bytes[index] == b'0' && (index == 0 || !bytes[index - 1].is_ascii_digit());
+        if !is_source_zero || !matches!(bytes[index + 1], b':' | b'(') {
+            return None;
+        }
+        let digits: String = entry[index + 2..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        digits.parse().ok()
+    })
+}
+
+#[cfg(test)]
+const TEST_SOURCE: &str = r#"
+    #ifndef HEADER
+        #include "common.glsl"
+    #endif
+    #ifndef VERTEX_SHADER
+        void main(){
+            gl_Position = vec4(0.0);
+        }
+    #endif
+    #ifndef FRAGMENT_SHADER
+        out vec4 color;
+        void main(){
+        #ifdef USE_FOG
+            color = fog(vec4(1.0));
+        #else
+            color = vec4(1.0);
+        #endif
+        }
+    #endif
+"#;
+
+#[cfg(test)]
+fn test_loader(path: &str) -> Option<String> {
+    match path {
+        "common.glsl" => Some(String::from("#include \"fog.glsl\"\nuniform vec4 tint;")),
+        "fog.glsl" => Some(String::from("vec4 fog(vec4 c){\n    return c*0.5;\n}")),
+        "cycle.glsl" => Some(String::from("#include <cycle.glsl>")),
+        _ => None,
+    }
+}
+
+#[test]
+fn preprocessor_builds_permutations() {
+    let options = ShaderOptions::new()
+        .with_target(ShaderTarget::WebGl2)
+        .with_define("USE_FOG", "")
+        .with_define("LIGHTS", "4")
+        .with_loader(test_loader)
+        .with_file_name("sprite.glsl");
+    let stages = preprocess(TEST_SOURCE, &options).unwrap();
+    assert_eq!(stages.len(), 2);
+    assert_eq!(stages[0].stage, glow::VERTEX_SHADER);
+
+    let fragment = &stages[1];
+    let lines: Vec<_> = fragment.source.lines().collect();
+    assert_eq!(
+        lines[..4],
+        [
+            "#version 300 es",
+            "precision highp float;",
+            "#define USE_FOG",
+            "#define LIGHTS 4"
+        ]
+    );
+    // the nested #ifdef/#else/#endif stay inside the block
+    assert!(fragment.source.contains("color = fog(vec4(1.0));"));
+    assert!(fragment.source.contains("#else"));
+    assert!(fragment.source.contains("uniform vec4 tint;"));
+    assert!(!stages[0].source.contains("out vec4 color;"));
+
+    // lines map back to the file they came from
+    let fog_line = lines
+        .iter()
+        .position(|l| l.contains("return c*0.5"))
+        .unwrap() as u32
+        + 1;
+    assert_eq!(fragment.source_map.locate(fog_line), Some(("fog.glsl", 2)));
+    let color_line = lines.iter().position(|l| l.contains("out vec4")).unwrap() as u32 + 1;
+    assert_eq!(
+        fragment.source_map.locate(color_line),
+        Some(("sprite.glsl", 11))
+    );
+    assert_eq!(fragment.source_map.locate(1), None);
+
+    let log = format!("0:{}(9): error: syntax error\nlink failed", fog_line);
+    assert_eq!(
+        fragment.source_map.remap_log(&log),
+        format!(
+            "fog.glsl:2: 0:{}(9): error: syntax error\nlink failed\n",
+            fog_line
+        )
+    );
+}
+
+#[test]
+fn preprocessor_reports_errors() {
+    let declared = "#ifndef HEADER\n#version 330 core\n#endif\n#ifndef VERTEX_SHADER\nvoid main(){}\n#endif\n#ifndef FRAGMENT_SHADER\nvoid main(){}\n#endif";
+    let stages = preprocess(declared, &ShaderOptions::new().with_define("A", "1")).unwrap();
+    assert!(stages[0]
+        .source
+        .starts_with("#version 330 core\n#define A 1\n"));
+    assert_eq!(stages[0].source.matches("#version").count(), 1);
+
+    assert_eq!(log_line_number("ERROR: 0:17: 'x' : undeclared"), Some(17));
+    assert_eq!(log_line_number("0(23) : error C1008"), Some(23));
+    assert_eq!(log_line_number("10:3 nothing"), None);
+
+    let include = |path: &str| {
+        format!(
+            "#ifndef VERTEX_SHADER\n#include {}\n#endif\n#ifndef FRAGMENT_SHADER\n#endif",
+            path
+        )
+    };
+    let options = ShaderOptions::new().with_loader(test_loader);
+    assert_eq!(
+        preprocess(&include("\"cycle.glsl\""), &options).unwrap_err(),
+        PreprocessError::IncludeCycle {
+            path: String::from("cycle.glsl")
+        }
+    );
+    assert!(matches!(
+        preprocess(&include("\"missing.glsl\""), &options),
+        Err(PreprocessError::IncludeNotFound { line: 2, .. })
+    ));
+    assert!(matches!(
+        preprocess(&include("common.glsl"), &options),
+        Err(PreprocessError::BadDirective { .. })
+    ));
+    assert!(matches!(
+        preprocess(&include("\"common.glsl\""), &ShaderOptions::new()),
+        Err(PreprocessError::NoLoader { .. })
+    ));
+    assert!(matches!(
+        preprocess("#ifndef VERTEX_SHADER\n", &options),
+        Err(PreprocessError::UnterminatedBlock { .. })
+    ));
+}
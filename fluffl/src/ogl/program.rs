@@ -1,9 +1,10 @@
-use super::*;
+use super::{preprocessor::*, reflection::*, *};
 use crate::*;
 
 pub struct OglProg {
     pub prog: glow::Program,
     pub gl: GlowGL,
+    reflection: ProgramReflection,
 }
 
 impl OglProg {
@@ -11,6 +12,66 @@ impl OglProg {
     pub fn prog(&self) -> glow::Program {
         self.prog.clone()
     }
+
+    /// the active uniforms and attributes, looked up when the program was linked
+    pub fn reflection(&self) -> &ProgramReflection {
+        &self.reflection
+    }
+
+    /// the cached location of an active uniform
+    pub fn uniform_location(&self, name: &str) -> Option<&UniformLocation> {
+        self.reflection.uniform(name)?.location.as_ref()
+    }
+
+    /// ## Description
+    /// writes `value` into the uniform called `name`, binding the program first
+    /// ## Comments
+    /// - takes `f32`, `i32`, `Vec2`, `Vec3`, `Vec4`, `Mat4` and `TextureUnit` (for samplers)
+    /// - fails if the uniform isn't active or its GLSL type doesn't match the value
+    pub fn set_uniform<V>(&self, name: &str, value: V) -> Result<(), UniformError>
+    where
+        V: Into<UniformValue>,
+    {
+        let value = value.into();
+        let uniform = self
+            .reflection
+            .uniform(name)
+            .ok_or_else(|| UniformError::NotFound {
+                name: String::from(name),
+            })?;
+        if !value.fits(uniform.ty) {
+            return Err(UniformError::TypeMismatch {
+                name: String::from(name),
+                expected: uniform.ty,
+                found: value.type_name(),
+            });
+        }
+        self.bind(true);
+        value.upload(&self.gl, uniform.location.as_ref());
+        Ok(())
+    }
+
+    /// ## Description
+    /// points the uniform block called `block` at the binding point `binding`
+    /// ## Comments
+    /// - pair it with `HasBufferObj::bind_base(binding)` on a `UNIFORM_BUFFER`
+    /// - fails on contexts without uniform buffers, so WebGL1 callers can fall back to plain uniforms
+    pub fn bind_uniform_block(&self, block: &str, binding: u32) -> Result<(), UniformError> {
+        let gl = &self.gl;
+        if !GlCapabilities::of(gl).uniform_buffers {
+            return Err(UniformError::UniformBuffersUnsupported);
+        }
+        unsafe {
+            let index = gl
+                .get_uniform_block_index(self.prog, block)
+                .ok_or_else(|| UniformError::BlockNotFound {
+                    name: String::from(block),
+                })?;
+            gl.uniform_block_binding(self.prog, index, binding);
+        }
+        Ok(())
+    }
+
     /// # Description
     /// This function does some preprocessing to seperate concatenated shaders into individual shaders before compilation.\
     /// Shaders are seperated with preprocessor if statements. Example of `raw_source` format:\
@@ -27,59 +88,43 @@ impl OglProg {
     ///  #endif
     ///  ```
     /// so again `raw_source` is just ONE block of text with multiple shaders jammed in one file.
+    /// # Comments
+    /// - `#version` and precision get added automatically when the source doesn't declare them, see
+    ///   `Self::compile_program_with(..)` for includes, defines and picking the target
     pub fn compile_program(gl: &GlowGL, raw_source: &str) -> Result<OglProg, CompilationError> {
-        let tokens = tokensize_source(raw_source);
-        let header = get_source_block(&tokens, "HEADER");
-        let uniforms = get_source_block(&tokens, "UNIFORMS");
-        let vertex_attributes = get_source_block(&tokens, "VERTEX_ATTRIBUTES");
-        let vertex_shader = get_source_block(&tokens, "VERTEX_SHADER");
-        let fragment_shader = get_source_block(&tokens, "FRAGMENT_SHADER");
-
-        // println!("START_DUMP");
-        // for (k, tok) in tokens.iter().enumerate() {
-        //     print!("{}:{}", k, tok.to_string());
-        // }
-        // println!("END_DUMP");
-
-        // println!("\n**START_BLOCK**\n");
-        // uniforms.map(|(lbound, ubound)| {
-        //     println!("({},{})", lbound, ubound);
-        //     for k in lbound..=ubound {
-        //         print!("{}", tokens[k].to_string());
-        //     }
-        // });
-        // println!("\n**END_BLOCK**");
-
-        let vertex_shader_module = gen_shader_module(
-            &tokens,
-            vec![header, uniforms, vertex_attributes, vertex_shader],
-        );
-
-        let fragment_shader_module =
-            gen_shader_module(&tokens, vec![header, uniforms, fragment_shader]);
-
-        let mut module_list = vec![
-            (glow::VERTEX_SHADER, vertex_shader_module),
-            (glow::FRAGMENT_SHADER, fragment_shader_module),
-        ];
+        Self::compile_program_with(gl, raw_source, &ShaderOptions::default())
+    }
 
-        let shader_iterator = module_list.iter_mut().filter(|(_, opt)| opt.is_some()).map(
-            |(shader_type, opt)| unsafe {
-                let source = opt.take().unwrap();
-                let shader: glow::Shader = gl.create_shader(*shader_type).unwrap();
-                gl.shader_source(shader, source.as_str());
-                gl.compile_shader(shader);
-                if !gl.get_shader_compile_status(shader) {
-                    let compile_error = gl.get_shader_info_log(shader);
-                    Err(CompilationError::ShaderError {
-                        ogl_error: compile_error,
-                        faulty_source: source,
-                    })
-                } else {
-                    Ok(shader)
-                }
-            },
-        );
+    /// # Description
+    /// Same as `Self::compile_program(..)` but preprocesses `raw_source` with `options` first
+    /// # Comments
+    /// - lines of the driver's error log that mention a line number get prefixed with the `file:line`
+    ///   it originally came from
+    pub fn compile_program_with(
+        gl: &GlowGL,
+        raw_source: &str,
+        options: &ShaderOptions,
+    ) -> Result<OglProg, CompilationError> {
+        let stages =
+            preprocess(raw_source, options).map_err(|err| CompilationError::PreprocessError {
+                message: err.to_string(),
+            })?;
+
+        let shader_iterator = stages.into_iter().map(|stage| unsafe {
+            let shader: glow::Shader = gl.create_shader(stage.stage).unwrap();
+            gl.shader_source(shader, stage.source.as_str());
+            gl.compile_shader(shader);
+            if !gl.get_shader_compile_status(shader) {
+                let compile_error = gl.get_shader_info_log(shader);
+                gl.delete_shader(shader);
+                Err(CompilationError::ShaderError {
+                    ogl_error: stage.source_map.remap_log(&compile_error),
+                    faulty_source: stage.source,
+                })
+            } else {
+                Ok(shader)
+            }
+        });
 
         #[allow(clippy::clone_on_copy)]
         let program = unsafe {
@@ -87,17 +132,25 @@ impl OglProg {
             let program = gl.create_program().unwrap();
 
             for shader_res in shader_iterator {
-                shaders.push(shader_res?);
-                let cur_shader: glow::Shader = shaders.last().unwrap().clone();
-                gl.attach_shader(program, cur_shader);
+                match shader_res {
+                    Ok(shader) => {
+                        gl.attach_shader(program, shader);
+                        shaders.push(shader);
+                    }
+                    Err(err) => {
+                        discard_program(gl, program, &shaders);
+                        return Err(err);
+                    }
+                }
             }
 
             gl.link_program(program);
             if !gl.get_program_link_status(program) {
                 let ogl_error = gl.get_program_info_log(program);
-                return Err(CompilationError::ShaderError {
+                discard_program(gl, program, &shaders);
+                return Err(CompilationError::LinkError {
                     ogl_error,
-                    faulty_source: String::new(),
+                    faulty_source: raw_source.to_string(),
                 });
             }
 
@@ -109,6 +162,7 @@ impl OglProg {
             OglProg {
                 gl: gl.clone(),
                 prog: program,
+                reflection: ProgramReflection::reflect(gl, program),
             }
         };
 
@@ -116,8 +170,18 @@ impl OglProg {
     }
 }
 
+/// deletes a program that failed to build along with the shaders attached to it so far
+unsafe fn discard_program(gl: &GlowGL, program: glow::Program, shaders: &[glow::Shader]) {
+    for &shader in shaders {
+        gl.detach_shader(program, shader);
+        gl.delete_shader(shader);
+    }
+    gl.delete_program(program);
+}
+
 impl Drop for OglProg {
     fn drop(&mut self) {
+        GlBindings::with(&self.gl, |bindings| bindings.forget_program(self.prog));
         unsafe {
             self.gl.delete_program(self.prog);
         }
@@ -126,8 +190,9 @@ impl Drop for OglProg {
 
 impl Bindable for OglProg {
     fn bind(&self, opt: bool) {
-        let gl = &self.gl;
-        unsafe { gl.use_program(opt.map(|| self.prog())) }
+        GlBindings::with(&self.gl, |bindings| {
+            bindings.use_program(opt.map(|| self.prog()))
+        });
     }
 }
 #[derive(Debug)]
@@ -140,168 +205,19 @@ pub enum CompilationError {
         ogl_error: String,
         faulty_source: String,
     },
+    /// a missing include or block, `message` says which and where
+    PreprocessError { message: String },
 }
 
-enum SlToken {
-    If(String),
-    Ifndef(String),
-    Define(String),
-    Source(String),
-    Endif,
-}
-
-impl ToString for SlToken {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for CompilationError {
+    /// only the driver's log, the source is left out
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            SlToken::If(text) => format!("#if {}\n", text),
-            SlToken::Ifndef(text) => format!("#ifndef {}\n", text),
-            SlToken::Define(text) => format!("#define {}\n", text),
-            SlToken::Source(text) => text.to_string(),
-            SlToken::Endif => "endif\n".to_string(),
+            Self::ShaderError { ogl_error, .. } => write!(f, "shader error:\n{}", ogl_error),
+            Self::LinkError { ogl_error, .. } => write!(f, "link error:\n{}", ogl_error),
+            Self::PreprocessError { message } => write!(f, "preprocess error: {}", message),
         }
     }
 }
-#[derive(PartialEq)]
-enum LexState {
-    Start,
-    If,
-    Ifndef,
-    Endif,
-    Define,
-}
-
-fn tokensize_source(raw_source: &str) -> Vec<SlToken> {
-    let mut tokens = Vec::new();
-    let mut state = LexState::Start;
-    let mut source_iter = raw_source.chars().peekable();
-    let mut accum = String::new();
-
-    let push_source = |accum: &mut String, tokens: &mut Vec<_>| {
-        let all_whitespece = accum.chars().all(|a| a.is_whitespace());
-        if !accum.is_empty() && !all_whitespece {
-            tokens.push(SlToken::Source(accum.clone()));
-        }
-        accum.clear();
-    };
 
-    while source_iter.peek().is_some() || !accum.is_empty() || state != LexState::Start {
-        let c = source_iter.next().unwrap_or('\0');
-        match state {
-            LexState::Start => {
-                if let ('#', true, _iter) = lookahead(c, "ifndef", source_iter.clone()) {
-                    source_iter = _iter;
-                    state = LexState::Ifndef;
-                    push_source(&mut accum, &mut tokens);
-                } else if let ('#', true, _iter) = lookahead(c, "define", source_iter.clone()) {
-                    source_iter = _iter;
-                    state = LexState::Define;
-                    push_source(&mut accum, &mut tokens);
-                } else if let ('#', true, _iter) = lookahead(c, "endif", source_iter.clone()) {
-                    source_iter = _iter;
-                    state = LexState::Endif;
-                    push_source(&mut accum, &mut tokens);
-                } else if let ('#', true, _iter) = lookahead(c, "if", source_iter.clone()) {
-                    source_iter = _iter;
-                    state = LexState::If;
-                    push_source(&mut accum, &mut tokens);
-                } else {
-                    accum.push(c);
-                }
-            }
-            LexState::Ifndef => {
-                if c == '\n' || c == '\0' {
-                    state = LexState::Start;
-                    tokens.push(SlToken::Ifndef(accum.clone()));
-                    accum.clear();
-                } else if c.is_alphanumeric() || c == '_' {
-                    accum.push(c);
-                }
-            }
-            LexState::If => {
-                if c == '\n' || c == '\0' {
-                    state = LexState::Start;
-                    tokens.push(SlToken::If(accum.clone()));
-                    accum.clear();
-                } else if c.is_alphanumeric() || c == '_' {
-                    accum.push(c);
-                }
-            }
-            LexState::Define => {
-                if c == '\n' || c == '\0' {
-                    state = LexState::Start;
-                    tokens.push(SlToken::Define(accum.clone()));
-                    accum.clear();
-                } else if c.is_alphanumeric() || c == '_' {
-                    accum.push(c);
-                }
-            }
-            LexState::Endif => {
-                if c == '\n' || c == '\0' {
-                    state = LexState::Start;
-                    tokens.push(SlToken::Endif);
-                    accum.clear();
-                }
-            }
-        }
-    }
-    tokens
-}
-
-fn lookahead<T>(c: char, ident: &'static str, mut iter: T) -> (char, bool, T)
-where
-    T: std::iter::Iterator<Item = char> + Sized,
-{
-    let eq_test = (&mut iter).take(ident.len()).collect::<String>() == ident;
-    (c, eq_test, iter)
-}
-
-fn get_source_block(tokens: &[SlToken], block_ident: &'static str) -> Option<(usize, usize)> {
-    let mut found_ifndef = false;
-    let query = tokens
-        .iter()
-        .enumerate()
-        .filter(|&(_, tok)| {
-            if let SlToken::Ifndef(text) = tok {
-                if text == block_ident {
-                    found_ifndef = true;
-                    true
-                } else {
-                    false
-                }
-            } else if let SlToken::Endif = tok {
-                found_ifndef
-            } else {
-                false
-            }
-        })
-        .take(2)
-        .map(|(index, _)| index)
-        .collect::<Vec<_>>();
-
-    if query.len() == 2 {
-        Some((query[0], query[1]))
-    } else {
-        None
-    }
-}
-
-fn gen_shader_module(
-    tokens: &[SlToken],
-    code_blocks: Vec<Option<(usize, usize)>>,
-) -> Option<String> {
-    let mut source = String::new();
-
-    //the last element in the tokens list is always the core shader source ( void main(){ ... }  )
-    tokens.last()?;
-
-    code_blocks
-        .iter()
-        .copied()
-        .flatten()
-        .flat_map(|(lbound, ubound)| tokens.iter().take(ubound).skip(lbound + 1))
-        .for_each(|tok| {
-            source.push_str(tok.to_string().as_str());
-        });
-
-    Some(source)
-}
+impl std::error::Error for CompilationError {}
@@ -0,0 +1,374 @@
+use super::*;
+use crate::{
+    math::{Mat4, Vec2, Vec3, Vec4},
+    GlowGL,
+};
+use std::fmt;
+
+/// The GLSL type of a uniform or attribute, as reported by GL
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GlslType {
+    Float,
+    Vec2,
+    Vec3,
+    Vec4,
+    Int,
+    IVec2,
+    IVec3,
+    IVec4,
+    UInt,
+    Bool,
+    Mat2,
+    Mat3,
+    Mat4,
+    Sampler2D,
+    Sampler3D,
+    SamplerCube,
+    Sampler2DArray,
+    Sampler2DShadow,
+    ISampler2D,
+    USampler2D,
+    /// anything else, holds the raw GL enum
+    Other(u32),
+}
+
+impl GlslType {
+    pub fn from_gl(gl_type: u32) -> Self {
+        match gl_type {
+            glow::FLOAT => Self::Float,
+            glow::FLOAT_VEC2 => Self::Vec2,
+            glow::FLOAT_VEC3 => Self::Vec3,
+            glow::FLOAT_VEC4 => Self::Vec4,
+            glow::INT => Self::Int,
+            glow::INT_VEC2 => Self::IVec2,
+            glow::INT_VEC3 => Self::IVec3,
+            glow::INT_VEC4 => Self::IVec4,
+            glow::UNSIGNED_INT => Self::UInt,
+            glow::BOOL => Self::Bool,
+            glow::FLOAT_MAT2 => Self::Mat2,
+            glow::FLOAT_MAT3 => Self::Mat3,
+            glow::FLOAT_MAT4 => Self::Mat4,
+            glow::SAMPLER_2D => Self::Sampler2D,
+            glow::SAMPLER_3D => Self::Sampler3D,
+            glow::SAMPLER_CUBE => Self::SamplerCube,
+            glow::SAMPLER_2D_ARRAY => Self::Sampler2DArray,
+            glow::SAMPLER_2D_SHADOW => Self::Sampler2DShadow,
+            glow::INT_SAMPLER_2D => Self::ISampler2D,
+            glow::UNSIGNED_INT_SAMPLER_2D => Self::USampler2D,
+            other => Self::Other(other),
+        }
+    }
+
+    /// the name the type has in GLSL source
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Float => "float",
+            Self::Vec2 => "vec2",
+            Self::Vec3 => "vec3",
+            Self::Vec4 => "vec4",
+            Self::Int => "int",
+            Self::IVec2 => "ivec2",
+            Self::IVec3 => "ivec3",
+            Self::IVec4 => "ivec4",
+            Self::UInt => "uint",
+            Self::Bool => "bool",
+            Self::Mat2 => "mat2",
+            Self::Mat3 => "mat3",
+            Self::Mat4 => "mat4",
+            Self::Sampler2D => "sampler2D",
+            Self::Sampler3D => "sampler3D",
+            Self::SamplerCube => "samplerCube",
+            Self::Sampler2DArray => "sampler2DArray",
+            Self::Sampler2DShadow => "sampler2DShadow",
+            Self::ISampler2D => "isampler2D",
+            Self::USampler2D => "usampler2D",
+            Self::Other(_) => "<unknown type>",
+        }
+    }
+
+    pub fn is_sampler(self) -> bool {
+        matches!(
+            self,
+            Self::Sampler2D
+                | Self::Sampler3D
+                | Self::SamplerCube
+                | Self::Sampler2DArray
+                | Self::Sampler2DShadow
+                | Self::ISampler2D
+                | Self::USampler2D
+        )
+    }
+}
+
+/// An active uniform of a linked program
+#[derive(Clone, Debug)]
+pub struct UniformInfo {
+    /// arrays are listed without the `[0]` GL appends to their name
+    pub name: String,
+    pub ty: GlslType,
+    /// number of elements for arrays, `1` otherwise
+    pub size: i32,
+    pub location: Option<glow::UniformLocation>,
+}
+
+/// An active vertex attribute of a linked program
+#[derive(Clone, Debug)]
+pub struct AttributeInfo {
+    pub name: String,
+    pub ty: GlslType,
+    pub size: i32,
+    pub location: Option<u32>,
+}
+
+/// # Description
+/// Every active uniform and attribute of a program, looked up once right after linking
+/// # Comments
+/// - uniforms the compiler optimized away aren't active, so they don't show up here
+#[derive(Clone, Default, Debug)]
+pub struct ProgramReflection {
+    uniforms: HashMap<String, UniformInfo>,
+    attributes: HashMap<String, AttributeInfo>,
+}
+
+impl ProgramReflection {
+    pub fn reflect(gl: &GlowGL, program: glow::Program) -> Self {
+        let mut reflection = Self::default();
+        unsafe {
+            for index in 0..gl.get_active_uniforms(program) {
+                if let Some(uniform) = gl.get_active_uniform(program, index) {
+                    let location = gl.get_uniform_location(program, &uniform.name);
+                    let name = String::from(strip_array_suffix(&uniform.name));
+                    reflection.uniforms.insert(
+                        name.clone(),
+                        UniformInfo {
+                            name,
+                            ty: GlslType::from_gl(uniform.utype),
+                            size: uniform.size,
+                            location,
+                        },
+                    );
+                }
+            }
+            for index in 0..gl.get_active_attributes(program) {
+                if let Some(attribute) = gl.get_active_attribute(program, index) {
+                    let location = gl.get_attrib_location(program, &attribute.name);
+                    let name = String::from(strip_array_suffix(&attribute.name));
+                    reflection.attributes.insert(
+                        name.clone(),
+                        AttributeInfo {
+                            name,
+                            ty: GlslType::from_gl(attribute.atype),
+                            size: attribute.size,
+                            location,
+                        },
+                    );
+                }
+            }
+        }
+        reflection
+    }
+
+    pub fn uniform(&self, name: &str) -> Option<&UniformInfo> {
+        self.uniforms.get(name)
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&AttributeInfo> {
+        self.attributes.get(name)
+    }
+
+    pub fn uniforms(&self) -> impl Iterator<Item = &UniformInfo> {
+        self.uniforms.values()
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item = &AttributeInfo> {
+        self.attributes.values()
+    }
+}
+
+/// GL names arrays `name[0]`, the `[0]` gets dropped so lookups can use the plain name
+fn strip_array_suffix(name: &str) -> &str {
+    name.strip_suffix("[0]").unwrap_or(name)
+}
+
+/// Marks a value as a texture unit, for setting `sampler*` uniforms
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TextureUnit(pub u32);
+
+/// A value that can be written into a uniform, see `OglProg::set_uniform(..)`
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum UniformValue {
+    Float(f32),
+    Int(i32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    /// row major, like `Mat4` stores it
+    Mat4([f32; 16]),
+    TextureUnit(u32),
+}
+
+impl UniformValue {
+    /// describes the value for error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Float(_) => "f32",
+            Self::Int(_) => "i32",
+            Self::Vec2(_) => "Vec2",
+            Self::Vec3(_) => "Vec3",
+            Self::Vec4(_) => "Vec4",
+            Self::Mat4(_) => "Mat4",
+            Self::TextureUnit(_) => "TextureUnit",
+        }
+    }
+
+    /// true if the value can be written into a uniform of type `ty`
+    pub fn fits(&self, ty: GlslType) -> bool {
+        match self {
+            Self::Float(_) => ty == GlslType::Float,
+            // GL sets bools through the int setters too
+            Self::Int(_) => matches!(ty, GlslType::Int | GlslType::Bool),
+            Self::Vec2(_) => ty == GlslType::Vec2,
+            Self::Vec3(_) => ty == GlslType::Vec3,
+            Self::Vec4(_) => ty == GlslType::Vec4,
+            Self::Mat4(_) => ty == GlslType::Mat4,
+            Self::TextureUnit(_) => ty.is_sampler(),
+        }
+    }
+
+    /// ## Description
+    /// writes the value into the uniform at `location` of the program currently in use
+    /// ## Comments
+    /// - doesn't check the type, use `Self::fits(..)` for that
+    pub fn upload(&self, gl: &GlowGL, location: Option<&glow::UniformLocation>) {
+        unsafe {
+            match self {
+                Self::Float(value) => gl.uniform_1_f32(location, *value),
+                Self::Int(value) => gl.uniform_1_i32(location, *value),
+                Self::Vec2(value) => gl.uniform_2_f32_slice(location, value),
+                Self::Vec3(value) => gl.uniform_3_f32_slice(location, value),
+                Self::Vec4(value) => gl.uniform_4_f32_slice(location, value),
+                Self::Mat4(value) => gl.uniform_matrix_4_f32_slice(location, true, value),
+                Self::TextureUnit(unit) => gl.uniform_1_i32(location, *unit as i32),
+            }
+        }
+    }
+}
+
+impl From<f32> for UniformValue {
+    fn from(value: f32) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<i32> for UniformValue {
+    fn from(value: i32) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<Vec2<f32>> for UniformValue {
+    fn from(value: Vec2<f32>) -> Self {
+        Self::Vec2(value.data)
+    }
+}
+
+impl From<Vec3<f32>> for UniformValue {
+    fn from(value: Vec3<f32>) -> Self {
+        Self::Vec3(value.data)
+    }
+}
+
+impl From<Vec4<f32>> for UniformValue {
+    fn from(value: Vec4<f32>) -> Self {
+        Self::Vec4(value.data)
+    }
+}
+
+impl From<Mat4<f32>> for UniformValue {
+    fn from(value: Mat4<f32>) -> Self {
+        let mut data = [0.0; 16];
+        data.copy_from_slice(value.as_slice());
+        Self::Mat4(data)
+    }
+}
+
+impl From<TextureUnit> for UniformValue {
+    fn from(TextureUnit(unit): TextureUnit) -> Self {
+        Self::TextureUnit(unit)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum UniformError {
+    /// the program has no active uniform by that name
+    NotFound { name: String },
+    TypeMismatch {
+        name: String,
+        expected: GlslType,
+        found: &'static str,
+    },
+    /// the program has no uniform block by that name
+    BlockNotFound { name: String },
+    /// the context has no uniform buffers (WebGL1, GL < 3.1)
+    UniformBuffersUnsupported,
+}
+
+impl fmt::Display for UniformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound { name } => write!(
+                f,
+                "uniform '{}' isn't active, it's either misspelled or unused by the shader",
+                name
+            ),
+            Self::TypeMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "uniform '{}' is a {} but was given a {}",
+                name,
+                expected.name(),
+                found
+            ),
+            Self::BlockNotFound { name } => write!(
+                f,
+                "uniform block '{}' isn't active, it's either misspelled or unused by the shader",
+                name
+            ),
+            Self::UniformBuffersUnsupported => {
+                write!(f, "uniform blocks aren't supported by this context")
+            }
+        }
+    }
+}
+
+impl std::error::Error for UniformError {}
+
+#[test]
+fn uniform_values_check_their_type() {
+    assert_eq!(GlslType::from_gl(glow::FLOAT_MAT4), GlslType::Mat4);
+    assert_eq!(GlslType::from_gl(glow::SAMPLER_2D), GlslType::Sampler2D);
+    assert_eq!(strip_array_suffix("lights[0]"), "lights");
+    assert_eq!(strip_array_suffix("model"), "model");
+
+    assert!(UniformValue::from(1.0f32).fits(GlslType::Float));
+    assert!(UniformValue::from(1).fits(GlslType::Bool));
+    assert!(UniformValue::from(TextureUnit(2)).fits(GlslType::SamplerCube));
+    assert!(!UniformValue::from(2).fits(GlslType::Sampler2D));
+    assert!(!UniformValue::from(Vec2::from_array([1.0f32, 2.0])).fits(GlslType::Vec4));
+
+    let identity = UniformValue::from(Mat4::<f32>::identity());
+    assert!(matches!(identity, UniformValue::Mat4(m) if m[0] == 1.0 && m[1] == 0.0 && m[5] == 1.0));
+
+    let error = UniformError::TypeMismatch {
+        name: String::from("bounds"),
+        expected: GlslType::Vec4,
+        found: UniformValue::Float(0.0).type_name(),
+    };
+    assert_eq!(
+        error.to_string(),
+        "uniform 'bounds' is a vec4 but was given a f32"
+    );
+}
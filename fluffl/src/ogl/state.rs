@@ -0,0 +1,854 @@
+use super::{capabilities::*, *};
+use crate::{console::*, console_log, GlowGL};
+use std::{cell::RefCell, collections::HashMap, panic::Location, rc::Rc, sync::Arc};
+
+/// # Description
+/// Blending, as set by `glEnable(GL_BLEND)`, `glBlendFunc` and `glBlendEquation`
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct BlendState {
+    pub enabled: bool,
+    pub src: u32,
+    pub dst: u32,
+    pub equation: u32,
+}
+
+impl BlendState {
+    pub const DISABLED: Self = Self {
+        enabled: false,
+        src: glow::ONE,
+        dst: glow::ZERO,
+        equation: glow::FUNC_ADD,
+    };
+
+    /// regular (non-premultiplied) alpha blending, what the GUI and text renderers use
+    pub const ALPHA: Self = Self {
+        enabled: true,
+        src: glow::SRC_ALPHA,
+        dst: glow::ONE_MINUS_SRC_ALPHA,
+        equation: glow::FUNC_ADD,
+    };
+
+    pub const ADDITIVE: Self = Self {
+        enabled: true,
+        src: glow::ONE,
+        dst: glow::ONE,
+        equation: glow::FUNC_ADD,
+    };
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct DepthState {
+    pub enabled: bool,
+    pub func: u32,
+    /// `glDepthMask`
+    pub write: bool,
+}
+
+impl DepthState {
+    pub const DISABLED: Self = Self {
+        enabled: false,
+        func: glow::LESS,
+        write: true,
+    };
+
+    pub const LESS_EQUAL: Self = Self {
+        enabled: true,
+        func: glow::LEQUAL,
+        write: true,
+    };
+}
+
+/// # Description
+/// Stencil testing, the same function and operations are used for front and back faces
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct StencilState {
+    pub enabled: bool,
+    pub func: u32,
+    pub reference: i32,
+    /// the mask passed to `glStencilFunc`
+    pub value_mask: u32,
+    /// `glStencilMask`
+    pub write_mask: u32,
+    pub fail: u32,
+    pub depth_fail: u32,
+    pub pass: u32,
+}
+
+impl StencilState {
+    pub const DISABLED: Self = Self {
+        enabled: false,
+        func: glow::ALWAYS,
+        reference: 0,
+        value_mask: !0,
+        write_mask: !0,
+        fail: glow::KEEP,
+        depth_fail: glow::KEEP,
+        pass: glow::KEEP,
+    };
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ScissorState {
+    pub enabled: bool,
+    /// `[x, y, width, height]` with the origin at the bottom-left, like `glScissor`
+    pub rect: [i32; 4],
+}
+
+impl ScissorState {
+    pub const DISABLED: Self = Self {
+        enabled: false,
+        rect: [0; 4],
+    };
+
+    pub fn rect(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            enabled: true,
+            rect: [x, y, width, height],
+        }
+    }
+}
+
+/// the fixed function state `push()`/`pop()` save and restore, `None` means "not known"
+#[derive(Copy, Clone, Default, Debug)]
+struct FixedFunctionState {
+    blend: Option<BlendState>,
+    depth: Option<DepthState>,
+    stencil: Option<StencilState>,
+    scissor: Option<ScissorState>,
+}
+
+thread_local! {
+    /// one cache per context, GL contexts are tied to a thread anyway
+    static SHARED_CACHES: RefCell<Vec<Rc<RefCell<GlStateCache>>>> = const { RefCell::new(Vec::new()) };
+    /// kept apart from `SHARED_CACHES` so the `ogl` wrappers can bind from inside of `GlStateCache::with(..)`
+    static SHARED_BINDINGS: RefCell<Vec<GlBindings>> = const { RefCell::new(Vec::new()) };
+}
+
+/// # Description
+/// Remembers the blend/depth/stencil/scissor state set through it, so redundant state changes never
+/// reach the driver, and lets systems `push()` that state before changing it and `pop()` it when
+/// they are done, so their changes don't leak into whatever renders next
+/// # Comments
+/// - state nobody set through the cache yet is "unknown" and always gets written on first use
+/// - bindings of programs, buffers, vertex arrays and textures are cached too (see `GlBindings`), but
+///   aren't part of what `push()`/`pop()` save
+/// - code that changes state behind the cache's back has to call `invalidate()` afterwards
+/// - in debug builds every call checks `glGetError` and logs failures with the location of the caller
+/// - `Self::with(..)` gives every system drawing into a context the same cache
+pub struct GlStateCache {
+    gl: GlowGL,
+    known: FixedFunctionState,
+    stack: Vec<FixedFunctionState>,
+    skipped: u64,
+}
+
+impl GlStateCache {
+    pub fn new(gl: &GlowGL) -> Self {
+        Self {
+            gl: gl.clone(),
+            known: FixedFunctionState::default(),
+            stack: Vec::new(),
+            skipped: 0,
+        }
+    }
+
+    /// ## Description
+    /// runs `f` with the cache shared by everything that draws into `gl`'s context
+    /// ## Comments
+    /// - don't call `with` again from inside `f`, the cache is borrowed mutably while `f` runs,
+    ///   the `ogl` wrappers never call it themselves so they are fine to use inside `f`
+    #[track_caller]
+    pub fn with<F, R>(gl: &GlowGL, f: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        let cache = SHARED_CACHES.with(|caches| {
+            let mut caches = caches.borrow_mut();
+            // forget caches whose context is only kept alive by the cache itself
+            caches.retain(|cache| Arc::strong_count(&cache.borrow().gl) > 1);
+            match caches
+                .iter()
+                .find(|cache| Arc::ptr_eq(&cache.borrow().gl, gl))
+            {
+                Some(cache) => cache.clone(),
+                None => {
+                    let cache = Rc::new(RefCell::new(Self::new(gl)));
+                    caches.push(cache.clone());
+                    cache
+                }
+            }
+        });
+        let mut cache = cache.try_borrow_mut().unwrap_or_else(|_| {
+            panic!(
+                "GlStateCache::with(..) called inside of another one at {}",
+                Location::caller()
+            )
+        });
+        f(&mut cache)
+    }
+
+    pub fn gl(&self) -> &GlowGL {
+        &self.gl
+    }
+
+    /// what the context supports, same as `GlCapabilities::of(..)`
+    pub fn capabilities(&self) -> GlCapabilities {
+        GlCapabilities::of(&self.gl)
+    }
+
+    /// how many state changes and binds were dropped for being redundant
+    pub fn skipped_calls(&self) -> u64 {
+        self.skipped + GlBindings::with(&self.gl, |bindings| bindings.skipped)
+    }
+
+    /// forgets everything, the next change of any state or binding goes through to GL
+    pub fn invalidate(&mut self) {
+        self.known = FixedFunctionState::default();
+        GlBindings::with(&self.gl, GlBindings::invalidate);
+    }
+
+    /// saves the blend, depth, stencil and scissor state
+    pub fn push(&mut self) {
+        self.stack.push(self.known);
+    }
+
+    /// ## Description
+    /// restores the state saved by the matching `push()`
+    /// ## Comments
+    /// - state that was unknown when it got pushed but got set since goes back to GL's defaults (the
+    ///   `DISABLED` states), which is what the systems using the cache leave behind. Set it through
+    ///   the cache before `push()` when something else is wanted
+    #[track_caller]
+    pub fn pop(&mut self) {
+        let saved = match self.stack.pop() {
+            Some(saved) => saved,
+            None => {
+                let caller = Location::caller();
+                console_log!("GlStateCache::pop() without a push() at {}\n", caller);
+                return;
+            }
+        };
+        // untouched unknown state stays unknown, there's no call to make
+        if let Some(blend) = saved
+            .blend
+            .or(self.known.blend.map(|_| BlendState::DISABLED))
+        {
+            self.set_blend(blend);
+        }
+        if let Some(depth) = saved
+            .depth
+            .or(self.known.depth.map(|_| DepthState::DISABLED))
+        {
+            self.set_depth(depth);
+        }
+        let stencil = saved
+            .stencil
+            .or(self.known.stencil.map(|_| StencilState::DISABLED));
+        if let Some(stencil) = stencil {
+            self.set_stencil(stencil);
+        }
+        let scissor = saved
+            .scissor
+            .or(self.known.scissor.map(|_| ScissorState::DISABLED));
+        if let Some(scissor) = scissor {
+            self.set_scissor(scissor);
+        }
+    }
+
+    /// the blend state set through the cache, if there is one
+    pub fn blend(&self) -> Option<BlendState> {
+        self.known.blend
+    }
+
+    pub fn depth(&self) -> Option<DepthState> {
+        self.known.depth
+    }
+
+    pub fn stencil(&self) -> Option<StencilState> {
+        self.known.stencil
+    }
+
+    pub fn scissor(&self) -> Option<ScissorState> {
+        self.known.scissor
+    }
+
+    #[track_caller]
+    pub fn use_program(&mut self, program: Option<glow::Program>) {
+        GlBindings::with(&self.gl, |bindings| bindings.use_program(program))
+    }
+
+    #[track_caller]
+    pub fn bind_vertex_array(&mut self, vertex_array: Option<glow::VertexArray>) {
+        GlBindings::with(&self.gl, |bindings| {
+            bindings.bind_vertex_array(vertex_array)
+        })
+    }
+
+    #[track_caller]
+    pub fn bind_buffer(&mut self, target: u32, buffer: Option<glow::Buffer>) {
+        GlBindings::with(&self.gl, |bindings| bindings.bind_buffer(target, buffer))
+    }
+
+    #[track_caller]
+    pub fn bind_texture(&mut self, unit: u32, target: u32, texture: Option<glow::Texture>) {
+        GlBindings::with(&self.gl, |bindings| {
+            bindings.bind_texture(unit, target, texture)
+        })
+    }
+
+    #[track_caller]
+    pub fn set_blend(&mut self, blend: BlendState) {
+        let known = self.known.blend;
+        if known == Some(blend) {
+            self.skipped += 1;
+            return;
+        }
+        let gl = &self.gl;
+        unsafe {
+            if known.map(|k| k.enabled) != Some(blend.enabled) {
+                toggle(gl, glow::BLEND, blend.enabled);
+            }
+            if known.map(|k| (k.src, k.dst)) != Some((blend.src, blend.dst)) {
+                gl.blend_func(blend.src, blend.dst);
+            }
+            if known.map(|k| k.equation) != Some(blend.equation) {
+                gl.blend_equation(blend.equation);
+            }
+        }
+        self.known.blend = Some(blend);
+        self.check_errors();
+    }
+
+    #[track_caller]
+    pub fn set_depth(&mut self, depth: DepthState) {
+        let known = self.known.depth;
+        if known == Some(depth) {
+            self.skipped += 1;
+            return;
+        }
+        let gl = &self.gl;
+        unsafe {
+            if known.map(|k| k.enabled) != Some(depth.enabled) {
+                toggle(gl, glow::DEPTH_TEST, depth.enabled);
+            }
+            if known.map(|k| k.func) != Some(depth.func) {
+                gl.depth_func(depth.func);
+            }
+            if known.map(|k| k.write) != Some(depth.write) {
+                gl.depth_mask(depth.write);
+            }
+        }
+        self.known.depth = Some(depth);
+        self.check_errors();
+    }
+
+    #[track_caller]
+    pub fn set_stencil(&mut self, stencil: StencilState) {
+        let known = self.known.stencil;
+        if known == Some(stencil) {
+            self.skipped += 1;
+            return;
+        }
+        let gl = &self.gl;
+        unsafe {
+            if known.map(|k| k.enabled) != Some(stencil.enabled) {
+                toggle(gl, glow::STENCIL_TEST, stencil.enabled);
+            }
+            let func = (stencil.func, stencil.reference, stencil.value_mask);
+            if known.map(|k| (k.func, k.reference, k.value_mask)) != Some(func) {
+                gl.stencil_func(stencil.func, stencil.reference, stencil.value_mask);
+            }
+            if known.map(|k| k.write_mask) != Some(stencil.write_mask) {
+                gl.stencil_mask(stencil.write_mask);
+            }
+            let ops = (stencil.fail, stencil.depth_fail, stencil.pass);
+            if known.map(|k| (k.fail, k.depth_fail, k.pass)) != Some(ops) {
+                gl.stencil_op(stencil.fail, stencil.depth_fail, stencil.pass);
+            }
+        }
+        self.known.stencil = Some(stencil);
+        self.check_errors();
+    }
+
+    #[track_caller]
+    pub fn set_scissor(&mut self, scissor: ScissorState) {
+        let known = self.known.scissor;
+        if known == Some(scissor) {
+            self.skipped += 1;
+            return;
+        }
+        let gl = &self.gl;
+        unsafe {
+            if known.map(|k| k.enabled) != Some(scissor.enabled) {
+                toggle(gl, glow::SCISSOR_TEST, scissor.enabled);
+            }
+            if known.map(|k| k.rect) != Some(scissor.rect) {
+                let [x, y, width, height] = scissor.rect;
+                gl.scissor(x, y, width, height);
+            }
+        }
+        self.known.scissor = Some(scissor);
+        self.check_errors();
+    }
+
+    /// logs GL errors raised since the last check, with the location of whoever called into the cache
+    #[track_caller]
+    fn check_errors(&self) {
+        if cfg!(debug_assertions) {
+            check_gl_errors(&self.gl, Location::caller());
+        }
+    }
+}
+
+/// # Description
+/// The program, vertex array, buffer and texture bindings of a context, so rebinding what is already
+/// bound never reaches the driver
+/// # Comments
+/// - the `ogl` wrappers bind through it, `GlStateCache` hands out the same bindings
+/// - unlike `GlStateCache::with(..)`, `Self::with(..)` can be called from anywhere except inside of
+///   itself, so wrappers can bind inside of `GlStateCache::with(..)`
+/// - deleting a bound object resets its bindings, so whatever deletes one has to tell the cache
+///   through `forget_*`, GL hands its name out again otherwise
+pub struct GlBindings {
+    gl: GlowGL,
+    program: Option<Option<glow::Program>>,
+    vertex_array: Option<Option<glow::VertexArray>>,
+    /// keyed by target
+    buffers: HashMap<u32, Option<glow::Buffer>>,
+    active_unit: Option<u32>,
+    /// keyed by `(unit, target)`
+    textures: HashMap<(u32, u32), Option<glow::Texture>>,
+    skipped: u64,
+}
+
+impl GlBindings {
+    fn new(gl: &GlowGL) -> Self {
+        Self {
+            gl: gl.clone(),
+            program: None,
+            vertex_array: None,
+            buffers: HashMap::new(),
+            active_unit: None,
+            textures: HashMap::new(),
+            skipped: 0,
+        }
+    }
+
+    /// ## Description
+    /// runs `f` with the bindings of `gl`'s context
+    /// ## Comments
+    /// - GL errors raised inside of `f` are logged with the location of the caller in debug builds
+    #[track_caller]
+    pub fn with<F, R>(gl: &GlowGL, f: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        let location = Location::caller();
+        SHARED_BINDINGS.with(|shared| {
+            let mut shared = shared.try_borrow_mut().unwrap_or_else(|_| {
+                panic!(
+                    "GlBindings::with(..) called inside of another one at {}",
+                    location
+                )
+            });
+            // forget contexts only kept alive by this list
+            shared.retain(|bindings| Arc::strong_count(&bindings.gl) > 1);
+            let index = match shared.iter().position(|b| Arc::ptr_eq(&b.gl, gl)) {
+                Some(index) => index,
+                None => {
+                    shared.push(Self::new(gl));
+                    shared.len() - 1
+                }
+            };
+            let result = f(&mut shared[index]);
+            if cfg!(debug_assertions) {
+                check_gl_errors(gl, location);
+            }
+            result
+        })
+    }
+
+    /// forgets every binding, the next bind of anything goes through to GL
+    pub fn invalidate(&mut self) {
+        let gl = self.gl.clone();
+        let skipped = self.skipped;
+        *self = Self::new(&gl);
+        self.skipped = skipped;
+    }
+
+    pub fn use_program(&mut self, program: Option<glow::Program>) {
+        if self.program == Some(program) {
+            self.skipped += 1;
+            return;
+        }
+        unsafe { self.gl.use_program(program) };
+        self.program = Some(program);
+    }
+
+    pub fn bind_vertex_array(&mut self, vertex_array: Option<glow::VertexArray>) {
+        if self.vertex_array == Some(vertex_array) {
+            self.skipped += 1;
+            return;
+        }
+        unsafe { self.gl.bind_vertex_array(vertex_array) };
+        self.vertex_array = Some(vertex_array);
+        // the element buffer binding is part of the vertex array
+        self.buffers.remove(&glow::ELEMENT_ARRAY_BUFFER);
+    }
+
+    pub fn bind_buffer(&mut self, target: u32, buffer: Option<glow::Buffer>) {
+        if self.buffers.get(&target) == Some(&buffer) {
+            self.skipped += 1;
+            return;
+        }
+        unsafe { self.gl.bind_buffer(target, buffer) };
+        self.buffers.insert(target, buffer);
+    }
+
+    /// `glBindBufferBase`, which binds `buffer` to the generic `target` binding as well
+    pub fn bind_buffer_base(&mut self, target: u32, index: u32, buffer: Option<glow::Buffer>) {
+        unsafe { self.gl.bind_buffer_base(target, index, buffer) };
+        self.buffers.insert(target, buffer);
+    }
+
+    /// makes `unit` the active texture unit and binds `texture` to it
+    pub fn bind_texture(&mut self, unit: u32, target: u32, texture: Option<glow::Texture>) {
+        if self.textures.get(&(unit, target)) == Some(&texture) {
+            self.skipped += 1;
+            return;
+        }
+        self.activate_unit(unit);
+        unsafe { self.gl.bind_texture(target, texture) };
+        self.textures.insert((unit, target), texture);
+    }
+
+    /// ## Description
+    /// binds `texture` to whatever unit is active, for uploads and parameter changes that don't
+    /// care about the unit
+    pub fn bind_texture_for_edit(&mut self, target: u32, texture: Option<glow::Texture>) {
+        let unit = self.active_unit.unwrap_or(0);
+        self.bind_texture(unit, target, texture);
+    }
+
+    fn activate_unit(&mut self, unit: u32) {
+        if self.active_unit != Some(unit) {
+            unsafe { self.gl.active_texture(glow::TEXTURE0 + unit) };
+            self.active_unit = Some(unit);
+        }
+    }
+
+    /// call it before deleting `program`
+    pub fn forget_program(&mut self, program: glow::Program) {
+        if self.program == Some(Some(program)) {
+            self.program = None;
+        }
+    }
+
+    /// call it before deleting `vertex_array`
+    pub fn forget_vertex_array(&mut self, vertex_array: glow::VertexArray) {
+        if self.vertex_array == Some(Some(vertex_array)) {
+            self.vertex_array = None;
+            self.buffers.remove(&glow::ELEMENT_ARRAY_BUFFER);
+        }
+    }
+
+    /// call it before deleting `buffer`
+    pub fn forget_buffer(&mut self, buffer: glow::Buffer) {
+        self.buffers.retain(|_, bound| *bound != Some(buffer));
+    }
+
+    /// call it before deleting `texture`
+    pub fn forget_texture(&mut self, texture: glow::Texture) {
+        self.textures.retain(|_, bound| *bound != Some(texture));
+    }
+}
+
+unsafe fn toggle(gl: &GlowGL, capability: u32, enabled: bool) {
+    if enabled {
+        gl.enable(capability);
+    } else {
+        gl.disable(capability);
+    }
+}
+
+fn error_name(error: u32) -> &'static str {
+    match error {
+        glow::INVALID_ENUM => "GL_INVALID_ENUM",
+        glow::INVALID_VALUE => "GL_INVALID_VALUE",
+        glow::INVALID_OPERATION => "GL_INVALID_OPERATION",
+        glow::INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION",
+        glow::OUT_OF_MEMORY => "GL_OUT_OF_MEMORY",
+        glow::STACK_UNDERFLOW => "GL_STACK_UNDERFLOW",
+        glow::STACK_OVERFLOW => "GL_STACK_OVERFLOW",
+        _ => "unknown GL error",
+    }
+}
+
+/// ## Description
+/// drains `glGetError` and logs every error along with `location`
+/// ## Returns
+/// how many errors there were
+pub fn check_gl_errors(gl: &GlowGL, location: &Location) -> usize {
+    let mut count = 0;
+    loop {
+        let error = unsafe { gl.get_error() };
+        // a lost context keeps returning errors, so don't loop forever
+        if error == glow::NO_ERROR || count == 16 {
+            return count;
+        }
+        console_log!("{} (0x{:x}) at {}\n", error_name(error), error, location);
+        count += 1;
+    }
+}
+
+/// ## Description
+/// like `check_gl_errors(..)` but only in debug builds, reporting the caller as the location
+/// ## Comments
+/// - put it after raw `gl.*` calls that don't go through `GlStateCache`
+#[track_caller]
+pub fn debug_check_gl(gl: &GlowGL) {
+    if cfg!(debug_assertions) {
+        check_gl_errors(gl, Location::caller());
+    }
+}
+
+/// ## Description
+/// turns on `KHR_debug` output, so the driver logs errors and warnings through `console_log!` as they
+/// happen, with a message that is usually a lot more specific than `glGetError`
+/// ## Returns
+/// `false` when the context doesn't support it (WebGL never does)
+pub fn enable_debug_output(gl: &GlowGL) -> bool {
+    if cfg!(target_family = "wasm") || !has_khr_debug(gl) {
+        return false;
+    }
+    unsafe {
+        gl.enable(glow::DEBUG_OUTPUT);
+        // synchronous, so the messages show up right after the call that caused them
+        gl.enable(glow::DEBUG_OUTPUT_SYNCHRONOUS);
+        gl.debug_message_control(glow::DONT_CARE, glow::DONT_CARE, glow::DONT_CARE, &[], true);
+        // glow 0.6 hands the driver a pointer to the callback that dies when this call returns, that
+        // is only sound for a zero sized callback like a plain `fn`
+        gl.debug_message_callback(log_debug_message);
+    }
+    true
+}
+
+fn has_khr_debug(gl: &GlowGL) -> bool {
+    unsafe {
+        let version = (
+            gl.get_parameter_i32(glow::MAJOR_VERSION),
+            gl.get_parameter_i32(glow::MINOR_VERSION),
+        );
+        if version >= (4, 3) {
+            return true;
+        }
+        let num_extensions = gl.get_parameter_i32(glow::NUM_EXTENSIONS).max(0) as u32;
+        let has_extension = (0..num_extensions).any(|index| {
+            gl.get_parameter_indexed_string(glow::EXTENSIONS, index) == "GL_KHR_debug"
+        });
+        // contexts older than 3.0 raise an error for the queries above
+        gl.get_error();
+        has_extension
+    }
+}
+
+fn log_debug_message(_source: u32, kind: u32, id: u32, severity: u32, message: &str) {
+    // notifications are mostly "buffer will use video memory" chatter
+    if severity == glow::DEBUG_SEVERITY_NOTIFICATION {
+        return;
+    }
+    let kind = match kind {
+        glow::DEBUG_TYPE_ERROR => "error",
+        glow::DEBUG_TYPE_DEPRECATED_BEHAVIOR => "deprecated",
+        glow::DEBUG_TYPE_UNDEFINED_BEHAVIOR => "undefined behaviour",
+        glow::DEBUG_TYPE_PERFORMANCE => "performance",
+        glow::DEBUG_TYPE_PORTABILITY => "portability",
+        _ => "other",
+    };
+    console_log!("GL {} [{}]: {}\n", kind, id, message);
+}
+
+#[cfg(test)]
+thread_local! {
+    /// every call that reached `fake_gl()`'s driver
+    static FAKE_GL_CALLS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+/// ## Description
+/// a context whose driver only records which state setting functions got called
+/// ## Comments
+/// - anything else panics with glow's "function not loaded" message
+#[cfg(test)]
+fn fake_gl() -> GlowGL {
+    use std::os::raw::c_void;
+
+    extern "system" fn get_integer(_parameter: u32, value: *mut i32) {
+        unsafe { *value = 0 };
+    }
+    extern "system" fn get_error() -> u32 {
+        glow::NO_ERROR
+    }
+    macro_rules! recorder {
+        ($name:literal, $($arg:ident: $ty:ty),*) => {{
+            extern "system" fn record($(_: $ty),*) {
+                FAKE_GL_CALLS.with(|calls| calls.borrow_mut().push($name));
+            }
+            record as *const c_void
+        }};
+    }
+
+    let gl = unsafe {
+        glow::Context::from_loader_function(|name| match name {
+            "glGetIntegerv" => get_integer as *const c_void,
+            "glGetError" => get_error as *const c_void,
+            "glEnable" => recorder!("enable", cap: u32),
+            "glDisable" => recorder!("disable", cap: u32),
+            "glBlendFunc" => recorder!("blend_func", src: u32, dst: u32),
+            "glBlendEquation" => recorder!("blend_equation", mode: u32),
+            "glDepthFunc" => recorder!("depth_func", func: u32),
+            "glDepthMask" => recorder!("depth_mask", flag: u8),
+            "glUseProgram" => recorder!("use_program", program: u32),
+            "glBindVertexArray" => recorder!("bind_vertex_array", array: u32),
+            "glBindBuffer" => recorder!("bind_buffer", target: u32, buffer: u32),
+            "glActiveTexture" => recorder!("active_texture", unit: u32),
+            "glBindTexture" => recorder!("bind_texture", target: u32, texture: u32),
+            _ => std::ptr::null(),
+        })
+    };
+    Arc::new(Box::new(gl))
+}
+
+#[cfg(test)]
+fn take_fake_gl_calls() -> Vec<&'static str> {
+    FAKE_GL_CALLS.with(|calls| std::mem::take(&mut *calls.borrow_mut()))
+}
+
+#[test]
+fn state_cache_skips_redundant_changes() {
+    let gl = fake_gl();
+    let mut state = GlStateCache::new(&gl);
+
+    // unknown state is written in full
+    state.set_blend(BlendState::ALPHA);
+    assert_eq!(
+        take_fake_gl_calls(),
+        ["enable", "blend_func", "blend_equation"]
+    );
+
+    state.set_blend(BlendState::ALPHA);
+    assert!(take_fake_gl_calls().is_empty());
+    assert_eq!(state.skipped_calls(), 1);
+
+    // only the parts that differ are sent
+    state.set_blend(BlendState::ADDITIVE);
+    assert_eq!(take_fake_gl_calls(), ["blend_func"]);
+
+    state.invalidate();
+    state.set_blend(BlendState::ADDITIVE);
+    assert_eq!(take_fake_gl_calls().len(), 3);
+}
+
+#[test]
+fn state_cache_skips_redundant_binds() {
+    let gl = fake_gl();
+    let mut state = GlStateCache::new(&gl);
+
+    state.use_program(Some(1));
+    state.use_program(Some(1));
+    state.bind_texture(0, glow::TEXTURE_2D, Some(2));
+    state.bind_texture(0, glow::TEXTURE_2D, Some(2));
+    // a different unit needs its own glActiveTexture
+    state.bind_texture(1, glow::TEXTURE_2D, Some(2));
+    assert_eq!(
+        take_fake_gl_calls(),
+        [
+            "use_program",
+            "active_texture",
+            "bind_texture",
+            "active_texture",
+            "bind_texture"
+        ]
+    );
+    assert_eq!(state.skipped_calls(), 2);
+
+    // binding a vertex array swaps out the element buffer
+    state.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(3));
+    state.bind_vertex_array(Some(4));
+    state.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(3));
+    assert_eq!(
+        take_fake_gl_calls(),
+        ["bind_buffer", "bind_vertex_array", "bind_buffer"]
+    );
+
+    // the wrappers bind through the same cache, even while it's borrowed
+    GlStateCache::with(&gl, |state| {
+        state.use_program(Some(5));
+        GlBindings::with(&gl, |bindings| bindings.use_program(Some(5)));
+    });
+    assert_eq!(take_fake_gl_calls(), ["use_program"]);
+
+    // deleted names get handed out again, so they have to be bound again
+    GlBindings::with(&gl, |bindings| bindings.forget_program(5));
+    state.use_program(Some(5));
+    state.invalidate();
+    state.bind_texture(1, glow::TEXTURE_2D, Some(2));
+    assert_eq!(
+        take_fake_gl_calls(),
+        ["use_program", "active_texture", "bind_texture"]
+    );
+}
+
+#[test]
+fn state_cache_pop_restores_pushed_state() {
+    let gl = fake_gl();
+    let mut state = GlStateCache::new(&gl);
+    state.set_blend(BlendState::ALPHA);
+    take_fake_gl_calls();
+
+    state.push();
+    state.set_blend(BlendState::ADDITIVE);
+    state.set_depth(DepthState::LESS_EQUAL);
+    state.push();
+    state.set_blend(BlendState::DISABLED);
+    state.pop();
+    assert_eq!(state.blend(), Some(BlendState::ADDITIVE));
+    assert_eq!(state.depth(), Some(DepthState::LESS_EQUAL));
+    take_fake_gl_calls();
+
+    state.pop();
+    assert_eq!(state.blend(), Some(BlendState::ALPHA));
+    // depth was unknown when pushed, so it goes back to GL's default
+    assert_eq!(state.depth(), Some(DepthState::DISABLED));
+    assert_eq!(
+        take_fake_gl_calls(),
+        ["blend_func", "disable", "depth_func"]
+    );
+
+    // an unmatched pop only gets logged
+    state.pop();
+    assert_eq!(state.blend(), Some(BlendState::ALPHA));
+
+    // a blend unknown when pushed doesn't stay enabled, like after the GUI and text renderers
+    let mut state = GlStateCache::new(&gl);
+    state.push();
+    state.set_blend(BlendState::ALPHA);
+    state.pop();
+    assert_eq!(state.blend(), Some(BlendState::DISABLED));
+    assert_eq!(
+        take_fake_gl_calls(),
+        [
+            "enable",
+            "blend_func",
+            "blend_equation",
+            "disable",
+            "blend_func"
+        ]
+    );
+
+    // state nobody touched stays unknown
+    state.invalidate();
+    state.push();
+    state.pop();
+    assert_eq!(state.blend(), None);
+    assert!(take_fake_gl_calls().is_empty());
+}
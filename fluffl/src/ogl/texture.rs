@@ -1,5 +1,143 @@
 use super::*;
-use crate::*;
+use crate::{codecs::image::DecodedImage, *};
+
+/// # Description
+/// How a texture gets sampled
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TextureFilter {
+    Nearest,
+    Linear,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TextureWrap {
+    ClampToEdge,
+    Repeat,
+    MirroredRepeat,
+}
+
+impl TextureWrap {
+    fn gl_enum(self) -> i32 {
+        let wrap = match self {
+            Self::ClampToEdge => glow::CLAMP_TO_EDGE,
+            Self::Repeat => glow::REPEAT,
+            Self::MirroredRepeat => glow::MIRRORED_REPEAT,
+        };
+        wrap as i32
+    }
+}
+
+/// # Description
+/// Whether texels are stored as sRGB (decoded to linear when sampled) or as plain linear values
+/// # Comments
+/// - colour textures authored in an image editor usually want `Srgb`, data textures (normals and the
+///   like) want `Linear`
+/// - WebGL1 has no sRGB formats without `EXT_sRGB`
+/// - GLES3 and WebGL2 can't generate mipmaps for `SRGB8`, so sRGB textures uploaded as `RGB` don't
+///   get any, upload `RGBA` to have both
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ColorSpace {
+    Linear,
+    Srgb,
+}
+
+/// # Description
+/// Filtering, wrapping and anisotropy of a texture
+/// # Comments
+/// - the default (clamped, linear, no mipmaps) is what textures got before this existed
+/// - a `mipmap_filter` makes `build()` generate mipmaps
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SamplerState {
+    pub min_filter: TextureFilter,
+    pub mag_filter: TextureFilter,
+    /// how to blend between mip levels, `None` samples level 0 only
+    pub mipmap_filter: Option<TextureFilter>,
+    pub wrap_s: TextureWrap,
+    pub wrap_t: TextureWrap,
+    /// `1.0` turns anisotropic filtering off, gets clamped to what the driver supports
+    pub anisotropy: f32,
+}
+
+impl Default for SamplerState {
+    fn default() -> Self {
+        Self {
+            min_filter: TextureFilter::Linear,
+            mag_filter: TextureFilter::Linear,
+            mipmap_filter: None,
+            wrap_s: TextureWrap::ClampToEdge,
+            wrap_t: TextureWrap::ClampToEdge,
+            anisotropy: 1.0,
+        }
+    }
+}
+
+impl SamplerState {
+    /// sets both the min and mag filter
+    pub fn with_filter(mut self, filter: TextureFilter) -> Self {
+        self.min_filter = filter;
+        self.mag_filter = filter;
+        self
+    }
+
+    pub fn with_mipmaps(mut self, filter: TextureFilter) -> Self {
+        self.mipmap_filter = Some(filter);
+        self
+    }
+
+    /// sets both wrap directions
+    pub fn with_wrap(mut self, wrap: TextureWrap) -> Self {
+        self.wrap_s = wrap;
+        self.wrap_t = wrap;
+        self
+    }
+
+    pub fn with_anisotropy(mut self, anisotropy: f32) -> Self {
+        self.anisotropy = anisotropy;
+        self
+    }
+
+    fn min_filter_enum(&self) -> u32 {
+        use TextureFilter::*;
+        match (self.min_filter, self.mipmap_filter) {
+            (Nearest, None) => glow::NEAREST,
+            (Linear, None) => glow::LINEAR,
+            (Nearest, Some(Nearest)) => glow::NEAREST_MIPMAP_NEAREST,
+            (Nearest, Some(Linear)) => glow::NEAREST_MIPMAP_LINEAR,
+            (Linear, Some(Nearest)) => glow::LINEAR_MIPMAP_NEAREST,
+            (Linear, Some(Linear)) => glow::LINEAR_MIPMAP_LINEAR,
+        }
+    }
+
+    /// ## Description
+    /// sets the parameters of the texture currently bound to `target`
+    /// ## Comments
+    /// - anisotropy is only applied where `EXT_texture_filter_anisotropic` is around, otherwise
+    ///   the GL error it raises gets swallowed
+    pub fn apply(&self, gl: &GlowGL, target: u32) {
+        let mag_filter = match self.mag_filter {
+            TextureFilter::Nearest => glow::NEAREST,
+            TextureFilter::Linear => glow::LINEAR,
+        };
+        unsafe {
+            gl.tex_parameter_i32(target, glow::TEXTURE_WRAP_S, self.wrap_s.gl_enum());
+            gl.tex_parameter_i32(target, glow::TEXTURE_WRAP_T, self.wrap_t.gl_enum());
+            gl.tex_parameter_i32(
+                target,
+                glow::TEXTURE_MIN_FILTER,
+                self.min_filter_enum() as i32,
+            );
+            gl.tex_parameter_i32(target, glow::TEXTURE_MAG_FILTER, mag_filter as i32);
+            if self.anisotropy > 1.0 {
+                let max_anisotropy = gl.get_parameter_i32(glow::MAX_TEXTURE_MAX_ANISOTROPY_EXT);
+                if max_anisotropy > 1 {
+                    let anisotropy = self.anisotropy.min(max_anisotropy as f32);
+                    gl.tex_parameter_f32(target, glow::TEXTURE_MAX_ANISOTROPY_EXT, anisotropy);
+                }
+                gl.get_error();
+            }
+        }
+    }
+}
 
 pub struct OglTexture {
     inner: Box<dyn HasTextureObj>,
@@ -36,6 +174,11 @@ pub trait HasTextureObj {
     fn get_info(&self) -> &TextureInfo;
     fn copy_image(&self, width: u32, height: u32, src_data: &[u8]);
     fn get_info_mut(&mut self) -> &mut TextureInfo;
+    /// the raw GL texture, for attaching it to framebuffers and the like
+    fn texture_id(&self) -> Option<glow::Texture>;
+    fn set_sampler(&self, sampler: &SamplerState);
+    /// rebuilds the mip chain from level 0, call it after `copy_image(..)` on mipmapped textures
+    fn generate_mipmaps(&self);
 }
 
 impl<T> HasTextureObj for TextureObj<T>
@@ -43,26 +186,27 @@ where
     T: Copy + Sized + Default,
 {
     fn bind(&self, texture_unit: u32, location: Option<&UniformLocation>) {
+        GlBindings::with(&self.gl, |bindings| {
+            bindings.bind_texture(texture_unit, self.info.target, self.obj_id)
+        });
         unsafe {
-            let gl = &self.gl;
-            gl.active_texture(glow::TEXTURE0 + texture_unit);
-            gl.bind_texture(self.info.target, self.obj_id);
-            gl.uniform_1_i32(location, texture_unit as i32);
+            self.gl.uniform_1_i32(location, texture_unit as i32);
         }
     }
 
     fn unbind(&self) {
-        unsafe {
-            let gl = &self.gl;
-            gl.bind_texture(self.info.target, None);
-        }
+        GlBindings::with(&self.gl, |bindings| {
+            bindings.bind_texture_for_edit(self.info.target, None)
+        });
     }
 
     fn set_param_i32(&self, param: u32, val: i32) {
+        let target = self.info.target;
+        GlBindings::with(&self.gl, |bindings| {
+            bindings.bind_texture_for_edit(target, self.obj_id)
+        });
         unsafe {
             let gl = &self.gl;
-            let target = self.info.target;
-            gl.bind_texture(target, self.obj_id);
             gl.tex_parameter_i32(target, param, val);
         }
     }
@@ -75,7 +219,9 @@ where
             let comp_type = self.info.comp_type;
             let internal_format = self.info.internal_format as i32;
             // I dont assume the texture is bound
-            gl.bind_texture(target, self.obj_id);
+            GlBindings::with(gl, |bindings| {
+                bindings.bind_texture_for_edit(target, self.obj_id)
+            });
             gl.tex_image_2d(
                 target,
                 0,
@@ -96,6 +242,23 @@ where
     fn get_info_mut(&mut self) -> &mut TextureInfo {
         &mut self.info
     }
+    fn texture_id(&self) -> Option<glow::Texture> {
+        self.obj_id
+    }
+    fn set_sampler(&self, sampler: &SamplerState) {
+        GlBindings::with(&self.gl, |bindings| {
+            bindings.bind_texture_for_edit(self.info.target, self.obj_id)
+        });
+        sampler.apply(&self.gl, self.info.target);
+    }
+    fn generate_mipmaps(&self) {
+        GlBindings::with(&self.gl, |bindings| {
+            bindings.bind_texture_for_edit(self.info.target, self.obj_id)
+        });
+        unsafe {
+            self.gl.generate_mipmap(self.info.target);
+        }
+    }
 }
 
 pub trait HasTextureBuilder<'a> {
@@ -109,11 +272,19 @@ pub trait HasTextureBuilder<'a> {
     fn with_internal_format(self, fmt: u32) -> Self;
     fn with_pixels_slice(self, cb: &'a [u8]) -> Self;
     fn with_format(self, fmt: u32) -> Self;
+    /// defaults to `SamplerState::default()`
+    fn with_sampler(self, sampler: SamplerState) -> Self;
+    /// picks a sized internal format matching `format` at build time, overriding `with_internal_format`
+    fn with_color_space(self, color_space: ColorSpace) -> Self;
+    /// sets the size, the RGBA8 format and the pixels of a decoded image
+    fn with_image(self, image: &'a DecodedImage) -> Self;
     fn build(self) -> Self::Inner;
 }
 
 pub struct TextureBuilder<'a, T> {
     pixel_slice: Option<&'a [u8]>,
+    sampler: SamplerState,
+    color_space: Option<ColorSpace>,
     tex_obj: TextureObj<T>,
 }
 
@@ -140,6 +311,16 @@ pub struct TextureInfo {
     comp_type: u32,
 }
 
+impl TextureInfo {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
 impl Default for TextureInfo {
     fn default() -> Self {
         Self {
@@ -164,6 +345,7 @@ pub struct TextureObj<T> {
 impl<T> Drop for TextureObj<T> {
     fn drop(&mut self) {
         if let Some(id) = self.obj_id {
+            GlBindings::with(&self.gl, |bindings| bindings.forget_texture(id));
             unsafe {
                 self.gl.delete_texture(id);
             }
@@ -182,6 +364,8 @@ impl<'a, T> TextureObj<T> {
         OglIncomplete::new(TextureBuilder {
             tex_obj,
             pixel_slice: None,
+            sampler: SamplerState::default(),
+            color_space: None,
         })
     }
 }
@@ -233,14 +417,48 @@ where
         self
     }
 
+    fn with_sampler(mut self, sampler: SamplerState) -> Self {
+        self.inner.sampler = sampler;
+        self
+    }
+
+    fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.inner.color_space = Some(color_space);
+        self
+    }
+
+    fn with_image(self, image: &'a DecodedImage) -> Self {
+        self.with_width(image.width)
+            .with_height(image.height)
+            .with_format(glow::RGBA)
+            .with_component_type(glow::UNSIGNED_BYTE)
+            .with_pixels_slice(&image.pixels)
+    }
+
     fn build(self) -> Self::Inner {
         unsafe {
             let mut tex = self.inner;
             let gl = tex.gl.clone();
 
+            match (tex.color_space, tex.info.format) {
+                (Some(ColorSpace::Srgb), glow::RGBA) => {
+                    tex.info.internal_format = glow::SRGB8_ALPHA8
+                }
+                (Some(ColorSpace::Srgb), glow::RGB) => {
+                    tex.info.internal_format = glow::SRGB8;
+                    // SRGB8 isn't color-renderable on GLES3/WebGL2, which glGenerateMipmap requires
+                    tex.sampler.mipmap_filter = None;
+                }
+                (Some(ColorSpace::Linear), glow::RGBA) => tex.info.internal_format = glow::RGBA8,
+                (Some(ColorSpace::Linear), glow::RGB) => tex.info.internal_format = glow::RGB8,
+                _ => (),
+            }
+
             tex.obj_id = Some(gl.create_texture().unwrap());
 
-            gl.bind_texture(tex.info.target, tex.obj_id);
+            GlBindings::with(&gl, |bindings| {
+                bindings.bind_texture_for_edit(tex.info.target, tex.obj_id)
+            });
 
             let pixels = &tex.pixel_vec;
 
@@ -286,11 +504,11 @@ where
                     );
                 }
 
-                //default texture parameters are here (can be changed later on obv)
-                gl.tex_parameter_i32(TEXTURE_2D, glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE as i32);
-                gl.tex_parameter_i32(TEXTURE_2D, glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE as i32);
-                gl.tex_parameter_i32(TEXTURE_2D, glow::TEXTURE_MIN_FILTER, glow::LINEAR as i32);
-                gl.tex_parameter_i32(TEXTURE_2D, glow::TEXTURE_MAG_FILTER, glow::LINEAR as i32);
+                //sampler parameters are here (can be changed later on with set_sampler)
+                tex.sampler.apply(&gl, TEXTURE_2D);
+                if tex.sampler.mipmap_filter.is_some() {
+                    gl.generate_mipmap(TEXTURE_2D);
+                }
             }
 
             tex.tex_obj
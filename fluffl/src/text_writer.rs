@@ -61,7 +61,7 @@ static DEFAULT_PROGRAM: &str = r"
             float dist = page.w; 
             vec2 grad = vec2( dFdx(dist), dFdy(dist));  
             float grad_mag = length(grad)*1.0;
-            color = vec4(1.)*smoothstep(0.5-grad_mag,0.5+grad_mag,dist);
+            color = text_color*smoothstep(0.5-grad_mag,0.5+grad_mag,dist);
         }
     #endif
 ";
@@ -157,12 +157,16 @@ pub struct TextWriter {
     atlas: Option<HieroAtlas>,
     projection_mat_loc: Option<UniformLocation>,
     model_loc: Option<UniformLocation>,
+    text_color_loc: Option<UniformLocation>,
     page_loc: Option<UniformLocation>,
     page_texture: Option<OglTexture>,
     whitespace_len: Option<f32>,
     page_history: [usize; 4],
     page_index: usize,
 
+    /// what text gets drawn with, white unless changed with `Self::set_text_color(..)`
+    text_color: Vec4<f32>,
+
     /// horizontal scaling for un-aspect-ratio-corrected text found in `Self::draw_text_line(..)` and `Self::`
     horizontal_scale_factor: f32,
 
@@ -220,6 +224,7 @@ impl TextWriter {
             let proj_loc = gl.get_uniform_location(renderer.prog(), "projection");
             let page_loc = gl.get_uniform_location(renderer.prog(), "page");
             let model_loc = gl.get_uniform_location(renderer.prog(), "model");
+            let text_color_loc = gl.get_uniform_location(renderer.prog(), "text_color");
 
             OglIncomplete::new(Self {
                 gl: gl.clone(),
@@ -227,6 +232,7 @@ impl TextWriter {
                 _atlas_table: HashMap::new(),
                 atlas: None,
                 model_loc,
+                text_color_loc,
                 projection_mat_loc: proj_loc,
                 text_geometry: array,
                 page_loc,
@@ -234,6 +240,7 @@ impl TextWriter {
                 whitespace_len: None,
                 page_history: [99999; 4],
                 page_index: 0,
+                text_color: Vec4::from([1.0; 4]),
                 horizontal_scale_factor: 1.0,
                 global_dy_b: None,
                 global_dy_t: None,
@@ -256,6 +263,18 @@ impl TextWriter {
         pf
     }
 
+    pub fn text_color(&self) -> Vec4<f32> {
+        self.text_color
+    }
+
+    /// sets the color every following line is drawn with, returns the previous color
+    pub fn set_text_color<T>(&mut self, color: T) -> Vec4<f32>
+    where
+        Vec4<f32>: From<T>,
+    {
+        std::mem::replace(&mut self.text_color, Vec4::from(color))
+    }
+
     /// # Description
     /// Calculates a tight bounding box of the text, but doesn't actually draw anything
     /// # Parameters
@@ -430,10 +449,12 @@ impl TextWriter {
             }
         }
 
+        //enable blending here, the old blend state comes back once the text is drawn
+        GlStateCache::with(gl, |state| {
+            state.push();
+            state.set_blend(BlendState::ALPHA);
+        });
         unsafe {
-            //enable blending here
-            gl.enable(glow::BLEND);
-            gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
             //make uniforms are up-to-date
             gl.uniform_matrix_4_f32_slice(
                 self.projection_mat_loc.as_ref(),
@@ -441,6 +462,14 @@ impl TextWriter {
                 proj_mat.as_slice(),
             );
             gl.uniform_matrix_4_f32_slice(self.model_loc.as_ref(), true, resize_matrix.as_slice());
+            let color = self.text_color;
+            gl.uniform_4_f32(
+                self.text_color_loc.as_ref(),
+                color[0],
+                color[1],
+                color[2],
+                color[3],
+            );
         }
 
         for (k, character) in text.char_indices() {
@@ -491,10 +520,7 @@ impl TextWriter {
             }
         }
 
-        unsafe {
-            //disable blending before exiting
-            gl.disable(glow::BLEND);
-        }
+        GlStateCache::with(gl, |state| state.pop());
     }
 
     fn decode_page(&mut self, new_page: usize) {
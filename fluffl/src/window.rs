@@ -5,7 +5,17 @@ use std::{
     sync::Arc,
 };
 
+pub mod dropped_file;
 pub mod event_util;
+pub mod gamepad;
+pub mod gesture;
+#[cfg(all(
+    feature = "glutin",
+    not(feature = "sdl2"),
+    not(all(target_family = "wasm", not(target_os = "wasi")))
+))]
+pub mod headless;
+pub mod input_map;
 pub mod touch_tracker;
 mod window_backends;
 
@@ -13,6 +23,8 @@ use crate::{audio::FlufflAudioContext, Error, GlowGL};
 use serde_json::{Map, Value};
 
 pub use event_util::FlufflEvent;
+// the headless backend tracks no touches
+#[cfg_attr(feature = "headless", allow(unused_imports))]
 use touch_tracker::*;
 pub use window_backends::*;
 
@@ -130,6 +142,16 @@ pub trait HasFlufflWindow: Sized {
     ///         - configures window to be resizable if `true` else the window stays fixed
     ///         - by default this setting is assumed to be false
     ///     - value type: `bool`
+    /// - `vsync`
+    ///     - description:
+    ///         - syncs buffer swaps to the display's refresh rate if `true`
+    ///         - by default this setting is assumed to be `true`
+    ///     - value type: `bool`
+    /// - `gl_debug`
+    ///     - description:
+    ///         - asks for a debug context and logs `KHR_debug` messages if `true` (desktop only)
+    ///         - by default this setting is assumed to be `false`
+    ///     - value type: `bool`
     fn init(config: &str) -> Result<Self, Error>;
     /// returns the window event queue
     fn get_events(&mut self) -> &mut FlufflEvent;
@@ -138,6 +160,7 @@ pub trait HasFlufflWindow: Sized {
     /// Exposes the glow api to user
     /// # Comments
     /// - make sure you `use fluffl::{window::{ ... , glow::*, ... }};` in order to actually get access to the interface functions
+    /// - with several windows open this is the context of `current_window()`
     fn gl(&self) -> GlowGL;
 
     /// Returns a hook to audio functions
@@ -159,6 +182,211 @@ pub trait HasFlufflWindow: Sized {
     /// - If the window is already in the desired state the function will do nothing
     fn set_fullscreen(&mut self, go_fullscreen: bool);
 
+    /// # Description
+    /// The state of every connected gamepad, up to date with every event collected so far
+    fn gamepads(&self) -> &gamepad::Gamepads;
+
+    /// # Description
+    /// Makes a gamepad vibrate
+    /// # Parameters
+    /// - `low_frequency`,`high_frequency`
+    ///     - strength of the low/high frequency motors in `[0,1]`
+    /// - `duration_ms`
+    ///     - how long the effect lasts, a new call replaces the previous effect
+    /// # Comments
+    /// - returns `false` if the gamepad isn't connected or the platform/device can't rumble
+    fn rumble(
+        &mut self,
+        id: event_util::GamepadId,
+        low_frequency: f32,
+        high_frequency: f32,
+        duration_ms: u32,
+    ) -> bool;
+
+    /// # Description
+    /// The files referenced by `EventKind::FileDropped` events
+    fn dropped_files(&self) -> &dropped_file::DroppedFiles;
+
+    /// # Description
+    /// Use this to take files out once they've been handled, otherwise they're kept around forever
+    fn dropped_files_mut(&mut self) -> &mut dropped_file::DroppedFiles;
+
+    /// # Description
+    /// The runtime settings of the window, as last set through this trait
+    /// # Comments
+    /// - most platforms can't be queried for these, so the backends keep track of them instead
+    fn attributes(&self) -> &WindowAttributes;
+
+    fn title(&self) -> &str {
+        &self.attributes().title
+    }
+
+    /// # Description
+    /// Sets the window title
+    /// # Comments
+    /// - sets the document title on the web
+    fn set_title(&mut self, title: &str);
+
+    /// # Description
+    /// Resizes the drawable area of the window to `width`x`height` pixels
+    /// # Comments
+    /// - resizes the canvas on the web
+    fn set_size(&mut self, width: u32, height: u32) -> Result<(), Error>;
+
+    /// # Description
+    /// Returns the position of the window's top left corner on the desktop
+    /// # Comments
+    /// - returns the position of the canvas on the page on the web
+    fn position(&self) -> Result<(i32, i32), Error>;
+
+    /// # Description
+    /// Moves the window's top left corner to `(x,y)` on the desktop
+    /// # Comments
+    /// - not supported on the web
+    fn set_position(&mut self, x: i32, y: i32) -> Result<(), Error>;
+
+    /// # Description
+    /// Keeps the user from resizing the window below `size`, `None` removes the limit
+    /// # Comments
+    /// - not supported on the web
+    fn set_min_size(&mut self, size: Option<(u32, u32)>) -> Result<(), Error>;
+
+    /// # Description
+    /// Keeps the user from resizing the window above `size`, `None` removes the limit
+    /// # Comments
+    /// - not supported on the web
+    fn set_max_size(&mut self, size: Option<(u32, u32)>) -> Result<(), Error>;
+
+    fn vsync(&self) -> bool {
+        self.attributes().vsync
+    }
+
+    /// # Description
+    /// Turns vsync on/off
+    /// # Comments
+    /// - glutin picks the swap interval when the context is created, so use the `vsync` config tag there
+    /// - the web always syncs to the display, since frames are driven by `requestAnimationFrame`
+    fn set_vsync(&mut self, enabled: bool) -> Result<(), Error>;
+
+    fn cursor_visible(&self) -> bool {
+        self.attributes().cursor_visible
+    }
+
+    /// # Description
+    /// Shows/hides the mouse cursor while it hovers over the window
+    fn set_cursor_visible(&mut self, visible: bool);
+
+    fn cursor_icon(&self) -> CursorIcon {
+        self.attributes().cursor_icon
+    }
+
+    /// # Description
+    /// Changes the shape of the mouse cursor while it hovers over the window
+    fn set_cursor_icon(&mut self, icon: CursorIcon);
+
+    fn cursor_grabbed(&self) -> bool {
+        self.attributes().cursor_grabbed
+    }
+
+    /// # Description
+    /// Keeps the mouse cursor from leaving the window
+    /// # Comments
+    /// - uses pointer lock on the web, which browsers only grant in response to a click or key press
+    /// - on the web `Ok` only means the lock was requested, if the browser refuses it or the user
+    ///   releases it with escape, `Self::cursor_grabbed()` goes back to `false` a frame or so later
+    fn set_cursor_grab(&mut self, grab: bool) -> Result<(), Error>;
+
+    fn relative_mouse_mode(&self) -> bool {
+        self.attributes().relative_mouse_mode
+    }
+
+    /// # Description
+    /// Hides and locks the cursor in place, `MouseMove` events keep reporting motion through `dx`/`dy`
+    /// # Comments
+    /// - this is what first person cameras want
+    /// - `x`/`y` of `MouseMove` events stop changing while this is enabled
+    /// - uses pointer lock on the web, which browsers only grant in response to a click or key press
+    /// - on the web `Ok` only means the lock was requested, if the browser refuses it or the user
+    ///   releases it with escape, `Self::relative_mouse_mode()` goes back to `false` a frame or so later
+    fn set_relative_mouse_mode(&mut self, enabled: bool) -> Result<(), Error>;
+
+    /// # Description
+    /// The ratio between physical pixels and logical (DPI independent) pixels
+    fn scale_factor(&self) -> f64;
+
+    /// # Description
+    /// Opens another window whose GL context shares textures, buffers and shaders with the main window
+    /// # Parameters
+    /// - `config` - the same format `init(..)` takes
+    /// # Returns
+    /// the id of the window, its events get queued under that id in `FlufflEvent`
+    /// (see `FlufflEvent::flush_window_iter_mut(..)`)
+    /// # Comments
+    /// - only supported on desktop
+    /// - vertex arrays and framebuffers are not shared between contexts, create those per window
+    /// - closing the window only queues a `Quit` event for it, call `close_window(..)` to actually close it
+    /// - the trait methods that don't take a `WindowId` keep referring to the main window
+    /// - the `vsync` config tag is ignored, only the main window waits for vblank so swapping
+    ///   several windows doesn't wait a full refresh for each of them
+    fn create_window(&mut self, _config: &str) -> Result<event_util::WindowId, Error> {
+        Err(Error::UnsupportedError(String::from(
+            "this backend only supports a single window",
+        )))
+    }
+
+    /// # Description
+    /// Closes a window opened with `create_window(..)`
+    /// # Comments
+    /// - the main window can't be closed this way, stop the app instead
+    /// - the main window's context becomes current if the closed window's context was
+    fn close_window(&mut self, id: event_util::WindowId) -> Result<(), Error> {
+        Err(Error::UnsupportedError(format!(
+            "there is no window {}",
+            id
+        )))
+    }
+
+    /// # Description
+    /// The ids of every open window, the main window included
+    fn window_ids(&self) -> Vec<event_util::WindowId> {
+        vec![event_util::MAIN_WINDOW]
+    }
+
+    /// # Description
+    /// Makes GL calls draw into window `id` until another window is made current
+    /// # Comments
+    /// - `gl()` returns the functions of the current context
+    /// - the main window is current at the start of every frame
+    fn make_current(&mut self, id: event_util::WindowId) -> Result<(), Error> {
+        if id == event_util::MAIN_WINDOW {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedError(format!(
+                "there is no window {}",
+                id
+            )))
+        }
+    }
+
+    /// # Description
+    /// The window whose context GL calls currently go to
+    fn current_window(&self) -> event_util::WindowId {
+        event_util::MAIN_WINDOW
+    }
+
+    /// # Description
+    /// Same as `get_bounds()` but for any open window
+    fn window_bounds(&self, id: event_util::WindowId) -> Result<(u32, u32), Error> {
+        if id == event_util::MAIN_WINDOW {
+            Ok(self.get_bounds())
+        } else {
+            Err(Error::UnsupportedError(format!(
+                "there is no window {}",
+                id
+            )))
+        }
+    }
+
     fn get_bounds(&self) -> (u32, u32) {
         (self.width(), self.height())
     }
@@ -178,6 +406,7 @@ impl fmt::Display for Error {
             Self::GenericError(err_str) => write!(f, "Generic Error: {}", err_str),
             Self::WindowInitError(err_str) => write!(f, "Window Init error: {}", err_str),
             Self::IOError(err_str) => write!(f, "File IO error: {}", err_str),
+            Self::UnsupportedError(err_str) => write!(f, "Unsupported: {}", err_str),
             _ => write!(f, "unimplemented display! [look in window_util.rs]"),
         }
     }
@@ -187,6 +416,74 @@ pub enum IconSetting {
     Base64(String),
     Path(String),
 }
+/// The shape of the mouse cursor while it hovers over the window
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CursorIcon {
+    Arrow,
+    /// the text cursor
+    IBeam,
+    Hand,
+    Crosshair,
+    Wait,
+    NotAllowed,
+    /// left-right arrow
+    ResizeHorizontal,
+    /// up-down arrow
+    ResizeVertical,
+    /// arrow from the top left to the bottom right corner
+    ResizeNwSe,
+    /// arrow from the top right to the bottom left corner
+    ResizeNeSw,
+    ResizeAll,
+}
+
+impl CursorIcon {
+    /// the equivalent value of the css `cursor` property
+    pub fn css_name(self) -> &'static str {
+        match self {
+            Self::Arrow => "default",
+            Self::IBeam => "text",
+            Self::Hand => "pointer",
+            Self::Crosshair => "crosshair",
+            Self::Wait => "wait",
+            Self::NotAllowed => "not-allowed",
+            Self::ResizeHorizontal => "ew-resize",
+            Self::ResizeVertical => "ns-resize",
+            Self::ResizeNwSe => "nwse-resize",
+            Self::ResizeNeSw => "nesw-resize",
+            Self::ResizeAll => "move",
+        }
+    }
+}
+
+/// The settings of a window that can be changed while the app is running
+#[derive(Clone, Debug)]
+pub struct WindowAttributes {
+    pub title: String,
+    pub min_size: Option<(u32, u32)>,
+    pub max_size: Option<(u32, u32)>,
+    pub vsync: bool,
+    pub cursor_visible: bool,
+    pub cursor_icon: CursorIcon,
+    pub cursor_grabbed: bool,
+    pub relative_mouse_mode: bool,
+}
+
+impl WindowAttributes {
+    pub fn new(config: &FlufflWindowConfigs) -> Self {
+        Self {
+            title: config.title.clone(),
+            min_size: None,
+            max_size: None,
+            vsync: config.vsync,
+            cursor_visible: true,
+            cursor_icon: CursorIcon::Arrow,
+            cursor_grabbed: false,
+            relative_mouse_mode: false,
+        }
+    }
+}
+
 /// Parses Config setting from xml to be used in window building on execution
 pub struct FlufflWindowConfigs {
     ///window width
@@ -207,6 +504,10 @@ pub struct FlufflWindowConfigs {
     pub resizable: bool,
     /// Specifies if window is fullscreen
     pub fullscreen: bool,
+    /// Specifies if buffer swaps wait for the display's refresh
+    pub vsync: bool,
+    /// Specifies if driver debug output gets logged, see `ogl::state::enable_debug_output(..)`
+    pub gl_debug: bool,
     pub icon: Option<IconSetting>,
 }
 
@@ -224,6 +525,8 @@ impl FlufflWindowConfigs {
             context_minor: 0,
             resizable: true,
             fullscreen: false,
+            vsync: true,
+            gl_debug: false,
             icon: None,
         }
     }
@@ -266,6 +569,12 @@ impl FlufflWindowConfigs {
         if let Some(val) = obj.get("fullscreen").and_then(|num| num.as_bool()) {
             self.fullscreen = val;
         }
+        if let Some(val) = obj.get("vsync").and_then(|num| num.as_bool()) {
+            self.vsync = val;
+        }
+        if let Some(val) = obj.get("gl_debug").and_then(|num| num.as_bool()) {
+            self.gl_debug = val;
+        }
 
         if let Some(icon_obj) = obj.get("icon").and_then(|val| val.as_object()) {
             if let Some(path) = icon_obj.get("path").and_then(|val| val.as_str()) {
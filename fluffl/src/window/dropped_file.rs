@@ -0,0 +1,127 @@
+use super::event_util::DroppedFileId;
+use crate::Error;
+use std::{collections::HashMap, path::PathBuf};
+
+/// A file the user dragged onto the window
+#[derive(Clone, Debug)]
+pub struct DroppedFile {
+    name: String,
+    path: Option<PathBuf>,
+    bytes: Option<Vec<u8>>,
+}
+
+impl DroppedFile {
+    /// for desktop platforms, where only the path of the file gets dropped
+    pub fn from_path<P: Into<PathBuf>>(path: P) -> Self {
+        let path = path.into();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Self {
+            name,
+            path: Some(path),
+            bytes: None,
+        }
+    }
+
+    /// for the web, where the browser hands over the contents but never the path
+    pub fn from_bytes(name: &str, bytes: Vec<u8>) -> Self {
+        Self {
+            name: String::from(name),
+            path: None,
+            bytes: Some(bytes),
+        }
+    }
+
+    /// the file name, including the extension
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// lowercased extension without the dot, so `song.OGG` gives `ogg`
+    pub fn extension(&self) -> Option<String> {
+        self.name
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_lowercase())
+    }
+
+    /// `None` on the web
+    pub fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+
+    /// ## Description
+    /// returns the contents of the file
+    /// ## Comments
+    /// - on desktop the file is read every time this gets called
+    pub fn read(&self) -> Result<Vec<u8>, Error> {
+        match (&self.bytes, &self.path) {
+            (Some(bytes), _) => Ok(bytes.clone()),
+            (None, Some(path)) => Ok(std::fs::read(path)?),
+            (None, None) => Err(Error::IOError(format!("{} has no contents", self.name))),
+        }
+    }
+}
+
+/// # Description
+/// Holds on to the files referenced by `EventKind::FileDropped`
+/// # Comments
+/// - files stay here until they are taken or cleared, so take them once they have been handled
+#[derive(Default)]
+pub struct DroppedFiles {
+    next_id: DroppedFileId,
+    files: HashMap<DroppedFileId, DroppedFile>,
+}
+
+impl DroppedFiles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// stores `file` and returns the id to send along with `EventKind::FileDropped`
+    pub fn insert(&mut self, file: DroppedFile) -> DroppedFileId {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.files.insert(id, file);
+        id
+    }
+
+    pub fn get(&self, id: DroppedFileId) -> Option<&DroppedFile> {
+        self.files.get(&id)
+    }
+
+    pub fn take(&mut self, id: DroppedFileId) -> Option<DroppedFile> {
+        self.files.remove(&id)
+    }
+
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+#[test]
+fn dropped_files_are_kept_until_taken() {
+    let mut files = DroppedFiles::new();
+    let song = files.insert(DroppedFile::from_path("/music/Song.OGG"));
+    let icon = files.insert(DroppedFile::from_bytes("app.ico", vec![0, 0, 1, 0]));
+    assert_ne!(song, icon);
+
+    let song_file = files.get(song).unwrap();
+    assert_eq!(song_file.name(), "Song.OGG");
+    assert_eq!(song_file.extension().as_deref(), Some("ogg"));
+
+    let icon_file = files.take(icon).unwrap();
+    assert!(icon_file.path().is_none());
+    assert_eq!(icon_file.read().unwrap(), vec![0, 0, 1, 0]);
+    assert!(files.get(icon).is_none());
+    assert_eq!(files.len(), 1);
+}
@@ -1,6 +1,8 @@
 pub mod constants;
+pub mod recording;
 pub use constants::*;
-use std::collections::VecDeque;
+pub use recording::{EventRecorder, EventRecording, EventReplay, RecordedFrame};
+use std::collections::{HashMap, VecDeque};
 
 /// A generic interface for events\
 /// Unfortunately I needed a layer between SDL2 and other WASM+JS interfaces for the web build\
@@ -8,12 +10,19 @@ use std::collections::VecDeque;
 #[derive(Default)]
 pub struct FlufflEvent {
     event_queue: VecDeque<EventKind>,
+    recorder: Option<EventRecorder>,
+    replay: Option<EventReplay>,
+    /// queues of the windows opened with `HasFlufflWindow::create_window(..)`
+    window_queues: HashMap<WindowId, VecDeque<EventKind>>,
 }
 
 impl FlufflEvent {
     pub fn new() -> Self {
         Self {
             event_queue: VecDeque::new(),
+            recorder: None,
+            replay: None,
+            window_queues: HashMap::new(),
         }
     }
 
@@ -21,18 +30,131 @@ impl FlufflEvent {
         self.event_queue.push_back(event);
     }
 
+    /// ## Description
+    /// queues `event` for `window`, events for `MAIN_WINDOW` go through `push_event(..)`
+    pub fn push_window_event(&mut self, window: WindowId, event: EventKind) {
+        if window == MAIN_WINDOW {
+            self.push_event(event);
+        } else {
+            self.window_queues
+                .entry(window)
+                .or_default()
+                .push_back(event);
+        }
+    }
+
+    /// ## Description
+    /// like `flush_iter_mut()` but for the events of a single window
+    /// ## Comments
+    /// - `MAIN_WINDOW` just forwards to `flush_iter_mut()`
+    /// - only the main window gets recorded and replayed
+    pub fn flush_window_iter_mut(&mut self, window: WindowId) -> EventIter<'_> {
+        if window == MAIN_WINDOW {
+            return self.flush_iter_mut();
+        }
+        EventIter {
+            event: self,
+            window,
+        }
+    }
+
+    /// drops whatever is still queued for `window`
+    pub fn remove_window(&mut self, window: WindowId) {
+        self.window_queues.remove(&window);
+    }
+
     /// returns an iterator that iterates through event queue.
     /// This iterator removes events as it walks through the queue.
+    /// Every call counts as one frame for recording and replay.
     pub fn flush_iter_mut(&mut self) -> EventIter {
-        EventIter { event: self }
+        if let Some(replay) = &mut self.replay {
+            self.event_queue.clear();
+            self.event_queue.extend(replay.advance().iter().copied());
+            if replay.is_finished() {
+                self.replay = None;
+            }
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_frame(self.event_queue.iter());
+        }
+        EventIter {
+            event: self,
+            window: MAIN_WINDOW,
+        }
+    }
+
+    /// ## Description
+    /// starts recording every event flushed out of the queue, restarting any recording in progress
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(EventRecorder::new());
+    }
+
+    /// returns `None` if nothing was being recorded
+    pub fn stop_recording(&mut self) -> Option<EventRecording> {
+        self.recorder.take().map(EventRecorder::finish)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// ## Description
+    /// replays `recording` one frame per `flush_iter_mut()` call
+    /// ## Comments
+    /// - live input pushed while the replay runs is discarded
+    /// - live input resumes on its own once the last recorded frame was delivered
+    pub fn start_replay(&mut self, recording: EventRecording) {
+        self.replay = (recording.num_frames > 0).then(|| EventReplay::new(recording));
+    }
+
+    pub fn stop_replay(&mut self) {
+        self.replay = None;
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    /// ## Description
+    /// the recorded timestamp (in milliseconds) of the frame the next `flush_iter_mut()` delivers
+    /// ## Comments
+    /// - use this instead of the wall clock to keep simulations deterministic during replay
+    pub fn replay_time_ms(&self) -> Option<f64> {
+        self.replay.as_ref().map(EventReplay::peek_time_ms)
     }
 }
 pub struct EventIter<'a> {
     event: &'a mut FlufflEvent,
+    window: WindowId,
 }
 impl<'a> Iterator for EventIter<'a> {
     type Item = EventKind;
     fn next(&mut self) -> Option<Self::Item> {
-        self.event.event_queue.pop_front()
+        if self.window == MAIN_WINDOW {
+            self.event.event_queue.pop_front()
+        } else {
+            self.event.window_queues.get_mut(&self.window)?.pop_front()
+        }
     }
 }
+
+#[test]
+fn window_events_are_routed_separately() {
+    let mut events = FlufflEvent::new();
+    let palette: WindowId = 1;
+    events.push_window_event(MAIN_WINDOW, EventKind::FocusLost);
+    events.push_window_event(palette, EventKind::FocusGained);
+    events.push_window_event(palette, EventKind::Quit);
+
+    let palette_events: Vec<_> = events.flush_window_iter_mut(palette).collect();
+    assert!(matches!(
+        palette_events[..],
+        [EventKind::FocusGained, EventKind::Quit]
+    ));
+    let main_events: Vec<_> = events.flush_iter_mut().collect();
+    assert!(matches!(main_events[..], [EventKind::FocusLost]));
+
+    events.push_window_event(palette, EventKind::FocusLost);
+    events.remove_window(palette);
+    assert_eq!(events.flush_window_iter_mut(palette).count(), 0);
+}
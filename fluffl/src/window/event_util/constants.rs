@@ -1,4 +1,4 @@
-use crate::math::Vec2;
+use crate::{math::Vec2, window::gesture::Gesture};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -21,6 +21,37 @@ pub enum EventKind {
         height: i32,
     },
 
+    /// # Description
+    /// The window (or the browser tab) got keyboard focus back
+    FocusGained,
+
+    /// # Description
+    /// The window (or the browser tab) lost keyboard focus, a good time to pause the mixer
+    FocusLost,
+
+    /// # Description
+    /// The window got minimized, or the browser tab got hidden
+    Minimized,
+
+    /// # Description
+    /// The window is visible again after being minimized
+    Restored,
+
+    /// # Description
+    /// The ratio between physical and logical pixels changed, usually because the window moved to
+    /// another monitor. Anything rasterized (like text) should be redone at the new scale
+    ScaleFactorChanged {
+        scale_factor: f32,
+    },
+
+    /// # Description
+    /// The user dragged a file onto the window
+    /// # Members
+    /// - `id` - look the file up with `HasFlufflWindow::dropped_files()`
+    FileDropped {
+        id: DroppedFileId,
+    },
+
     /// # Description
     /// If the user moves the mouse, this event gets enqueued
     MouseMove {
@@ -91,6 +122,86 @@ pub enum EventKind {
     KeyUp {
         code: KeyCode,
     },
+
+    /// # Description
+    /// Text the user typed, after the keyboard layout, shift and dead keys have been applied.
+    /// This is what text fields should listen to instead of `KeyDown`
+    /// # Comments
+    /// - never contains control characters (backspace, enter, tab, etc), those only show up as `KeyDown`
+    /// - long strings (like a paste from an input method) get split across several events
+    TextInput {
+        text: TextChunk,
+    },
+
+    /// # Description
+    /// An input method editor (IME) started composing text, like a CJK input method or a dead key sequence
+    ImePreeditStart,
+
+    /// # Description
+    /// The text currently being composed changed. It hasn't been committed so it should only be displayed.
+    /// # Members
+    /// - `text` - the whole in-progress string, not just what changed
+    /// - `cursor` - where the cursor sits inside the whole preedit string, in chars
+    /// - `continued` - `text` continues the preedit of the previous update instead of replacing it
+    /// # Comments
+    /// - preedit strings longer than `TextChunk::CAPACITY` arrive split over several updates, see
+    ///   `EventKind::preedit_updates(..)`
+    /// - the glutin backend never sends this, winit 0.26 has no way of reporting preedit text
+    ImePreeditUpdate {
+        text: TextChunk,
+        cursor: i32,
+        #[serde(default)]
+        continued: bool,
+    },
+
+    /// # Description
+    /// Composition finished, `text` should be inserted like a `TextInput`
+    /// # Comments
+    /// - an empty `text` means the composition was cancelled
+    ImeCommit {
+        text: TextChunk,
+    },
+
+    /// # Description
+    /// A gamepad was plugged in (or was already plugged in when the window was created)
+    /// # Members
+    /// - `id` - stays the same until the gamepad is disconnected, ids may be reused afterwards
+    GamepadConnected {
+        id: GamepadId,
+    },
+
+    GamepadDisconnected {
+        id: GamepadId,
+    },
+
+    GamepadButtonDown {
+        id: GamepadId,
+        button: GamepadButton,
+    },
+
+    GamepadButtonUp {
+        id: GamepadId,
+        button: GamepadButton,
+    },
+
+    /// # Description
+    /// An analog stick or trigger moved
+    /// # Members
+    /// - `value` - sticks are in `[-1,1]` (`-1` is left/up), triggers are in `[0,1]`
+    GamepadAxisMotion {
+        id: GamepadId,
+        axis: GamepadAxis,
+        value: f32,
+    },
+
+    /// # Description
+    /// A gesture recognized by a `GestureRecognizer` from the touch events
+    /// # Comments
+    /// - window backends never emit this, the gui pushes it to itself after recognizing a gesture,
+    ///   game code can do the same with its own recognizer
+    Gesture {
+        gesture: Gesture,
+    },
 }
 impl EventKind {
     pub fn mouse_pos(&self) -> Vec2<f32> {
@@ -126,6 +237,147 @@ impl EventKind {
             _ => Vec2::zero(),
         }
     }
+
+    /// ## Description
+    /// the `ImePreeditUpdate`s a backend sends for the preedit string `text`
+    /// ## Comments
+    /// - every chunk after the first one is marked as `continued`
+    /// - an empty `text` still gives one update, clearing the preedit
+    pub fn preedit_updates(text: &str, cursor: i32) -> impl Iterator<Item = EventKind> + '_ {
+        let first = text.is_empty().then(TextChunk::default);
+        first
+            .into_iter()
+            .chain(TextChunk::split(text))
+            .enumerate()
+            .map(move |(idx, text)| Self::ImePreeditUpdate {
+                text,
+                cursor,
+                continued: idx > 0,
+            })
+    }
+
+    /// the text carried by `TextInput`, `ImePreeditUpdate` and `ImeCommit`
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            Self::TextInput { text } => Some(text.as_str()),
+            Self::ImePreeditUpdate { text, .. } => Some(text.as_str()),
+            Self::ImeCommit { text } => Some(text.as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// # Description
+/// A short utf-8 string stored inline, so that `EventKind` can stay `Copy`
+/// # Comments
+/// - strings longer than `TextChunk::CAPACITY` bytes are truncated on a char boundary, use
+///   `TextChunk::split(..)` to carry them across several events instead
+/// - deserializing checks that `len` fits and the bytes are utf-8, recordings are just files
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "RawTextChunk")]
+pub struct TextChunk {
+    len: u8,
+    bytes: [u8; TextChunk::CAPACITY],
+}
+
+impl TextChunk {
+    pub const CAPACITY: usize = 31;
+
+    pub fn new(text: &str) -> Self {
+        let mut len = text.len().min(Self::CAPACITY);
+        while !text.is_char_boundary(len) {
+            len -= 1;
+        }
+        let mut bytes = [0; Self::CAPACITY];
+        bytes[..len].copy_from_slice(&text.as_bytes()[..len]);
+        Self {
+            len: len as u8,
+            bytes,
+        }
+    }
+
+    /// chops `text` into chunks without splitting any chars
+    pub fn split(text: &str) -> impl Iterator<Item = TextChunk> + '_ {
+        let mut remaining = text;
+        std::iter::from_fn(move || {
+            if remaining.is_empty() {
+                return None;
+            }
+            let chunk = TextChunk::new(remaining);
+            remaining = &remaining[chunk.len()..];
+            Some(chunk)
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        // bytes are only ever copied out of a `&str` on a char boundary
+        std::str::from_utf8(&self.bytes[..self.len()]).unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// `TextChunk` straight out of the deserializer, before its fields are checked
+#[derive(Deserialize)]
+struct RawTextChunk {
+    len: u8,
+    bytes: [u8; TextChunk::CAPACITY],
+}
+
+impl TryFrom<RawTextChunk> for TextChunk {
+    type Error = String;
+
+    fn try_from(raw: RawTextChunk) -> Result<Self, Self::Error> {
+        let len = raw.len as usize;
+        if len > Self::CAPACITY {
+            return Err(format!(
+                "text chunk is {} bytes long, at most {} fit",
+                len,
+                Self::CAPACITY
+            ));
+        }
+        std::str::from_utf8(&raw.bytes[..len]).map_err(|err| err.to_string())?;
+        Ok(Self {
+            len: raw.len,
+            bytes: raw.bytes,
+        })
+    }
+}
+
+impl Default for TextChunk {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl From<char> for TextChunk {
+    fn from(c: char) -> Self {
+        Self::new(c.encode_utf8(&mut [0; 4]))
+    }
+}
+
+impl<'a> From<&'a str> for TextChunk {
+    fn from(text: &'a str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl fmt::Debug for TextChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for TextChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Hash, Debug)]
@@ -273,8 +525,93 @@ impl From<KeyCode> for i128 {
     }
 }
 
+pub type GamepadId = u32;
+
+/// identifies a file stored in `DroppedFiles`
+pub type DroppedFileId = u32;
+
+/// identifies a window, see `HasFlufflWindow::create_window(..)`
+pub type WindowId = u32;
+
+/// the window created by `HasFlufflWindow::init(..)`
+pub const MAIN_WINDOW: WindowId = 0;
+
+/// Buttons of a gamepad, laid out like an SDL GameController (an xbox style pad)
+/// # Comments
+/// - buttons are named by position, so `A` is the bottom face button on every pad
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Hash, Debug)]
+#[rustfmt::skip]
+pub enum GamepadButton {
+    A             =  0,
+    B             =  1,
+    X             =  2,
+    Y             =  3,
+    Back          =  4,
+    Guide         =  5,
+    Start         =  6,
+    LeftStick     =  7,
+    RightStick    =  8,
+    LeftShoulder  =  9,
+    RightShoulder = 10,
+    DPadUp        = 11,
+    DPadDown      = 12,
+    DPadLeft      = 13,
+    DPadRight     = 14,
+}
+
+impl GamepadButton {
+    pub const ALL: [GamepadButton; 15] = [
+        Self::A,
+        Self::B,
+        Self::X,
+        Self::Y,
+        Self::Back,
+        Self::Guide,
+        Self::Start,
+        Self::LeftStick,
+        Self::RightStick,
+        Self::LeftShoulder,
+        Self::RightShoulder,
+        Self::DPadUp,
+        Self::DPadDown,
+        Self::DPadLeft,
+        Self::DPadRight,
+    ];
+
+    /// the bit this button occupies in `GamepadState::buttons()`
+    pub const fn mask(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Hash, Debug)]
+#[rustfmt::skip]
+pub enum GamepadAxis {
+    LeftX        = 0,
+    LeftY        = 1,
+    RightX       = 2,
+    RightY       = 3,
+    TriggerLeft  = 4,
+    TriggerRight = 5,
+}
+
+impl GamepadAxis {
+    pub const ALL: [GamepadAxis; 6] = [
+        Self::LeftX,
+        Self::LeftY,
+        Self::RightX,
+        Self::RightY,
+        Self::TriggerLeft,
+        Self::TriggerRight,
+    ];
+
+    pub fn is_trigger(self) -> bool {
+        matches!(self, Self::TriggerLeft | Self::TriggerRight)
+    }
+}
+
 #[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
 pub enum MouseCode {
     LEFT_BUTTON,
     RIGHT_BUTTON,
@@ -290,3 +627,57 @@ impl fmt::Display for MouseCode {
         }
     }
 }
+
+#[test]
+fn text_chunk_respects_char_boundaries() {
+    let chunk = TextChunk::from('é');
+    assert_eq!(chunk.as_str(), "é");
+    assert_eq!(chunk.len(), 2);
+
+    // 12 three-byte chars = 36 bytes, only the first 10 (30 bytes) fit in a single chunk
+    let text = "日本語の文字列を入力する";
+    let truncated = TextChunk::new(text);
+    assert_eq!(truncated.len(), 30);
+    assert!(text.starts_with(truncated.as_str()));
+
+    let joined: String = TextChunk::split(text).map(|c| c.to_string()).collect();
+    assert_eq!(joined, text);
+    assert_eq!(TextChunk::split("").count(), 0);
+}
+
+#[test]
+fn text_chunks_are_validated_when_deserialized() {
+    let chunk = TextChunk::new("ïme");
+    let bytes = bincode::serialize(&chunk).unwrap();
+    assert!(bincode::deserialize::<TextChunk>(&bytes).unwrap() == chunk);
+
+    // `len` is the first byte, anything past the capacity would make `as_str()` panic
+    let mut too_long = bytes.clone();
+    too_long[0] = TextChunk::CAPACITY as u8 + 1;
+    assert!(bincode::deserialize::<TextChunk>(&too_long).is_err());
+
+    // cuts 'ï' in half
+    let mut split_char = bytes;
+    split_char[0] = 1;
+    assert!(bincode::deserialize::<TextChunk>(&split_char).is_err());
+}
+
+#[test]
+fn long_preedit_text_is_split_across_updates() {
+    let text = "日本語の文字列を入力する";
+    let mut preedit = String::new();
+    for event in EventKind::preedit_updates(text, 3) {
+        if let EventKind::ImePreeditUpdate {
+            text,
+            cursor,
+            continued,
+        } = event
+        {
+            assert_eq!(cursor, 3);
+            assert_eq!(continued, !preedit.is_empty());
+            preedit.push_str(text.as_str());
+        }
+    }
+    assert_eq!(preedit, text);
+    assert_eq!(EventKind::preedit_updates("", 0).count(), 1);
+}
@@ -0,0 +1,264 @@
+use super::{EventKind, FlufflEvent};
+use crate::time::Instant;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// The events delivered during a single frame, frames without events aren't stored
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RecordedFrame {
+    /// counts every `FlufflEvent::flush_iter_mut()` since the recording started
+    pub frame: u64,
+    /// milliseconds since the recording started
+    pub time_ms: f64,
+    pub events: Vec<EventKind>,
+}
+
+/// # Description
+/// Every event an app received, frame by frame, so it can be replayed later
+/// # Comments
+/// - serializes to JSON, so recordings attached to bug reports stay readable and diffable
+#[derive(Clone, Default, Serialize, Deserialize, Debug)]
+pub struct EventRecording {
+    /// how many frames were recorded, including the ones without events
+    pub num_frames: u64,
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl EventRecording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ## Description
+    /// records a frame with the given events
+    /// ## Comments
+    /// - meant for writing recordings by hand, like scripted input for a test
+    pub fn push_frame<I>(&mut self, time_ms: f64, events: I)
+    where
+        I: IntoIterator<Item = EventKind>,
+    {
+        let events: Vec<_> = events.into_iter().collect();
+        if !events.is_empty() {
+            self.frames.push(RecordedFrame {
+                frame: self.num_frames,
+                time_ms,
+                events,
+            });
+        }
+        self.num_frames += 1;
+    }
+
+    /// total number of events across all frames
+    pub fn num_events(&self) -> usize {
+        self.frames.iter().map(|frame| frame.events.len()).sum()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("recordings should always serialize")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+        serde_json::to_writer(writer, self)
+    }
+
+    pub fn read_from<R: Read>(reader: R) -> Result<Self, serde_json::Error> {
+        serde_json::from_reader(reader)
+    }
+
+    /// ## Description
+    /// replays the recording without a window: for every recorded frame (empty ones included)
+    /// `frame_callback` gets the frame's timestamp and a queue holding exactly that frame's events
+    /// ## Comments
+    /// - empty frames get the timestamp of the last frame that had events
+    /// - this is meant for CI, drive the app's update logic (or a `GuiManager`) from the callback
+    pub fn replay_headless<CB>(&self, mut frame_callback: CB)
+    where
+        CB: FnMut(f64, &mut FlufflEvent),
+    {
+        let mut replay = EventReplay::new(self.clone());
+        let mut events = FlufflEvent::new();
+        while !replay.is_finished() {
+            let time_ms = replay.peek_time_ms();
+            for &event in replay.advance() {
+                events.push_event(event);
+            }
+            frame_callback(time_ms, &mut events);
+            // whatever the callback didn't flush must not leak into the next frame
+            events.event_queue.clear();
+        }
+    }
+}
+
+/// Collects the events flushed out of a `FlufflEvent`
+pub struct EventRecorder {
+    recording: EventRecording,
+    clock: Instant,
+}
+
+impl EventRecorder {
+    pub fn new() -> Self {
+        Self {
+            recording: EventRecording::new(),
+            clock: Instant::now(),
+        }
+    }
+
+    pub fn record_frame<'a, I>(&mut self, events: I)
+    where
+        I: IntoIterator<Item = &'a EventKind>,
+    {
+        let time_ms = self.clock.elapsed().as_millis() as f64;
+        self.recording
+            .push_frame(time_ms, events.into_iter().copied());
+    }
+
+    pub fn recording(&self) -> &EventRecording {
+        &self.recording
+    }
+
+    pub fn finish(self) -> EventRecording {
+        self.recording
+    }
+}
+
+impl Default for EventRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Feeds a recording back frame by frame
+pub struct EventReplay {
+    recording: EventRecording,
+    next_frame: u64,
+    next_index: usize,
+    time_ms: f64,
+}
+
+impl EventReplay {
+    pub fn new(recording: EventRecording) -> Self {
+        Self {
+            recording,
+            next_frame: 0,
+            next_index: 0,
+            time_ms: 0.0,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_frame >= self.recording.num_frames
+    }
+
+    /// the frame that will be delivered next
+    pub fn frame(&self) -> u64 {
+        self.next_frame
+    }
+
+    /// timestamp of the frame that will be delivered next
+    pub fn peek_time_ms(&self) -> f64 {
+        match self.recording.frames.get(self.next_index) {
+            Some(frame) if frame.frame == self.next_frame => frame.time_ms,
+            _ => self.time_ms,
+        }
+    }
+
+    /// the events of the next frame, empty if nothing happened that frame
+    pub fn advance(&mut self) -> &[EventKind] {
+        let frame_index = self.next_frame;
+        self.next_frame += 1;
+        match self.recording.frames.get(self.next_index) {
+            Some(frame) if frame.frame == frame_index => {
+                self.next_index += 1;
+                self.time_ms = frame.time_ms;
+                &frame.events
+            }
+            _ => &[],
+        }
+    }
+}
+
+#[test]
+fn recording_round_trips_through_json() {
+    use super::KeyCode;
+    let mut recording = EventRecording::new();
+    recording.push_frame(
+        0.0,
+        [EventKind::KeyDown {
+            code: KeyCode::KEY_A,
+        }],
+    );
+    recording.push_frame(16.0, []);
+    recording.push_frame(
+        33.0,
+        [
+            EventKind::KeyUp {
+                code: KeyCode::KEY_A,
+            },
+            EventKind::MouseMove {
+                x: 1.0,
+                y: 2.0,
+                dx: 0.5,
+                dy: 0.0,
+            },
+        ],
+    );
+
+    let loaded = EventRecording::from_json(&recording.to_json()).unwrap();
+    assert_eq!(loaded.num_frames, 3);
+    assert_eq!(loaded.frames.len(), 2);
+    assert_eq!(loaded.num_events(), 3);
+
+    let mut bytes = Vec::new();
+    recording.write_to(&mut bytes).unwrap();
+    let loaded = EventRecording::read_from(&bytes[..]).unwrap();
+    assert_eq!(loaded.frames[1].frame, 2);
+}
+
+#[test]
+fn replay_replaces_live_input() {
+    use super::KeyCode;
+    let mut events = FlufflEvent::new();
+    events.start_recording();
+    events.push_event(EventKind::KeyDown {
+        code: KeyCode::KEY_A,
+    });
+    assert_eq!(events.flush_iter_mut().count(), 1);
+    assert_eq!(events.flush_iter_mut().count(), 0);
+    events.push_event(EventKind::KeyUp {
+        code: KeyCode::KEY_A,
+    });
+    events.push_event(EventKind::Quit);
+    assert_eq!(events.flush_iter_mut().count(), 2);
+    let recording = events.stop_recording().unwrap();
+    assert_eq!(recording.num_frames, 3);
+
+    // live input is dropped while the replay runs
+    events.start_replay(recording.clone());
+    events.push_event(EventKind::KeyDown {
+        code: KeyCode::KEY_Z,
+    });
+    let frame: Vec<_> = events.flush_iter_mut().collect();
+    assert!(matches!(
+        frame[..],
+        [EventKind::KeyDown {
+            code: KeyCode::KEY_A
+        }]
+    ));
+    assert_eq!(events.flush_iter_mut().count(), 0);
+    assert_eq!(events.flush_iter_mut().count(), 2);
+    assert!(!events.is_replaying());
+
+    // back to live input afterwards
+    events.push_event(EventKind::KeyDown {
+        code: KeyCode::KEY_Z,
+    });
+    assert_eq!(events.flush_iter_mut().count(), 1);
+
+    let mut frames = Vec::new();
+    recording.replay_headless(|_, events| frames.push(events.flush_iter_mut().count()));
+    assert_eq!(frames, vec![1, 0, 2]);
+}
@@ -0,0 +1,294 @@
+use super::event_util::{EventKind, GamepadAxis, GamepadButton, GamepadId};
+use crate::math::Vec2;
+
+#[cfg(all(target_os = "linux", feature = "glutin"))]
+pub mod evdev;
+
+/// The last known state of a single gamepad
+#[derive(Clone, Debug)]
+pub struct GamepadState {
+    id: GamepadId,
+    name: String,
+    buttons: u32,
+    axes: [f32; 6],
+}
+
+impl GamepadState {
+    pub fn new(id: GamepadId) -> Self {
+        Self {
+            id,
+            name: String::new(),
+            buttons: 0,
+            axes: [0.0; 6],
+        }
+    }
+
+    pub fn id(&self) -> GamepadId {
+        self.id
+    }
+
+    /// a human readable name reported by the platform, may be empty
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// every button that is currently held, as a bitmask of `GamepadButton::mask()`
+    pub fn buttons(&self) -> u32 {
+        self.buttons
+    }
+
+    pub fn is_button_down(&self, button: GamepadButton) -> bool {
+        self.buttons & button.mask() != 0
+    }
+
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes[axis as usize]
+    }
+
+    pub fn left_stick(&self) -> Vec2<f32> {
+        Vec2::from([self.axis(GamepadAxis::LeftX), self.axis(GamepadAxis::LeftY)])
+    }
+
+    pub fn right_stick(&self) -> Vec2<f32> {
+        Vec2::from([
+            self.axis(GamepadAxis::RightX),
+            self.axis(GamepadAxis::RightY),
+        ])
+    }
+}
+
+/// # Description
+/// Tracks the state of every connected gamepad so it can be polled instead of listened to
+/// # Comments
+/// - window backends keep this up to date as they collect events, so it always reflects every
+///   gamepad event collected so far, even the ones still sitting in the event queue
+#[derive(Default)]
+pub struct Gamepads {
+    pads: Vec<GamepadState>,
+}
+
+impl Gamepads {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// applies a gamepad event to the tracked state, every other kind of event is ignored
+    pub fn update(&mut self, event: &EventKind) {
+        match *event {
+            EventKind::GamepadConnected { id } if self.get(id).is_none() => {
+                self.pads.push(GamepadState::new(id));
+            }
+            EventKind::GamepadDisconnected { id } => {
+                self.pads.retain(|pad| pad.id != id);
+            }
+            EventKind::GamepadButtonDown { id, button } => {
+                if let Some(pad) = self.get_mut(id) {
+                    pad.buttons |= button.mask();
+                }
+            }
+            EventKind::GamepadButtonUp { id, button } => {
+                if let Some(pad) = self.get_mut(id) {
+                    pad.buttons &= !button.mask();
+                }
+            }
+            EventKind::GamepadAxisMotion { id, axis, value } => {
+                if let Some(pad) = self.get_mut(id) {
+                    pad.axes[axis as usize] = value;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// sets the name reported by `GamepadState::name()`, does nothing if `id` isn't connected
+    pub fn set_name(&mut self, id: GamepadId, name: &str) {
+        if let Some(pad) = self.get_mut(id) {
+            pad.name.clear();
+            pad.name.push_str(name);
+        }
+    }
+
+    pub fn get(&self, id: GamepadId) -> Option<&GamepadState> {
+        self.pads.iter().find(|pad| pad.id == id)
+    }
+
+    fn get_mut(&mut self, id: GamepadId) -> Option<&mut GamepadState> {
+        self.pads.iter_mut().find(|pad| pad.id == id)
+    }
+
+    /// every connected gamepad, in the order they were connected
+    pub fn iter(&self) -> impl Iterator<Item = &GamepadState> + '_ {
+        self.pads.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pads.is_empty()
+    }
+
+    /// false if `id` isn't connected
+    pub fn is_button_down(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.get(id)
+            .map(|pad| pad.is_button_down(button))
+            .unwrap_or(false)
+    }
+
+    /// `0` if `id` isn't connected
+    pub fn axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        self.get(id).map(|pad| pad.axis(axis)).unwrap_or(0.0)
+    }
+
+    /// ## Description
+    /// for platforms that can only be polled: emits whatever events turn the tracked state of `id`
+    /// into the given snapshot
+    /// ## Comments
+    /// - `GamepadConnected` is emitted first if `id` isn't tracked yet
+    /// - the events are not applied, pass them through `Self::update(..)` like any other event
+    pub fn diff_snapshot<CB>(&self, id: GamepadId, buttons: u32, axes: [f32; 6], mut emit: CB)
+    where
+        CB: FnMut(EventKind),
+    {
+        let (prev_buttons, prev_axes) = match self.get(id) {
+            Some(pad) => (pad.buttons, pad.axes),
+            None => {
+                emit(EventKind::GamepadConnected { id });
+                (0, [0.0; 6])
+            }
+        };
+
+        for button in GamepadButton::ALL {
+            let was_down = prev_buttons & button.mask() != 0;
+            let is_down = buttons & button.mask() != 0;
+            match (was_down, is_down) {
+                (false, true) => emit(EventKind::GamepadButtonDown { id, button }),
+                (true, false) => emit(EventKind::GamepadButtonUp { id, button }),
+                _ => (),
+            }
+        }
+
+        for axis in GamepadAxis::ALL {
+            let value = axes[axis as usize];
+            if value != prev_axes[axis as usize] {
+                emit(EventKind::GamepadAxisMotion { id, axis, value });
+            }
+        }
+    }
+}
+
+/// maps a raw axis value in `[min,max]` to `[-1,1]` (or `[0,1]` for triggers)
+pub fn normalize_axis(axis: GamepadAxis, raw: i32, min: i32, max: i32) -> f32 {
+    let range = (max as f32 - min as f32).max(1.0);
+    let t = ((raw as f32 - min as f32) / range).clamp(0.0, 1.0);
+    if axis.is_trigger() {
+        t
+    } else {
+        t * 2.0 - 1.0
+    }
+}
+
+#[test]
+fn gamepads_track_events() {
+    let mut pads = Gamepads::new();
+    let events = [
+        EventKind::GamepadConnected { id: 3 },
+        EventKind::GamepadButtonDown {
+            id: 3,
+            button: GamepadButton::A,
+        },
+        EventKind::GamepadButtonDown {
+            id: 3,
+            button: GamepadButton::DPadLeft,
+        },
+        EventKind::GamepadButtonUp {
+            id: 3,
+            button: GamepadButton::A,
+        },
+        EventKind::GamepadAxisMotion {
+            id: 3,
+            axis: GamepadAxis::LeftX,
+            value: -0.5,
+        },
+        // events for pads that never connected are dropped
+        EventKind::GamepadButtonDown {
+            id: 9,
+            button: GamepadButton::B,
+        },
+    ];
+    for event in &events {
+        pads.update(event);
+    }
+    pads.set_name(3, "test pad");
+
+    let pad = pads.get(3).unwrap();
+    assert_eq!(pad.name(), "test pad");
+    assert!(!pad.is_button_down(GamepadButton::A));
+    assert!(pad.is_button_down(GamepadButton::DPadLeft));
+    assert_eq!(pad.left_stick().x(), -0.5);
+    assert_eq!(pads.len(), 1);
+    assert!(!pads.is_button_down(9, GamepadButton::B));
+
+    pads.update(&EventKind::GamepadDisconnected { id: 3 });
+    assert!(pads.is_empty());
+    assert_eq!(pads.axis(3, GamepadAxis::LeftX), 0.0);
+}
+
+#[test]
+fn gamepads_diff_snapshot() {
+    let mut pads = Gamepads::new();
+    let mut events = Vec::new();
+
+    let mut axes = [0.0; 6];
+    axes[GamepadAxis::TriggerRight as usize] = 0.25;
+    pads.diff_snapshot(0, GamepadButton::Start.mask(), axes, |e| events.push(e));
+    assert!(matches!(events[0], EventKind::GamepadConnected { id: 0 }));
+    assert!(matches!(
+        events[1],
+        EventKind::GamepadButtonDown {
+            button: GamepadButton::Start,
+            ..
+        }
+    ));
+    assert!(matches!(
+        events[2],
+        EventKind::GamepadAxisMotion {
+            axis: GamepadAxis::TriggerRight,
+            ..
+        }
+    ));
+    assert_eq!(events.len(), 3);
+    for event in events.drain(..) {
+        pads.update(&event);
+    }
+
+    // an unchanged snapshot produces nothing
+    pads.diff_snapshot(0, GamepadButton::Start.mask(), axes, |e| events.push(e));
+    assert!(events.is_empty());
+
+    pads.diff_snapshot(0, 0, axes, |e| events.push(e));
+    assert!(matches!(
+        events[..],
+        [EventKind::GamepadButtonUp {
+            button: GamepadButton::Start,
+            ..
+        }]
+    ));
+}
+
+#[test]
+fn normalize_axis_ranges() {
+    assert_eq!(
+        normalize_axis(GamepadAxis::LeftX, -32768, -32768, 32767),
+        -1.0
+    );
+    assert_eq!(
+        normalize_axis(GamepadAxis::LeftX, 32767, -32768, 32767),
+        1.0
+    );
+    assert_eq!(normalize_axis(GamepadAxis::LeftY, 128, 0, 256), 0.0);
+    assert_eq!(normalize_axis(GamepadAxis::TriggerLeft, 0, 0, 255), 0.0);
+    assert_eq!(normalize_axis(GamepadAxis::TriggerLeft, 255, 0, 255), 1.0);
+    assert_eq!(normalize_axis(GamepadAxis::TriggerLeft, 999, 0, 255), 1.0);
+}
@@ -0,0 +1,612 @@
+//! Linux gamepad support on top of the raw evdev interface in `/dev/input/event*`
+
+use super::*;
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Read, Write},
+    os::unix::{fs::OpenOptionsExt, io::AsRawFd},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_ABS: u16 = 0x03;
+const EV_FF: u16 = 0x15;
+const SYN_DROPPED: u16 = 0x03;
+
+const BTN_SOUTH: u16 = 0x130;
+const BTN_TL2: u16 = 0x138;
+const BTN_TR2: u16 = 0x139;
+const ABS_HAT0X: u16 = 0x10;
+const ABS_HAT0Y: u16 = 0x11;
+const FF_RUMBLE: u16 = 0x50;
+
+/// number of abs codes the mapper cares about (`ABS_X` up to and including `ABS_HAT0Y`)
+const NUM_ABS_CODES: usize = ABS_HAT0Y as usize + 1;
+
+/// how often `/dev/input` gets rescanned for newly plugged in devices
+const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// the size of a single `input_event` on this platform, it contains a `timeval` so it varies
+pub const INPUT_EVENT_SIZE: usize = std::mem::size_of::<libc::input_event>();
+
+/// a raw evdev event with the timestamp stripped off
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RawInputEvent {
+    pub type_: u16,
+    pub code: u16,
+    pub value: i32,
+}
+
+impl RawInputEvent {
+    pub fn new(type_: u16, code: u16, value: i32) -> Self {
+        Self { type_, code, value }
+    }
+
+    /// encodes the event the same way the kernel does, with a zeroed timestamp
+    pub fn encode(&self, bytes: &mut Vec<u8>) {
+        let base = bytes.len();
+        bytes.resize(base + INPUT_EVENT_SIZE, 0);
+        let event = &mut bytes[base..];
+        event[INPUT_EVENT_SIZE - 8..INPUT_EVENT_SIZE - 6]
+            .copy_from_slice(&self.type_.to_ne_bytes());
+        event[INPUT_EVENT_SIZE - 6..INPUT_EVENT_SIZE - 4].copy_from_slice(&self.code.to_ne_bytes());
+        event[INPUT_EVENT_SIZE - 4..].copy_from_slice(&self.value.to_ne_bytes());
+    }
+}
+
+/// decodes every complete `input_event` in `bytes`, trailing partial events are ignored
+pub fn decode_input_events(bytes: &[u8]) -> impl Iterator<Item = RawInputEvent> + '_ {
+    bytes.chunks_exact(INPUT_EVENT_SIZE).map(|event| {
+        let type_ = u16::from_ne_bytes([event[INPUT_EVENT_SIZE - 8], event[INPUT_EVENT_SIZE - 7]]);
+        let code = u16::from_ne_bytes([event[INPUT_EVENT_SIZE - 6], event[INPUT_EVENT_SIZE - 5]]);
+        let mut value = [0u8; 4];
+        value.copy_from_slice(&event[INPUT_EVENT_SIZE - 4..]);
+        RawInputEvent::new(type_, code, i32::from_ne_bytes(value))
+    })
+}
+
+/// the range of an absolute axis as reported by `EVIOCGABS`
+#[derive(Copy, Clone, Debug)]
+pub struct AbsRange {
+    pub min: i32,
+    pub max: i32,
+    /// values this close to the center (or to `min` for triggers) are treated as zero
+    pub flat: i32,
+}
+
+impl AbsRange {
+    pub fn new(min: i32, max: i32, flat: i32) -> Self {
+        Self { min, max, flat }
+    }
+}
+
+fn map_button(code: u16) -> Option<GamepadButton> {
+    let button = match code {
+        0x130 => GamepadButton::A,
+        0x131 => GamepadButton::B,
+        0x133 => GamepadButton::Y,
+        0x134 => GamepadButton::X,
+        0x136 => GamepadButton::LeftShoulder,
+        0x137 => GamepadButton::RightShoulder,
+        0x13a => GamepadButton::Back,
+        0x13b => GamepadButton::Start,
+        0x13c => GamepadButton::Guide,
+        0x13d => GamepadButton::LeftStick,
+        0x13e => GamepadButton::RightStick,
+        0x220 => GamepadButton::DPadUp,
+        0x221 => GamepadButton::DPadDown,
+        0x222 => GamepadButton::DPadLeft,
+        0x223 => GamepadButton::DPadRight,
+        _ => return None,
+    };
+    Some(button)
+}
+
+fn map_axis(code: u16) -> Option<GamepadAxis> {
+    let axis = match code {
+        0x00 => GamepadAxis::LeftX,
+        0x01 => GamepadAxis::LeftY,
+        0x03 => GamepadAxis::RightX,
+        0x04 => GamepadAxis::RightY,
+        0x02 | 0x0a => GamepadAxis::TriggerLeft,
+        0x05 | 0x09 => GamepadAxis::TriggerRight,
+        _ => return None,
+    };
+    Some(axis)
+}
+
+/// # Description
+/// Turns raw evdev events of a single device into fluffl gamepad events
+/// # Comments
+/// - this does no io at all, so it can be fed recorded events
+/// - the hat switch is reported as dpad buttons, like SDL does
+/// - digital triggers (`BTN_TL2`/`BTN_TR2`) are reported as trigger axes snapping between 0 and 1
+pub struct EvdevMapper {
+    id: GamepadId,
+    ranges: [Option<AbsRange>; NUM_ABS_CODES],
+    axes: [f32; 6],
+    hat: [i32; 2],
+}
+
+impl EvdevMapper {
+    pub fn new(id: GamepadId) -> Self {
+        Self {
+            id,
+            ranges: [None; NUM_ABS_CODES],
+            axes: [0.0; 6],
+            hat: [0; 2],
+        }
+    }
+
+    /// axes without a range assume the full `i16` range
+    pub fn set_range(&mut self, code: u16, range: AbsRange) {
+        if let Some(slot) = self.ranges.get_mut(code as usize) {
+            *slot = Some(range);
+        }
+    }
+
+    pub fn map<CB>(&mut self, event: RawInputEvent, mut emit: CB)
+    where
+        CB: FnMut(EventKind),
+    {
+        let id = self.id;
+        match (event.type_, event.code) {
+            // 2 is key repeat, which gamepads have no use for
+            (EV_KEY, code) if event.value != 2 => {
+                let is_down = event.value != 0;
+                if let Some(button) = map_button(code) {
+                    emit(if is_down {
+                        EventKind::GamepadButtonDown { id, button }
+                    } else {
+                        EventKind::GamepadButtonUp { id, button }
+                    });
+                } else if code == BTN_TL2 || code == BTN_TR2 {
+                    let axis = if code == BTN_TL2 {
+                        GamepadAxis::TriggerLeft
+                    } else {
+                        GamepadAxis::TriggerRight
+                    };
+                    self.set_axis(axis, if is_down { 1.0 } else { 0.0 }, &mut emit);
+                }
+            }
+            (EV_ABS, ABS_HAT0X) | (EV_ABS, ABS_HAT0Y) => {
+                let hat_axis = (event.code - ABS_HAT0X) as usize;
+                let [negative, positive] = if hat_axis == 0 {
+                    [GamepadButton::DPadLeft, GamepadButton::DPadRight]
+                } else {
+                    [GamepadButton::DPadUp, GamepadButton::DPadDown]
+                };
+                let old = self.hat[hat_axis].signum();
+                let new = event.value.signum();
+                if old != new {
+                    let button_of = |dir: i32| if dir < 0 { negative } else { positive };
+                    if old != 0 {
+                        emit(EventKind::GamepadButtonUp {
+                            id,
+                            button: button_of(old),
+                        });
+                    }
+                    if new != 0 {
+                        emit(EventKind::GamepadButtonDown {
+                            id,
+                            button: button_of(new),
+                        });
+                    }
+                }
+                self.hat[hat_axis] = event.value;
+            }
+            (EV_ABS, code) => {
+                if let Some(axis) = map_axis(code) {
+                    let value = self.normalize(axis, code, event.value);
+                    self.set_axis(axis, value, &mut emit);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn normalize(&self, axis: GamepadAxis, code: u16, raw: i32) -> f32 {
+        let range = self
+            .ranges
+            .get(code as usize)
+            .copied()
+            .flatten()
+            .unwrap_or_else(|| AbsRange::new(i16::MIN as i32, i16::MAX as i32, 0));
+        let value = normalize_axis(axis, raw, range.min, range.max);
+        let half_range = (range.max as f32 - range.min as f32).max(1.0) * 0.5;
+        let deadzone = if axis.is_trigger() {
+            range.flat as f32 / (half_range * 2.0)
+        } else {
+            range.flat as f32 / half_range
+        };
+        if value.abs() <= deadzone {
+            0.0
+        } else {
+            value
+        }
+    }
+
+    fn set_axis<CB>(&mut self, axis: GamepadAxis, value: f32, emit: &mut CB)
+    where
+        CB: FnMut(EventKind),
+    {
+        if self.axes[axis as usize] != value {
+            self.axes[axis as usize] = value;
+            emit(EventKind::GamepadAxisMotion {
+                id: self.id,
+                axis,
+                value,
+            });
+        }
+    }
+}
+
+// ioctl request encoding, see `include/uapi/asm-generic/ioctl.h`
+const IOC_WRITE: u64 = 1;
+const IOC_READ: u64 = 2;
+
+const fn ioc(dir: u64, nr: u64, size: usize) -> u64 {
+    (dir << 30) | ((size as u64) << 16) | ((b'E' as u64) << 8) | nr
+}
+
+const fn eviocgname(len: usize) -> u64 {
+    ioc(IOC_READ, 0x06, len)
+}
+
+const fn eviocgbit(ev: u16, len: usize) -> u64 {
+    ioc(IOC_READ, 0x20 + ev as u64, len)
+}
+
+const fn eviocgabs(abs: u16) -> u64 {
+    ioc(
+        IOC_READ,
+        0x40 + abs as u64,
+        std::mem::size_of::<libc::input_absinfo>(),
+    )
+}
+
+const fn eviocsff() -> u64 {
+    ioc(IOC_WRITE, 0x80, std::mem::size_of::<libc::ff_effect>())
+}
+
+fn test_bit(bits: &[u8], bit: u16) -> bool {
+    bits.get(bit as usize / 8)
+        .map(|byte| byte & (1 << (bit % 8)) != 0)
+        .unwrap_or(false)
+}
+
+struct EvdevDevice {
+    path: PathBuf,
+    file: File,
+    name: String,
+    mapper: EvdevMapper,
+    can_rumble: bool,
+    rumble_effect_id: i16,
+    /// initial axis values read when the device was opened
+    initial_events: Vec<RawInputEvent>,
+}
+
+impl EvdevDevice {
+    /// opens `path` if it looks like a gamepad, `None` otherwise
+    fn open(path: &Path, id: GamepadId) -> Option<Self> {
+        // rumble needs write access, but reading is enough for everything else
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)
+            .or_else(|_| {
+                OpenOptions::new()
+                    .read(true)
+                    .custom_flags(libc::O_NONBLOCK)
+                    .open(path)
+            })
+            .ok()?;
+        let fd = file.as_raw_fd();
+
+        let mut key_bits = [0u8; 96];
+        let mut abs_bits = [0u8; 8];
+        let mut ff_bits = [0u8; 16];
+        let mut name = [0u8; 128];
+        unsafe {
+            if libc::ioctl(
+                fd,
+                eviocgbit(EV_KEY, key_bits.len()) as _,
+                key_bits.as_mut_ptr(),
+            ) < 0
+            {
+                return None;
+            }
+            libc::ioctl(
+                fd,
+                eviocgbit(EV_ABS, abs_bits.len()) as _,
+                abs_bits.as_mut_ptr(),
+            );
+            libc::ioctl(
+                fd,
+                eviocgbit(EV_FF, ff_bits.len()) as _,
+                ff_bits.as_mut_ptr(),
+            );
+            libc::ioctl(fd, eviocgname(name.len()) as _, name.as_mut_ptr());
+        }
+        if !test_bit(&key_bits, BTN_SOUTH) {
+            return None;
+        }
+
+        let mut mapper = EvdevMapper::new(id);
+        let mut initial_events = Vec::new();
+        for code in 0..NUM_ABS_CODES as u16 {
+            if !test_bit(&abs_bits, code) {
+                continue;
+            }
+            let mut info: libc::input_absinfo = unsafe { std::mem::zeroed() };
+            let status = unsafe { libc::ioctl(fd, eviocgabs(code) as _, &mut info) };
+            if status >= 0 {
+                mapper.set_range(code, AbsRange::new(info.minimum, info.maximum, info.flat));
+                initial_events.push(RawInputEvent::new(EV_ABS, code, info.value));
+            }
+        }
+
+        let name_len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+        Some(Self {
+            path: path.to_path_buf(),
+            file,
+            name: String::from_utf8_lossy(&name[..name_len]).into_owned(),
+            mapper,
+            can_rumble: test_bit(&ff_bits, FF_RUMBLE),
+            rumble_effect_id: -1,
+            initial_events,
+        })
+    }
+
+    /// reads everything that is pending, returns `false` once the device is gone
+    fn read<CB>(&mut self, emit: &mut CB) -> bool
+    where
+        CB: FnMut(EventKind),
+    {
+        let mut buffer = [0u8; INPUT_EVENT_SIZE * 64];
+        loop {
+            match self.file.read(&mut buffer) {
+                Ok(0) => return false,
+                Ok(len) => {
+                    for event in decode_input_events(&buffer[..len]) {
+                        if event.type_ == EV_SYN && event.code == SYN_DROPPED {
+                            continue;
+                        }
+                        self.mapper.map(event, &mut *emit);
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return true,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => return false,
+            }
+        }
+    }
+
+    fn rumble(&mut self, low_frequency: f32, high_frequency: f32, duration_ms: u32) -> bool {
+        if !self.can_rumble {
+            return false;
+        }
+        let mut effect: libc::ff_effect = unsafe { std::mem::zeroed() };
+        effect.type_ = FF_RUMBLE;
+        effect.id = self.rumble_effect_id;
+        effect.replay.length = duration_ms.min(u16::MAX as u32) as u16;
+        let rumble = libc::ff_rumble_effect {
+            strong_magnitude: (low_frequency.clamp(0.0, 1.0) * u16::MAX as f32) as u16,
+            weak_magnitude: (high_frequency.clamp(0.0, 1.0) * u16::MAX as f32) as u16,
+        };
+        unsafe {
+            std::ptr::write_unaligned(
+                &mut effect.u as *mut _ as *mut libc::ff_rumble_effect,
+                rumble,
+            );
+            if libc::ioctl(self.file.as_raw_fd(), eviocsff() as _, &mut effect) < 0 {
+                return false;
+            }
+        }
+        self.rumble_effect_id = effect.id;
+
+        let mut bytes = Vec::with_capacity(INPUT_EVENT_SIZE);
+        RawInputEvent::new(EV_FF, effect.id as u16, 1).encode(&mut bytes);
+        self.file.write_all(&bytes).is_ok()
+    }
+}
+
+/// # Description
+/// Finds gamepads in `/dev/input` and turns their input into fluffl events
+/// # Comments
+/// - devices are only readable by users in the `input` group on most distros, devices that can't be
+///   opened are silently skipped
+/// - rumble also needs write access
+pub struct EvdevGamepads {
+    devices: Vec<EvdevDevice>,
+    next_id: GamepadId,
+    last_scan: Option<Instant>,
+}
+
+impl EvdevGamepads {
+    pub fn new() -> Self {
+        Self {
+            devices: Vec::new(),
+            next_id: 0,
+            last_scan: None,
+        }
+    }
+
+    /// ## Description
+    /// picks up newly plugged in devices, reads every pending event and drops unplugged devices
+    /// ## Comments
+    /// - `pads` is updated before the event is handed to `emit`
+    pub fn poll<CB>(&mut self, pads: &mut Gamepads, mut emit: CB)
+    where
+        CB: FnMut(EventKind),
+    {
+        let should_scan = self
+            .last_scan
+            .map(|time| time.elapsed() >= RESCAN_INTERVAL)
+            .unwrap_or(true);
+        if should_scan {
+            self.last_scan = Some(Instant::now());
+            for device in self.scan() {
+                let event = EventKind::GamepadConnected {
+                    id: device.mapper.id,
+                };
+                pads.update(&event);
+                pads.set_name(device.mapper.id, &device.name);
+                emit(event);
+                self.devices.push(device);
+            }
+        }
+
+        let mut emit = |event: EventKind| {
+            pads.update(&event);
+            emit(event);
+        };
+
+        for device in self.devices.iter_mut() {
+            for event in device.initial_events.drain(..) {
+                device.mapper.map(event, &mut emit);
+            }
+        }
+
+        self.devices.retain_mut(|device| {
+            let is_connected = device.read(&mut emit);
+            if !is_connected {
+                emit(EventKind::GamepadDisconnected {
+                    id: device.mapper.id,
+                });
+            }
+            is_connected
+        });
+    }
+
+    fn scan(&mut self) -> Vec<EvdevDevice> {
+        let mut found = Vec::new();
+        let entries = match std::fs::read_dir("/dev/input") {
+            Ok(entries) => entries,
+            Err(_) => return found,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_event_node = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.starts_with("event"))
+                .unwrap_or(false);
+            let is_open = self.devices.iter().any(|device| device.path == path);
+            if !is_event_node || is_open {
+                continue;
+            }
+            if let Some(device) = EvdevDevice::open(&path, self.next_id) {
+                self.next_id += 1;
+                found.push(device);
+            }
+        }
+        found
+    }
+
+    /// returns `false` if `id` isn't connected or can't rumble
+    pub fn rumble(
+        &mut self,
+        id: GamepadId,
+        low_frequency: f32,
+        high_frequency: f32,
+        duration_ms: u32,
+    ) -> bool {
+        self.devices
+            .iter_mut()
+            .find(|device| device.mapper.id == id)
+            .map(|device| device.rumble(low_frequency, high_frequency, duration_ms))
+            .unwrap_or(false)
+    }
+}
+
+impl Default for EvdevGamepads {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+fn map_fixture(mapper: &mut EvdevMapper, fixture: &[RawInputEvent]) -> Vec<EventKind> {
+    let mut bytes = Vec::new();
+    for event in fixture {
+        event.encode(&mut bytes);
+    }
+    // a partial trailing event must not be decoded
+    bytes.extend_from_slice(&[0xff; 5]);
+
+    let mut events = Vec::new();
+    for event in decode_input_events(&bytes) {
+        mapper.map(event, |e| events.push(e));
+    }
+    events
+}
+
+#[test]
+fn evdev_buttons_and_hat() {
+    // recorded from an xbox one pad: A press/release, hat left then right then centered
+    let fixture = [
+        RawInputEvent::new(EV_KEY, 0x130, 1),
+        RawInputEvent::new(EV_SYN, 0, 0),
+        RawInputEvent::new(EV_KEY, 0x130, 0),
+        RawInputEvent::new(EV_SYN, 0, 0),
+        RawInputEvent::new(EV_ABS, ABS_HAT0X, -1),
+        RawInputEvent::new(EV_SYN, 0, 0),
+        RawInputEvent::new(EV_ABS, ABS_HAT0X, 1),
+        RawInputEvent::new(EV_SYN, 0, 0),
+        RawInputEvent::new(EV_ABS, ABS_HAT0X, 0),
+        RawInputEvent::new(EV_SYN, 0, 0),
+    ];
+    let events = map_fixture(&mut EvdevMapper::new(1), &fixture);
+
+    let mut pads = Gamepads::new();
+    pads.update(&EventKind::GamepadConnected { id: 1 });
+    let mut history = Vec::new();
+    for event in &events {
+        pads.update(event);
+        history.push(pads.get(1).unwrap().buttons());
+    }
+    use GamepadButton::*;
+    assert_eq!(
+        history,
+        vec![A.mask(), 0, DPadLeft.mask(), 0, DPadRight.mask(), 0]
+    );
+}
+
+#[test]
+fn evdev_axes_respect_range_and_deadzone() {
+    let mut mapper = EvdevMapper::new(0);
+    mapper.set_range(0x00, AbsRange::new(-32768, 32767, 4000));
+    mapper.set_range(0x02, AbsRange::new(0, 1023, 0));
+
+    let fixture = [
+        // inside the deadzone
+        RawInputEvent::new(EV_ABS, 0x00, 1500),
+        RawInputEvent::new(EV_ABS, 0x00, -32768),
+        RawInputEvent::new(EV_ABS, 0x02, 1023),
+        // digital right trigger
+        RawInputEvent::new(EV_KEY, BTN_TR2, 1),
+        // key repeat is ignored
+        RawInputEvent::new(EV_KEY, 0x131, 2),
+    ];
+    let events = map_fixture(&mut mapper, &fixture);
+    let axes: Vec<_> = events
+        .iter()
+        .map(|event| match *event {
+            EventKind::GamepadAxisMotion { axis, value, .. } => (axis, value),
+            _ => panic!("unexpected event {:?}", event),
+        })
+        .collect();
+    assert_eq!(
+        axes,
+        vec![
+            (GamepadAxis::LeftX, -1.0),
+            (GamepadAxis::TriggerLeft, 1.0),
+            (GamepadAxis::TriggerRight, 1.0),
+        ]
+    );
+}
@@ -0,0 +1,693 @@
+use super::{event_util::EventKind, touch_tracker::TouchTracker};
+use crate::time::Instant;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Debug)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl SwipeDirection {
+    /// the dominant direction of a displacement, screen space so `Up` is negative y
+    pub fn from_disp(dx: f32, dy: f32) -> Self {
+        if dx.abs() > dy.abs() {
+            if dx < 0.0 {
+                Self::Left
+            } else {
+                Self::Right
+            }
+        } else if dy < 0.0 {
+            Self::Up
+        } else {
+            Self::Down
+        }
+    }
+}
+
+/// where a continuous gesture (pinch/rotate) is in its lifetime
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Debug)]
+pub enum GesturePhase {
+    Began,
+    Changed,
+    Ended,
+}
+
+/// # Description
+/// A gesture recognized from a stream of touch events
+/// # Comments
+/// - positions are in the same coordinates as the touch events they came from
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum Gesture {
+    /// a short touch that didn't move
+    Tap { x: f32, y: f32 },
+    /// a second tap close to the previous one, the first tap is still reported as a `Tap`
+    DoubleTap { x: f32, y: f32 },
+    /// a touch that stayed put for a while, fires while the finger is still down
+    LongPress { x: f32, y: f32 },
+    /// a quick single finger flick
+    /// # Members
+    /// - `x`/`y` - where the finger lifted
+    /// - `vx`/`vy` - average velocity over the whole swipe in units per second
+    Swipe {
+        direction: SwipeDirection,
+        x: f32,
+        y: f32,
+        vx: f32,
+        vy: f32,
+    },
+    /// two fingers moving apart/together
+    /// # Members
+    /// - `x`/`y` - the point between the two fingers
+    /// - `scale` - finger distance relative to when the pinch began
+    /// - `delta_scale` - scale relative to the previous pinch event
+    Pinch {
+        phase: GesturePhase,
+        x: f32,
+        y: f32,
+        scale: f32,
+        delta_scale: f32,
+    },
+    /// two fingers twisting around each other
+    /// # Members
+    /// - `angle` - radians relative to when the rotation began, positive is clockwise on screen
+    /// - `delta_angle` - radians relative to the previous rotate event
+    Rotate {
+        phase: GesturePhase,
+        x: f32,
+        y: f32,
+        angle: f32,
+        delta_angle: f32,
+    },
+}
+
+impl Gesture {
+    /// where the gesture happened
+    pub fn pos(&self) -> [f32; 2] {
+        match *self {
+            Self::Tap { x, y }
+            | Self::DoubleTap { x, y }
+            | Self::LongPress { x, y }
+            | Self::Swipe { x, y, .. }
+            | Self::Pinch { x, y, .. }
+            | Self::Rotate { x, y, .. } => [x, y],
+        }
+    }
+}
+
+/// where the time fed to a `GestureRecognizer` comes from, returns milliseconds
+pub type GestureClock = Box<dyn FnMut() -> f64>;
+
+/// ## Description
+/// a `GestureClock` reading real time, it starts at `0` when this is called
+/// ## Comments
+/// - tests should use a clock they advance by hand instead, so they don't depend on how fast they run
+pub fn wall_clock() -> GestureClock {
+    let start = Instant::now();
+    Box::new(move || start.elapsed().as_millis() as f64)
+}
+
+/// Thresholds used by `GestureRecognizer`, distances are in touch event units
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct GestureConfig {
+    /// a touch held longer than this isn't a tap
+    pub tap_max_duration_ms: f64,
+    /// a touch that travels farther than this isn't a tap or long press
+    pub tap_max_distance: f32,
+    /// the most time allowed between the two taps of a double tap
+    pub double_tap_max_interval_ms: f64,
+    /// the farthest the two taps of a double tap can be apart
+    pub double_tap_max_distance: f32,
+    pub long_press_min_duration_ms: f64,
+    pub swipe_min_distance: f32,
+    /// units per second
+    pub swipe_min_velocity: f32,
+    /// how much the finger distance has to change (relative) before a pinch begins
+    pub pinch_min_scale_change: f32,
+    /// radians the fingers have to turn before a rotation begins
+    pub rotate_min_angle: f32,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            tap_max_duration_ms: 250.0,
+            tap_max_distance: 10.0,
+            double_tap_max_interval_ms: 300.0,
+            double_tap_max_distance: 30.0,
+            long_press_min_duration_ms: 500.0,
+            swipe_min_distance: 48.0,
+            swipe_min_velocity: 200.0,
+            pinch_min_scale_change: 0.05,
+            rotate_min_angle: 0.1,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct FingerState {
+    start_pos: [f32; 2],
+    start_time: f64,
+    pos: [f32; 2],
+    /// moved beyond `tap_max_distance` at some point
+    has_wandered: bool,
+    long_press_fired: bool,
+}
+
+/// the two fingers a pinch/rotate is measured between
+#[derive(Copy, Clone)]
+struct TwoFingerState {
+    fingers: [i32; 2],
+    start_dist: f32,
+    start_angle: f32,
+    last_scale: f32,
+    last_angle: f32,
+    is_pinching: bool,
+    is_rotating: bool,
+}
+
+/// # Description
+/// Turns `TouchDown`/`TouchMove`/`TouchUp` events into taps, double taps, long presses, swipes,
+/// pinches and rotations
+/// # Comments
+/// - time is passed in explicitly (in milliseconds) so scripted touch sequences can be replayed
+/// - call `Self::poll(..)` every frame, long presses fire while the finger is still down so they
+///   can't wait for the next touch event
+/// - once a second finger touches down nothing in that touch session is reported as a
+///   tap/long press/swipe anymore
+pub struct GestureRecognizer {
+    config: GestureConfig,
+    tracker: TouchTracker<i32>,
+    fingers: HashMap<i32, FingerState>,
+    two_finger: Option<TwoFingerState>,
+    is_multi_touch_session: bool,
+    last_tap: Option<(f64, [f32; 2])>,
+    gestures: VecDeque<Gesture>,
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        Self::new(GestureConfig::default())
+    }
+}
+
+impl GestureRecognizer {
+    pub fn new(config: GestureConfig) -> Self {
+        Self {
+            config,
+            tracker: TouchTracker::new(),
+            fingers: HashMap::new(),
+            two_finger: None,
+            is_multi_touch_session: false,
+            last_tap: None,
+            gestures: VecDeque::new(),
+        }
+    }
+
+    pub fn config(&self) -> &GestureConfig {
+        &self.config
+    }
+
+    pub fn config_mut(&mut self) -> &mut GestureConfig {
+        &mut self.config
+    }
+
+    /// the per-finger displacement tracker the recognizer is built on
+    pub fn tracker(&self) -> &TouchTracker<i32> {
+        &self.tracker
+    }
+
+    /// number of fingers currently down
+    pub fn num_fingers(&self) -> usize {
+        self.fingers.len()
+    }
+
+    /// feeds a window event at time `now_ms`, anything that isn't a touch event is ignored
+    pub fn handle_event(&mut self, event: &EventKind, now_ms: f64) {
+        self.poll(now_ms);
+        match *event {
+            EventKind::TouchDown {
+                finger_id, x, y, ..
+            } => self.touch_down(finger_id, [x, y], now_ms),
+            EventKind::TouchMove {
+                finger_id, x, y, ..
+            } => self.touch_move(finger_id, [x, y]),
+            EventKind::TouchUp {
+                finger_id, x, y, ..
+            } => self.touch_up(finger_id, [x, y], now_ms),
+            _ => (),
+        }
+    }
+
+    /// fires long presses that are due at `now_ms`
+    pub fn poll(&mut self, now_ms: f64) {
+        if self.is_multi_touch_session {
+            return;
+        }
+        let config = self.config;
+        for finger in self.fingers.values_mut() {
+            let is_due = now_ms - finger.start_time >= config.long_press_min_duration_ms;
+            if is_due && !finger.has_wandered && !finger.long_press_fired {
+                finger.long_press_fired = true;
+                let [x, y] = finger.pos;
+                self.gestures.push_back(Gesture::LongPress { x, y });
+            }
+        }
+    }
+
+    /// removes and returns every gesture recognized so far, oldest first
+    pub fn flush_iter_mut(&mut self) -> impl Iterator<Item = Gesture> + '_ {
+        self.gestures.drain(..)
+    }
+
+    /// forgets every finger and any gesture in progress
+    pub fn reset(&mut self) {
+        self.tracker.clear();
+        self.fingers.clear();
+        self.two_finger = None;
+        self.is_multi_touch_session = false;
+        self.last_tap = None;
+        self.gestures.clear();
+    }
+
+    fn touch_down(&mut self, id: i32, pos: [f32; 2], now_ms: f64) {
+        self.tracker.remove(&id);
+        self.tracker.get_touch_displacement(id, pos);
+        self.fingers.insert(
+            id,
+            FingerState {
+                start_pos: pos,
+                start_time: now_ms,
+                pos,
+                has_wandered: false,
+                long_press_fired: false,
+            },
+        );
+
+        if self.fingers.len() >= 2 {
+            self.is_multi_touch_session = true;
+        }
+        if self.fingers.len() == 2 && self.two_finger.is_none() {
+            let mut ids = self.fingers.keys().copied();
+            let fingers = [ids.next().unwrap(), ids.next().unwrap()];
+            let (_, dist, angle) = self.measure(fingers);
+            self.two_finger = Some(TwoFingerState {
+                fingers,
+                start_dist: dist.max(f32::EPSILON),
+                start_angle: angle,
+                last_scale: 1.0,
+                last_angle: 0.0,
+                is_pinching: false,
+                is_rotating: false,
+            });
+        }
+    }
+
+    fn touch_move(&mut self, id: i32, pos: [f32; 2]) {
+        let tap_max_distance = self.config.tap_max_distance;
+        let finger = match self.fingers.get_mut(&id) {
+            Some(finger) => finger,
+            None => return,
+        };
+        self.tracker.get_touch_displacement(id, pos);
+        finger.pos = pos;
+        if distance(finger.start_pos, pos) > tap_max_distance {
+            finger.has_wandered = true;
+        }
+
+        let mut two_finger = match self.two_finger {
+            Some(two_finger) if two_finger.fingers.contains(&id) => two_finger,
+            _ => return,
+        };
+        let ([x, y], dist, angle) = self.measure(two_finger.fingers);
+        let scale = dist / two_finger.start_dist;
+        let angle = wrap_angle(angle - two_finger.start_angle);
+
+        if !two_finger.is_pinching && (scale - 1.0).abs() >= self.config.pinch_min_scale_change {
+            two_finger.is_pinching = true;
+            two_finger.last_scale = 1.0;
+            self.gestures.push_back(Gesture::Pinch {
+                phase: GesturePhase::Began,
+                x,
+                y,
+                scale: 1.0,
+                delta_scale: 1.0,
+            });
+        }
+        if two_finger.is_pinching {
+            self.gestures.push_back(Gesture::Pinch {
+                phase: GesturePhase::Changed,
+                x,
+                y,
+                scale,
+                delta_scale: scale / two_finger.last_scale,
+            });
+            two_finger.last_scale = scale;
+        }
+
+        if !two_finger.is_rotating && angle.abs() >= self.config.rotate_min_angle {
+            two_finger.is_rotating = true;
+            two_finger.last_angle = 0.0;
+            self.gestures.push_back(Gesture::Rotate {
+                phase: GesturePhase::Began,
+                x,
+                y,
+                angle: 0.0,
+                delta_angle: 0.0,
+            });
+        }
+        if two_finger.is_rotating {
+            self.gestures.push_back(Gesture::Rotate {
+                phase: GesturePhase::Changed,
+                x,
+                y,
+                angle,
+                delta_angle: wrap_angle(angle - two_finger.last_angle),
+            });
+            two_finger.last_angle = angle;
+        }
+
+        self.two_finger = Some(two_finger);
+    }
+
+    fn touch_up(&mut self, id: i32, pos: [f32; 2], now_ms: f64) {
+        let finger = self.fingers.remove(&id);
+        self.tracker.remove(&id);
+
+        if let Some(two_finger) = self.two_finger {
+            if two_finger.fingers.contains(&id) {
+                self.end_two_finger_gestures(two_finger, pos);
+                self.two_finger = None;
+            }
+        }
+
+        let finger = match finger {
+            Some(finger) => finger,
+            None => return,
+        };
+
+        if self.is_multi_touch_session {
+            if self.fingers.is_empty() {
+                self.is_multi_touch_session = false;
+            }
+            return;
+        }
+        if finger.long_press_fired {
+            return;
+        }
+
+        let config = self.config;
+        let duration = now_ms - finger.start_time;
+        let [x, y] = pos;
+        let dx = x - finger.start_pos[0];
+        let dy = y - finger.start_pos[1];
+
+        let is_tap = !finger.has_wandered
+            && distance(finger.start_pos, pos) <= config.tap_max_distance
+            && duration <= config.tap_max_duration_ms;
+        if is_tap {
+            let is_double_tap = self
+                .last_tap
+                .map(|(time, last_pos)| {
+                    now_ms - time <= config.double_tap_max_interval_ms
+                        && distance(last_pos, pos) <= config.double_tap_max_distance
+                })
+                .unwrap_or(false);
+            self.gestures.push_back(Gesture::Tap { x, y });
+            if is_double_tap {
+                self.gestures.push_back(Gesture::DoubleTap { x, y });
+                self.last_tap = None;
+            } else {
+                self.last_tap = Some((now_ms, pos));
+            }
+            return;
+        }
+
+        // frames can batch a whole flick into a single update, so the duration is floored at 1ms
+        let seconds = (duration.max(1.0) / 1000.0) as f32;
+        let (vx, vy) = (dx / seconds, dy / seconds);
+        let speed = (vx * vx + vy * vy).sqrt();
+        if (dx * dx + dy * dy).sqrt() >= config.swipe_min_distance
+            && speed >= config.swipe_min_velocity
+        {
+            self.gestures.push_back(Gesture::Swipe {
+                direction: SwipeDirection::from_disp(dx, dy),
+                x,
+                y,
+                vx,
+                vy,
+            });
+        }
+    }
+
+    fn end_two_finger_gestures(&mut self, two_finger: TwoFingerState, lifted_pos: [f32; 2]) {
+        // the lifted finger is already gone, so whichever one is left is the other finger
+        let other = two_finger
+            .fingers
+            .iter()
+            .find_map(|id| self.fingers.get(id))
+            .map(|finger| finger.pos);
+        let [x, y] = match other {
+            Some(other) => midpoint(other, lifted_pos),
+            None => lifted_pos,
+        };
+        if two_finger.is_pinching {
+            self.gestures.push_back(Gesture::Pinch {
+                phase: GesturePhase::Ended,
+                x,
+                y,
+                scale: two_finger.last_scale,
+                delta_scale: 1.0,
+            });
+        }
+        if two_finger.is_rotating {
+            self.gestures.push_back(Gesture::Rotate {
+                phase: GesturePhase::Ended,
+                x,
+                y,
+                angle: two_finger.last_angle,
+                delta_angle: 0.0,
+            });
+        }
+    }
+
+    /// returns `(center, distance, angle)` of the two fingers
+    fn measure(&self, fingers: [i32; 2]) -> ([f32; 2], f32, f32) {
+        let a = self.fingers[&fingers[0]].pos;
+        let b = self.fingers[&fingers[1]].pos;
+        let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+        (midpoint(a, b), distance(a, b), dy.atan2(dx))
+    }
+}
+
+fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    (dx * dx + dy * dy).sqrt()
+}
+
+fn midpoint(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [(a[0] + b[0]) * 0.5, (a[1] + b[1]) * 0.5]
+}
+
+/// wraps to `[-pi,pi]` so turning past the atan2 seam doesn't jump by a full turn
+fn wrap_angle(angle: f32) -> f32 {
+    use std::f32::consts::{PI, TAU};
+    let wrapped = (angle + PI).rem_euclid(TAU) - PI;
+    if wrapped < -PI {
+        wrapped + TAU
+    } else {
+        wrapped
+    }
+}
+
+#[cfg(test)]
+fn touch(kind: &str, finger_id: i32, x: f32, y: f32) -> EventKind {
+    let (dx, dy) = (0.0, 0.0);
+    match kind {
+        "down" => EventKind::TouchDown {
+            finger_id,
+            x,
+            y,
+            dx,
+            dy,
+        },
+        "move" => EventKind::TouchMove {
+            finger_id,
+            x,
+            y,
+            dx,
+            dy,
+        },
+        _ => EventKind::TouchUp {
+            finger_id,
+            x,
+            y,
+            dx,
+            dy,
+        },
+    }
+}
+
+#[cfg(test)]
+fn run_script(recognizer: &mut GestureRecognizer, script: &[(f64, EventKind)]) -> Vec<Gesture> {
+    for (time, event) in script {
+        recognizer.handle_event(event, *time);
+    }
+    recognizer.flush_iter_mut().collect()
+}
+
+#[test]
+fn gesture_tap_double_tap_and_long_press() {
+    let mut recognizer = GestureRecognizer::default();
+
+    let gestures = run_script(
+        &mut recognizer,
+        &[
+            (0.0, touch("down", 0, 100.0, 100.0)),
+            (80.0, touch("move", 0, 103.0, 101.0)),
+            (120.0, touch("up", 0, 103.0, 101.0)),
+            (250.0, touch("down", 1, 110.0, 98.0)),
+            (300.0, touch("up", 1, 110.0, 98.0)),
+        ],
+    );
+    assert!(matches!(
+        gestures[..],
+        [
+            Gesture::Tap { .. },
+            Gesture::Tap { .. },
+            Gesture::DoubleTap { x, .. }
+        ] if x == 110.0
+    ));
+
+    // too slow for a tap, the long press fires from poll() while the finger is still down
+    recognizer.handle_event(&touch("down", 2, 50.0, 50.0), 1000.0);
+    recognizer.poll(1400.0);
+    assert_eq!(recognizer.flush_iter_mut().count(), 0);
+    recognizer.poll(1600.0);
+    let gestures = run_script(&mut recognizer, &[(2000.0, touch("up", 2, 50.0, 50.0))]);
+    assert!(matches!(gestures[..], [Gesture::LongPress { x, y }] if x == 50.0 && y == 50.0));
+
+    // wandering off cancels both
+    let gestures = run_script(
+        &mut recognizer,
+        &[
+            (3000.0, touch("down", 3, 0.0, 0.0)),
+            (3100.0, touch("move", 3, 30.0, 0.0)),
+            (3900.0, touch("move", 3, 0.0, 0.0)),
+            (4000.0, touch("up", 3, 0.0, 0.0)),
+        ],
+    );
+    assert!(gestures.is_empty());
+}
+
+#[test]
+fn gesture_swipe_direction_and_velocity() {
+    let mut recognizer = GestureRecognizer::default();
+    let gestures = run_script(
+        &mut recognizer,
+        &[
+            (0.0, touch("down", 0, 300.0, 200.0)),
+            (50.0, touch("move", 0, 250.0, 205.0)),
+            (100.0, touch("move", 0, 200.0, 210.0)),
+            (100.0, touch("up", 0, 200.0, 210.0)),
+        ],
+    );
+    match gestures[..] {
+        [Gesture::Swipe {
+            direction, vx, vy, ..
+        }] => {
+            assert_eq!(direction, SwipeDirection::Left);
+            assert!((vx + 1000.0).abs() < 1e-3);
+            assert!((vy - 100.0).abs() < 1e-3);
+        }
+        _ => panic!("expected a swipe, got {:?}", gestures),
+    }
+
+    // far enough but way too slow
+    recognizer.config_mut().swipe_min_velocity = 500.0;
+    let gestures = run_script(
+        &mut recognizer,
+        &[
+            (1000.0, touch("down", 0, 0.0, 0.0)),
+            (1100.0, touch("move", 0, 0.0, 50.0)),
+            (2000.0, touch("up", 0, 0.0, 100.0)),
+        ],
+    );
+    assert!(gestures.is_empty());
+}
+
+#[test]
+fn gesture_pinch_and_rotate() {
+    let mut recognizer = GestureRecognizer::default();
+    let gestures = run_script(
+        &mut recognizer,
+        &[
+            (0.0, touch("down", 0, 100.0, 100.0)),
+            (10.0, touch("down", 1, 200.0, 100.0)),
+            // fingers spread symmetrically to twice the distance
+            (50.0, touch("move", 0, 50.0, 100.0)),
+            (50.0, touch("move", 1, 250.0, 100.0)),
+            (100.0, touch("up", 1, 250.0, 100.0)),
+            (120.0, touch("up", 0, 50.0, 100.0)),
+        ],
+    );
+    let pinches: Vec<_> = gestures
+        .iter()
+        .filter_map(|g| match *g {
+            Gesture::Pinch {
+                phase, scale, x, ..
+            } => Some((phase, scale, x)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(pinches.first().map(|p| p.0), Some(GesturePhase::Began));
+    let &(phase, scale, x) = pinches.last().unwrap();
+    assert_eq!(phase, GesturePhase::Ended);
+    assert!((scale - 2.0).abs() < 1e-4);
+    assert!((x - 150.0).abs() < 1e-4);
+    // no taps or swipes come out of a two finger session
+    assert!(gestures.iter().all(|g| matches!(g, Gesture::Pinch { .. })));
+
+    // a quarter turn around the center without changing distance
+    let gestures = run_script(
+        &mut recognizer,
+        &[
+            (1000.0, touch("down", 0, 100.0, 100.0)),
+            (1000.0, touch("down", 1, 200.0, 100.0)),
+            (1050.0, touch("move", 0, 150.0, 50.0)),
+            (1050.0, touch("move", 1, 150.0, 150.0)),
+            (1100.0, touch("up", 0, 150.0, 50.0)),
+            (1100.0, touch("up", 1, 150.0, 150.0)),
+        ],
+    );
+    let angle = gestures
+        .iter()
+        .filter_map(|g| match *g {
+            Gesture::Rotate {
+                phase: GesturePhase::Ended,
+                angle,
+                ..
+            } => Some(angle),
+            _ => None,
+        })
+        .next()
+        .expect("rotation should end");
+    assert!((angle.abs() - std::f32::consts::FRAC_PI_2).abs() < 1e-4);
+    // the first finger moving alone briefly squeezes them together, but it ends where it started
+    let final_scale = gestures.iter().rev().find_map(|g| match *g {
+        Gesture::Pinch { scale, .. } => Some(scale),
+        _ => None,
+    });
+    assert!((final_scale.unwrap_or(1.0) - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn wrap_angle_stays_in_range() {
+    use std::f32::consts::PI;
+    assert!((wrap_angle(1.5 * PI) + 0.5 * PI).abs() < 1e-5);
+    assert!((wrap_angle(-1.5 * PI) - 0.5 * PI).abs() < 1e-5);
+    assert_eq!(wrap_angle(0.25), 0.25);
+}
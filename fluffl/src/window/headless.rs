@@ -0,0 +1,323 @@
+use super::{
+    dropped_file::DroppedFiles,
+    event_util::{EventKind, EventRecording, GamepadId},
+    gamepad::Gamepads,
+    *,
+};
+use crate::ogl::Capture;
+
+#[cfg(feature = "headless")]
+use crate::FlufflState;
+
+/// # Description
+/// A window that never shows up on screen, for running apps in CI or rendering on a server
+/// # Comments
+/// - on linux an offscreen OSMesa context gets created if libOSMesa is installed, otherwise there is no GL
+///   at all and `HasFlufflWindow::gl()` panics, check `Self::has_gl()` first
+/// - input comes from a script (an `EventRecording`) and from whatever gets pushed into `get_events()`
+/// - with the `headless` feature this is what `FlufflWindow` refers to, so apps run unchanged. The
+///   `FLUFFL_HEADLESS_SCRIPT` (path to a recording) and `FLUFFL_HEADLESS_FRAMES` (frame limit)
+///   environment variables configure it in that case
+pub struct HeadlessWindow {
+    gl: Option<GlowGL>,
+    /// keeps the offscreen context alive
+    #[cfg(target_os = "linux")]
+    _context: Option<be_glutin::Context<be_glutin::PossiblyCurrent>>,
+    events: FlufflEvent,
+    width: u32,
+    height: u32,
+    gamepads: Gamepads,
+    dropped_files: DroppedFiles,
+    attributes: WindowAttributes,
+    has_script: bool,
+    frame: u64,
+    max_frames: Option<u64>,
+}
+
+impl HeadlessWindow {
+    /// true if an offscreen GL context could be created
+    pub fn has_gl(&self) -> bool {
+        self.gl.is_some()
+    }
+
+    /// ## Description
+    /// replays `script` one frame at a time, a `Quit` event gets queued once it runs out
+    pub fn set_script(&mut self, script: EventRecording) {
+        self.has_script = true;
+        self.events.start_replay(script);
+    }
+
+    /// ## Description
+    /// makes `Self::run(..)` stop after `max_frames` frames, `None` lets it run until the app stops it
+    pub fn set_max_frames(&mut self, max_frames: Option<u64>) {
+        self.max_frames = max_frames;
+    }
+
+    /// how many frames started so far
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// ## Description
+    /// call this once before every frame when driving the window by hand instead of `Self::run(..)`
+    /// ## Comments
+    /// - returns `false` once `max_frames` frames have been started
+    pub fn begin_frame(&mut self) -> bool {
+        if self.max_frames.is_some_and(|max| self.frame >= max) {
+            return false;
+        }
+        if self.has_script && !self.events.is_replaying() {
+            self.has_script = false;
+            self.events.push_event(EventKind::Quit);
+        }
+        self.frame += 1;
+        true
+    }
+
+    /// ## Description
+    /// reads the whole offscreen framebuffer back
+    pub fn capture(&self) -> Result<Capture, Error> {
+        let gl = self.gl.as_ref().ok_or_else(|| {
+            Error::UnsupportedError(String::from("the headless window has no GL context"))
+        })?;
+        Ok(Capture::from_framebuffer(gl, 0, 0, self.width, self.height))
+    }
+
+    /// ## Description
+    /// calls `core_loop` once per frame, until the app stops running or the frame limit is hit
+    #[cfg(feature = "headless")]
+    pub fn run<Loop, LoopOut, State>(self, app_state: State, core_loop: Loop)
+    where
+        Loop: Fn(FlufflWindowPtr, FlufflRunning, FlufflState<State>) -> LoopOut + Copy + 'static,
+        LoopOut: std::future::Future<Output = ()>,
+        State: 'static,
+    {
+        let window_ptr = FlufflWindowPtr {
+            ptr: Arc::new(RefCell::new(self)),
+        };
+        let state_ptr = FlufflState::new(app_state);
+
+        let mut is_running = true;
+        while is_running && window_ptr.window_mut().begin_frame() {
+            futures::executor::block_on(core_loop(
+                window_ptr.clone(),
+                FlufflRunning::new(&mut is_running),
+                state_ptr.clone(),
+            ));
+        }
+    }
+
+    /// reads the `FLUFFL_HEADLESS_*` variables described on the struct
+    fn configure_from_env(&mut self) -> Result<(), Error> {
+        if let Ok(path) = std::env::var("FLUFFL_HEADLESS_SCRIPT") {
+            let file = std::fs::File::open(&path)?;
+            let script = EventRecording::read_from(std::io::BufReader::new(file))
+                .map_err(|err| Error::IOError(format!("{}: {}", path, err)))?;
+            self.set_script(script);
+        }
+        if let Ok(frames) = std::env::var("FLUFFL_HEADLESS_FRAMES") {
+            let max_frames = frames
+                .parse::<u64>()
+                .map_err(|err| Error::GenericError(format!("FLUFFL_HEADLESS_FRAMES: {}", err)))?;
+            self.set_max_frames(Some(max_frames));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn create_offscreen_context(
+    width: u32,
+    height: u32,
+) -> Result<(be_glutin::Context<be_glutin::PossiblyCurrent>, GlowGL), Error> {
+    use be_glutin::platform::unix::HeadlessContextExt;
+
+    let context = be_glutin::ContextBuilder::new()
+        .build_osmesa(be_glutin::dpi::PhysicalSize::new(width, height))
+        .map_err(|err| Error::UnsupportedError(err.to_string()))?;
+    let context = unsafe { context.make_current() }
+        .map_err(|(_, err)| Error::WindowInitError(err.to_string()))?;
+    let gl =
+        unsafe { glow::Context::from_loader_function(|s| context.get_proc_address(s) as *const _) };
+    Ok((context, Arc::new(Box::new(gl))))
+}
+
+impl HasFlufflWindow for HeadlessWindow {
+    fn init(config: &str) -> Result<Self, Error> {
+        let settings = FlufflWindowConfigs::new()
+            .parse_config_file(config)
+            .map_err(|err| Error::WindowInitError(err.to_string()))?;
+
+        #[cfg(target_os = "linux")]
+        let (context, gl) = match create_offscreen_context(settings.width, settings.height) {
+            Ok((context, gl)) => (Some(context), Some(gl)),
+            Err(_) => (None, None),
+        };
+        #[cfg(not(target_os = "linux"))]
+        let gl = None;
+
+        let mut window = Self {
+            gl,
+            #[cfg(target_os = "linux")]
+            _context: context,
+            events: FlufflEvent::new(),
+            width: settings.width,
+            height: settings.height,
+            gamepads: Gamepads::new(),
+            dropped_files: DroppedFiles::new(),
+            attributes: WindowAttributes::new(&settings),
+            has_script: false,
+            frame: 0,
+            max_frames: None,
+        };
+        window.configure_from_env()?;
+        Ok(window)
+    }
+
+    fn get_events(&mut self) -> &mut FlufflEvent {
+        &mut self.events
+    }
+
+    fn gl(&self) -> GlowGL {
+        self.gl
+            .clone()
+            .expect("the headless window has no GL context, check HeadlessWindow::has_gl()")
+    }
+
+    fn audio_context(&self) -> FlufflAudioContext {
+        FlufflAudioContext::default()
+    }
+
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn set_fullscreen(&mut self, _go_fullscreen: bool) {}
+
+    fn gamepads(&self) -> &Gamepads {
+        &self.gamepads
+    }
+
+    fn rumble(&mut self, _: GamepadId, _: f32, _: f32, _: u32) -> bool {
+        false
+    }
+
+    fn dropped_files(&self) -> &DroppedFiles {
+        &self.dropped_files
+    }
+
+    fn dropped_files_mut(&mut self) -> &mut DroppedFiles {
+        &mut self.dropped_files
+    }
+
+    fn attributes(&self) -> &WindowAttributes {
+        &self.attributes
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.attributes.title = String::from(title);
+    }
+
+    /// the offscreen buffer can't be resized once it is created
+    fn set_size(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        if self.has_gl() {
+            return Err(Error::UnsupportedError(String::from(
+                "the offscreen buffer can't be resized",
+            )));
+        }
+        self.width = width;
+        self.height = height;
+        self.events.push_event(EventKind::Resize {
+            width: width as i32,
+            height: height as i32,
+        });
+        Ok(())
+    }
+
+    fn position(&self) -> Result<(i32, i32), Error> {
+        Ok((0, 0))
+    }
+
+    fn set_position(&mut self, _x: i32, _y: i32) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn set_min_size(&mut self, size: Option<(u32, u32)>) -> Result<(), Error> {
+        self.attributes.min_size = size;
+        Ok(())
+    }
+
+    fn set_max_size(&mut self, size: Option<(u32, u32)>) -> Result<(), Error> {
+        self.attributes.max_size = size;
+        Ok(())
+    }
+
+    fn set_vsync(&mut self, enabled: bool) -> Result<(), Error> {
+        self.attributes.vsync = enabled;
+        Ok(())
+    }
+
+    fn set_cursor_visible(&mut self, visible: bool) {
+        self.attributes.cursor_visible = visible;
+    }
+
+    fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        self.attributes.cursor_icon = icon;
+    }
+
+    fn set_cursor_grab(&mut self, grab: bool) -> Result<(), Error> {
+        self.attributes.cursor_grabbed = grab;
+        Ok(())
+    }
+
+    fn set_relative_mouse_mode(&mut self, enabled: bool) -> Result<(), Error> {
+        self.attributes.relative_mouse_mode = enabled;
+        Ok(())
+    }
+
+    fn scale_factor(&self) -> f64 {
+        1.0
+    }
+}
+
+#[test]
+fn headless_window_plays_its_script() {
+    use super::event_util::KeyCode;
+
+    let mut window = HeadlessWindow::init(r#"{"width":64,"height":32}"#).unwrap();
+    assert_eq!(window.get_bounds(), (64, 32));
+
+    let mut script = EventRecording::new();
+    script.push_frame(
+        0.0,
+        [EventKind::KeyDown {
+            code: KeyCode::KEY_A,
+        }],
+    );
+    script.push_frame(16.0, []);
+    window.set_script(script);
+    window.set_max_frames(Some(10));
+
+    let mut frames = Vec::new();
+    while window.begin_frame() {
+        let events: Vec<_> = window.get_events().flush_iter_mut().collect();
+        let quit = events.iter().any(|event| matches!(event, EventKind::Quit));
+        frames.push(events.len());
+        if quit {
+            break;
+        }
+    }
+    // two scripted frames, then the quit
+    assert_eq!(frames, vec![1, 0, 1]);
+    assert_eq!(window.frame(), 3);
+
+    if window.has_gl() {
+        assert_eq!(window.capture().unwrap().pixels().len(), 64 * 32 * 4);
+    } else {
+        assert!(window.capture().is_err());
+    }
+}
@@ -0,0 +1,617 @@
+pub use super::gesture::SwipeDirection;
+use super::{
+    event_util::{EventKind, GamepadAxis, GamepadButton, GamepadId, KeyCode, MouseCode},
+    gesture::{self, Gesture, GestureClock, GestureRecognizer},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// # Description
+/// A single physical input that can drive an action
+/// # Comments
+/// - `WheelUp`,`WheelDown` and `TouchSwipe(..)` are impulses, they are pressed and released in the
+///   same frame and are never held
+/// - `TouchSwipe(..)` fires for the swipes `GestureRecognizer` recognizes, using its thresholds
+/// - gamepad buttons are shared by every connected pad
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Debug)]
+pub enum Binding {
+    Key(KeyCode),
+    /// only `LEFT_BUTTON` and `RIGHT_BUTTON` make sense here, use `WheelUp`/`WheelDown` for the wheel
+    Mouse(MouseCode),
+    WheelUp,
+    WheelDown,
+    /// held while any finger is on the screen
+    Touch,
+    TouchSwipe(SwipeDirection),
+    GamepadButton(GamepadButton),
+}
+
+/// # Description
+/// Something that produces an analog value for a named axis
+/// # Comments
+/// - when several bindings of an axis are active, the one with the largest magnitude wins
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum AxisBinding {
+    /// `-1` while `negative` is held, `1` while `positive` is held, `0` if both or neither are
+    Digital {
+        negative: Binding,
+        positive: Binding,
+    },
+    /// the value of `axis` furthest from zero across every pad, values inside `deadzone` snap to zero
+    Gamepad {
+        axis: GamepadAxis,
+        deadzone: f32,
+        invert: bool,
+    },
+    /// wheel clicks this frame, times `scale`
+    MouseWheel { scale: f32 },
+    /// horizontal mouse displacement this frame, times `scale`
+    MouseX { scale: f32 },
+    /// vertical mouse displacement this frame, times `scale`
+    MouseY { scale: f32 },
+}
+
+/// # Description
+/// The serializable part of an `InputMap`: which bindings belong to which action/axis
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize, Debug)]
+pub struct InputConfig {
+    pub actions: BTreeMap<String, Vec<Binding>>,
+    pub axes: BTreeMap<String, Vec<AxisBinding>>,
+}
+
+impl InputConfig {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("input config should always serialize")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+/// # Description
+/// Maps raw `EventKind`s to named actions and axes so game code can ask "was `jump` pressed?"
+/// instead of matching on every key, button and gamepad that could mean jump.
+/// # Comments
+/// - feed it every event with `InputMap::handle_event(..)` and call `InputMap::begin_frame()`
+///   before the events of a new frame, or just call `InputMap::update(..)` which does both
+/// - bindings can be changed at any time, queries always use the current bindings
+/// - swipes are timed with `gesture::wall_clock()` unless `InputMap::set_gesture_clock(..)` says otherwise
+pub struct InputMap {
+    config: InputConfig,
+    held: HashSet<Binding>,
+    held_at_frame_start: HashSet<Binding>,
+    pressed: HashSet<Binding>,
+    released: HashSet<Binding>,
+    gamepad_buttons: HashSet<(GamepadId, GamepadButton)>,
+    gamepad_axes: HashMap<(GamepadId, GamepadAxis), f32>,
+    gestures: GestureRecognizer,
+    gesture_clock: GestureClock,
+    wheel: f32,
+    mouse_disp: [f32; 2],
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self {
+            config: InputConfig::default(),
+            held: HashSet::new(),
+            held_at_frame_start: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+            gamepad_buttons: HashSet::new(),
+            gamepad_axes: HashMap::new(),
+            gestures: GestureRecognizer::default(),
+            gesture_clock: gesture::wall_clock(),
+            wheel: 0.0,
+            mouse_disp: [0.0; 2],
+        }
+    }
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_config(config: InputConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    pub fn config(&self) -> &InputConfig {
+        &self.config
+    }
+
+    /// replaces every binding, the current state of the devices is kept
+    pub fn set_config(&mut self, config: InputConfig) {
+        self.config = config;
+    }
+
+    /// the recognizer `TouchSwipe(..)` bindings are driven by, use it to tune the swipe thresholds
+    pub fn gestures(&self) -> &GestureRecognizer {
+        &self.gestures
+    }
+
+    pub fn gestures_mut(&mut self) -> &mut GestureRecognizer {
+        &mut self.gestures
+    }
+
+    /// replaces the clock swipes are timed with
+    pub fn set_gesture_clock<F>(&mut self, clock: F)
+    where
+        F: FnMut() -> f64 + 'static,
+    {
+        self.gesture_clock = Box::new(clock);
+    }
+
+    pub fn with_action<I>(mut self, action: &str, bindings: I) -> Self
+    where
+        I: IntoIterator<Item = Binding>,
+    {
+        for binding in bindings {
+            self.bind(action, binding);
+        }
+        self
+    }
+
+    pub fn with_axis<I>(mut self, axis: &str, bindings: I) -> Self
+    where
+        I: IntoIterator<Item = AxisBinding>,
+    {
+        for binding in bindings {
+            self.bind_axis(axis, binding);
+        }
+        self
+    }
+
+    /// adds `binding` to `action`, creating the action if it doesn't exist yet
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        let bindings = self.config.actions.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// removes `binding` from `action`, returns `false` if it wasn't bound
+    pub fn unbind(&mut self, action: &str, binding: Binding) -> bool {
+        match self.config.actions.get_mut(action) {
+            Some(bindings) => {
+                let len = bindings.len();
+                bindings.retain(|&b| b != binding);
+                bindings.len() != len
+            }
+            None => false,
+        }
+    }
+
+    /// ## Description
+    /// swaps `old` for `new` in `action`, keeping its position in the binding list
+    /// ## Comments
+    /// - if `old` isn't bound, `new` gets appended instead
+    pub fn rebind(&mut self, action: &str, old: Binding, new: Binding) {
+        let bindings = self.config.actions.entry(action.to_string()).or_default();
+        match bindings.iter().position(|&b| b == old) {
+            Some(index) => bindings[index] = new,
+            None => bindings.push(new),
+        }
+        let mut seen = HashSet::new();
+        bindings.retain(|&b| seen.insert(b));
+    }
+
+    /// removes every binding of `action`
+    pub fn clear_action(&mut self, action: &str) {
+        self.config.actions.remove(action);
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.config
+            .actions
+            .get(action)
+            .map(|bindings| &bindings[..])
+            .unwrap_or(&[])
+    }
+
+    pub fn bind_axis(&mut self, axis: &str, binding: AxisBinding) {
+        let bindings = self.config.axes.entry(axis.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn clear_axis(&mut self, axis: &str) {
+        self.config.axes.remove(axis);
+    }
+
+    pub fn axis_bindings(&self, axis: &str) -> &[AxisBinding] {
+        self.config
+            .axes
+            .get(axis)
+            .map(|bindings| &bindings[..])
+            .unwrap_or(&[])
+    }
+
+    /// ## Description
+    /// returns the first binding that went down this frame, handy for "press a key to rebind" menus
+    /// ## Comments
+    /// - when several bindings went down in the same frame the pick is arbitrary
+    pub fn any_pressed(&self) -> Option<Binding> {
+        self.pressed.iter().copied().next()
+    }
+
+    /// forgets what happened last frame, call this before handling the events of a new frame
+    pub fn begin_frame(&mut self) {
+        self.held_at_frame_start.clone_from(&self.held);
+        self.pressed.clear();
+        self.released.clear();
+        self.wheel = 0.0;
+        self.mouse_disp = [0.0; 2];
+    }
+
+    /// `begin_frame()` followed by `handle_event(..)` for every event
+    pub fn update<'a, I>(&mut self, events: I)
+    where
+        I: IntoIterator<Item = &'a EventKind>,
+    {
+        self.begin_frame();
+        for event in events {
+            self.handle_event(event);
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &EventKind) {
+        match *event {
+            EventKind::KeyDown { code } => self.press(Binding::Key(code)),
+            EventKind::KeyUp { code } => self.release(Binding::Key(code)),
+            EventKind::MouseDown { button_code, .. } => self.press(Binding::Mouse(button_code)),
+            EventKind::MouseUp { button_code, .. } => self.release(Binding::Mouse(button_code)),
+            EventKind::MouseMove { dx, dy, .. } => {
+                self.mouse_disp[0] += dx;
+                self.mouse_disp[1] += dy;
+            }
+            EventKind::MouseWheel {
+                button_code: MouseCode::WHEEL { direction },
+            } => {
+                self.wheel += direction as f32;
+                match direction.signum() {
+                    1 => self.impulse(Binding::WheelUp),
+                    -1 => self.impulse(Binding::WheelDown),
+                    _ => (),
+                }
+            }
+            EventKind::TouchDown { .. } => {
+                self.handle_touch_event(event);
+                self.press(Binding::Touch);
+            }
+            EventKind::TouchMove { .. } => self.handle_touch_event(event),
+            EventKind::TouchUp { .. } => {
+                self.handle_touch_event(event);
+                if self.gestures.num_fingers() == 0 {
+                    self.release(Binding::Touch);
+                }
+            }
+            EventKind::GamepadButtonDown { id, button } => {
+                self.gamepad_buttons.insert((id, button));
+                self.press(Binding::GamepadButton(button));
+            }
+            EventKind::GamepadButtonUp { id, button } => {
+                self.gamepad_buttons.remove(&(id, button));
+                self.release_gamepad_button_if_unheld(button);
+            }
+            EventKind::GamepadAxisMotion { id, axis, value } => {
+                self.gamepad_axes.insert((id, axis), value);
+            }
+            EventKind::GamepadDisconnected { id } => {
+                let buttons: Vec<_> = self
+                    .gamepad_buttons
+                    .iter()
+                    .filter(|&&(pad, _)| pad == id)
+                    .map(|&(_, button)| button)
+                    .collect();
+                for button in buttons {
+                    self.gamepad_buttons.remove(&(id, button));
+                    self.release_gamepad_button_if_unheld(button);
+                }
+                self.gamepad_axes.retain(|&(pad, _), _| pad != id);
+            }
+            _ => (),
+        }
+    }
+
+    /// `true` while any binding of `action` is held
+    pub fn is_held(&self, action: &str) -> bool {
+        self.bindings(action).iter().any(|b| self.held.contains(b))
+    }
+
+    /// `true` on the frame `action` went from not held to held (or an impulse binding fired)
+    pub fn is_pressed(&self, action: &str) -> bool {
+        let bindings = self.bindings(action);
+        let was_held = bindings
+            .iter()
+            .any(|b| self.held_at_frame_start.contains(b));
+        !was_held && bindings.iter().any(|b| self.pressed.contains(b))
+    }
+
+    /// `true` on the frame `action` stopped being held (or an impulse binding fired)
+    pub fn is_released(&self, action: &str) -> bool {
+        let bindings = self.bindings(action);
+        !self.is_held(action) && bindings.iter().any(|b| self.released.contains(b))
+    }
+
+    /// the value of `axis` this frame, `0` if it isn't bound
+    pub fn axis(&self, axis: &str) -> f32 {
+        self.axis_bindings(axis)
+            .iter()
+            .map(|&binding| self.axis_binding_value(binding))
+            .fold(0.0, furthest_from_zero)
+    }
+
+    fn axis_binding_value(&self, binding: AxisBinding) -> f32 {
+        let is_active = |b: Binding| self.held.contains(&b) || self.pressed.contains(&b);
+        match binding {
+            AxisBinding::Digital { negative, positive } => {
+                let negative = if is_active(negative) { 1.0 } else { 0.0 };
+                let positive = if is_active(positive) { 1.0 } else { 0.0 };
+                positive - negative
+            }
+            AxisBinding::Gamepad {
+                axis,
+                deadzone,
+                invert,
+            } => {
+                let value = self
+                    .gamepad_axes
+                    .iter()
+                    .filter(|(&(_, a), _)| a == axis)
+                    .map(|(_, &value)| value)
+                    .fold(0.0, furthest_from_zero);
+                let value = if value.abs() <= deadzone { 0.0 } else { value };
+                if invert {
+                    -value
+                } else {
+                    value
+                }
+            }
+            AxisBinding::MouseWheel { scale } => self.wheel * scale,
+            AxisBinding::MouseX { scale } => self.mouse_disp[0] * scale,
+            AxisBinding::MouseY { scale } => self.mouse_disp[1] * scale,
+        }
+    }
+
+    fn handle_touch_event(&mut self, event: &EventKind) {
+        let now = (self.gesture_clock)();
+        self.gestures.handle_event(event, now);
+        let swipes: Vec<_> = self
+            .gestures
+            .flush_iter_mut()
+            .filter_map(|gesture| match gesture {
+                Gesture::Swipe { direction, .. } => Some(direction),
+                _ => None,
+            })
+            .collect();
+        for direction in swipes {
+            self.impulse(Binding::TouchSwipe(direction));
+        }
+    }
+
+    fn press(&mut self, binding: Binding) {
+        // key repeat sends KeyDown without a KeyUp in between, that isn't a new press
+        if self.held.insert(binding) {
+            self.pressed.insert(binding);
+        }
+    }
+
+    fn release(&mut self, binding: Binding) {
+        if self.held.remove(&binding) {
+            self.released.insert(binding);
+        }
+    }
+
+    fn impulse(&mut self, binding: Binding) {
+        self.pressed.insert(binding);
+        self.released.insert(binding);
+    }
+
+    fn release_gamepad_button_if_unheld(&mut self, button: GamepadButton) {
+        let held_elsewhere = self.gamepad_buttons.iter().any(|&(_, b)| b == button);
+        if !held_elsewhere {
+            self.release(Binding::GamepadButton(button));
+        }
+    }
+}
+
+/// keeps whichever of the two is further from zero
+fn furthest_from_zero(best: f32, value: f32) -> f32 {
+    if value.abs() > best.abs() {
+        value
+    } else {
+        best
+    }
+}
+
+#[test]
+fn input_map_pressed_held_released() {
+    let mut input = InputMap::new().with_action(
+        "jump",
+        [
+            Binding::Key(KeyCode::SPACE),
+            Binding::GamepadButton(GamepadButton::A),
+        ],
+    );
+    let space_down = EventKind::KeyDown {
+        code: KeyCode::SPACE,
+    };
+    let space_up = EventKind::KeyUp {
+        code: KeyCode::SPACE,
+    };
+
+    input.update(&[space_down]);
+    assert!(input.is_pressed("jump") && input.is_held("jump"));
+
+    // key repeat is not a new press
+    input.update(&[space_down]);
+    assert!(!input.is_pressed("jump") && input.is_held("jump"));
+
+    // a second binding going down while the action is held isn't a new press either
+    input.update(&[EventKind::GamepadButtonDown {
+        id: 0,
+        button: GamepadButton::A,
+    }]);
+    assert!(!input.is_pressed("jump"));
+
+    // still held by the pad
+    input.update(&[space_up]);
+    assert!(input.is_held("jump") && !input.is_released("jump"));
+
+    input.update(&[EventKind::GamepadDisconnected { id: 0 }]);
+    assert!(!input.is_held("jump") && input.is_released("jump"));
+
+    // a tap within a single frame is both pressed and released
+    input.update(&[space_down, space_up]);
+    assert!(input.is_pressed("jump") && input.is_released("jump") && !input.is_held("jump"));
+
+    input.update(&[]);
+    assert!(!input.is_pressed("jump") && !input.is_released("jump"));
+    assert!(!input.is_pressed("not an action"));
+}
+
+#[test]
+fn input_map_axes_and_impulses() {
+    let mut input = InputMap::new()
+        .with_axis(
+            "walk",
+            [
+                AxisBinding::Digital {
+                    negative: Binding::Key(KeyCode::ARROW_L),
+                    positive: Binding::Key(KeyCode::ARROW_R),
+                },
+                AxisBinding::Gamepad {
+                    axis: GamepadAxis::LeftX,
+                    deadzone: 0.2,
+                    invert: false,
+                },
+            ],
+        )
+        .with_axis("zoom", [AxisBinding::MouseWheel { scale: 0.5 }])
+        .with_action(
+            "next",
+            [Binding::WheelUp, Binding::TouchSwipe(SwipeDirection::Left)],
+        );
+
+    input.update(&[
+        EventKind::KeyDown {
+            code: KeyCode::ARROW_L,
+        },
+        EventKind::GamepadAxisMotion {
+            id: 0,
+            axis: GamepadAxis::LeftX,
+            value: 0.5,
+        },
+    ]);
+    assert_eq!(input.axis("walk"), -1.0);
+
+    input.update(&[EventKind::KeyUp {
+        code: KeyCode::ARROW_L,
+    }]);
+    assert_eq!(input.axis("walk"), 0.5);
+
+    input.update(&[EventKind::GamepadAxisMotion {
+        id: 0,
+        axis: GamepadAxis::LeftX,
+        value: 0.1,
+    }]);
+    assert_eq!(input.axis("walk"), 0.0);
+
+    // one pad leaving doesn't take the other pad's stick with it
+    input.update(&[
+        EventKind::GamepadAxisMotion {
+            id: 1,
+            axis: GamepadAxis::LeftX,
+            value: -0.75,
+        },
+        EventKind::GamepadDisconnected { id: 0 },
+    ]);
+    assert_eq!(input.axis("walk"), -0.75);
+    input.update(&[EventKind::GamepadDisconnected { id: 1 }]);
+    assert_eq!(input.axis("walk"), 0.0);
+
+    input.update(&[
+        EventKind::MouseWheel {
+            button_code: MouseCode::WHEEL { direction: 1 },
+        },
+        EventKind::MouseWheel {
+            button_code: MouseCode::WHEEL { direction: 1 },
+        },
+    ]);
+    assert_eq!(input.axis("zoom"), 1.0);
+    assert!(input.is_pressed("next") && input.is_released("next"));
+
+    // a 200 unit flick over 100ms
+    let time_ms = std::rc::Rc::new(std::cell::Cell::new(0.0));
+    let clock = time_ms.clone();
+    input.set_gesture_clock(move || clock.get());
+    input.update(&[EventKind::TouchDown {
+        finger_id: 4,
+        x: 300.0,
+        y: 100.0,
+        dx: 0.0,
+        dy: 0.0,
+    }]);
+    assert!(!input.is_pressed("next"));
+    time_ms.set(100.0);
+    input.update(&[EventKind::TouchUp {
+        finger_id: 4,
+        x: 100.0,
+        y: 110.0,
+        dx: 0.0,
+        dy: 0.0,
+    }]);
+    assert_eq!(input.axis("zoom"), 0.0);
+    assert!(input.is_pressed("next"));
+}
+
+#[test]
+fn input_map_rebind_and_serialize() {
+    let mut input = InputMap::new().with_action(
+        "fire",
+        [
+            Binding::Key(KeyCode::KEY_F),
+            Binding::Mouse(MouseCode::LEFT_BUTTON),
+        ],
+    );
+    input.rebind(
+        "fire",
+        Binding::Key(KeyCode::KEY_F),
+        Binding::Key(KeyCode::KEY_G),
+    );
+    assert_eq!(
+        input.bindings("fire"),
+        &[
+            Binding::Key(KeyCode::KEY_G),
+            Binding::Mouse(MouseCode::LEFT_BUTTON)
+        ]
+    );
+    assert!(input.unbind("fire", Binding::Mouse(MouseCode::LEFT_BUTTON)));
+    assert!(!input.unbind("fire", Binding::Mouse(MouseCode::LEFT_BUTTON)));
+    input.bind_axis(
+        "look",
+        AxisBinding::Gamepad {
+            axis: GamepadAxis::RightY,
+            deadzone: 0.1,
+            invert: true,
+        },
+    );
+
+    let json = input.config().to_json();
+    let config = InputConfig::from_json(&json).unwrap();
+    assert_eq!(&config, input.config());
+
+    let mut input = InputMap::from_config(config);
+    input.update(&[EventKind::KeyDown {
+        code: KeyCode::KEY_G,
+    }]);
+    assert!(input.is_pressed("fire"));
+    assert_eq!(input.any_pressed(), Some(Binding::Key(KeyCode::KEY_G)));
+}
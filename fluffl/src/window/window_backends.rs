@@ -12,8 +12,14 @@ use super::*;
 //     }
 // }
 
+// sdl2 takes priority over headless like it does for audio, `window::headless` isn't compiled next to it
 cfg_if::cfg_if! {
-    if #[cfg(all(not(all(target_family = "wasm", not(target_os = "wasi"))), feature="sdl2"  ))]  {
+    if #[cfg(all(not(all(target_family = "wasm", not(target_os = "wasi"))), feature="headless", not(feature="sdl2")  ))]  {
+        /// the headless window stands in for the real one, so apps run unchanged without a display
+        mod window_backend {
+            pub use super::headless::HeadlessWindow as FlufflWindow;
+        }
+    }else if #[cfg(all(not(all(target_family = "wasm", not(target_os = "wasi"))), feature="sdl2"  ))]  {
         /// This just forwards to the sdl audio backend
         #[path ="./window_backends/sdl2_window.rs"]
         mod window_backend;
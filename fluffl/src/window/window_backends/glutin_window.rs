@@ -1,21 +1,27 @@
 use super::{
+    dropped_file::{DroppedFile, DroppedFiles},
     event_util::{constants::*, FlufflEvent},
+    gamepad::Gamepads,
     *,
 };
 use crate::FlufflState;
 
+#[cfg(target_os = "linux")]
+use super::gamepad::evdev::EvdevGamepads;
+
 use be_glutin::{
     self,
     dpi::{PhysicalPosition, PhysicalSize},
     event::{
-        DeviceId, ElementState, Event, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent,
+        DeviceEvent, DeviceId, ElementState, Event, MouseButton, MouseScrollDelta, VirtualKeyCode,
+        WindowEvent,
     },
-    event_loop::ControlFlow,
-    window::Icon,
-    ContextWrapper,
+    event_loop::{ControlFlow, EventLoopWindowTarget},
+    window::{self as be_window, Icon, WindowBuilder},
+    PossiblyCurrent, WindowedContext,
 };
 
-use std::io::Cursor;
+use std::{io::Cursor, mem};
 
 ///Global for touch tracker
 static mut GLOBAL_TOUCH_TRACKER: Option<TouchTracker<DeviceId>> = None;
@@ -37,11 +43,83 @@ impl TouchTracker<DeviceId> {
     }
 }
 
+/// A window along with its GL context.
+/// Making a context current consumes it in glutin, so it has to sit in an `Option`
+struct GlSurface {
+    context: Option<WindowedContext<PossiblyCurrent>>,
+}
+
+impl GlSurface {
+    fn new(context: WindowedContext<PossiblyCurrent>) -> Self {
+        Self {
+            context: Some(context),
+        }
+    }
+
+    fn context(&self) -> &WindowedContext<PossiblyCurrent> {
+        self.context
+            .as_ref()
+            .expect("context got lost while switching contexts")
+    }
+
+    fn window(&self) -> &be_window::Window {
+        self.context().window()
+    }
+
+    fn swap_buffers(&self) -> Result<(), Error> {
+        self.context()
+            .swap_buffers()
+            .map_err(|err| Error::GenericError(err.to_string()))
+    }
+
+    fn make_current(&mut self) -> Result<(), Error> {
+        if self.context().is_current() {
+            return Ok(());
+        }
+        let context = self
+            .context
+            .take()
+            .expect("context got lost while switching contexts");
+        match unsafe { context.make_current() } {
+            Ok(context) => {
+                self.context = Some(context);
+                Ok(())
+            }
+            Err((context, err)) => {
+                self.context = Some(context);
+                Err(Error::GenericError(err.to_string()))
+            }
+        }
+    }
+}
+
+/// A window opened with `HasFlufflWindow::create_window(..)`
+struct ExtraWindow {
+    surface: GlSurface,
+    gl: GlowGL,
+    is_minimized: bool,
+}
+
 pub struct FlufflWindow {
     gl: GlowGL,
     events: FlufflEvent,
     render_loop: Option<be_glutin::event_loop::EventLoop<()>>,
-    window: ContextWrapper<be_glutin::PossiblyCurrent, be_glutin::window::Window>,
+    window: GlSurface,
+    gamepads: Gamepads,
+    #[cfg(target_os = "linux")]
+    evdev_gamepads: EvdevGamepads,
+    attributes: WindowAttributes,
+    /// where the cursor was last seen, `MouseMove` events report this while relative mode is on
+    cursor_pos: [f32; 2],
+    dropped_files: DroppedFiles,
+    /// winit has no minimize event, minimized windows get resized to 0x0 instead
+    is_minimized: bool,
+    extra_windows: HashMap<WindowId, ExtraWindow>,
+    /// windows can only be built through the event loop, so the ones requested while it runs
+    /// get opened at the start of the next frame
+    pending_windows: Vec<(WindowId, FlufflWindowConfigs)>,
+    next_window_id: WindowId,
+    current_window: WindowId,
 }
 
 impl FlufflWindow {
@@ -65,15 +143,13 @@ impl FlufflWindow {
         };
         let state_ptr = FlufflState::new(app_state);
 
-        render_loop.run(move |event, _, control_flow| {
+        render_loop.run(move |event, target, control_flow| {
             let mut is_running = true;
             match event {
                 Event::MainEventsCleared | Event::RedrawRequested(_) => {
-                    window_ptr
-                        .window()
-                        .window
-                        .swap_buffers()
-                        .expect("failed to swap buffers");
+                    window_ptr.window_mut().open_pending_windows(target);
+                    window_ptr.window_mut().poll_gamepads();
+                    window_ptr.window_mut().swap_all_buffers();
                     //execute future
                     futures::executor::block_on(core_loop(
                         window_ptr.clone(),
@@ -93,95 +169,224 @@ impl FlufflWindow {
         });
     }
 
+    /// swaps every window, leaving the main window's context current for the next frame.
+    /// Only the main window has vsync, so it's the one swap that waits for vblank
+    fn swap_all_buffers(&mut self) {
+        for extra in self.extra_windows.values_mut() {
+            if extra.surface.make_current().is_ok() {
+                extra
+                    .surface
+                    .swap_buffers()
+                    .expect("failed to swap buffers");
+            }
+        }
+        self.window
+            .make_current()
+            .expect("failed to make the main context current");
+        self.current_window = MAIN_WINDOW;
+        self.window.swap_buffers().expect("failed to swap buffers");
+    }
+
+    fn open_pending_windows(&mut self, target: &EventLoopWindowTarget<()>) {
+        if self.pending_windows.is_empty() {
+            return;
+        }
+        for (id, settings) in mem::take(&mut self.pending_windows) {
+            match build_shared_window(&self.window, &settings, target) {
+                Ok(extra) => {
+                    self.extra_windows.insert(id, extra);
+                }
+                // nobody is around to take the error, so the window reports itself closed instead
+                Err(_) => self.events.push_window_event(id, EventKind::Quit),
+            }
+        }
+        // building a window leaves its context current
+        let _ = self.make_current(self.current_window);
+    }
+
+    /// maps a winit window to the id handed out by `create_window(..)`
+    fn fluffl_window_id(&self, window_id: be_window::WindowId) -> WindowId {
+        self.extra_windows
+            .iter()
+            .find(|(_, extra)| extra.surface.window().id() == window_id)
+            .map(|(&id, _)| id)
+            .unwrap_or(MAIN_WINDOW)
+    }
+
+    /// winit has no gamepad support, so gamepads are polled separately once per frame
+    fn poll_gamepads(&mut self) {
+        #[cfg(target_os = "linux")]
+        {
+            let events = &mut self.events;
+            self.evdev_gamepads
+                .poll(&mut self.gamepads, |event| events.push_event(event));
+        }
+    }
+
     fn convert_glutin_event_to_fluffl_event(&mut self, glutin_event: be_glutin::event::Event<()>) {
         match glutin_event {
             Event::Resumed => {}
-            Event::WindowEvent { event, .. } => match event {
-                WindowEvent::CloseRequested => {
-                    self.events.push_event(EventKind::Quit);
+            Event::WindowEvent { window_id, event } => {
+                let window = self.fluffl_window_id(window_id);
+                self.convert_window_event(window, event);
+            }
+            // winit has no relative mouse mode, so it's emulated with a grabbed, hidden cursor
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta: (dx, dy) },
+                ..
+            } if self.attributes.relative_mouse_mode => {
+                self.events.push_event(EventKind::MouseMove {
+                    x: self.cursor_pos[0],
+                    y: self.cursor_pos[1],
+                    dx: dx as f32,
+                    dy: dy as f32,
+                })
+            }
+            _ => (),
+        }
+    }
+
+    fn convert_window_event(&mut self, window: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => {
+                self.events.push_window_event(window, EventKind::Quit);
+            }
+            WindowEvent::KeyboardInput {
+                input,
+                is_synthetic: _,
+                ..
+            } => match (input.state, input.virtual_keycode) {
+                (ElementState::Pressed, Some(code)) => {
+                    let code = map_virtual_keycode_to_fluffl(code).expect("couldnt map key");
+                    self.events
+                        .push_window_event(window, EventKind::KeyDown { code })
                 }
-                WindowEvent::KeyboardInput {
-                    input,
-                    is_synthetic: _,
-                    ..
-                } => match (input.state, input.virtual_keycode) {
-                    (ElementState::Pressed, Some(code)) => {
-                        let code = map_virtual_keycode_to_fluffl(code).expect("couldnt map key");
-                        self.events.push_event(EventKind::KeyDown { code })
-                    }
-                    (ElementState::Released, Some(code)) => {
-                        let code = map_virtual_keycode_to_fluffl(code).expect("couldnt map key");
-                        self.events.push_event(EventKind::KeyUp { code })
-                    }
-                    _ => (),
+                (ElementState::Released, Some(code)) => {
+                    let code = map_virtual_keycode_to_fluffl(code).expect("couldnt map key");
+                    self.events
+                        .push_window_event(window, EventKind::KeyUp { code })
+                }
+                _ => (),
+            },
+            // winit reports committed IME text through here as well, but it has no way
+            // of reporting preedit text so no Ime* events get emitted on this backend
+            WindowEvent::ReceivedCharacter(c) if !c.is_control() => self.events.push_window_event(
+                window,
+                EventKind::TextInput {
+                    text: TextChunk::from(c),
                 },
-                WindowEvent::MouseInput {
-                    device_id,
-                    state,
-                    button,
-                    ..
-                } => {
-                    let button_code = mouse_button_to_mouse_code(button)
-                        .expect("glutin::MouseButton could not be translated");
-
-                    let device_state = TouchTracker::get_mut()
-                        .get(&device_id)
-                        .copied()
-                        .expect("device_id not found");
-
-                    let fluffl_event = match state {
-                        ElementState::Pressed => EventKind::MouseDown {
+            ),
+            WindowEvent::MouseInput {
+                device_id,
+                state,
+                button,
+                ..
+            } => {
+                let button_code = mouse_button_to_mouse_code(button)
+                    .expect("glutin::MouseButton could not be translated");
+
+                let device_state = TouchTracker::get_mut()
+                    .get(&device_id)
+                    .copied()
+                    .expect("device_id not found");
+
+                let fluffl_event = match state {
+                    ElementState::Pressed => EventKind::MouseDown {
+                        button_code,
+                        x: device_state.prev_pos[0],
+                        y: device_state.prev_pos[1],
+                    },
+                    ElementState::Released => {
+                        // it appears that I dont have to remove on glutin
+                        // TouchTracker::get_mut().remove(&device_id);
+                        EventKind::MouseUp {
                             button_code,
                             x: device_state.prev_pos[0],
                             y: device_state.prev_pos[1],
-                        },
-                        ElementState::Released => {
-                            // it appears that I dont have to remove on glutin
-                            // TouchTracker::get_mut().remove(&device_id);
-                            EventKind::MouseUp {
-                                button_code,
-                                x: device_state.prev_pos[0],
-                                y: device_state.prev_pos[1],
-                            }
                         }
-                    };
+                    }
+                };
 
-                    self.events.push_event(fluffl_event);
+                self.events.push_window_event(window, fluffl_event);
+            }
+            // raw motion gets reported through `DeviceEvent::MouseMotion` instead
+            WindowEvent::CursorMoved { .. }
+                if window == MAIN_WINDOW && self.attributes.relative_mouse_mode => {}
+            WindowEvent::CursorMoved {
+                device_id,
+                position: PhysicalPosition { x, y },
+                ..
+            } => {
+                if window == MAIN_WINDOW {
+                    self.cursor_pos = [x as f32, y as f32];
                 }
-                WindowEvent::CursorMoved {
-                    device_id,
-                    position: PhysicalPosition { x, y },
-                    ..
-                } => {
-                    let device_displacement = TouchTracker::get_mut()
-                        .get_touch_displacement(device_id, [x as f32, y as f32]);
-
-                    self.events.push_event(EventKind::MouseMove {
+                let device_displacement =
+                    TouchTracker::get_mut().get_touch_displacement(device_id, [x as f32, y as f32]);
+
+                self.events.push_window_event(
+                    window,
+                    EventKind::MouseMove {
                         x: x as f32,
                         y: y as f32,
                         dx: device_displacement[0],
                         dy: device_displacement[1],
-                    })
-                }
+                    },
+                )
+            }
 
-                WindowEvent::MouseWheel { delta, .. } => {
-                    self.events.push_event(EventKind::MouseWheel {
-                        button_code: MouseCode::WHEEL {
-                            direction: match delta {
-                                MouseScrollDelta::LineDelta(_x, y) => y.signum() as i32,
-                                _ => 0,
-                            },
+            WindowEvent::MouseWheel { delta, .. } => self.events.push_window_event(
+                window,
+                EventKind::MouseWheel {
+                    button_code: MouseCode::WHEEL {
+                        direction: match delta {
+                            MouseScrollDelta::LineDelta(_x, y) => y.signum() as i32,
+                            _ => 0,
+                        },
+                    },
+                },
+            ),
+            WindowEvent::Resized(PhysicalSize { width, height }) => {
+                let is_minimized = width == 0 || height == 0;
+                let was_minimized = match self.extra_windows.get_mut(&window) {
+                    Some(extra) => &mut extra.is_minimized,
+                    None => &mut self.is_minimized,
+                };
+                if is_minimized != *was_minimized {
+                    *was_minimized = is_minimized;
+                    self.events.push_window_event(
+                        window,
+                        if is_minimized {
+                            EventKind::Minimized
+                        } else {
+                            EventKind::Restored
                         },
-                    })
+                    );
                 }
-                WindowEvent::Resized(PhysicalSize { width, height }) => {
-                    self.events.push_event(EventKind::Resize {
+                self.events.push_window_event(
+                    window,
+                    EventKind::Resize {
                         width: width as i32,
                         height: height as i32,
-                    })
-                }
-                _ => (),
-            },
+                    },
+                )
+            }
+            WindowEvent::Focused(true) => self
+                .events
+                .push_window_event(window, EventKind::FocusGained),
+            WindowEvent::Focused(false) => {
+                self.events.push_window_event(window, EventKind::FocusLost)
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => self.events.push_window_event(
+                window,
+                EventKind::ScaleFactorChanged {
+                    scale_factor: scale_factor as f32,
+                },
+            ),
+            WindowEvent::DroppedFile(path) => {
+                let id = self.dropped_files.insert(DroppedFile::from_path(path));
+                self.events
+                    .push_window_event(window, EventKind::FileDropped { id })
+            }
             _ => (),
         }
     }
@@ -193,57 +398,44 @@ impl HasFlufflWindow for FlufflWindow {
             .expect("parse error");
 
         let event_loop = be_glutin::event_loop::EventLoop::new();
-        let mut window_builder = be_glutin::window::WindowBuilder::new();
-
-        window_builder = window_builder
-            .with_title(settings.title)
-            .with_inner_size(be_glutin::dpi::LogicalSize::new(
-                settings.width,
-                settings.height,
-            ))
-            .with_resizable(settings.resizable);
-
-        match settings.icon {
-            Some(IconSetting::Path(path)) => {
-                let mut ico =
-                    crate::codecs::ico::Ico::load(std::fs::File::open(path).unwrap()).unwrap();
-                let entry = ico.entries.swap_remove(0);
-                window_builder = window_builder.with_window_icon(
-                    Icon::from_rgba(entry.bitmap, entry.width as u32, entry.height as u32).ok(),
-                );
-            }
-            Some(IconSetting::Base64(b64)) => {
-                let ico_bytes = crate::codecs::base64::decode(b64).expect("decode failed");
-                let mut ico = crate::codecs::ico::Ico::load(Cursor::new(ico_bytes)).unwrap();
-                let entry = ico.entries.swap_remove(0);
-                window_builder = window_builder.with_window_icon(
-                    Icon::from_rgba(entry.bitmap, entry.width as u32, entry.height as u32).ok(),
-                );
-            }
-            _ => (),
-        }
+        let attributes = WindowAttributes::new(&settings);
 
         let window = unsafe {
             be_glutin::ContextBuilder::new()
-                .with_vsync(true)
-                .build_windowed(window_builder, &event_loop)
+                .with_vsync(settings.vsync)
+                .with_gl_debug_flag(settings.gl_debug)
+                .build_windowed(window_builder(&settings), &event_loop)
                 .expect("failed to build window")
                 .make_current()
                 .expect("failed to make_current(..)")
         };
-        let gl = unsafe {
+        let gl: GlowGL = Arc::new(Box::new(unsafe {
             glow::Context::from_loader_function(|s| window.get_proc_address(s) as *const _)
-        };
+        }));
+        if settings.gl_debug {
+            crate::ogl::state::enable_debug_output(&gl);
+        }
 
         // window.window().set_cursor_visible(false);
 
         TouchTracker::init();
 
         Ok(Self {
-            window,
             events: FlufflEvent::new(),
             render_loop: Some(event_loop),
-            gl: Arc::new(Box::new(gl)),
+            gl,
+            window: GlSurface::new(window),
+            gamepads: Gamepads::new(),
+            #[cfg(target_os = "linux")]
+            evdev_gamepads: EvdevGamepads::new(),
+            attributes,
+            cursor_pos: [0.0; 2],
+            dropped_files: DroppedFiles::new(),
+            is_minimized: false,
+            extra_windows: HashMap::new(),
+            pending_windows: Vec::new(),
+            next_window_id: MAIN_WINDOW + 1,
+            current_window: MAIN_WINDOW,
         })
     }
 
@@ -252,7 +444,10 @@ impl HasFlufflWindow for FlufflWindow {
     }
 
     fn gl(&self) -> GlowGL {
-        self.gl.clone()
+        match self.extra_windows.get(&self.current_window) {
+            Some(extra) => extra.gl.clone(),
+            None => self.gl.clone(),
+        }
     }
 
     fn audio_context(&self) -> FlufflAudioContext {
@@ -274,6 +469,267 @@ impl HasFlufflWindow for FlufflWindow {
             go_fullscreen.then_some(be_glutin::window::Fullscreen::Borderless(monitor_handle)),
         );
     }
+
+    fn gamepads(&self) -> &Gamepads {
+        &self.gamepads
+    }
+
+    #[cfg(target_os = "linux")]
+    fn rumble(
+        &mut self,
+        id: GamepadId,
+        low_frequency: f32,
+        high_frequency: f32,
+        duration_ms: u32,
+    ) -> bool {
+        self.evdev_gamepads
+            .rumble(id, low_frequency, high_frequency, duration_ms)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn rumble(&mut self, _: GamepadId, _: f32, _: f32, _: u32) -> bool {
+        false
+    }
+
+    fn dropped_files(&self) -> &DroppedFiles {
+        &self.dropped_files
+    }
+
+    fn dropped_files_mut(&mut self) -> &mut DroppedFiles {
+        &mut self.dropped_files
+    }
+
+    fn attributes(&self) -> &WindowAttributes {
+        &self.attributes
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.window.window().set_title(title);
+        self.attributes.title = String::from(title);
+    }
+
+    fn set_size(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        self.window
+            .window()
+            .set_inner_size(PhysicalSize::new(width, height));
+        Ok(())
+    }
+
+    fn position(&self) -> Result<(i32, i32), Error> {
+        self.window
+            .window()
+            .outer_position()
+            .map(|PhysicalPosition { x, y }| (x, y))
+            .map_err(|err| Error::UnsupportedError(err.to_string()))
+    }
+
+    fn set_position(&mut self, x: i32, y: i32) -> Result<(), Error> {
+        self.window
+            .window()
+            .set_outer_position(PhysicalPosition::new(x, y));
+        Ok(())
+    }
+
+    fn set_min_size(&mut self, size: Option<(u32, u32)>) -> Result<(), Error> {
+        self.window
+            .window()
+            .set_min_inner_size(size.map(|(w, h)| PhysicalSize::new(w, h)));
+        self.attributes.min_size = size;
+        Ok(())
+    }
+
+    fn set_max_size(&mut self, size: Option<(u32, u32)>) -> Result<(), Error> {
+        self.window
+            .window()
+            .set_max_inner_size(size.map(|(w, h)| PhysicalSize::new(w, h)));
+        self.attributes.max_size = size;
+        Ok(())
+    }
+
+    fn set_vsync(&mut self, enabled: bool) -> Result<(), Error> {
+        if enabled == self.attributes.vsync {
+            return Ok(());
+        }
+        Err(Error::UnsupportedError(String::from(
+            "glutin can't change vsync after the window is created, use the `vsync` config tag",
+        )))
+    }
+
+    fn set_cursor_visible(&mut self, visible: bool) {
+        self.attributes.cursor_visible = visible;
+        self.window
+            .window()
+            .set_cursor_visible(visible && !self.attributes.relative_mouse_mode);
+    }
+
+    fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        self.window.window().set_cursor_icon(map_cursor_icon(icon));
+        self.attributes.cursor_icon = icon;
+    }
+
+    fn set_cursor_grab(&mut self, grab: bool) -> Result<(), Error> {
+        // relative mode needs the grab, it gets released when relative mode ends
+        if !self.attributes.relative_mouse_mode {
+            self.window
+                .window()
+                .set_cursor_grab(grab)
+                .map_err(|err| Error::UnsupportedError(err.to_string()))?;
+        }
+        self.attributes.cursor_grabbed = grab;
+        Ok(())
+    }
+
+    fn set_relative_mouse_mode(&mut self, enabled: bool) -> Result<(), Error> {
+        let window = self.window.window();
+        window
+            .set_cursor_grab(enabled || self.attributes.cursor_grabbed)
+            .map_err(|err| Error::UnsupportedError(err.to_string()))?;
+        window.set_cursor_visible(!enabled && self.attributes.cursor_visible);
+        self.attributes.relative_mouse_mode = enabled;
+        Ok(())
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.window.window().scale_factor()
+    }
+
+    fn create_window(&mut self, config: &str) -> Result<WindowId, Error> {
+        let settings = FlufflWindowConfigs::new()
+            .parse_config_file(config)
+            .map_err(|err| Error::WindowInitError(err.to_string()))?;
+        let id = self.next_window_id;
+        self.next_window_id += 1;
+
+        match &self.render_loop {
+            // `run(..)` hasn't been called yet, so the window can be built right away
+            Some(event_loop) => {
+                let extra = build_shared_window(&self.window, &settings, event_loop)?;
+                self.extra_windows.insert(id, extra);
+                self.make_current(self.current_window)?;
+            }
+            None => self.pending_windows.push((id, settings)),
+        }
+        Ok(id)
+    }
+
+    fn close_window(&mut self, id: WindowId) -> Result<(), Error> {
+        let is_pending = self
+            .pending_windows
+            .iter()
+            .any(|&(pending, _)| pending == id);
+        if !is_pending && !self.extra_windows.contains_key(&id) {
+            return Err(Error::GenericError(format!("there is no window {}", id)));
+        }
+        if self.current_window == id {
+            self.make_current(MAIN_WINDOW)?;
+        }
+        self.pending_windows.retain(|&(pending, _)| pending != id);
+        self.extra_windows.remove(&id);
+        self.events.remove_window(id);
+        Ok(())
+    }
+
+    fn window_ids(&self) -> Vec<WindowId> {
+        let mut ids = vec![MAIN_WINDOW];
+        ids.extend(self.extra_windows.keys().copied());
+        ids[1..].sort_unstable();
+        ids
+    }
+
+    fn make_current(&mut self, id: WindowId) -> Result<(), Error> {
+        let surface = match self.extra_windows.get_mut(&id) {
+            Some(extra) => &mut extra.surface,
+            None if id == MAIN_WINDOW => &mut self.window,
+            None => return Err(Error::GenericError(format!("there is no window {}", id))),
+        };
+        surface.make_current()?;
+        self.current_window = id;
+        Ok(())
+    }
+
+    fn current_window(&self) -> WindowId {
+        self.current_window
+    }
+
+    fn window_bounds(&self, id: WindowId) -> Result<(u32, u32), Error> {
+        let window = match self.extra_windows.get(&id) {
+            Some(extra) => extra.surface.window(),
+            None if id == MAIN_WINDOW => self.window.window(),
+            None => return Err(Error::GenericError(format!("there is no window {}", id))),
+        };
+        let PhysicalSize { width, height } = window.inner_size();
+        Ok((width, height))
+    }
+}
+
+fn window_builder(settings: &FlufflWindowConfigs) -> WindowBuilder {
+    let mut window_builder = WindowBuilder::new()
+        .with_title(settings.title.as_str())
+        .with_inner_size(be_glutin::dpi::LogicalSize::new(
+            settings.width,
+            settings.height,
+        ))
+        .with_resizable(settings.resizable);
+
+    match &settings.icon {
+        Some(IconSetting::Path(path)) => {
+            let ico = crate::codecs::ico::Ico::load(std::fs::File::open(path).unwrap()).unwrap();
+            window_builder = window_builder.with_window_icon(window_icon(ico));
+        }
+        Some(IconSetting::Base64(b64)) => {
+            let ico_bytes = crate::codecs::base64::decode(b64).expect("decode failed");
+            let ico = crate::codecs::ico::Ico::load(Cursor::new(ico_bytes)).unwrap();
+            window_builder = window_builder.with_window_icon(window_icon(ico));
+        }
+        _ => (),
+    }
+    window_builder
+}
+
+/// the first entry of `ico` as an RGBA window icon, `None` if it can't be decoded
+fn window_icon(mut ico: crate::codecs::ico::Ico) -> Option<Icon> {
+    let image = ico.entries.swap_remove(0).decode().ok()?;
+    Icon::from_rgba(image.pixels, image.width, image.height).ok()
+}
+
+/// builds a window whose context shares objects with `main`, the new context is left current
+/// ## Comments
+/// - never uses vsync, `swap_all_buffers` would otherwise block once per window every frame
+fn build_shared_window(
+    main: &GlSurface,
+    settings: &FlufflWindowConfigs,
+    target: &EventLoopWindowTarget<()>,
+) -> Result<ExtraWindow, Error> {
+    let context = be_glutin::ContextBuilder::new()
+        .with_vsync(false)
+        .with_shared_lists(main.context())
+        .build_windowed(window_builder(settings), target)
+        .map_err(|err| Error::WindowInitError(err.to_string()))?;
+    let context = unsafe { context.make_current() }
+        .map_err(|(_, err)| Error::WindowInitError(err.to_string()))?;
+    let gl =
+        unsafe { glow::Context::from_loader_function(|s| context.get_proc_address(s) as *const _) };
+    Ok(ExtraWindow {
+        surface: GlSurface::new(context),
+        gl: Arc::new(Box::new(gl)),
+        is_minimized: false,
+    })
+}
+
+fn map_cursor_icon(icon: CursorIcon) -> be_window::CursorIcon {
+    match icon {
+        CursorIcon::Arrow => be_window::CursorIcon::Default,
+        CursorIcon::IBeam => be_window::CursorIcon::Text,
+        CursorIcon::Hand => be_window::CursorIcon::Hand,
+        CursorIcon::Crosshair => be_window::CursorIcon::Crosshair,
+        CursorIcon::Wait => be_window::CursorIcon::Wait,
+        CursorIcon::NotAllowed => be_window::CursorIcon::NotAllowed,
+        CursorIcon::ResizeHorizontal => be_window::CursorIcon::EwResize,
+        CursorIcon::ResizeVertical => be_window::CursorIcon::NsResize,
+        CursorIcon::ResizeNwSe => be_window::CursorIcon::NwseResize,
+        CursorIcon::ResizeNeSw => be_window::CursorIcon::NeswResize,
+        CursorIcon::ResizeAll => be_window::CursorIcon::Move,
+    }
 }
 
 pub fn mouse_button_to_mouse_code(mb: MouseButton) -> Option<MouseCode> {
@@ -1,14 +1,22 @@
 use super::{
+    dropped_file::{DroppedFile, DroppedFiles},
     event_util::{constants::*, FlufflEvent},
+    gamepad::Gamepads,
     *,
 };
 use crate::FlufflState;
 
 use glow::*;
 
-use std::{cell::RefCell, mem, rc::Rc, sync::Arc};
+use std::{cell::RefCell, collections::HashMap, mem, rc::Rc, sync::Arc};
 
-use be_sdl2::{event::Event, keyboard::Scancode, mouse::MouseButton, video::FullscreenType};
+use be_sdl2::{
+    controller::{self, GameController},
+    event::Event,
+    keyboard::Scancode,
+    mouse::{Cursor, MouseButton, SystemCursor},
+    video::{FullscreenType, WindowPos},
+};
 
 ///Global for touch tracker
 static mut GLOBAL_TOUCH_TRACKER: Option<TouchTracker<i32>> = None;
@@ -43,6 +51,10 @@ impl CustomSDL2Window {
         Self { context }
     }
 
+    fn as_sdl2_window(&self) -> &be_sdl2::video::Window {
+        unsafe { mem::transmute(self) }
+    }
+
     fn as_sdl2_window_mut(&mut self) -> &mut be_sdl2::video::Window {
         let mut_ref: &mut Self = self;
         unsafe { mem::transmute(mut_ref) }
@@ -56,6 +68,32 @@ macro_rules! push_event {
     };
 }
 
+/// same as `push_event!` but for events that belong to a specific window
+macro_rules! push_window_event {
+    ( $event_pump:ident , $window:expr, $event:expr  ) => {
+        $event_pump
+            .as_mut()
+            .unwrap()
+            .push_window_event($window, $event)
+    };
+}
+
+/// same as `push_event!` but also keeps the gamepad polling state up to date
+macro_rules! push_gamepad_event {
+    ( $event_pump:ident , $gamepads:ident, $event:expr  ) => {{
+        let event = $event;
+        $gamepads.update(&event);
+        push_event!($event_pump, event)
+    }};
+}
+
+/// A window opened with `HasFlufflWindow::create_window(..)`
+struct ExtraSDL2Window {
+    window: be_sdl2::video::Window,
+    gl_context: be_sdl2::video::GLContext,
+    gl: GlowGL,
+}
+
 #[allow(dead_code)]
 /// A cross-platform window handler, use it to set up opengl and listen to input devices in a platform
 /// agnostic fashion
@@ -71,6 +109,21 @@ pub struct FlufflWindow {
     window_width: u32,
     window_height: u32,
     window_pointer: CustomSDL2Window,
+    /// SDL2 has no explicit preedit start/commit events, so this tracks whether an IME is composing
+    is_composing: bool,
+    controller_ss: be_sdl2::GameControllerSubsystem,
+    /// open controllers keyed by their joystick instance id, which is also their `GamepadId`
+    controllers: HashMap<GamepadId, GameController>,
+    gamepads: Gamepads,
+    attributes: WindowAttributes,
+    /// SDL only keeps a pointer to the active cursor, so it has to be kept alive here
+    cursor: Option<Cursor>,
+    dropped_files: DroppedFiles,
+    /// SDL2 has no event for DPI changes, so the scale factor is compared after the window moves or resizes
+    scale_factor: f64,
+    extra_windows: HashMap<WindowId, ExtraSDL2Window>,
+    next_window_id: WindowId,
+    current_window: WindowId,
 }
 
 impl FlufflWindow {
@@ -111,8 +164,21 @@ impl FlufflWindow {
 
             //execute future
             futures::executor::block_on(unexecuted_iteration);
+
+            window_ptr.window_mut().swap_extra_windows();
         });
     }
+
+    /// the render loop only swaps the main window, so every other window gets swapped here.
+    /// The main context is left current, since that's the one the render loop swaps
+    fn swap_extra_windows(&mut self) {
+        for extra in self.extra_windows.values() {
+            if extra.window.gl_make_current(&extra.gl_context).is_ok() {
+                extra.window.gl_swap_window();
+            }
+        }
+        let _ = self.make_current(MAIN_WINDOW);
+    }
 }
 
 impl HasFlufflWindow for FlufflWindow {
@@ -129,7 +195,10 @@ impl HasFlufflWindow for FlufflWindow {
     }
 
     fn gl(&self) -> Arc<Box<Context>> {
-        self.gl.clone()
+        match self.extra_windows.get(&self.current_window) {
+            Some(extra) => extra.gl.clone(),
+            None => self.gl.clone(),
+        }
     }
 
     fn get_events(&mut self) -> &mut FlufflEvent {
@@ -145,11 +214,15 @@ impl HasFlufflWindow for FlufflWindow {
         let sdl = be_sdl2::init()?;
         let audio = sdl.audio()?;
         let video = sdl.video()?;
+        let controller_ss = sdl.game_controller()?;
 
         let gl_attr = video.gl_attr();
 
         gl_attr.set_context_profile(be_sdl2::video::GLProfile::Core);
         gl_attr.set_context_version(settings.context_major, settings.context_minor);
+        if settings.gl_debug {
+            gl_attr.set_context_flags().debug().set();
+        }
         //set stencil buffer
         video.gl_attr().set_stencil_size(8);
 
@@ -175,9 +248,12 @@ impl HasFlufflWindow for FlufflWindow {
 
         let gl_context = window.gl_create_context()?;
 
-        let context = unsafe {
+        let context: GlowGL = Arc::new(Box::new(unsafe {
             glow::Context::from_loader_function(|s| video.gl_get_proc_address(s) as *const _)
-        };
+        }));
+        if settings.gl_debug {
+            crate::ogl::state::enable_debug_output(&context);
+        }
 
         let render_loop = Some(glow::RenderLoop::<be_sdl2::video::Window>::from_sdl_window(
             window,
@@ -186,14 +262,17 @@ impl HasFlufflWindow for FlufflWindow {
 
         TouchTracker::init();
 
-        // disable vsync
-        // video.gl_set_swap_interval(0).expect("failed to disable vsync:");
+        let attributes = WindowAttributes::new(&settings);
+        set_swap_interval(attributes.vsync)?;
+
+        // text input is usually on by default on desktop but not on every platform
+        video.text_input().start();
 
-        let fluffl_window = Self {
+        let mut fluffl_window = Self {
             sdl_gl_context: gl_context,
             sdl_event_pump: event_loop,
             glue_event: Some(FlufflEvent::new()),
-            gl: Arc::new(Box::new(context)),
+            gl: context,
             render_loop,
             window_width: settings.width,
             window_height: settings.height,
@@ -203,7 +282,19 @@ impl HasFlufflWindow for FlufflWindow {
             },
             video_ss: video,
             window_pointer: CustomSDL2Window::new(window_context_ref.clone()),
+            is_composing: false,
+            controller_ss,
+            controllers: HashMap::new(),
+            gamepads: Gamepads::new(),
+            attributes,
+            cursor: None,
+            dropped_files: DroppedFiles::new(),
+            scale_factor: 1.0,
+            extra_windows: HashMap::new(),
+            next_window_id: MAIN_WINDOW + 1,
+            current_window: MAIN_WINDOW,
         };
+        fluffl_window.scale_factor = fluffl_window.scale_factor();
 
         Ok(fluffl_window)
     }
@@ -218,6 +309,258 @@ impl HasFlufflWindow for FlufflWindow {
             panic!("Error:{}", msg)
         }
     }
+
+    fn gamepads(&self) -> &Gamepads {
+        &self.gamepads
+    }
+
+    fn rumble(
+        &mut self,
+        id: GamepadId,
+        low_frequency: f32,
+        high_frequency: f32,
+        duration_ms: u32,
+    ) -> bool {
+        let to_magnitude = |strength: f32| (strength.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+        self.controllers
+            .get_mut(&id)
+            .map(|controller| {
+                controller
+                    .set_rumble(
+                        to_magnitude(low_frequency),
+                        to_magnitude(high_frequency),
+                        duration_ms,
+                    )
+                    .is_ok()
+            })
+            .unwrap_or(false)
+    }
+
+    fn dropped_files(&self) -> &DroppedFiles {
+        &self.dropped_files
+    }
+
+    fn dropped_files_mut(&mut self) -> &mut DroppedFiles {
+        &mut self.dropped_files
+    }
+
+    fn attributes(&self) -> &WindowAttributes {
+        &self.attributes
+    }
+
+    fn set_title(&mut self, title: &str) {
+        // SDL wants a C string
+        let title: String = title.chars().filter(|&c| c != '\0').collect();
+        let window = self.window_pointer.as_sdl2_window_mut();
+        if window.set_title(&title).is_ok() {
+            self.attributes.title = title;
+        }
+    }
+
+    fn set_size(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        let window = self.window_pointer.as_sdl2_window_mut();
+        window
+            .set_size(width, height)
+            .map_err(|err| Error::GenericError(err.to_string()))?;
+        self.window_width = width;
+        self.window_height = height;
+        Ok(())
+    }
+
+    fn position(&self) -> Result<(i32, i32), Error> {
+        let window = self.window_pointer.as_sdl2_window();
+        Ok(window.position())
+    }
+
+    fn set_position(&mut self, x: i32, y: i32) -> Result<(), Error> {
+        let window = self.window_pointer.as_sdl2_window_mut();
+        window.set_position(WindowPos::Positioned(x), WindowPos::Positioned(y));
+        Ok(())
+    }
+
+    fn set_min_size(&mut self, size: Option<(u32, u32)>) -> Result<(), Error> {
+        let (width, height) = size.unwrap_or((0, 0));
+        let window = self.window_pointer.as_sdl2_window_mut();
+        window
+            .set_minimum_size(width, height)
+            .map_err(|err| Error::GenericError(err.to_string()))?;
+        self.attributes.min_size = size;
+        Ok(())
+    }
+
+    fn set_max_size(&mut self, size: Option<(u32, u32)>) -> Result<(), Error> {
+        // SDL has no way of removing the limit, so it gets raised as far as it goes
+        let (width, height) = size.unwrap_or((i32::MAX as u32, i32::MAX as u32));
+        let window = self.window_pointer.as_sdl2_window_mut();
+        window
+            .set_maximum_size(width, height)
+            .map_err(|err| Error::GenericError(err.to_string()))?;
+        self.attributes.max_size = size;
+        Ok(())
+    }
+
+    fn set_vsync(&mut self, enabled: bool) -> Result<(), Error> {
+        // the swap interval belongs to the current context
+        let current = self.current_window;
+        self.make_current(MAIN_WINDOW)?;
+        set_swap_interval(enabled)?;
+        self.make_current(current)?;
+        self.attributes.vsync = enabled;
+        Ok(())
+    }
+
+    fn set_cursor_visible(&mut self, visible: bool) {
+        self.sdl_state.mouse().show_cursor(visible);
+        self.attributes.cursor_visible = visible;
+    }
+
+    fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        // system cursors are available everywhere SDL runs, if one fails the old cursor stays
+        if let Ok(cursor) = Cursor::from_system(map_cursor_icon(icon)) {
+            cursor.set();
+            self.cursor = Some(cursor);
+            self.attributes.cursor_icon = icon;
+        }
+    }
+
+    fn set_cursor_grab(&mut self, grab: bool) -> Result<(), Error> {
+        let window = self.window_pointer.as_sdl2_window_mut();
+        window.set_grab(grab);
+        self.attributes.cursor_grabbed = grab;
+        Ok(())
+    }
+
+    fn set_relative_mouse_mode(&mut self, enabled: bool) -> Result<(), Error> {
+        let mouse = self.sdl_state.mouse();
+        mouse.set_relative_mouse_mode(enabled);
+        if mouse.relative_mouse_mode() != enabled {
+            return Err(Error::UnsupportedError(be_sdl2::get_error()));
+        }
+        self.attributes.relative_mouse_mode = enabled;
+        Ok(())
+    }
+
+    fn scale_factor(&self) -> f64 {
+        let window = self.window_pointer.as_sdl2_window();
+        let (drawable_width, _) = window.drawable_size();
+        let (width, _) = window.size();
+        if width == 0 {
+            1.0
+        } else {
+            drawable_width as f64 / width as f64
+        }
+    }
+
+    fn create_window(&mut self, config: &str) -> Result<WindowId, Error> {
+        let settings = FlufflWindowConfigs::new()
+            .parse_config_file(config)
+            .map_err(|err| Error::WindowInitError(err.to_string()))?;
+
+        // SDL shares objects with whatever context is current when the new one gets created
+        self.make_current(MAIN_WINDOW)?;
+        self.video_ss.gl_attr().set_share_with_current_context(true);
+
+        let mut builder =
+            self.video_ss
+                .window(settings.title.as_str(), settings.width, settings.height);
+        builder.opengl();
+        if settings.resizable {
+            builder.resizable();
+        }
+        let window = builder.build()?;
+        let gl_context = window.gl_create_context()?;
+        // only the main window waits for vblank, otherwise every extra window costs a whole refresh
+        set_swap_interval(false)?;
+        let gl = unsafe {
+            glow::Context::from_loader_function(|s| {
+                self.video_ss.gl_get_proc_address(s) as *const _
+            })
+        };
+
+        let id = self.next_window_id;
+        self.next_window_id += 1;
+        self.extra_windows.insert(
+            id,
+            ExtraSDL2Window {
+                window,
+                gl_context,
+                gl: Arc::new(Box::new(gl)),
+            },
+        );
+        // creating the context made it current
+        self.current_window = id;
+        self.make_current(MAIN_WINDOW)?;
+        Ok(id)
+    }
+
+    fn close_window(&mut self, id: WindowId) -> Result<(), Error> {
+        if !self.extra_windows.contains_key(&id) {
+            return Err(Error::GenericError(format!("there is no window {}", id)));
+        }
+        if self.current_window == id {
+            self.make_current(MAIN_WINDOW)?;
+        }
+        self.extra_windows.remove(&id);
+        self.glue_event.as_mut().unwrap().remove_window(id);
+        Ok(())
+    }
+
+    fn window_ids(&self) -> Vec<WindowId> {
+        let mut ids = vec![MAIN_WINDOW];
+        ids.extend(self.extra_windows.keys().copied());
+        ids[1..].sort_unstable();
+        ids
+    }
+
+    fn make_current(&mut self, id: WindowId) -> Result<(), Error> {
+        match self.extra_windows.get(&id) {
+            Some(extra) => extra.window.gl_make_current(&extra.gl_context)?,
+            None if id == MAIN_WINDOW => self
+                .window_pointer
+                .as_sdl2_window()
+                .gl_make_current(&self.sdl_gl_context)?,
+            None => return Err(Error::GenericError(format!("there is no window {}", id))),
+        }
+        self.current_window = id;
+        Ok(())
+    }
+
+    fn current_window(&self) -> WindowId {
+        self.current_window
+    }
+
+    fn window_bounds(&self, id: WindowId) -> Result<(u32, u32), Error> {
+        match self.extra_windows.get(&id) {
+            Some(extra) => Ok(extra.window.size()),
+            None if id == MAIN_WINDOW => Ok(self.get_bounds()),
+            None => Err(Error::GenericError(format!("there is no window {}", id))),
+        }
+    }
+}
+
+/// `VideoSubsystem::gl_set_swap_interval` changed signatures between sdl2 releases, so this goes to SDL directly
+fn set_swap_interval(vsync: bool) -> Result<(), Error> {
+    let status = unsafe { be_sdl2::sys::SDL_GL_SetSwapInterval(vsync as i32) };
+    if status != 0 {
+        return Err(Error::UnsupportedError(be_sdl2::get_error()));
+    }
+    Ok(())
+}
+
+fn map_cursor_icon(icon: CursorIcon) -> SystemCursor {
+    match icon {
+        CursorIcon::Arrow => SystemCursor::Arrow,
+        CursorIcon::IBeam => SystemCursor::IBeam,
+        CursorIcon::Hand => SystemCursor::Hand,
+        CursorIcon::Crosshair => SystemCursor::Crosshair,
+        CursorIcon::Wait => SystemCursor::Wait,
+        CursorIcon::NotAllowed => SystemCursor::No,
+        CursorIcon::ResizeHorizontal => SystemCursor::SizeWE,
+        CursorIcon::ResizeVertical => SystemCursor::SizeNS,
+        CursorIcon::ResizeNwSe => SystemCursor::SizeNWSE,
+        CursorIcon::ResizeNeSw => SystemCursor::SizeNESW,
+        CursorIcon::ResizeAll => SystemCursor::SizeAll,
+    }
 }
 
 impl From<String> for Error {
@@ -240,6 +583,23 @@ impl HasEventCollection for FlufflWindow {
         let mut height_update = None;
 
         let (cur_width, cur_height) = self.get_bounds_f32();
+        let mut is_composing = self.is_composing;
+        let controller_ss = &self.controller_ss;
+        let controllers = &mut self.controllers;
+        let gamepads = &mut self.gamepads;
+        let dropped_files = &mut self.dropped_files;
+        let mut might_change_scale = false;
+        let sdl_window_ids: HashMap<u32, WindowId> = self
+            .extra_windows
+            .iter()
+            .map(|(&id, extra)| (extra.window.id(), id))
+            .collect();
+        let route = |sdl_window_id: u32| {
+            sdl_window_ids
+                .get(&sdl_window_id)
+                .copied()
+                .unwrap_or(MAIN_WINDOW)
+        };
 
         self.sdl_event_pump
             .poll_iter()
@@ -307,47 +667,167 @@ impl HasEventCollection for FlufflWindow {
                 }
 
                 Event::Window {
+                    window_id,
                     win_event: be_sdl2::event::WindowEvent::Resized(width, height),
                     ..
                 } => {
-                    push_event!(gevent, EventKind::Resize { width, height });
-                    width_update = Some(width as u32);
-                    height_update = Some(height as u32);
+                    let window = route(window_id);
+                    push_window_event!(gevent, window, EventKind::Resize { width, height });
+                    if window == MAIN_WINDOW {
+                        width_update = Some(width as u32);
+                        height_update = Some(height as u32);
+                        might_change_scale = true;
+                    }
+                }
+
+                Event::Window {
+                    window_id,
+                    win_event: be_sdl2::event::WindowEvent::Moved(..),
+                    ..
+                } => {
+                    might_change_scale |= route(window_id) == MAIN_WINDOW;
+                }
+
+                // SDL only sends `Quit` once the last window closes, so with several windows open
+                // every close request gets reported to the window it belongs to
+                Event::Window {
+                    window_id,
+                    win_event: be_sdl2::event::WindowEvent::Close,
+                    ..
+                } if !sdl_window_ids.is_empty() => {
+                    push_window_event!(gevent, route(window_id), EventKind::Quit);
+                }
+
+                Event::Window {
+                    window_id,
+                    win_event: be_sdl2::event::WindowEvent::FocusGained,
+                    ..
+                } => {
+                    push_window_event!(gevent, route(window_id), EventKind::FocusGained);
+                }
+
+                Event::Window {
+                    window_id,
+                    win_event: be_sdl2::event::WindowEvent::FocusLost,
+                    ..
+                } => {
+                    push_window_event!(gevent, route(window_id), EventKind::FocusLost);
+                }
+
+                Event::Window {
+                    window_id,
+                    win_event: be_sdl2::event::WindowEvent::Minimized,
+                    ..
+                } => {
+                    push_window_event!(gevent, route(window_id), EventKind::Minimized);
+                }
+
+                // SDL also sends this when a maximized window gets restored, so it can show up
+                // without a `Minimized` before it
+                Event::Window {
+                    window_id,
+                    win_event: be_sdl2::event::WindowEvent::Restored,
+                    ..
+                } => {
+                    push_window_event!(gevent, route(window_id), EventKind::Restored);
+                }
+
+                Event::DropFile {
+                    window_id,
+                    filename,
+                    ..
+                } => {
+                    let id = dropped_files.insert(DroppedFile::from_path(filename));
+                    push_window_event!(gevent, route(window_id), EventKind::FileDropped { id });
                 }
 
                 Event::KeyUp {
-                    scancode: Some(sc), ..
+                    window_id,
+                    scancode: Some(sc),
+                    ..
                 } => {
                     let code = map_scancode(sc);
-                    push_event!(gevent, EventKind::KeyUp { code })
+                    push_window_event!(gevent, route(window_id), EventKind::KeyUp { code })
                 }
                 Event::KeyDown {
-                    scancode: Some(sc), ..
+                    window_id,
+                    scancode: Some(sc),
+                    ..
                 } => {
                     let code = map_scancode(sc);
-                    push_event!(gevent, EventKind::KeyDown { code })
+                    push_window_event!(gevent, route(window_id), EventKind::KeyDown { code })
+                }
+                Event::TextEditing {
+                    window_id,
+                    text,
+                    start,
+                    ..
+                } => {
+                    let window = route(window_id);
+                    if !text.is_empty() {
+                        if !is_composing {
+                            is_composing = true;
+                            push_window_event!(gevent, window, EventKind::ImePreeditStart);
+                        }
+                        for event in EventKind::preedit_updates(&text, start) {
+                            push_window_event!(gevent, window, event);
+                        }
+                    } else if is_composing {
+                        // preedit got cleared without anything being committed
+                        is_composing = false;
+                        push_window_event!(
+                            gevent,
+                            window,
+                            EventKind::ImeCommit {
+                                text: TextChunk::default(),
+                            }
+                        );
+                    }
+                }
+                Event::TextInput {
+                    window_id, text, ..
+                } => {
+                    let window = route(window_id);
+                    // while composing, SDL2 delivers the committed string as plain text input
+                    if is_composing {
+                        is_composing = false;
+                        for text in TextChunk::split(&text) {
+                            push_window_event!(gevent, window, EventKind::ImeCommit { text });
+                        }
+                    } else {
+                        for text in TextChunk::split(&text) {
+                            push_window_event!(gevent, window, EventKind::TextInput { text });
+                        }
+                    }
                 }
                 Event::MouseButtonDown {
-                    mouse_btn, x, y, ..
+                    window_id,
+                    mouse_btn,
+                    x,
+                    y,
+                    ..
                 } => match mouse_btn {
-                    MouseButton::Left => push_event!(
+                    MouseButton::Left => push_window_event!(
                         gevent,
+                        route(window_id),
                         EventKind::MouseDown {
                             button_code: MouseCode::LEFT_BUTTON,
                             x: x as f32,
                             y: y as f32,
                         }
                     ),
-                    MouseButton::Right => push_event!(
+                    MouseButton::Right => push_window_event!(
                         gevent,
+                        route(window_id),
                         EventKind::MouseDown {
                             button_code: MouseCode::RIGHT_BUTTON,
                             x: x as f32,
                             y: y as f32,
                         }
                     ),
-                    MouseButton::Middle => push_event!(
+                    MouseButton::Middle => push_window_event!(
                         gevent,
+                        route(window_id),
                         EventKind::MouseDown {
                             button_code: MouseCode::WHEEL { direction: 0 },
                             x: x as f32,
@@ -357,26 +837,33 @@ impl HasEventCollection for FlufflWindow {
                     _ => (),
                 },
                 Event::MouseButtonUp {
-                    mouse_btn, x, y, ..
+                    window_id,
+                    mouse_btn,
+                    x,
+                    y,
+                    ..
                 } => match mouse_btn {
-                    MouseButton::Left => push_event!(
+                    MouseButton::Left => push_window_event!(
                         gevent,
+                        route(window_id),
                         EventKind::MouseUp {
                             button_code: MouseCode::LEFT_BUTTON,
                             x: x as f32,
                             y: y as f32,
                         }
                     ),
-                    MouseButton::Right => push_event!(
+                    MouseButton::Right => push_window_event!(
                         gevent,
+                        route(window_id),
                         EventKind::MouseUp {
                             button_code: MouseCode::RIGHT_BUTTON,
                             x: x as f32,
                             y: y as f32,
                         }
                     ),
-                    MouseButton::Middle => push_event!(
+                    MouseButton::Middle => push_window_event!(
                         gevent,
+                        route(window_id),
                         EventKind::MouseUp {
                             button_code: MouseCode::WHEEL { direction: 0 },
                             x: x as f32,
@@ -386,10 +873,16 @@ impl HasEventCollection for FlufflWindow {
                     _ => (),
                 },
                 Event::MouseMotion {
-                    x, y, xrel, yrel, ..
+                    window_id,
+                    x,
+                    y,
+                    xrel,
+                    yrel,
+                    ..
                 } => {
-                    push_event!(
+                    push_window_event!(
                         gevent,
+                        route(window_id),
                         EventKind::MouseMove {
                             x: x as f32,
                             y: y as f32,
@@ -398,14 +891,71 @@ impl HasEventCollection for FlufflWindow {
                         }
                     );
                 }
-                Event::MouseWheel { y, .. } => {
-                    push_event!(
+                Event::MouseWheel { window_id, y, .. } => {
+                    push_window_event!(
                         gevent,
+                        route(window_id),
                         EventKind::MouseWheel {
                             button_code: MouseCode::WHEEL { direction: y }
                         }
                     );
                 }
+                // `which` is a joystick index here but an instance id in every other controller event
+                Event::ControllerDeviceAdded { which, .. } => {
+                    if let Ok(controller) = controller_ss.open(which as u32) {
+                        let id = controller.instance_id() as GamepadId;
+                        let name = controller.name();
+                        controllers.insert(id, controller);
+                        push_gamepad_event!(gevent, gamepads, EventKind::GamepadConnected { id });
+                        gamepads.set_name(id, &name);
+                    }
+                }
+                Event::ControllerDeviceRemoved { which, .. } => {
+                    let id = which as GamepadId;
+                    if controllers.remove(&id).is_some() {
+                        push_gamepad_event!(
+                            gevent,
+                            gamepads,
+                            EventKind::GamepadDisconnected { id }
+                        );
+                    }
+                }
+                Event::ControllerButtonDown { which, button, .. } => {
+                    if let Some(button) = map_controller_button(button) {
+                        let id = which as GamepadId;
+                        push_gamepad_event!(
+                            gevent,
+                            gamepads,
+                            EventKind::GamepadButtonDown { id, button }
+                        );
+                    }
+                }
+                Event::ControllerButtonUp { which, button, .. } => {
+                    if let Some(button) = map_controller_button(button) {
+                        let id = which as GamepadId;
+                        push_gamepad_event!(
+                            gevent,
+                            gamepads,
+                            EventKind::GamepadButtonUp { id, button }
+                        );
+                    }
+                }
+                Event::ControllerAxisMotion {
+                    which, axis, value, ..
+                } => {
+                    let axis = map_controller_axis(axis);
+                    let id = which as GamepadId;
+                    let value = if axis.is_trigger() {
+                        (value as f32 / i16::MAX as f32).clamp(0.0, 1.0)
+                    } else {
+                        (value as f32 / i16::MAX as f32).clamp(-1.0, 1.0)
+                    };
+                    push_gamepad_event!(
+                        gevent,
+                        gamepads,
+                        EventKind::GamepadAxisMotion { id, axis, value }
+                    );
+                }
                 _ => (),
             });
 
@@ -415,11 +965,58 @@ impl HasEventCollection for FlufflWindow {
         if let Some(height) = height_update {
             self.window_height = height;
         }
+        if might_change_scale {
+            let scale_factor = self.scale_factor();
+            if scale_factor != self.scale_factor {
+                self.scale_factor = scale_factor;
+                push_event!(
+                    gevent,
+                    EventKind::ScaleFactorChanged {
+                        scale_factor: scale_factor as f32
+                    }
+                );
+            }
+        }
+        self.is_composing = is_composing;
         //make sure to give the pollevent back
         self.glue_event = gevent;
     }
 }
 
+fn map_controller_button(button: controller::Button) -> Option<GamepadButton> {
+    let button = match button {
+        controller::Button::A => GamepadButton::A,
+        controller::Button::B => GamepadButton::B,
+        controller::Button::X => GamepadButton::X,
+        controller::Button::Y => GamepadButton::Y,
+        controller::Button::Back => GamepadButton::Back,
+        controller::Button::Guide => GamepadButton::Guide,
+        controller::Button::Start => GamepadButton::Start,
+        controller::Button::LeftStick => GamepadButton::LeftStick,
+        controller::Button::RightStick => GamepadButton::RightStick,
+        controller::Button::LeftShoulder => GamepadButton::LeftShoulder,
+        controller::Button::RightShoulder => GamepadButton::RightShoulder,
+        controller::Button::DPadUp => GamepadButton::DPadUp,
+        controller::Button::DPadDown => GamepadButton::DPadDown,
+        controller::Button::DPadLeft => GamepadButton::DPadLeft,
+        controller::Button::DPadRight => GamepadButton::DPadRight,
+        #[allow(unreachable_patterns)]
+        _ => return None,
+    };
+    Some(button)
+}
+
+fn map_controller_axis(axis: controller::Axis) -> GamepadAxis {
+    match axis {
+        controller::Axis::LeftX => GamepadAxis::LeftX,
+        controller::Axis::LeftY => GamepadAxis::LeftY,
+        controller::Axis::RightX => GamepadAxis::RightX,
+        controller::Axis::RightY => GamepadAxis::RightY,
+        controller::Axis::TriggerLeft => GamepadAxis::TriggerLeft,
+        controller::Axis::TriggerRight => GamepadAxis::TriggerRight,
+    }
+}
+
 fn map_scancode(scancode: be_sdl2::keyboard::Scancode) -> KeyCode {
     match scancode {
         Scancode::A => KeyCode::KEY_A,
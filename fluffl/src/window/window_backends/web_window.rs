@@ -1,4 +1,9 @@
-use super::{event_util::*, Error, HasFlufflWindow, TouchTracker};
+use super::{
+    dropped_file::{DroppedFile, DroppedFiles},
+    event_util::{GamepadButton, *},
+    gamepad::Gamepads,
+    CursorIcon, Error, HasFlufflWindow, TouchTracker, WindowAttributes,
+};
 use crate::{
     audio::{init_audio_threads, FlufflAudioContext},
     console::*,
@@ -11,7 +16,11 @@ pub use wasm_bindgen;
 pub use wasm_bindgen::{prelude::*, JsCast};
 pub use web_sys::*;
 
-use std::{cell::RefCell, rc::Rc, sync::Arc};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    sync::Arc,
+};
 
 use glow;
 use glow::*;
@@ -43,9 +52,15 @@ impl TouchTracker<i32> {
 // Global variables that are only visible inside of this module.
 // The use of global variables should be fine if there is no multithreading going on.
 static mut GLOBAL_EVENT_QUEUE: Option<FlufflEvent> = None;
+static mut GLOBAL_DROPPED_FILES: Option<DroppedFiles> = None;
 static mut GLOBAL_CANVAS_REF: Option<Rc<HtmlCanvasElement>> = None;
 static mut IS_MOBILE: bool = false;
 
+thread_local! {
+    /// set when the browser refuses pointer lock or takes it away, `transfer_events(..)` picks it up
+    static POINTER_LOCK_LOST: Cell<bool> = const { Cell::new(false) };
+}
+
 /// determines if desktop is mobile or not
 fn determine_desktop_or_mobile() {
     let navigator = web_sys::window().unwrap().navigator();
@@ -87,7 +102,10 @@ fn is_mobile() -> bool {
 }
 
 fn init_global_event_queue() {
-    unsafe { GLOBAL_EVENT_QUEUE = Some(FlufflEvent::new()) };
+    unsafe {
+        GLOBAL_EVENT_QUEUE = Some(FlufflEvent::new());
+        GLOBAL_DROPPED_FILES = Some(DroppedFiles::new());
+    };
 }
 
 fn init_global_canvas(canvas_ptr: Rc<HtmlCanvasElement>) {
@@ -105,6 +123,22 @@ fn get_canvas() -> Rc<HtmlCanvasElement> {
     }
 }
 
+/// ## Description
+/// asks the canvas' WebGL1 context whether it has the extension called `name`
+/// ## Comments
+/// - always `false` for WebGL2 canvases, a canvas only hands out the kind of context it was created with
+pub fn webgl_extension_supported(name: &str) -> bool {
+    let context = match get_canvas().get_context("webgl") {
+        Ok(Some(context)) => context,
+        _ => return false,
+    };
+    js_sys::Reflect::get(&context, &JsValue::from_str("getExtension"))
+        .ok()
+        .and_then(|get_extension| get_extension.dyn_into::<js_sys::Function>().ok())
+        .and_then(|get_extension| get_extension.call1(&context, &JsValue::from_str(name)).ok())
+        .is_some_and(|extension| !extension.is_null())
+}
+
 #[allow(dead_code)]
 pub struct FlufflWindow {
     glue_event: Option<FlufflEvent>,
@@ -114,6 +148,10 @@ pub struct FlufflWindow {
     window_height: u32,
     audio_ctx: FlufflAudioContext,
     canvas: Rc<HtmlCanvasElement>,
+    gamepads: Gamepads,
+    attributes: WindowAttributes,
+    /// browsers have no event for `devicePixelRatio` changes, so it gets compared every frame
+    scale_factor: f64,
 }
 
 impl FlufflWindow {
@@ -205,6 +243,11 @@ impl HasFlufflWindow for FlufflWindow {
 
         let canvas = Rc::new(canvas);
 
+        // the config title doesn't apply here, the page already has one
+        let mut attributes = WindowAttributes::new(&settings);
+        attributes.title = web_window.document().unwrap().title();
+        attributes.vsync = true;
+
         //I need a global reference to the canvas in this module
         init_global_canvas(canvas.clone());
 
@@ -216,6 +259,9 @@ impl HasFlufflWindow for FlufflWindow {
             gl: Arc::new(Box::new(gl)),
             audio_ctx: FlufflAudioContext::new(),
             canvas,
+            gamepads: Gamepads::new(),
+            attributes,
+            scale_factor: web_window.device_pixel_ratio(),
         };
 
         //I use this table to track touch displacements
@@ -261,9 +307,193 @@ impl HasFlufflWindow for FlufflWindow {
             document.exit_fullscreen();
         }
     }
+
+    fn gamepads(&self) -> &Gamepads {
+        &self.gamepads
+    }
+
+    /// uses `vibrationActuator`, which isn't in web-sys yet, so it has to go through `Reflect`
+    fn rumble(
+        &mut self,
+        id: GamepadId,
+        low_frequency: f32,
+        high_frequency: f32,
+        duration_ms: u32,
+    ) -> bool {
+        let gamepad = match connected_gamepads().find(|gamepad| gamepad.index() == id) {
+            Some(gamepad) => gamepad,
+            None => return false,
+        };
+        let actuator = match js_sys::Reflect::get(&gamepad, &"vibrationActuator".into()) {
+            Ok(actuator) if actuator.is_object() => actuator,
+            _ => return false,
+        };
+        let play_effect = match js_sys::Reflect::get(&actuator, &"playEffect".into()) {
+            Ok(play_effect) => play_effect.dyn_into::<js_sys::Function>(),
+            Err(_) => return false,
+        };
+
+        let params = js_sys::Object::new();
+        let set = |key: &str, value: f64| {
+            js_sys::Reflect::set(&params, &key.into(), &value.into()).is_ok()
+        };
+        let params_ok = set("startDelay", 0.0)
+            && set("duration", duration_ms as f64)
+            && set("strongMagnitude", low_frequency.clamp(0.0, 1.0) as f64)
+            && set("weakMagnitude", high_frequency.clamp(0.0, 1.0) as f64);
+
+        params_ok
+            && play_effect
+                .map(|play_effect| {
+                    play_effect
+                        .call2(&actuator, &"dual-rumble".into(), &params)
+                        .is_ok()
+                })
+                .unwrap_or(false)
+    }
+
+    fn dropped_files(&self) -> &DroppedFiles {
+        get_global_dropped_files_mut()
+    }
+
+    fn dropped_files_mut(&mut self) -> &mut DroppedFiles {
+        get_global_dropped_files_mut()
+    }
+
+    fn attributes(&self) -> &WindowAttributes {
+        &self.attributes
+    }
+
+    fn set_title(&mut self, title: &str) {
+        let document: Document = web_sys::window().unwrap().document().unwrap();
+        document.set_title(title);
+        self.attributes.title = String::from(title);
+    }
+
+    fn set_size(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        self.canvas.set_width(width);
+        self.canvas.set_height(height);
+        self.window_width = width;
+        self.window_height = height;
+        Ok(())
+    }
+
+    fn position(&self) -> Result<(i32, i32), Error> {
+        let rect = self.canvas.get_bounding_client_rect();
+        Ok((rect.left() as i32, rect.top() as i32))
+    }
+
+    fn set_position(&mut self, _x: i32, _y: i32) -> Result<(), Error> {
+        Err(Error::UnsupportedError(String::from(
+            "the canvas is positioned by the page",
+        )))
+    }
+
+    fn set_min_size(&mut self, _size: Option<(u32, u32)>) -> Result<(), Error> {
+        Err(Error::UnsupportedError(String::from(
+            "the canvas can't be resized by the user",
+        )))
+    }
+
+    fn set_max_size(&mut self, _size: Option<(u32, u32)>) -> Result<(), Error> {
+        Err(Error::UnsupportedError(String::from(
+            "the canvas can't be resized by the user",
+        )))
+    }
+
+    fn set_vsync(&mut self, enabled: bool) -> Result<(), Error> {
+        if enabled {
+            return Ok(());
+        }
+        Err(Error::UnsupportedError(String::from(
+            "requestAnimationFrame always syncs to the display",
+        )))
+    }
+
+    fn set_cursor_visible(&mut self, visible: bool) {
+        self.attributes.cursor_visible = visible;
+        self.update_cursor_style();
+    }
+
+    fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        self.attributes.cursor_icon = icon;
+        self.update_cursor_style();
+    }
+
+    fn set_cursor_grab(&mut self, grab: bool) -> Result<(), Error> {
+        self.attributes.cursor_grabbed = grab;
+        self.update_pointer_lock();
+        Ok(())
+    }
+
+    fn set_relative_mouse_mode(&mut self, enabled: bool) -> Result<(), Error> {
+        self.attributes.relative_mouse_mode = enabled;
+        self.update_pointer_lock();
+        Ok(())
+    }
+
+    fn scale_factor(&self) -> f64 {
+        web_sys::window().unwrap().device_pixel_ratio()
+    }
+}
+
+impl FlufflWindow {
+    fn update_cursor_style(&self) {
+        let cursor = if self.attributes.cursor_visible {
+            self.attributes.cursor_icon.css_name()
+        } else {
+            "none"
+        };
+        let _ = self.canvas.style().set_property("cursor", cursor);
+    }
+
+    /// browsers have no cursor grab, so both grabbing and relative mode use pointer lock
+    fn update_pointer_lock(&self) {
+        if self.attributes.cursor_grabbed || self.attributes.relative_mouse_mode {
+            // whatever happened to an earlier lock doesn't apply to this request
+            POINTER_LOCK_LOST.with(|lost| lost.set(false));
+            self.canvas.request_pointer_lock();
+        } else {
+            let document: Document = web_sys::window().unwrap().document().unwrap();
+            document.exit_pointer_lock();
+        }
+    }
+}
+
+/// ## Description
+/// creates the invisible `<input>` that keyboard focus goes to while the canvas is in use
+/// ## Comments
+/// - browsers only run an input method for a focused editable element, a canvas never gets
+///   composition events
+/// - the input sits over the top-left corner of the page, some IMEs place their candidate
+///   window next to it
+fn create_ime_input(document: &Document) -> Result<HtmlInputElement, JsValue> {
+    let input = document
+        .create_element("input")?
+        .dyn_into::<HtmlInputElement>()?;
+    input.set_type("text");
+    input.set_attribute("autocomplete", "off")?;
+    input.set_attribute("aria-hidden", "true")?;
+    let style = input.style();
+    style.set_property("position", "fixed")?;
+    style.set_property("left", "0px")?;
+    style.set_property("top", "0px")?;
+    style.set_property("width", "1px")?;
+    style.set_property("height", "1px")?;
+    style.set_property("opacity", "0")?;
+    style.set_property("pointer-events", "none")?;
+    document
+        .body()
+        .ok_or_else(|| JsValue::from_str("document has no body"))?
+        .append_child(&input)?;
+    Ok(input)
 }
 
 fn attach_event_handlers(window: &Window, canvas: &HtmlCanvasElement) -> Result<(), JsValue> {
+    let document = window
+        .document()
+        .ok_or_else(|| JsValue::from_str("window has no document"))?;
+    let ime_input = create_ime_input(&document)?;
     canvas.style().set_property("border", "solid")?;
     /*canvas resize handler*/
     {
@@ -412,7 +642,12 @@ fn attach_event_handlers(window: &Window, canvas: &HtmlCanvasElement) -> Result<
 
     /*mouse down handler*/
     {
+        let ime_input = ime_input.clone();
         let closure = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
+            // the default action would move focus back to the body, away from the input
+            event.prevent_default();
+            let _ = ime_input.focus();
+
             let (x, y) = (event.client_x() as f64, event.client_y() as f64);
 
             let (x, y, _, _) = convert_from_viewport_to_window(x, y, 0., 0.);
@@ -430,6 +665,31 @@ fn attach_event_handlers(window: &Window, canvas: &HtmlCanvasElement) -> Result<
         closure.forget();
     }
 
+    /*pointer lock handlers*/
+    {
+        let closure = Closure::wrap(Box::new(move |_event: web_sys::Event| {
+            POINTER_LOCK_LOST.with(|lost| lost.set(true));
+        }) as Box<dyn FnMut(_)>);
+        document.add_event_listener_with_callback(
+            "pointerlockerror",
+            closure.as_ref().unchecked_ref(),
+        )?;
+        closure.forget();
+
+        let lock_document = document.clone();
+        let closure = Closure::wrap(Box::new(move |_event: web_sys::Event| {
+            // escape (or switching tabs) releases the lock without the app asking
+            if lock_document.pointer_lock_element().is_none() {
+                POINTER_LOCK_LOST.with(|lost| lost.set(true));
+            }
+        }) as Box<dyn FnMut(_)>);
+        document.add_event_listener_with_callback(
+            "pointerlockchange",
+            closure.as_ref().unchecked_ref(),
+        )?;
+        closure.forget();
+    }
+
     /*mouse up handler*/
     {
         let closure = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
@@ -478,7 +738,20 @@ fn attach_event_handlers(window: &Window, canvas: &HtmlCanvasElement) -> Result<
             // console_log!("[down] key() = {} code = {}", event.key() , event.code() );
             let eq = get_global_event_queue_mut();
             let code = map_keycode(event.code().as_str());
-            eq.push_event(EventKind::KeyDown { code })
+            eq.push_event(EventKind::KeyDown { code });
+
+            // `key()` is already layout/shift/dead-key aware, named keys like "Enter" or "Dead"
+            // are more than one char so they get filtered out here
+            let key = event.key();
+            let mut chars = key.chars();
+            let is_printable =
+                matches!((chars.next(), chars.next()), (Some(c), None) if !c.is_control());
+            let is_shortcut = event.ctrl_key() || event.meta_key();
+            if is_printable && !is_shortcut && !event.is_composing() {
+                eq.push_event(EventKind::TextInput {
+                    text: TextChunk::new(&key),
+                });
+            }
         }) as Box<dyn FnMut(_)>);
         window.add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref())?;
         closure.forget();
@@ -496,6 +769,136 @@ fn attach_event_handlers(window: &Window, canvas: &HtmlCanvasElement) -> Result<
         closure.forget();
     }
 
+    // composition events only fire for a focused editable element, which is why they are
+    // attached to the hidden input the canvas hands its focus to
+    {
+        let closure = Closure::wrap(Box::new(move |_event: web_sys::CompositionEvent| {
+            let eq = get_global_event_queue_mut();
+            eq.push_event(EventKind::ImePreeditStart);
+        }) as Box<dyn FnMut(_)>);
+        ime_input.add_event_listener_with_callback(
+            "compositionstart",
+            closure.as_ref().unchecked_ref(),
+        )?;
+        closure.forget();
+    }
+
+    {
+        let closure = Closure::wrap(Box::new(move |event: web_sys::CompositionEvent| {
+            let eq = get_global_event_queue_mut();
+            let text = event.data().unwrap_or_default();
+            for event in EventKind::preedit_updates(&text, text.chars().count() as i32) {
+                eq.push_event(event);
+            }
+        }) as Box<dyn FnMut(_)>);
+        ime_input.add_event_listener_with_callback(
+            "compositionupdate",
+            closure.as_ref().unchecked_ref(),
+        )?;
+        closure.forget();
+    }
+
+    {
+        let input = ime_input.clone();
+        let closure = Closure::wrap(Box::new(move |event: web_sys::CompositionEvent| {
+            input.set_value("");
+            let eq = get_global_event_queue_mut();
+            let text = event.data().unwrap_or_default();
+            if text.is_empty() {
+                eq.push_event(EventKind::ImeCommit {
+                    text: TextChunk::default(),
+                });
+            }
+            for text in TextChunk::split(&text) {
+                eq.push_event(EventKind::ImeCommit { text });
+            }
+        }) as Box<dyn FnMut(_)>);
+        ime_input
+            .add_event_listener_with_callback("compositionend", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+    }
+
+    // plain typing already arrives through keydown, the input only has to stay empty
+    {
+        let input = ime_input.clone();
+        let closure = Closure::wrap(Box::new(move |event: web_sys::InputEvent| {
+            if !event.is_composing() {
+                input.set_value("");
+            }
+        }) as Box<dyn FnMut(_)>);
+        ime_input.add_event_listener_with_callback("input", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+    }
+
+    //focus handlers
+    {
+        let closure = Closure::wrap(Box::new(move |_event: web_sys::Event| {
+            get_global_event_queue_mut().push_event(EventKind::FocusGained);
+        }) as Box<dyn FnMut(_)>);
+        window.add_event_listener_with_callback("focus", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+    }
+
+    {
+        let closure = Closure::wrap(Box::new(move |_event: web_sys::Event| {
+            get_global_event_queue_mut().push_event(EventKind::FocusLost);
+        }) as Box<dyn FnMut(_)>);
+        window.add_event_listener_with_callback("blur", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+    }
+
+    // a hidden tab is the closest thing the browser has to a minimized window
+    {
+        let closure = Closure::wrap(Box::new(move |_event: web_sys::Event| {
+            let document: Document = web_sys::window().unwrap().document().unwrap();
+            get_global_event_queue_mut().push_event(if document.hidden() {
+                EventKind::Minimized
+            } else {
+                EventKind::Restored
+            });
+        }) as Box<dyn FnMut(_)>);
+        window
+            .document()
+            .unwrap()
+            .add_event_listener_with_callback(
+                "visibilitychange",
+                closure.as_ref().unchecked_ref(),
+            )?;
+        closure.forget();
+    }
+
+    // the browser only allows dropping if dragover gets cancelled
+    {
+        let closure = Closure::wrap(Box::new(move |event: web_sys::DragEvent| {
+            event.prevent_default();
+        }) as Box<dyn FnMut(_)>);
+        canvas.add_event_listener_with_callback("dragover", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+    }
+
+    {
+        let closure = Closure::wrap(Box::new(move |event: web_sys::DragEvent| {
+            event.prevent_default();
+            let files = match event.data_transfer().and_then(|data| data.files()) {
+                Some(files) => files,
+                None => return,
+            };
+            for file in (0..files.length()).filter_map(|index| files.get(index)) {
+                // the event is only pushed once the contents have been read
+                spawn_local(async move {
+                    if let Ok(buffer) = JsFuture::from(file.array_buffer()).await {
+                        let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+                        let id = get_global_dropped_files_mut()
+                            .insert(DroppedFile::from_bytes(&file.name(), bytes));
+                        get_global_event_queue_mut().push_event(EventKind::FileDropped { id });
+                    }
+                });
+            }
+        }) as Box<dyn FnMut(_)>);
+        canvas.add_event_listener_with_callback("drop", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+    }
+
     Ok(())
 }
 
@@ -508,6 +911,126 @@ fn transfer_events(window: &mut FlufflWindow) {
         .for_each(|e| {
             window.get_events().push_event(e);
         });
+    poll_gamepads(window);
+
+    // pointer lock is granted asynchronously, so a refused or released lock only shows up here
+    if POINTER_LOCK_LOST.with(|lost| lost.replace(false)) {
+        let document: Document = web_sys::window().unwrap().document().unwrap();
+        let attributes = &mut window.attributes;
+        let wants_lock = attributes.cursor_grabbed || attributes.relative_mouse_mode;
+        if wants_lock && document.pointer_lock_element().is_none() {
+            console_log!("pointer lock was refused or released\n");
+            attributes.cursor_grabbed = false;
+            attributes.relative_mouse_mode = false;
+        }
+    }
+
+    let scale_factor = web_sys::window().unwrap().device_pixel_ratio();
+    if scale_factor != window.scale_factor {
+        window.scale_factor = scale_factor;
+        window
+            .get_events()
+            .push_event(EventKind::ScaleFactorChanged {
+                scale_factor: scale_factor as f32,
+            });
+    }
+}
+
+/// every gamepad the browser currently reports as connected
+fn connected_gamepads() -> impl Iterator<Item = Gamepad> {
+    web_sys::window()
+        .and_then(|window| window.navigator().get_gamepads().ok())
+        .map(|list| list.iter().collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|gamepad| gamepad.dyn_into::<Gamepad>().ok())
+        .filter(|gamepad| gamepad.connected())
+}
+
+/// # Description
+/// The gamepad api can only be polled, so this diffs what the browser reports against the last known
+/// state to produce events
+/// # Comments
+/// - assumes the `standard` mapping, which is the only layout browsers agree on
+/// - the analog triggers are buttons 6 and 7 in the standard mapping, they get reported as axes
+fn poll_gamepads(window: &mut FlufflWindow) {
+    let mut events = Vec::new();
+    let mut seen = Vec::new();
+
+    for gamepad in connected_gamepads() {
+        let id = gamepad.index();
+        let mut buttons = 0;
+        let mut axes = [0.0; 6];
+
+        for (index, button) in gamepad.buttons().iter().enumerate() {
+            let button = match button.dyn_into::<web_sys::GamepadButton>() {
+                Ok(button) => button,
+                Err(_) => continue,
+            };
+            match index {
+                6 => axes[GamepadAxis::TriggerLeft as usize] = button.value() as f32,
+                7 => axes[GamepadAxis::TriggerRight as usize] = button.value() as f32,
+                _ => {
+                    if let Some(mapped) = map_standard_button(index) {
+                        if button.pressed() {
+                            buttons |= mapped.mask();
+                        }
+                    }
+                }
+            }
+        }
+        for (index, value) in gamepad.axes().iter().take(4).enumerate() {
+            axes[index] = value.as_f64().unwrap_or(0.0) as f32;
+        }
+
+        let is_new = window.gamepads.get(id).is_none();
+        window
+            .gamepads
+            .diff_snapshot(id, buttons, axes, |e| events.push(e));
+        for event in events.drain(..) {
+            window.gamepads.update(&event);
+            window.get_events().push_event(event);
+        }
+        if is_new {
+            window.gamepads.set_name(id, &gamepad.id());
+        }
+        seen.push(id);
+    }
+
+    let disconnected: Vec<_> = window
+        .gamepads
+        .iter()
+        .map(|pad| pad.id())
+        .filter(|id| !seen.contains(id))
+        .collect();
+    for id in disconnected {
+        let event = EventKind::GamepadDisconnected { id };
+        window.gamepads.update(&event);
+        window.get_events().push_event(event);
+    }
+}
+
+/// maps a button index of the `standard` gamepad mapping, see https://w3c.github.io/gamepad/#remapping
+fn map_standard_button(index: usize) -> Option<GamepadButton> {
+    let button = match index {
+        0 => GamepadButton::A,
+        1 => GamepadButton::B,
+        2 => GamepadButton::X,
+        3 => GamepadButton::Y,
+        4 => GamepadButton::LeftShoulder,
+        5 => GamepadButton::RightShoulder,
+        8 => GamepadButton::Back,
+        9 => GamepadButton::Start,
+        10 => GamepadButton::LeftStick,
+        11 => GamepadButton::RightStick,
+        12 => GamepadButton::DPadUp,
+        13 => GamepadButton::DPadDown,
+        14 => GamepadButton::DPadLeft,
+        15 => GamepadButton::DPadRight,
+        16 => GamepadButton::Guide,
+        _ => return None,
+    };
+    Some(button)
 }
 
 fn get_button_code(button: i16) -> MouseCode {
@@ -524,6 +1047,11 @@ fn get_global_event_queue_mut<'a>() -> &'a mut FlufflEvent {
     unsafe { GLOBAL_EVENT_QUEUE.as_mut().unwrap() }
 }
 
+/// dropped files get read asynchronously, so they're stored globally just like events
+fn get_global_dropped_files_mut<'a>() -> &'a mut DroppedFiles {
+    unsafe { GLOBAL_DROPPED_FILES.as_mut().unwrap() }
+}
+
 /// # Description
 /// converts javascript viewport coordinates to expected window coordinates
 /// # Retuns